@@ -272,8 +272,18 @@ impl<S: Scalar> Arc<S> {
         // Here we make the approximation that for small tolerance values we consider
         // the radius to be constant over each approximated segment.
         let r = (self.from() - self.center).length();
-        let a = S::TWO * tolerance * r - tolerance * tolerance;
-        S::acos((a * a) / r)
+        if r <= tolerance {
+            return S::ONE;
+        }
+        // The angle of the chord whose distance to the arc equals the tolerance,
+        // as a fraction of the sweep angle.
+        let step = S::TWO * S::acos((r - tolerance) / r);
+        let sweep = S::abs(self.sweep_angle.get());
+        if step >= sweep {
+            S::ONE
+        } else {
+            step / sweep
+        }
     }
 
     /// Returns the flattened representation of the curve as an iterator, starting *after* the
@@ -1,16 +1,44 @@
+//! Clothoid (Euler spiral) related maths and tools.
+
+use std::ops::Range;
+
 use scalar::{cast, Float, Scalar};
-use generic_math::{Point, point, Vector, vector, Rotation2D, Transform2D, Angle, Rect};
+use generic_math::{Point, point, Vector, vector, size, Angle, Rect};
+use segment::{Segment, FlatteningStep, BoundingRect};
+use segment;
+
+/// A flattening iterator for clothoid segments.
+pub type Flattened<S> = segment::Flattened<S, Clothoid<S>>;
 
-/// A clothoid segment
+/// A clothoid segment, i.e. a curve whose curvature changes linearly with its
+/// arc length.
+///
+/// The curve is described by its start point, start angle and start curvature,
+/// the rate of change of the curvature along the curve and its total length.
 #[derive(Copy, Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub struct Clothoid<S> {
-    pub scale: S,
-    pub arg_start: S,
-    pub arg_end: S,
+    pub from: Point<S>,
+    pub start_angle: Angle<S>,
+    pub start_curvature: S,
+    pub curvature_rate: S,
+    pub length: S,
 }
 
+/// Abscissae and weights of the 5-point Gauss-Legendre quadrature on [-1, 1].
+pub const GAUSS_LEGENDRE_5: [(f64, f64); 5] = [
+    (0.0, 0.568_888_888_888_888_9),
+    (-0.538_469_310_105_683_1, 0.478_628_670_499_366_5),
+    (0.538_469_310_105_683_1, 0.478_628_670_499_366_5),
+    (-0.906_179_845_938_664_0, 0.236_926_885_056_189_1),
+    (0.906_179_845_938_664_0, 0.236_926_885_056_189_1),
+];
+
+/// Maximum change of the tangent angle integrated in a single quadrature step.
+const MAX_ANGLE_STEP: f64 = 0.25;
+
 impl<S: Scalar> Clothoid<S> {
+    /// Create a clothoid from the parameters of an OpenDRIVE `<spiral>` record.
     pub fn from_opendrive(
         from: Point<S>,
         start_curv: S,
@@ -20,13 +48,269 @@ impl<S: Scalar> Clothoid<S> {
     ) -> Clothoid<S> {
         debug_assert!(!from.x.is_nan());
         debug_assert!(!from.y.is_nan());
+        debug_assert!(length >= S::ZERO);
+
+        let curvature_rate = if length > S::ZERO {
+            (end_curv - start_curv) / length
+        } else {
+            S::ZERO
+        };
+
+        Clothoid {
+            from,
+            start_angle: rot,
+            start_curvature: start_curv,
+            curvature_rate,
+            length,
+        }
+    }
+
+    /// Curvature at the end of the curve.
+    #[inline]
+    pub fn end_curvature(&self) -> S {
+        self.curvature_at_length(self.length)
+    }
+
+    /// Curvature at a distance `s` along the curve.
+    #[inline]
+    pub fn curvature_at_length(&self, s: S) -> S {
+        self.start_curvature + self.curvature_rate * s
+    }
+
+    /// Tangent angle at a distance `s` along the curve.
+    #[inline]
+    pub fn angle_at_length(&self, s: S) -> Angle<S> {
+        self.start_angle
+            + Angle::radians(self.start_curvature * s + self.curvature_rate * s * s / S::TWO)
+    }
+
+    /// Tangent angle at the end of the curve.
+    #[inline]
+    pub fn end_angle(&self) -> Angle<S> {
+        self.angle_at_length(self.length)
+    }
+
+    /// Sample the curve at a distance `s` along the curve.
+    pub fn sample_at_length(&self, s: S) -> Point<S> {
+        // Upper bound of the tangent angle change over [0, s], used to pick the
+        // number of quadrature steps.
+        let turn = S::abs(self.start_curvature) * S::abs(s)
+            + S::abs(self.curvature_rate) * s * s / S::TWO;
+        let max_step: S = cast(MAX_ANGLE_STEP).unwrap();
+        let n = S::max(S::ceil(turn / max_step), S::ONE);
+        let h = s / n;
+
+        let mut x = S::ZERO;
+        let mut y = S::ZERO;
+        for i in 0..cast::<S, u32>(n).unwrap() {
+            let mid = h * (cast::<u32, S>(i).unwrap() + S::HALF);
+            for &(node, weight) in GAUSS_LEGENDRE_5.iter() {
+                let u = mid + h * S::HALF * cast::<f64, S>(node).unwrap();
+                let w = h * S::HALF * cast::<f64, S>(weight).unwrap();
+                let a = self.angle_at_length(u).get();
+                x += w * Float::cos(a);
+                y += w * Float::sin(a);
+            }
+        }
 
-        let abs_k0 = start_curv.abs();
-        let abs_k1 = end_curv.abs();
-        let x = (-length / (abs_k0 - abs_k1)).sqrt();
+        point(self.from.x + x, self.from.y + y)
+    }
+
+    /// Sample the curve at t (expecting t between 0 and 1).
+    #[inline]
+    pub fn sample(&self, t: S) -> Point<S> {
+        self.sample_at_length(self.length * t)
+    }
+
+    #[inline]
+    pub fn x(&self, t: S) -> S { self.sample(t).x }
+
+    #[inline]
+    pub fn y(&self, t: S) -> S { self.sample(t).y }
+
+    /// Sample the derivative at t (expecting t between 0 and 1).
+    #[inline]
+    pub fn derivative(&self, t: S) -> Vector<S> {
+        let a = self.angle_at_length(self.length * t).get();
+        vector(Float::cos(a), Float::sin(a)) * self.length
+    }
+
+    #[inline]
+    pub fn dx(&self, t: S) -> S { self.derivative(t).x }
+
+    #[inline]
+    pub fn dy(&self, t: S) -> S { self.derivative(t).y }
 
-        let a = -k1 * sqrt(-l / (Abs(k0) - Abs(k1))) / Abs(k1);
-        let t0 = k1 * sqrt(-l / (Abs(k0) - Abs(k1))) * Abs(k0) / Abs(k1);
-        let t1 = k1 * sqrt(-l / (Abs(k0) - Abs(k1)));
+    #[inline]
+    pub fn from(&self) -> Point<S> {
+        self.from
     }
+
+    #[inline]
+    pub fn to(&self) -> Point<S> {
+        self.sample_at_length(self.length)
+    }
+
+    /// Return the sub-curve inside a given range of t.
+    ///
+    /// This is equivalent splitting at the range's end points.
+    pub fn split_range(&self, t_range: Range<S>) -> Self {
+        let s0 = self.length * t_range.start;
+        let s1 = self.length * t_range.end;
+        Clothoid {
+            from: self.sample_at_length(s0),
+            start_angle: self.angle_at_length(s0),
+            start_curvature: self.curvature_at_length(s0),
+            curvature_rate: self.curvature_rate,
+            length: s1 - s0,
+        }
+    }
+
+    /// Split this curve into two sub-curves.
+    pub fn split(&self, t: S) -> (Clothoid<S>, Clothoid<S>) {
+        (self.before_split(t), self.after_split(t))
+    }
+
+    /// Return the curve before the split point.
+    pub fn before_split(&self, t: S) -> Clothoid<S> {
+        Clothoid {
+            length: self.length * t,
+            ..*self
+        }
+    }
+
+    /// Return the curve after the split point.
+    pub fn after_split(&self, t: S) -> Clothoid<S> {
+        self.split_range(t..S::ONE)
+    }
+
+    /// Swap the direction of the segment.
+    pub fn flip(&self) -> Self {
+        Clothoid {
+            from: self.to(),
+            start_angle: self.end_angle() + Angle::pi(),
+            start_curvature: -self.end_curvature(),
+            curvature_rate: self.curvature_rate,
+            length: self.length,
+        }
+    }
+
+    /// Approximates the curve with a sequence of line segments.
+    pub fn for_each_flattened<F: FnMut(Point<S>)>(&self, tolerance: S, call_back: &mut F) {
+        <Self as segment::FlattenedForEach>::for_each_flattened(self, tolerance, call_back);
+    }
+
+    /// Finds the interval of the begining of the curve that can be approximated with a
+    /// line segment.
+    pub fn flattening_step(&self, tolerance: S) -> S {
+        // The curvature is linear so its maximum is reached at one of the end points.
+        let k = S::max(S::abs(self.start_curvature), S::abs(self.end_curvature()));
+        if k <= S::EPSILON || self.length <= S::EPSILON {
+            return S::ONE;
+        }
+
+        // For a circular arc of radius 1/k the distance between a chord of length l
+        // and the arc is approximately k * l² / 8.
+        let step = S::sqrt(S::EIGHT * tolerance / k);
+        S::min(step / self.length, S::ONE)
+    }
+
+    /// Returns the flattened representation of the curve as an iterator, starting *after* the
+    /// current point.
+    pub fn flattened(&self, tolerance: S) -> Flattened<S> {
+        Flattened::new(*self, tolerance)
+    }
+
+    /// Returns a rectangle that contains the curve.
+    pub fn bounding_rect(&self) -> Rect<S> {
+        let (min_x, max_x) = self.bounding_range_x();
+        let (min_y, max_y) = self.bounding_range_y();
+        Rect::new(point(min_x, min_y), size(max_x - min_x, max_y - min_y))
+    }
+
+    pub fn bounding_range_x(&self) -> (S, S) {
+        self.bounding_range(|p| p.x)
+    }
+
+    pub fn bounding_range_y(&self) -> (S, S) {
+        self.bounding_range(|p| p.y)
+    }
+
+    fn bounding_range<F: Fn(Point<S>) -> S>(&self, coord: F) -> (S, S) {
+        let tolerance: S = cast(0.001).unwrap();
+        let mut min = coord(self.from);
+        let mut max = min;
+        self.for_each_flattened(tolerance, &mut |p| {
+            min = S::min(min, coord(p));
+            max = S::max(max, coord(p));
+        });
+        (min, max)
+    }
+
+    /// The length of a clothoid is part of its definition, so this is exact.
+    pub fn approximate_length(&self, _tolerance: S) -> S {
+        self.length
+    }
+}
+
+impl<S: Scalar> Segment for Clothoid<S> { impl_segment!(S); }
+
+impl<S: Scalar> BoundingRect for Clothoid<S> {
+    type Scalar = S;
+    fn bounding_rect(&self) -> Rect<S> { self.bounding_rect() }
+    fn fast_bounding_rect(&self) -> Rect<S> { self.bounding_rect() }
+    fn bounding_range_x(&self) -> (S, S) { self.bounding_range_x() }
+    fn bounding_range_y(&self) -> (S, S) { self.bounding_range_y() }
+    fn fast_bounding_range_x(&self) -> (S, S) { self.bounding_range_x() }
+    fn fast_bounding_range_y(&self) -> (S, S) { self.bounding_range_y() }
+}
+
+impl<S: Scalar> FlatteningStep for Clothoid<S> {
+    fn flattening_step(&self, tolerance: S) -> S {
+        self.flattening_step(tolerance)
+    }
+}
+
+#[test]
+fn straight_clothoid() {
+    let c = Clothoid::from_opendrive(point(1.0, 2.0), 0.0, 0.0, 10.0, Angle::frac_pi_2());
+    let to = c.to();
+    assert!((to.x - 1.0).abs() < 1e-12);
+    assert!((to.y - 12.0).abs() < 1e-12);
+}
+
+#[test]
+fn constant_curvature_clothoid() {
+    // With zero curvature rate the clothoid degenerates into a circular arc.
+    let c = Clothoid::from_opendrive(point(0.0, 0.0), 1.0, 1.0, ::std::f64::consts::PI, Angle::zero());
+    let to = c.to();
+    assert!(to.x.abs() < 1e-10);
+    assert!((to.y - 2.0).abs() < 1e-10);
+    assert!((c.end_angle().get() - ::std::f64::consts::PI).abs() < 1e-12);
+}
+
+#[test]
+fn clothoid_split_and_flip() {
+    let c = Clothoid::from_opendrive(point(3.0, -1.0), 0.0, 0.2, 20.0, Angle::radians(0.3));
+    let (a, b) = c.split(0.4);
+    assert!((a.to() - b.from()).length() < 1e-10);
+    assert!((b.to() - c.to()).length() < 1e-10);
+    assert!((a.length + b.length - c.length).abs() < 1e-12);
+
+    let f = c.flip();
+    assert!((f.to() - c.from()).length() < 1e-9);
+    assert!((f.end_curvature() + c.start_curvature).abs() < 1e-12);
+}
+
+#[test]
+fn clothoid_flattening() {
+    let c = Clothoid::from_opendrive(point(0.0, 0.0), 0.0, 0.1, 30.0, Angle::zero());
+    let mut last = c.from();
+    let mut n = 0;
+    c.for_each_flattened(0.01, &mut |p| {
+        last = p;
+        n += 1;
+    });
+    assert!(n > 1);
+    assert!((last - c.to()).length() < 1e-10);
 }
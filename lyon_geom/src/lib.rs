@@ -81,6 +81,7 @@ pub extern crate serde;
 pub mod quadratic_bezier;
 pub mod cubic_bezier;
 pub mod arc;
+pub mod clothoid;
 pub mod utils;
 pub mod cubic_to_quadratic;
 mod flatten_cubic;
@@ -99,6 +100,8 @@ pub use line::{LineSegment, Line, LineEquation};
 #[doc(inline)]
 pub use arc::{Arc, SvgArc, ArcFlags};
 #[doc(inline)]
+pub use clothoid::Clothoid;
+#[doc(inline)]
 pub use segment::Segment;
 #[doc(inline)]
 pub use monotonic::Monotonic;
@@ -0,0 +1,202 @@
+//! Evaluation of road geometry by arc length
+//!
+//! `lyon_geom::Segment` and the `types::Segment` wrapper are parameterised
+//! by t in [0, 1], which is not proportional to the distance along the curve
+//! for Bezier curves and polynomials. OpenDRIVE positions everything by s,
+//! so `ArcLengthCurve` evaluates the geometry elements with the formulas of
+//! the specification: lines, arcs and spirals directly by their length, poly3
+//! elements by inverting the arc length of v(u) and paramPoly3 elements with
+//! the parameter p proportional to s over its range.
+//!
+//! Splitting is exact as well. Lines, arcs and spirals split into elements of
+//! the same type, the parts of a spiral with the curvature at the split
+//! position. The parts of a poly3 or paramPoly3 element keep the orientation
+//! of the local u/v co-ordinate system and shift the polynomials to the
+//! start of the part, so the heading of the second part starts with the
+//! slope of the polynomial rather than 0.
+use crate::opendrive::*;
+use crate::types;
+
+/// A curve parameterised by the distance s along it, measured from its start
+pub trait ArcLengthCurve: Sized {
+    /// m length of the curve
+    fn length(&self) -> f64;
+
+    /// Inertial x, y at s
+    fn position_at_s(&self, s: f64) -> (f64, f64);
+
+    /// rad inertial heading at s
+    fn heading_at_s(&self, s: f64) -> f64;
+
+    /// 1/m signed curvature at s, positive to the left
+    fn curvature_at_s(&self, s: f64) -> f64;
+
+    /// The parts of the curve before and after s, which is clamped to the
+    /// curve
+    fn split_at_s(&self, s: f64) -> (Self, Self);
+}
+
+impl ArcLengthCurve for Geometry {
+    fn length(&self) -> f64 {
+        self.length.get()
+    }
+
+    fn position_at_s(&self, s: f64) -> (f64, f64) {
+        let (x, y, _) = self.pose_at(s);
+        (x, y)
+    }
+
+    fn heading_at_s(&self, s: f64) -> f64 {
+        self.pose_at(s).2
+    }
+
+    fn curvature_at_s(&self, s: f64) -> f64 {
+        self.curvature_at(s)
+    }
+
+    /// The second part is placed at `self.s + s` along the reference line
+    fn split_at_s(&self, s: f64) -> (Geometry, Geometry) {
+        let length = self.length.get();
+        let s = s.max(0.0).min(length);
+        let (x, y, hdg) = self.pose_at(s);
+        let (first, second) = match self.element {
+            GeometryElement::Line => (GeometryElement::Line, GeometryElement::Line),
+            GeometryElement::Arc { curvature } => (
+                GeometryElement::Arc { curvature },
+                GeometryElement::Arc { curvature },
+            ),
+            GeometryElement::Spiral {
+                curv_start,
+                curv_end,
+            } => {
+                let curvature = self.curvature_at(s);
+                (
+                    GeometryElement::Spiral {
+                        curv_start,
+                        curv_end: curvature,
+                    },
+                    GeometryElement::Spiral {
+                        curv_start: curvature,
+                        curv_end,
+                    },
+                )
+            }
+            GeometryElement::Poly3 { a, b, c, d } => {
+                let u = poly3_u_at_length(b, c, d, s);
+                let [_, b1, c1, d1] = shift([a, b, c, d], u, 1.0);
+                (
+                    GeometryElement::Poly3 { a, b, c, d },
+                    GeometryElement::Poly3 {
+                        a: 0.0,
+                        b: b1,
+                        c: c1,
+                        d: d1,
+                    },
+                )
+            }
+            GeometryElement::ParamPoly3 { .. } => {
+                let (u, v) = self.param_poly3_coefficients().unwrap();
+                let p = if length > 0.0 { s / length } else { 0.0 };
+                let (u0, v0) = (shift(u, 0.0, p), shift(v, 0.0, p));
+                let (mut u1, mut v1) = (shift(u, p, 1.0 - p), shift(v, p, 1.0 - p));
+                u1[0] = 0.0;
+                v1[0] = 0.0;
+                (param_poly3(u0, v0), param_poly3(u1, v1))
+            }
+        };
+        // polynomials keep the orientation of their u/v co-ordinate system
+        let second_hdg = match self.element {
+            GeometryElement::Poly3 { .. } | GeometryElement::ParamPoly3 { .. } => self.hdg.radians,
+            _ => hdg,
+        };
+        (
+            Geometry {
+                s: self.s,
+                x: self.x,
+                y: self.y,
+                hdg: self.hdg,
+                length: types::Length::new(s),
+                element: first,
+            },
+            Geometry {
+                s: types::Length::new(self.s.get() + s),
+                x: types::Length::new(x),
+                y: types::Length::new(y),
+                hdg: types::Angle::radians(second_hdg),
+                length: types::Length::new(length - s),
+                element: second,
+            },
+        )
+    }
+}
+
+/// The whole reference line of a road
+impl ArcLengthCurve for PlanView {
+    fn length(&self) -> f64 {
+        self.sum_length().get()
+    }
+
+    fn position_at_s(&self, s: f64) -> (f64, f64) {
+        let (x, y, _) = self.pose_at(s);
+        (x, y)
+    }
+
+    fn heading_at_s(&self, s: f64) -> f64 {
+        self.pose_at(s).2
+    }
+
+    fn curvature_at_s(&self, s: f64) -> f64 {
+        self.geometry_at(s)
+            .map_or(0.0, |g| g.curvature_at(s - g.s.get()))
+    }
+
+    /// Geometries that end at s are not split. The second part is a
+    /// reference line of its own, starting at s = 0.
+    fn split_at_s(&self, s: f64) -> (PlanView, PlanView) {
+        let mut first = PlanView::default();
+        let mut second = PlanView::default();
+        for g in self.geometries.iter() {
+            let (start, end) = (g.s.get(), g.s.get() + g.length.get());
+            if end <= s + 1e-9 {
+                first.geometries.push(g.clone());
+            } else if start >= s - 1e-9 {
+                second.geometries.push(g.clone());
+            } else {
+                let (a, b) = g.split_at_s(s - start);
+                first.geometries.push(a);
+                second.geometries.push(b);
+            }
+        }
+        let start = second.geometries.first().map_or(0.0, |g| g.s.get());
+        for g in second.geometries.iter_mut() {
+            g.s = types::Length::new(g.s.get() - start);
+        }
+        (first, second)
+    }
+}
+
+/// The coefficients of the cubic f(p0 + q * dp) as polynomial of q
+pub(crate) fn shift(f: [f64; 4], p0: f64, dp: f64) -> [f64; 4] {
+    let [a, b, c, d] = f;
+    [
+        a + p0 * (b + p0 * (c + p0 * d)),
+        (b + p0 * (2.0 * c + 3.0 * d * p0)) * dp,
+        (c + 3.0 * d * p0) * dp * dp,
+        d * dp * dp * dp,
+    ]
+}
+
+/// A paramPoly3 element with p in [0, 1]
+pub(crate) fn param_poly3(u: [f64; 4], v: [f64; 4]) -> GeometryElement {
+    GeometryElement::ParamPoly3 {
+        a_u: u[0],
+        b_u: u[1],
+        c_u: u[2],
+        d_u: u[3],
+        a_v: v[0],
+        b_v: v[1],
+        c_v: v[2],
+        d_v: v[3],
+        p_range: PRange::Normalized,
+    }
+}
@@ -0,0 +1,426 @@
+//! `odr`, a command line tool to inspect, validate, convert and query
+//! OpenDRIVE road networks
+//!
+//! Exit status is 0 on success, 1 if `validate` has findings, `query` finds
+//! no lane or `diff` finds differences and 2 on errors.
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use opendrive_rs::curvature::{self, CurvatureOptions, CurvatureProfile};
+use opendrive_rs::diff::{self, DiffOptions};
+use opendrive_rs::geojson::{self, Coordinates, GeoJsonOptions};
+use opendrive_rs::lanelet2::{self, Lanelet2Options};
+use opendrive_rs::mesh::{self, MeshOptions};
+use opendrive_rs::revision::{self, ParseOptions, Revision};
+use opendrive_rs::svg::{self, SvgOptions};
+use opendrive_rs::types::RoadId;
+use opendrive_rs::Root;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::process;
+
+/// m chord error of the samples the bounding box is computed from
+const INFO_TOLERANCE: f64 = 0.1;
+
+fn main() {
+    let input = || {
+        Arg::with_name("input")
+            .help("OpenDRIVE file")
+            .required(true)
+            .index(1)
+    };
+    let strict = || {
+        Arg::with_name("strict")
+            .long("strict")
+            .help("reject features the declared OpenDRIVE revision doesn't define")
+    };
+    let tolerance = |help| {
+        Arg::with_name("tolerance")
+            .long("tolerance")
+            .help(help)
+            .takes_value(true)
+    };
+    let matches = App::new("odr")
+        .about("Inspect, validate, convert and query OpenDRIVE road networks")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("info")
+                .about("Print the header, the number of roads and junctions and the extent")
+                .arg(input()),
+        )
+        .subcommand(
+            SubCommand::with_name("validate")
+                .about("Check the consistency of the road network")
+                .arg(input())
+                .arg(strict()),
+        )
+        .subcommand(
+            SubCommand::with_name("convert")
+                .about("Convert the road network to another format")
+                .arg(input())
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .help("output format")
+                        .takes_value(true)
+                        .possible_values(&["geojson", "svg", "obj", "glb", "lanelet2"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .help("output file, standard output if omitted")
+                        .takes_value(true),
+                )
+                .arg(tolerance(
+                    "maximum deviation of the output from the exact curves [m]",
+                ))
+                .arg(
+                    Arg::with_name("geodetic")
+                        .long("geodetic")
+                        .help("write WGS84 longitude/latitude to GeoJSON"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("query")
+                .about("Print road, lane, s and t of an inertial position")
+                .arg(input())
+                .arg(
+                    Arg::with_name("xy")
+                        .long("xy")
+                        .value_name("X,Y")
+                        .help("inertial position [m]")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("yaw")
+                        .long("yaw")
+                        .help("heading [rad], also prints the pose relative to the lane")
+                        .takes_value(true)
+                        .allow_hyphen_values(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Compare two versions of a road network by road and junction id")
+                .arg(input().help("old OpenDRIVE file"))
+                .arg(
+                    Arg::with_name("other")
+                        .help("new OpenDRIVE file")
+                        .required(true)
+                        .index(2),
+                )
+                .arg(tolerance("maximum difference of numbers considered equal"))
+                .arg(strict()),
+        )
+        .subcommand(
+            SubCommand::with_name("curvature")
+                .about("Analyze the curvature of the reference lines")
+                .arg(input())
+                .arg(
+                    Arg::with_name("road")
+                        .long("road")
+                        .help("id of the road to analyze, all roads if omitted")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("step")
+                        .long("step")
+                        .help("distance of the samples along the reference line [m]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("friction")
+                        .long("friction")
+                        .help("side friction factor of the design speed")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("csv")
+                        .long("csv")
+                        .help("print the samples s, x, y, hdg, kappa, z as CSV"),
+                ),
+        )
+        .get_matches();
+
+    let result = match matches.subcommand() {
+        ("info", Some(matches)) => info(matches),
+        ("validate", Some(matches)) => validate(matches),
+        ("convert", Some(matches)) => convert(matches),
+        ("query", Some(matches)) => query(matches),
+        ("diff", Some(matches)) => diff(matches),
+        ("curvature", Some(matches)) => curvature(matches),
+        _ => unreachable!(),
+    };
+    match result {
+        Ok(status) => process::exit(status),
+        Err(err) => {
+            eprintln!("odr: {}", err);
+            process::exit(2);
+        }
+    }
+}
+
+fn read(matches: &ArgMatches) -> Result<Root, failure::Error> {
+    read_file(matches.value_of("input").unwrap(), &parse_options(matches))
+}
+
+fn parse_options(matches: &ArgMatches) -> ParseOptions {
+    ParseOptions {
+        strict: matches.is_present("strict"),
+    }
+}
+
+fn read_file(path: &str, options: &ParseOptions) -> Result<Root, failure::Error> {
+    let file = File::open(path).map_err(|err| failure::format_err!("{}: {}", path, err))?;
+    opendrive_rs::from_reader_with(BufReader::new(file), options)
+}
+
+/// The value of `--tolerance`, which must be finite and positive, or zero
+/// as well if `allow_zero`
+fn tolerance(matches: &ArgMatches, allow_zero: bool) -> Result<Option<f64>, failure::Error> {
+    let value = match matches.value_of("tolerance") {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let tolerance = value
+        .parse::<f64>()
+        .map_err(|err| failure::format_err!("invalid tolerance {}: {}", value, err))?;
+    if !tolerance.is_finite() || tolerance < 0.0 || (tolerance == 0.0 && !allow_zero) {
+        let expected = if allow_zero {
+            "non-negative"
+        } else {
+            "positive"
+        };
+        return Err(failure::format_err!(
+            "tolerance {} is not {} and finite",
+            value,
+            expected
+        ));
+    }
+    Ok(Some(tolerance))
+}
+
+fn info(matches: &ArgMatches) -> Result<i32, failure::Error> {
+    let root = read(matches)?;
+    let header = &root.header;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    writeln!(out, "name:       {}", header.name)?;
+    let declared = header.revision();
+    if declared.is_supported() {
+        writeln!(out, "revision:   {}", declared)?;
+    } else {
+        writeln!(out, "revision:   {} (read as {})", declared, Revision::V1_8)?;
+    }
+    writeln!(out, "version:    {}", header.version)?;
+    writeln!(out, "date:       {}", header.date.to_rfc3339())?;
+    if let Some(ref geo_reference) = header.geo_reference {
+        writeln!(out, "geo ref:    {}", geo_reference.projection.trim())?;
+    }
+    writeln!(out, "roads:      {}", root.roads.len())?;
+    writeln!(out, "junctions:  {}", root.junctions.len())?;
+    let length: f64 = root.roads.iter().map(|road| road.length.get()).sum();
+    writeln!(out, "length:     {:.3} m", length)?;
+    if let Some(b) = root.bounding_box(INFO_TOLERANCE)? {
+        writeln!(
+            out,
+            "bounds:     x {:.3} .. {:.3}, y {:.3} .. {:.3}",
+            b.west, b.east, b.south, b.north
+        )?;
+    }
+    let report = revision::feature_report(&root);
+    if !report.usages.is_empty() {
+        match report.required_revision() {
+            Some(required) => writeln!(out, "features:   requires {}", required)?,
+            None => writeln!(out, "features:")?,
+        }
+        for line in report.to_string().lines() {
+            writeln!(out, "  {}", line)?;
+        }
+    }
+    Ok(0)
+}
+
+fn validate(matches: &ArgMatches) -> Result<i32, failure::Error> {
+    let root = read(matches)?;
+    let findings = root.validate();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for finding in findings.iter() {
+        writeln!(out, "{}", finding)?;
+    }
+    if findings.is_empty() {
+        writeln!(out, "ok")?;
+        Ok(0)
+    } else {
+        writeln!(out, "{} finding(s)", findings.len())?;
+        Ok(1)
+    }
+}
+
+fn convert(matches: &ArgMatches) -> Result<i32, failure::Error> {
+    let tolerance = tolerance(matches, false)?;
+    let root = read(matches)?;
+    let output = matches.value_of("output").map(Path::new);
+    let writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    let mut writer = BufWriter::new(writer);
+
+    match matches.value_of("to").unwrap() {
+        "geojson" => {
+            let mut options = GeoJsonOptions::default();
+            options.tolerance = tolerance.unwrap_or(options.tolerance);
+            if matches.is_present("geodetic") {
+                options.coordinates = Coordinates::Geodetic;
+            }
+            geojson::write_geojson(&root, &options, &mut writer)?;
+        }
+        "svg" => {
+            let mut options = SvgOptions::default();
+            options.tolerance = tolerance.unwrap_or(options.tolerance);
+            svg::write_svg(&root, &options, &mut writer)?;
+        }
+        "obj" | "glb" => {
+            let mut options = MeshOptions::default();
+            options.tolerance = tolerance.unwrap_or(options.tolerance);
+            let mesh = mesh::road_mesh(&root, &options)?;
+            if matches.value_of("to") == Some("glb") {
+                mesh.write_glb(&mut writer)?;
+            } else if let Some(path) = output {
+                // the materials go next to the OBJ file
+                let mtl = path.with_extension("mtl");
+                mesh.write_mtl(BufWriter::new(File::create(&mtl)?))?;
+                let name = mtl.file_name().unwrap().to_string_lossy();
+                mesh.write_obj(&mut writer, Some(&name))?;
+            } else {
+                mesh.write_obj(&mut writer, None)?;
+            }
+        }
+        "lanelet2" => {
+            let mut options = Lanelet2Options::default();
+            options.tolerance = tolerance.unwrap_or(options.tolerance);
+            lanelet2::write_lanelet2(&root, &options, &mut writer)?;
+        }
+        _ => unreachable!(),
+    }
+    writer.flush()?;
+    Ok(0)
+}
+
+fn query(matches: &ArgMatches) -> Result<i32, failure::Error> {
+    let xy = matches.value_of("xy").unwrap();
+    let (x, y) = match xy.split(',').map(str::trim).collect::<Vec<_>>()[..] {
+        [x, y] => (x.parse::<f64>()?, y.parse::<f64>()?),
+        _ => {
+            return Err(failure::format_err!(
+                "invalid position \"{}\", expected X,Y",
+                xy
+            ))
+        }
+    };
+    let yaw = match matches.value_of("yaw") {
+        Some(yaw) => Some(yaw.parse::<f64>()?),
+        None => None,
+    };
+    let root = read(matches)?;
+    if let Some(yaw) = yaw {
+        return match root.localize(x, y, yaw) {
+            Some(l) => {
+                println!(
+                    "road {} section {} lane {} s {:.3} t {:.3} offset {:.3} heading {:.4} left {:.3} right {:.3}",
+                    l.road_id,
+                    l.position.section,
+                    l.position.lane,
+                    l.position.s,
+                    l.position.t,
+                    l.offset,
+                    l.heading,
+                    l.left,
+                    l.right
+                );
+                Ok(0)
+            }
+            None => {
+                println!("no lane at {}, {}", x, y);
+                Ok(1)
+            }
+        };
+    }
+    match root.lane_position_at(x, y) {
+        Some(position) => {
+            println!(
+                "road {} section {} lane {} s {:.3} t {:.3}",
+                root.roads[position.road].id,
+                position.section,
+                position.lane,
+                position.s,
+                position.t
+            );
+            Ok(0)
+        }
+        None => {
+            println!("no lane at {}, {}", x, y);
+            Ok(1)
+        }
+    }
+}
+
+fn diff(matches: &ArgMatches) -> Result<i32, failure::Error> {
+    let mut options = DiffOptions::default();
+    options.tolerance = tolerance(matches, true)?.unwrap_or(options.tolerance);
+    let a = read(matches)?;
+    let b = read_file(matches.value_of("other").unwrap(), &parse_options(matches))?;
+    let diff = diff::diff(&a, &b, &options)?;
+    print!("{}", diff);
+    Ok(if diff.is_empty() { 0 } else { 1 })
+}
+
+fn curvature(matches: &ArgMatches) -> Result<i32, failure::Error> {
+    let mut options = CurvatureOptions::default();
+    if let Some(value) = matches.value_of("step") {
+        let step = value
+            .parse::<f64>()
+            .map_err(|err| failure::format_err!("invalid step {}: {}", value, err))?;
+        if !(step > 0.0 && step.is_finite()) {
+            return Err(failure::format_err!(
+                "step {} is not positive and finite",
+                value
+            ));
+        }
+        options.step = step;
+    }
+    if let Some(friction) = matches.value_of("friction") {
+        options.side_friction = friction.parse::<f64>()?;
+    }
+    let root = read(matches)?;
+    let roads: Vec<_> = match matches.value_of("road") {
+        Some(id) => {
+            let id = RoadId::from(id);
+            let road = root
+                .road(&id)
+                .ok_or_else(|| failure::format_err!("no road {}", id))?;
+            vec![road]
+        }
+        None => root.roads.iter().collect(),
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if matches.is_present("csv") {
+        curvature::write_csv(roads, &options, &mut out)?;
+        return Ok(0);
+    }
+    for road in roads {
+        writeln!(out, "road {}", road.id)?;
+        let analysis = CurvatureProfile::new(road).analyze(&options);
+        for line in analysis.to_string().lines() {
+            writeln!(out, "  {}", line)?;
+        }
+    }
+    Ok(0)
+}
@@ -0,0 +1,614 @@
+//! Fluent builders for constructing roads programmatically
+//!
+//! The builders take care of the bookkeeping that is easy to get wrong when
+//! filling in the OpenDRIVE structures by hand: the start position and heading
+//! of each `Geometry` record is chained from the end of the previous one, `s`
+//! offsets are accumulated, `Road::length` always matches
+//! `PlanView::sum_length` and lane ids are assigned according to convention.
+//!
+//! ```ignore
+//! let road = RoadBuilder::new()
+//!     .id(1)
+//!     .line(50.0)
+//!     .arc(30.0, 0.02)
+//!     .spiral(20.0, 0.02, 0.0)
+//!     .lanes(
+//!         LaneSectionBuilder::new()
+//!             .center_mark(RoadMarkType::Broken)
+//!             .left(LaneBuilder::new(LaneType::Driving, 3.5).mark(RoadMarkType::Solid))
+//!             .right(LaneBuilder::new(LaneType::Driving, 3.5).mark(RoadMarkType::Solid)),
+//!     )
+//!     .build();
+//! ```
+use crate::errors::BuildError;
+use crate::opendrive::*;
+use crate::types;
+use lyon_geom::Segment;
+
+/// Default width of a road mark line [m]
+pub(crate) const DEFAULT_ROAD_MARK_WIDTH: f64 = 0.12;
+
+/// Builds a `Road` by appending geometry elements to its reference line
+pub struct RoadBuilder {
+    name: String,
+    id: types::RoadId,
+    junction: Option<types::JunctionId>,
+    predecessor: Option<LinkElement>,
+    successor: Option<LinkElement>,
+    road_types: Vec<RoadType>,
+    /// start of the next geometry element
+    s: f64,
+    x: f64,
+    y: f64,
+    hdg: f64,
+    geometries: Vec<Geometry>,
+    elevations: Vec<Elevation>,
+    superelevations: Vec<Superelevation>,
+    lane_offsets: Vec<LaneOffset>,
+    lane_sections: Vec<LaneSection>,
+}
+
+impl Default for RoadBuilder {
+    fn default() -> RoadBuilder {
+        RoadBuilder::new()
+    }
+}
+
+impl RoadBuilder {
+    /// Start a new road at the origin with zero heading
+    pub fn new() -> RoadBuilder {
+        RoadBuilder {
+            name: String::new(),
+            id: types::RoadId::from(0),
+            junction: None,
+            predecessor: None,
+            successor: None,
+            road_types: Vec::new(),
+            s: 0.0,
+            x: 0.0,
+            y: 0.0,
+            hdg: 0.0,
+            geometries: Vec::new(),
+            elevations: Vec::new(),
+            superelevations: Vec::new(),
+            lane_offsets: Vec::new(),
+            lane_sections: Vec::new(),
+        }
+    }
+
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn id<I: Into<types::RoadId>>(mut self, id: I) -> Self {
+        self.id = id.into();
+        self
+    }
+
+    /// Mark the road as a connecting road of the junction `junction`
+    pub fn junction<I: Into<types::JunctionId>>(mut self, junction: I) -> Self {
+        self.junction = Some(junction.into());
+        self
+    }
+
+    /// Set the inertial start position and heading of the reference line.
+    ///
+    /// This must be called before any geometry is added.
+    pub fn start(mut self, x: f64, y: f64, hdg: f64) -> Self {
+        debug_assert!(
+            self.geometries.is_empty(),
+            "the start pose must be set before adding geometry"
+        );
+        self.x = x;
+        self.y = y;
+        self.hdg = hdg;
+        self
+    }
+
+    /// Start the reference line where the reference line of `road` ends
+    pub fn start_at_end_of(self, road: &Road) -> Self {
+        match road.plan_view.geometries.last() {
+            Some(last) => {
+                let end = last.as_segment().to();
+                self.start(end.x, end.y, last.end_hdg().radians)
+            }
+            None => self,
+        }
+    }
+
+    /// Length of the reference line built so far
+    pub fn s(&self) -> f64 {
+        self.s
+    }
+
+    /// Apply `f` to the builder, e.g. to add elements that depend on the
+    /// geometry built so far without breaking the chain of calls
+    pub fn apply<F: FnOnce(Self) -> Self>(self, f: F) -> Self {
+        f(self)
+    }
+
+    /// Append a straight line of `length`
+    pub fn line(self, length: f64) -> Self {
+        self.push(length, GeometryElement::Line)
+    }
+
+    /// Append an arc of `length` with constant `curvature`
+    pub fn arc(self, length: f64, curvature: f64) -> Self {
+        if curvature == 0.0 {
+            return self.line(length);
+        }
+        self.push(length, GeometryElement::Arc { curvature })
+    }
+
+    /// Append a spiral of `length` whose curvature changes linearly from
+    /// `curv_start` to `curv_end`
+    pub fn spiral(self, length: f64, curv_start: f64, curv_end: f64) -> Self {
+        self.push(
+            length,
+            GeometryElement::Spiral {
+                curv_start,
+                curv_end,
+            },
+        )
+    }
+
+    /// Append a pair of arcs (a biarc) that ends at the inertial position
+    /// `x`, `y` with heading `hdg`.
+    ///
+    /// Both arcs have tangents of equal length which yields a single arc or a
+    /// straight line if the end pose allows it. Fails if no such pair of arcs
+    /// exists, e.g. for an end pose behind the current one.
+    pub fn connect_to(self, x: f64, y: f64, hdg: f64) -> Result<Self, BuildError> {
+        let (t1x, t1y) = (self.hdg.cos(), self.hdg.sin());
+        let (t2x, t2y) = (hdg.cos(), hdg.sin());
+        let (vx, vy) = (x - self.x, y - self.y);
+        let (tx, ty) = (t1x + t2x, t1y + t2y);
+
+        let vv = vx * vx + vy * vy;
+        let vt = vx * tx + vy * ty;
+        let denom = 4.0 - (tx * tx + ty * ty);
+
+        // Length of both tangents, solving |v - d * (t1 + t2)| = 2 * d
+        let d = if denom.abs() < 1e-12 {
+            vv / (4.0 * (vx * t2x + vy * t2y))
+        } else {
+            ((vt * vt + denom * vv).sqrt() - vt) / denom
+        };
+        if !d.is_finite() || d < 0.0 {
+            return Err(BuildError::NoBiarc(x, y, hdg));
+        }
+
+        let mx = (self.x + d * t1x + x - d * t2x) / 2.0;
+        let my = (self.y + d * t1y + y - d * t2y) / 2.0;
+        Ok(self.arc_to(mx, my).arc_to(x, y))
+    }
+
+    /// Append the arc that is tangent to the current heading and ends at `x`, `y`
+    fn arc_to(self, x: f64, y: f64) -> Self {
+        let (cx, cy) = (x - self.x, y - self.y);
+        let chord = (cx * cx + cy * cy).sqrt();
+        if chord < 1e-9 {
+            return self;
+        }
+
+        let (tx, ty) = (self.hdg.cos(), self.hdg.sin());
+        let cross = tx * cy - ty * cx;
+        let dot = tx * cx + ty * cy;
+        if cross.abs() < 1e-12 * chord {
+            return self.line(chord);
+        }
+
+        let curvature = 2.0 * cross / (chord * chord);
+        let sweep = 2.0 * cross.atan2(dot);
+        self.arc(sweep / curvature, curvature)
+    }
+
+    fn push(mut self, length: f64, element: GeometryElement) -> Self {
+        if length <= 0.0 {
+            return self;
+        }
+
+        let geometry = Geometry {
+            s: types::Length::new(self.s),
+            x: types::Length::new(self.x),
+            y: types::Length::new(self.y),
+            hdg: types::Angle::radians(self.hdg),
+            length: types::Length::new(length),
+            element,
+        };
+
+        let end = geometry.as_segment().to();
+        self.s += length;
+        self.x = end.x;
+        self.y = end.y;
+        self.hdg = geometry.end_hdg().radians;
+
+        self.geometries.push(geometry);
+        self
+    }
+
+    /// Add a road type record starting at `s`
+    pub fn road_type(mut self, s: f64, road_type: RoadTypeKind, speed: Option<Speed>) -> Self {
+        self.road_types.push(RoadType {
+            s,
+            road_type,
+            speed,
+        });
+        self
+    }
+
+    /// Add an elevation record
+    pub fn elevation(mut self, s: f64, a: f64, b: f64, c: f64, d: f64) -> Self {
+        self.elevations.push(Elevation { s, a, b, c, d });
+        self
+    }
+
+    /// Add a superelevation record
+    pub fn superelevation(mut self, s: f64, a: f64, b: f64, c: f64, d: f64) -> Self {
+        self.superelevations.push(Superelevation { s, a, b, c, d });
+        self
+    }
+
+    /// Add a lane offset record
+    pub fn lane_offset(mut self, s: f64, a: f64, b: f64, c: f64, d: f64) -> Self {
+        self.lane_offsets.push(LaneOffset { s, a, b, c, d });
+        self
+    }
+
+    /// Use `section` as the lane layout of the whole road
+    pub fn lanes(self, section: LaneSectionBuilder) -> Self {
+        self.lane_section(0.0, section)
+    }
+
+    /// Add a lane section starting at `s`. Lane sections must be added in
+    /// ascending order.
+    pub fn lane_section(mut self, s: f64, section: LaneSectionBuilder) -> Self {
+        debug_assert!(
+            self.lane_sections.iter().all(|last| last.s < s),
+            "lane sections must be added in ascending order"
+        );
+        self.lane_sections.push(section.build(s));
+        self
+    }
+
+    pub fn predecessor<I: Into<types::ElementId>>(
+        mut self,
+        element_type: ElementType,
+        element_id: I,
+        contact_point: ContactPoint,
+    ) -> Self {
+        self.predecessor = Some(LinkElement {
+            element_type,
+            element_id: element_id.into(),
+            contact_point,
+        });
+        self
+    }
+
+    pub fn successor<I: Into<types::ElementId>>(
+        mut self,
+        element_type: ElementType,
+        element_id: I,
+        contact_point: ContactPoint,
+    ) -> Self {
+        self.successor = Some(LinkElement {
+            element_type,
+            element_id: element_id.into(),
+            contact_point,
+        });
+        self
+    }
+
+    pub fn build(self) -> Road {
+        let plan_view = PlanView {
+            geometries: self.geometries,
+        };
+
+        let link = if self.predecessor.is_some() || self.successor.is_some() {
+            Some(Link {
+                predecessor: self.predecessor,
+                successor: self.successor,
+                neighbors: vec![],
+            })
+        } else {
+            None
+        };
+
+        let lanes = if self.lane_offsets.is_empty() && self.lane_sections.is_empty() {
+            None
+        } else {
+            Some(Lanes {
+                lane_offsets: self.lane_offsets,
+                lane_sections: self.lane_sections,
+            })
+        };
+
+        Road {
+            name: self.name,
+            length: plan_view.sum_length(),
+            id: self.id,
+            junction: self.junction,
+            rule: None,
+            link,
+            road_types: self.road_types,
+            plan_view,
+            elevation_profile: if self.elevations.is_empty() {
+                None
+            } else {
+                Some(ElevationProfile {
+                    elevations: self.elevations,
+                })
+            },
+            lateral_profile: if self.superelevations.is_empty() {
+                None
+            } else {
+                Some(LateralProfile {
+                    superelevations: self.superelevations,
+                    shapes: vec![],
+                })
+            },
+            lanes,
+            objects: None,
+            signals: None,
+        }
+    }
+}
+
+/// Builds a `LaneSection`. Lanes are added from the center outwards and their
+/// ids are assigned accordingly.
+#[derive(Default)]
+pub struct LaneSectionBuilder {
+    center_mark: Option<RoadMark>,
+    left: Vec<LaneBuilder>,
+    right: Vec<LaneBuilder>,
+}
+
+impl LaneSectionBuilder {
+    pub fn new() -> LaneSectionBuilder {
+        Default::default()
+    }
+
+    /// Set the road mark separating the left and right lanes
+    pub fn center_mark(mut self, road_mark_type: RoadMarkType) -> Self {
+        self.center_mark = Some(road_mark(0.0, road_mark_type));
+        self
+    }
+
+    /// Set a fully specified road mark record separating the left and right
+    /// lanes
+    pub fn center_road_mark(mut self, road_mark: RoadMark) -> Self {
+        self.center_mark = Some(road_mark);
+        self
+    }
+
+    /// Add the next lane on the left side of the center lane
+    pub fn left(mut self, lane: LaneBuilder) -> Self {
+        self.left.push(lane);
+        self
+    }
+
+    /// Add the next lane on the right side of the center lane
+    pub fn right(mut self, lane: LaneBuilder) -> Self {
+        self.right.push(lane);
+        self
+    }
+
+    /// Build the lane section starting at `s`
+    pub fn build(self, s: f64) -> LaneSection {
+        // Lanes are listed from left to right, i.e. with descending id
+        let left: Vec<Lane> = self
+            .left
+            .into_iter()
+            .enumerate()
+            .map(|(i, lane)| lane.build(i as i32 + 1))
+            .rev()
+            .collect();
+        let right: Vec<Lane> = self
+            .right
+            .into_iter()
+            .enumerate()
+            .map(|(i, lane)| lane.build(-(i as i32) - 1))
+            .collect();
+
+        let center = Lane {
+            road_marks: self.center_mark.into_iter().collect(),
+            ..Default::default()
+        };
+
+        LaneSection {
+            s,
+            single_side: false,
+            left: if left.is_empty() {
+                None
+            } else {
+                Some(LaneSubSection { lane: left })
+            },
+            center: LaneSubSection { lane: vec![center] },
+            right: if right.is_empty() {
+                None
+            } else {
+                Some(LaneSubSection { lane: right })
+            },
+        }
+    }
+}
+
+/// Builds a single `Lane`. The lane id is assigned by `LaneSectionBuilder`.
+pub struct LaneBuilder {
+    lane_type: LaneType,
+    level: bool,
+    widths: Vec<Width>,
+    road_marks: Vec<RoadMark>,
+    heights: Vec<LaneHeight>,
+    predecessor: Option<i32>,
+    successor: Option<i32>,
+}
+
+impl LaneBuilder {
+    /// A lane of `lane_type` with a constant `width`
+    pub fn new(lane_type: LaneType, width: f64) -> LaneBuilder {
+        LaneBuilder {
+            lane_type,
+            level: false,
+            widths: vec![Width {
+                s_offset: 0.0,
+                a: width,
+                b: 0.0,
+                c: 0.0,
+                d: 0.0,
+            }],
+            road_marks: vec![],
+            heights: vec![],
+            predecessor: None,
+            successor: None,
+        }
+    }
+
+    /// Add a width polynomial starting at `s_offset` relative to the lane
+    /// section. Width records at or after `s_offset` are replaced.
+    pub fn width(mut self, s_offset: f64, a: f64, b: f64, c: f64, d: f64) -> Self {
+        self.widths.retain(|w| w.s_offset < s_offset);
+        self.widths.push(Width {
+            s_offset,
+            a,
+            b,
+            c,
+            d,
+        });
+        self
+    }
+
+    /// Raise the inner and outer border of the lane above the road surface
+    /// from `s_offset` relative to the lane section on
+    pub fn height(mut self, s_offset: f64, inner: f64, outer: f64) -> Self {
+        self.heights.push(LaneHeight {
+            s_offset,
+            inner,
+            outer,
+        });
+        self
+    }
+
+    /// Keep the lane on level, i.e. do not apply superelevation or crossfall
+    pub fn level(mut self, level: bool) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Add a standard road mark of `road_mark_type` along the whole lane
+    pub fn mark(self, road_mark_type: RoadMarkType) -> Self {
+        self.road_mark(road_mark(0.0, road_mark_type))
+    }
+
+    /// Add a fully specified road mark record
+    pub fn road_mark(mut self, road_mark: RoadMark) -> Self {
+        self.road_marks.push(road_mark);
+        self
+    }
+
+    pub fn predecessor(mut self, id: i32) -> Self {
+        self.predecessor = Some(id);
+        self
+    }
+
+    pub fn successor(mut self, id: i32) -> Self {
+        self.successor = Some(id);
+        self
+    }
+
+    fn build(self, id: i32) -> Lane {
+        let link = if self.predecessor.is_some() || self.successor.is_some() {
+            Some(LaneLink {
+                predecessor: self.predecessor.map(|id| LaneLinkElement { id }),
+                successor: self.successor.map(|id| LaneLinkElement { id }),
+            })
+        } else {
+            None
+        };
+
+        Lane {
+            id,
+            lane_type: self.lane_type,
+            level: self.level,
+            direction: None,
+            advisory: None,
+            link,
+            widths: self.widths,
+            road_marks: self.road_marks,
+            heights: self.heights,
+        }
+    }
+}
+
+/// A standard white road mark starting at `s_offset`
+pub fn road_mark(s_offset: f64, road_mark_type: RoadMarkType) -> RoadMark {
+    RoadMark {
+        s_offset,
+        road_mark_type,
+        weight: RoadMarkWeight::Standard,
+        color: RoadMarkColor::Standard,
+        material: None,
+        width: DEFAULT_ROAD_MARK_WIDTH,
+        lane_change: LaneChangeType::Both,
+        height: 0.0,
+    }
+}
+
+/// Link the end of road `pred` to the start of road `succ`.
+///
+/// Besides the road links, lanes with the same id in the last lane section of
+/// `pred` and the first lane section of `succ` are linked to each other unless
+/// they already have a link.
+pub fn link_roads(pred: &mut Road, succ: &mut Road) {
+    pred.link.get_or_insert_with(empty_link).successor = Some(LinkElement {
+        element_type: ElementType::Road,
+        element_id: succ.id.clone().into(),
+        contact_point: ContactPoint::Start,
+    });
+    succ.link.get_or_insert_with(empty_link).predecessor = Some(LinkElement {
+        element_type: ElementType::Road,
+        element_id: pred.id.clone().into(),
+        contact_point: ContactPoint::End,
+    });
+
+    let last = pred
+        .lanes
+        .as_mut()
+        .and_then(|lanes| lanes.lane_sections.last_mut());
+    let first = succ
+        .lanes
+        .as_mut()
+        .and_then(|lanes| lanes.lane_sections.first_mut());
+    if let (Some(last), Some(first)) = (last, first) {
+        for from in side_lanes_mut(last) {
+            if let Some(to) = side_lanes_mut(first).find(|to| to.id == from.id) {
+                let from_link = from.link.get_or_insert_with(Default::default);
+                if from_link.successor.is_none() {
+                    from_link.successor = Some(LaneLinkElement { id: to.id });
+                }
+                let to_link = to.link.get_or_insert_with(Default::default);
+                if to_link.predecessor.is_none() {
+                    to_link.predecessor = Some(LaneLinkElement { id: from.id });
+                }
+            }
+        }
+    }
+}
+
+fn empty_link() -> Link {
+    Link {
+        predecessor: None,
+        successor: None,
+        neighbors: vec![],
+    }
+}
+
+/// The non-center lanes of a lane section
+fn side_lanes_mut(section: &mut LaneSection) -> impl Iterator<Item = &mut Lane> {
+    section
+        .left
+        .iter_mut()
+        .chain(section.right.iter_mut())
+        .flat_map(|sub| sub.lane.iter_mut())
+}
@@ -0,0 +1,183 @@
+//! Binary cache of parsed road networks
+//!
+//! Parsing the XML of a large network and deriving the data needed to
+//! query it takes much longer than reading it back in binary form. `load`
+//! keeps a `Network`, the parsed `Root` together with its lane graph,
+//! spatial index and reference line samples, in a cache file and only
+//! parses the source file if the cache is missing or outdated.
+//!
+//! A cache file starts with a header of
+//!
+//! - the magic bytes `ODRC`
+//! - the u32 format version
+//! - the u64 key, a hash of the source file and the cache options
+//! - the u64 length of the payload
+//! - the u64 checksum of the payload
+//!
+//! followed by the payload, the network serialized with bincode. All
+//! numbers are little endian and the hashes are 64 bit FNV-1a. The cache is
+//! outdated if its key differs from the key of the current source file and
+//! options, and invalid if the format version differs or the checksum does
+//! not match.
+use crate::curvature::{CurvatureProfile, ProfileSample};
+use crate::errors::{CacheError, SampleError};
+use crate::lane_graph::LaneGraph;
+use crate::opendrive::*;
+use crate::parallel;
+use crate::spatial_index::SpatialIndex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"ODRC";
+
+/// Version of the cache format, to be increased with every change of the
+/// layout of `Network` or of the types it contains
+pub const FORMAT_VERSION: u32 = 4;
+
+/// Bytes of the header
+const HEADER_LENGTH: usize = 32;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Debug, Clone)]
+pub struct CacheOptions {
+    /// m step of the reference line samples
+    pub step: f64,
+    /// m edge length of the cells of the spatial index
+    pub cell_size: f64,
+    /// m chord error of the samples the spatial index is built from
+    pub tolerance: f64,
+}
+
+impl Default for CacheOptions {
+    fn default() -> CacheOptions {
+        CacheOptions {
+            step: 1.0,
+            cell_size: 50.0,
+            tolerance: 0.1,
+        }
+    }
+}
+
+/// A parsed road network with the data derived from it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Network {
+    pub root: Root,
+    pub lane_graph: LaneGraph,
+    pub spatial_index: SpatialIndex,
+    /// samples of the reference line of each road, see
+    /// `CurvatureProfile::samples`
+    pub samples: Vec<Vec<ProfileSample>>,
+}
+
+impl Network {
+    /// Fails if the sampling tolerance is not positive and finite
+    pub fn new(root: Root, options: &CacheOptions) -> Result<Network, SampleError> {
+        Ok(Network {
+            lane_graph: LaneGraph::new(&root),
+            spatial_index: SpatialIndex::new(&root, options.cell_size, options.tolerance)?,
+            samples: parallel::map(&root.roads, |road| {
+                CurvatureProfile::new(road).samples(options.step)
+            }),
+            root,
+        })
+    }
+
+    /// The lane containing the inertial position x, y, like
+    /// `Root::lane_position_at` but only considering the roads of the cell
+    /// of the spatial index containing the position
+    pub fn lane_position_at(&self, x: f64, y: f64) -> Option<LanePosition> {
+        self.root
+            .lane_position_in(x, y, self.spatial_index.roads_at(x, y).iter().cloned())
+    }
+
+    /// Write the network as cache with `key`, see `key`
+    pub fn write<W: Write>(&self, key: u64, mut writer: W) -> Result<(), CacheError> {
+        let payload =
+            bincode::serialize(self).map_err(|err| CacheError::Format(err.to_string()))?;
+        let mut header = Vec::with_capacity(HEADER_LENGTH);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        header.extend_from_slice(&key.to_le_bytes());
+        header.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        header.extend_from_slice(&fnv1a(FNV_OFFSET, &payload).to_le_bytes());
+        writer.write_all(&header)?;
+        writer.write_all(&payload)?;
+        Ok(())
+    }
+
+    /// Read a network from a cache written with `key`
+    pub fn read<R: Read>(mut reader: R, key: u64) -> Result<Network, CacheError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        if data.len() < HEADER_LENGTH || &data[..4] != MAGIC {
+            return Err(CacheError::NotACache);
+        }
+        let u64_at = |i: usize| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&data[i..i + 8]);
+            u64::from_le_bytes(bytes)
+        };
+        let mut version = [0; 4];
+        version.copy_from_slice(&data[4..8]);
+        let version = u32::from_le_bytes(version);
+        if version != FORMAT_VERSION {
+            return Err(CacheError::UnsupportedVersion(version));
+        }
+        if u64_at(8) != key {
+            return Err(CacheError::Outdated);
+        }
+        let payload = &data[HEADER_LENGTH..];
+        if u64_at(16) != payload.len() as u64 || u64_at(24) != fnv1a(FNV_OFFSET, payload) {
+            return Err(CacheError::Corrupt);
+        }
+        bincode::deserialize(payload).map_err(|err| CacheError::Format(err.to_string()))
+    }
+}
+
+/// The key of the cache of the source file content `source` with `options`
+pub fn key(source: &[u8], options: &CacheOptions) -> u64 {
+    let mut hash = fnv1a(FNV_OFFSET, source);
+    for value in [options.step, options.cell_size, options.tolerance].iter() {
+        hash = fnv1a(hash, &value.to_bits().to_le_bytes());
+    }
+    hash
+}
+
+/// Continue the FNV-1a hash `hash` with `bytes`
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Load the network of the OpenDRIVE file at `source` from the cache file
+/// at `cache`. If the cache is missing, outdated or invalid, the source
+/// file is parsed and the cache written anew.
+pub fn load<P: AsRef<Path>, Q: AsRef<Path>>(
+    source: P,
+    cache: Q,
+    options: &CacheOptions,
+) -> Result<Network, failure::Error> {
+    let data = fs::read(source)?;
+    let key = key(&data, options);
+    let cache = cache.as_ref();
+    if let Ok(file) = fs::File::open(cache) {
+        if let Ok(network) = Network::read(std::io::BufReader::new(file), key) {
+            return Ok(network);
+        }
+    }
+
+    let network = Network::new(crate::from_reader(&data[..])?, options)?;
+    // write a temporary file first so readers never see a partial cache
+    let mut temporary = cache.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let mut writer = std::io::BufWriter::new(fs::File::create(&temporary)?);
+    network.write(key, &mut writer)?;
+    writer.flush()?;
+    fs::rename(&temporary, cache)?;
+    Ok(network)
+}
@@ -0,0 +1,299 @@
+//! Cropping a road network to a region
+//!
+//! `Root::crop` extracts the part of a network inside a region, e.g. a small
+//! test area of a city map. Roads are kept where their reference line runs
+//! inside the region and cut where it crosses the boundary, at the s
+//! position of the crossing. A road that leaves the region and enters it
+//! again is kept in several parts, the first with the id of the road and the
+//! others with the id suffixed by `_1`, `_2` and so on, skipping ids of other
+//! roads. Objects extending over several parts get new ids in the same way.
+//!
+//! Links, lane links and junction connections to roads or road ends that
+//! were cropped away are removed, as are junctions without any remaining
+//! connection. The header is kept as it is, including its bounds and
+//! geographic reference, so the cropped network projects like the original.
+use crate::edit::{lanes_mut, object_ids, unique_id};
+use crate::opendrive::*;
+use crate::types::{JunctionId, RoadId};
+use std::collections::{HashMap, HashSet};
+
+/// m chord error of the samples of the reference line
+const SAMPLE_TOLERANCE: f64 = 0.01;
+
+/// m largest distance between samples of the reference line, shorter
+/// passages through a corner of the region may be missed
+const MAX_STEP: f64 = 1.0;
+
+/// m precision of the s positions of boundary crossings
+const CROSSING_PRECISION: f64 = 1e-9;
+
+/// m shortest part of a road that is kept
+const MIN_LENGTH: f64 = 1e-6;
+
+/// An area of the inertial x/y-plane
+#[derive(Debug, Clone, PartialEq)]
+pub enum Region {
+    BoundingBox(BoundingBox),
+    /// simple polygon given by its vertices, closed implicitly
+    Polygon(Vec<(f64, f64)>),
+}
+
+impl Region {
+    /// Whether the inertial position x, y is inside the region or on its
+    /// boundary
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        match self {
+            Region::BoundingBox(b) => x >= b.west && x <= b.east && y >= b.south && y <= b.north,
+            Region::Polygon(vertices) => {
+                // even-odd rule
+                let mut inside = false;
+                for (i, &(x0, y0)) in vertices.iter().enumerate() {
+                    let (x1, y1) = vertices[(i + 1) % vertices.len()];
+                    if (y0 > y) != (y1 > y) && x < x0 + (x1 - x0) * (y - y0) / (y1 - y0) {
+                        inside = !inside;
+                    }
+                }
+                inside
+            }
+        }
+    }
+
+    /// The smallest box containing the region
+    pub fn bounding_box(&self) -> BoundingBox {
+        match self {
+            Region::BoundingBox(b) => *b,
+            Region::Polygon(vertices) => vertices.iter().fold(
+                BoundingBox {
+                    west: f64::INFINITY,
+                    south: f64::INFINITY,
+                    east: f64::NEG_INFINITY,
+                    north: f64::NEG_INFINITY,
+                },
+                |b, &(x, y)| BoundingBox {
+                    west: b.west.min(x),
+                    south: b.south.min(y),
+                    east: b.east.max(x),
+                    north: b.north.max(y),
+                },
+            ),
+        }
+    }
+}
+
+/// The s ranges along which the reference line of `road` is inside `region`
+fn inside_ranges(road: &Road, region: &Region) -> Vec<(f64, f64)> {
+    let length = road.length.get();
+    let inside = |s: f64| {
+        let (x, y) = road.position_at(s, 0.0);
+        region.contains(x, y)
+    };
+    let mut samples = road
+        .plan_view
+        .sample_s(SAMPLE_TOLERANCE)
+        .expect("positive sample tolerance");
+    samples.push(0.0);
+    samples.push(length);
+    samples.retain(|&s| s >= 0.0 && s <= length);
+    samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    samples.dedup();
+    let mut stations = vec![samples[0]];
+    for pair in samples.windows(2) {
+        let steps = ((pair[1] - pair[0]) / MAX_STEP).ceil().max(1.0);
+        stations
+            .extend((1..=steps as usize).map(|i| pair[0] + (pair[1] - pair[0]) * i as f64 / steps));
+    }
+
+    let mut ranges = Vec::new();
+    let mut start = if inside(stations[0]) { Some(0.0) } else { None };
+    for pair in stations.windows(2) {
+        let (s0, s1) = (pair[0], pair[1]);
+        let was_inside = start.is_some();
+        if inside(s1) == was_inside {
+            continue;
+        }
+        // bisect the crossing of the boundary
+        let (mut a, mut b) = (s0, s1);
+        while b - a > CROSSING_PRECISION {
+            let middle = (a + b) / 2.0;
+            if inside(middle) == was_inside {
+                a = middle;
+            } else {
+                b = middle;
+            }
+        }
+        match start.take() {
+            Some(start) => ranges.push((start, a)),
+            None => start = Some(b),
+        }
+    }
+    if let Some(start) = start {
+        ranges.push((start, length));
+    }
+    ranges.retain(|&(start, end)| end - start > MIN_LENGTH);
+    ranges
+}
+
+impl Root {
+    /// The part of the network inside `region`, see the module documentation
+    pub fn crop(&self, region: &Region) -> Root {
+        let bounds = region.bounding_box();
+        let mut roads = Vec::new();
+        // ids of the parts that keep the start and the end of the roads
+        let mut starts: HashMap<RoadId, RoadId> = HashMap::new();
+        let mut ends: HashMap<RoadId, RoadId> = HashMap::new();
+        let mut taken_roads: HashSet<String> =
+            self.roads.iter().map(|road| road.id.to_string()).collect();
+        let mut taken_objects = object_ids(self.roads.iter());
+        for road in self.roads.iter() {
+            let bounding_box = road
+                .bounding_box(SAMPLE_TOLERANCE)
+                .expect("positive sample tolerance");
+            let ranges = match bounding_box {
+                Some(b) if b.intersects(&bounds) => inside_ranges(road, region),
+                _ => continue,
+            };
+            let length = road.length.get();
+            // ids of the objects in the previous parts
+            let mut seen = HashSet::new();
+            for (i, &(start, end)) in ranges.iter().enumerate() {
+                let mut part = road.trimmed(start, end).expect("range within the road");
+                if i > 0 {
+                    let id = unique_id(&road.id.to_string(), |id| taken_roads.contains(id));
+                    taken_roads.insert(id.clone());
+                    part.id = RoadId::from(id);
+                }
+                for object in part.objects.iter_mut().flat_map(|o| o.objects.iter_mut()) {
+                    if !seen.insert(object.id.clone()) {
+                        object.id = unique_id(&object.id, |id| taken_objects.contains(id));
+                        taken_objects.insert(object.id.clone());
+                    }
+                }
+                if start == 0.0 {
+                    starts.insert(road.id.clone(), part.id.clone());
+                }
+                if end == length {
+                    ends.insert(road.id.clone(), part.id.clone());
+                }
+                roads.push(part);
+            }
+        }
+        let part_at = |id: &RoadId, contact_point: &ContactPoint| match contact_point {
+            ContactPoint::Start => starts.get(id).cloned(),
+            ContactPoint::End => ends.get(id).cloned(),
+        };
+
+        let mut junctions = Vec::new();
+        for junction in self.junctions.iter() {
+            let connections: Vec<Connection> = junction
+                .connections
+                .iter()
+                .filter_map(|connection| {
+                    // the end of the incoming road linked to the junction
+                    let incoming = self
+                        .roads
+                        .iter()
+                        .find(|road| road.id == connection.incoming_road)?;
+                    let link = incoming.link.as_ref()?;
+                    let links_to_junction = |element: &Option<LinkElement>| {
+                        element.iter().any(|element| {
+                            element.element_type == ElementType::Junction
+                                && element.element_id == junction.id
+                        })
+                    };
+                    let incoming = if links_to_junction(&link.successor) {
+                        ends.get(&incoming.id)?
+                    } else if links_to_junction(&link.predecessor) {
+                        starts.get(&incoming.id)?
+                    } else {
+                        return None;
+                    };
+                    let mut connection = Connection {
+                        incoming_road: incoming.clone(),
+                        ..connection.clone()
+                    };
+                    let contact_point = connection.contact_point.clone();
+                    let connected = connection.connected_road_mut()?;
+                    *connected = part_at(connected, &contact_point)?;
+                    Some(connection)
+                })
+                .collect();
+            if !connections.is_empty() {
+                junctions.push(Junction {
+                    connections,
+                    ..junction.clone()
+                });
+            }
+        }
+
+        let junction_ids: HashSet<JunctionId> = junctions.iter().map(|j| j.id.clone()).collect();
+        let road_ids: HashSet<RoadId> = roads.iter().map(|road| road.id.clone()).collect();
+        // redirect a link to the part of the road it links to, or remove it
+        let retarget = |element: &mut Option<LinkElement>| {
+            let target = match element {
+                Some(element) => match element.element_type {
+                    ElementType::Road => {
+                        part_at(&element.element_id.clone().into(), &element.contact_point)
+                            .map(Into::into)
+                    }
+                    ElementType::Junction => Some(element.element_id.clone())
+                        .filter(|id| junction_ids.contains(&id.clone().into())),
+                },
+                None => return false,
+            };
+            match target {
+                Some(target) => {
+                    element.as_mut().unwrap().element_id = target;
+                    false
+                }
+                None => {
+                    *element = None;
+                    true
+                }
+            }
+        };
+        for road in roads.iter_mut() {
+            if road
+                .junction
+                .iter()
+                .any(|junction| !junction_ids.contains(junction))
+            {
+                road.junction = None;
+            }
+            let (start_lost, end_lost) = match road.link.as_mut() {
+                Some(link) => {
+                    link.neighbors
+                        .retain(|neighbor| road_ids.contains(&neighbor.element_id));
+                    (
+                        retarget(&mut link.predecessor),
+                        retarget(&mut link.successor),
+                    )
+                }
+                None => (false, false),
+            };
+            let sections = match road.lanes.as_mut() {
+                Some(lanes) => &mut lanes.lane_sections,
+                None => continue,
+            };
+            if let (true, Some(section)) = (start_lost, sections.first_mut()) {
+                for lane in lanes_mut(section) {
+                    if let Some(link) = lane.link.as_mut() {
+                        link.predecessor = None;
+                    }
+                }
+            }
+            if let (true, Some(section)) = (end_lost, sections.last_mut()) {
+                for lane in lanes_mut(section) {
+                    if let Some(link) = lane.link.as_mut() {
+                        link.successor = None;
+                    }
+                }
+            }
+        }
+
+        Root {
+            header: self.header.clone(),
+            roads,
+            junctions,
+        }
+    }
+}
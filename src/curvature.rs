@@ -0,0 +1,349 @@
+//! Curvature and heading profiles of road reference lines
+//!
+//! The profile of a road has a piece per geometry element: lines and arcs
+//! have a constant curvature, spirals a curvature linear in s, and the
+//! curvature of poly3 and paramPoly3 elements is evaluated from the
+//! polynomials. The analysis of a profile finds the maximum curvature and
+//! minimum radius, jumps of the curvature and heading between elements and
+//! estimates the design speed from the curvature, the superelevation and a
+//! side friction factor, `v = sqrt(g * r * (e + f))`.
+use crate::opendrive::*;
+use crate::parallel;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
+
+/// m/s² standard gravity
+const GRAVITY: f64 = 9.806_65;
+
+/// Number of samples the curvature of a polynomial element is examined at
+const POLYNOMIAL_SAMPLES: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct CurvatureOptions {
+    /// m step of the samples along the reference line, must be positive
+    pub step: f64,
+    /// 1/m curvature jumps up to this size are continuous
+    pub curvature_tolerance: f64,
+    /// rad heading jumps up to this size are continuous
+    pub heading_tolerance: f64,
+    /// side friction factor of the design speed
+    pub side_friction: f64,
+}
+
+impl Default for CurvatureOptions {
+    fn default() -> CurvatureOptions {
+        CurvatureOptions {
+            step: 1.0,
+            curvature_tolerance: 1e-4,
+            heading_tolerance: 1e-3,
+            side_friction: 0.15,
+        }
+    }
+}
+
+/// Curvature of a geometry element as a function of s
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CurvatureFunction {
+    /// line and arc
+    Constant(f64),
+    /// spiral, changing linearly from `start` to `end`
+    Linear { start: f64, end: f64 },
+    /// poly3 and paramPoly3
+    Polynomial,
+}
+
+/// The curvature along one geometry element
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurvaturePiece {
+    /// m start position (s-coordinate)
+    pub s: f64,
+    /// m
+    pub length: f64,
+    pub function: CurvatureFunction,
+    /// 1/m curvature at the start
+    pub start: f64,
+    /// 1/m curvature at the end
+    pub end: f64,
+    /// rad heading at the start
+    pub start_hdg: f64,
+    /// rad heading at the end
+    pub end_hdg: f64,
+}
+
+/// Piecewise curvature of the reference line of a road
+#[derive(Debug, Clone)]
+pub struct CurvatureProfile<'a> {
+    road: &'a Road,
+    pub pieces: Vec<CurvaturePiece>,
+}
+
+/// A sample of the reference line
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProfileSample {
+    pub s: f64,
+    pub x: f64,
+    pub y: f64,
+    /// rad
+    pub hdg: f64,
+    /// 1/m
+    pub kappa: f64,
+    /// m elevation
+    pub z: f64,
+}
+
+/// Quantity that jumps at a discontinuity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quantity {
+    Curvature,
+    Heading,
+}
+
+/// A jump between two geometry elements
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Discontinuity {
+    pub s: f64,
+    pub quantity: Quantity,
+    /// value at the end of the preceding element
+    pub before: f64,
+    /// value at the start of the following element
+    pub after: f64,
+}
+
+/// Curve characteristics of a road
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurvatureAnalysis {
+    /// 1/m maximum absolute curvature
+    pub max_curvature: f64,
+    /// m position of the maximum absolute curvature
+    pub max_curvature_s: f64,
+    /// m minimum radius, `None` for straight roads
+    pub min_radius: Option<f64>,
+    pub discontinuities: Vec<Discontinuity>,
+    /// m/s lowest design speed along the road, `None` for straight roads
+    pub design_speed: Option<f64>,
+    /// m position of the lowest design speed
+    pub design_speed_s: f64,
+}
+
+impl<'a> CurvatureProfile<'a> {
+    pub fn new(road: &'a Road) -> CurvatureProfile<'a> {
+        let pieces = road
+            .plan_view
+            .geometries
+            .iter()
+            .map(|g| {
+                let length = g.length.get();
+                let function = match g.element {
+                    GeometryElement::Line => CurvatureFunction::Constant(0.0),
+                    GeometryElement::Arc { curvature } => CurvatureFunction::Constant(curvature),
+                    GeometryElement::Spiral {
+                        curv_start,
+                        curv_end,
+                    } => CurvatureFunction::Linear {
+                        start: curv_start,
+                        end: curv_end,
+                    },
+                    GeometryElement::Poly3 { .. } | GeometryElement::ParamPoly3 { .. } => {
+                        CurvatureFunction::Polynomial
+                    }
+                };
+                CurvaturePiece {
+                    s: g.s.get(),
+                    length,
+                    function,
+                    start: g.curvature_at(0.0),
+                    end: g.curvature_at(length),
+                    start_hdg: g.hdg.radians,
+                    end_hdg: g.pose_at(length).2,
+                }
+            })
+            .collect();
+        CurvatureProfile { road, pieces }
+    }
+
+    /// Signed curvature at s
+    pub fn curvature_at(&self, s: f64) -> f64 {
+        self.road
+            .plan_view
+            .geometry_at(s)
+            .map_or(0.0, |g| g.curvature_at(s - g.s.get()))
+    }
+
+    /// Samples every `step` along each element, including the start of every
+    /// element and the end of the road
+    pub fn samples(&self, step: f64) -> Vec<ProfileSample> {
+        let mut stations = Vec::new();
+        for piece in self.pieces.iter() {
+            let n = ((piece.length / step).ceil() as usize).max(1);
+            stations.extend((0..n).map(|i| piece.s + piece.length * i as f64 / n as f64));
+        }
+        if let Some(last) = self.pieces.last() {
+            stations.push(last.s + last.length);
+        }
+        stations
+            .into_iter()
+            .map(|s| {
+                let (x, y, hdg) = self.road.plan_view.pose_at(s);
+                ProfileSample {
+                    s,
+                    x,
+                    y,
+                    hdg,
+                    kappa: self.curvature_at(s),
+                    z: self.road.elevation_at(s),
+                }
+            })
+            .collect()
+    }
+
+    /// Jumps of the curvature or heading between consecutive elements
+    pub fn discontinuities(&self, options: &CurvatureOptions) -> Vec<Discontinuity> {
+        let mut discontinuities = Vec::new();
+        for pair in self.pieces.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            let heading_jump = (b.start_hdg - a.end_hdg + std::f64::consts::PI)
+                .rem_euclid(2.0 * std::f64::consts::PI)
+                - std::f64::consts::PI;
+            if heading_jump.abs() > options.heading_tolerance {
+                discontinuities.push(Discontinuity {
+                    s: b.s,
+                    quantity: Quantity::Heading,
+                    before: a.end_hdg,
+                    after: b.start_hdg,
+                });
+            }
+            if (b.start - a.end).abs() > options.curvature_tolerance {
+                discontinuities.push(Discontinuity {
+                    s: b.s,
+                    quantity: Quantity::Curvature,
+                    before: a.end,
+                    after: b.start,
+                });
+            }
+        }
+        discontinuities
+    }
+
+    /// Maximum absolute curvature and its position
+    pub fn max_curvature(&self) -> (f64, f64) {
+        let mut max = (0.0, self.pieces.first().map_or(0.0, |piece| piece.s));
+        for piece in self.pieces.iter() {
+            let candidates = match piece.function {
+                CurvatureFunction::Constant(_) | CurvatureFunction::Linear { .. } => {
+                    vec![(piece.start, piece.s), (piece.end, piece.s + piece.length)]
+                }
+                CurvatureFunction::Polynomial => (0..=POLYNOMIAL_SAMPLES)
+                    .map(|i| {
+                        let s = piece.s + piece.length * i as f64 / POLYNOMIAL_SAMPLES as f64;
+                        (self.curvature_at(s), s)
+                    })
+                    .collect(),
+            };
+            for (curvature, s) in candidates {
+                if curvature.abs() > max.0 {
+                    max = (curvature.abs(), s);
+                }
+            }
+        }
+        max
+    }
+
+    /// Lowest design speed and its position from samples every `step`,
+    /// `None` for straight roads
+    pub fn design_speed(&self, options: &CurvatureOptions) -> Option<(f64, f64)> {
+        self.samples(options.step)
+            .into_iter()
+            .filter(|sample| sample.kappa != 0.0)
+            .map(|sample| {
+                // superelevation towards the inside of the curve helps
+                let e = self.road.superelevation_at(sample.s).tan() * -sample.kappa.signum();
+                let speed =
+                    (GRAVITY * (e + options.side_friction).max(0.0) / sample.kappa.abs()).sqrt();
+                (speed, sample.s)
+            })
+            .fold(None, |min: Option<(f64, f64)>, v| match min {
+                Some(min) if min.0 <= v.0 => Some(min),
+                _ => Some(v),
+            })
+    }
+
+    pub fn analyze(&self, options: &CurvatureOptions) -> CurvatureAnalysis {
+        let (max_curvature, max_curvature_s) = self.max_curvature();
+        let (design_speed, design_speed_s) = match self.design_speed(options) {
+            Some((speed, s)) => (Some(speed), s),
+            None => (None, 0.0),
+        };
+        CurvatureAnalysis {
+            max_curvature,
+            max_curvature_s,
+            min_radius: if max_curvature > 0.0 {
+                Some(1.0 / max_curvature)
+            } else {
+                None
+            },
+            discontinuities: self.discontinuities(options),
+            design_speed,
+            design_speed_s,
+        }
+    }
+}
+
+impl fmt::Display for CurvatureAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "max curvature {:.6} 1/m at s {:.3}",
+            self.max_curvature, self.max_curvature_s
+        )?;
+        match self.min_radius {
+            Some(radius) => writeln!(f, "min radius {:.3} m", radius)?,
+            None => writeln!(f, "min radius -")?,
+        }
+        match self.design_speed {
+            Some(speed) => writeln!(
+                f,
+                "design speed {:.1} km/h at s {:.3}",
+                speed * 3.6,
+                self.design_speed_s
+            )?,
+            None => writeln!(f, "design speed -")?,
+        }
+        for d in self.discontinuities.iter() {
+            let quantity = match d.quantity {
+                Quantity::Curvature => "curvature",
+                Quantity::Heading => "heading",
+            };
+            writeln!(
+                f,
+                "{} jump at s {:.3}: {:.6} -> {:.6}",
+                quantity, d.s, d.before, d.after
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Write the samples of the reference lines of `roads` as CSV with the
+/// columns road, s, x, y, hdg, kappa and z
+pub fn write_csv<'a, W: Write>(
+    roads: impl IntoIterator<Item = &'a Road>,
+    options: &CurvatureOptions,
+    mut writer: W,
+) -> std::io::Result<()> {
+    writeln!(writer, "road,s,x,y,hdg,kappa,z")?;
+    let roads: Vec<&Road> = roads.into_iter().collect();
+    let samples = parallel::map(&roads, |road| {
+        CurvatureProfile::new(road).samples(options.step)
+    });
+    for (road, samples) in roads.into_iter().zip(samples) {
+        for sample in samples {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                road.id, sample.s, sample.x, sample.y, sample.hdg, sample.kappa, sample.z
+            )?;
+        }
+    }
+    Ok(())
+}
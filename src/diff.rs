@@ -0,0 +1,347 @@
+//! Structural and geometric comparison of two road networks
+//!
+//! Roads and junctions are matched by id. Records of matched elements are
+//! compared field by field through their serialized form, so every attribute
+//! of the data model is covered and reported with its OpenDRIVE name, e.g.
+//! `planView.geometry[1].length`. Numbers are equal if they differ by no more
+//! than the tolerance. For changed roads the maximum lateral distance between
+//! the two reference lines and between the lane boundaries is reported as
+//! well.
+use crate::errors::SampleError;
+use crate::opendrive::*;
+use crate::types::{JunctionId, RoadId};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    /// maximum difference of two numbers considered equal
+    pub tolerance: f64,
+    /// m chord error of the samples the geometric deviation is computed from
+    pub sample_tolerance: f64,
+}
+
+impl Default for DiffOptions {
+    fn default() -> DiffOptions {
+        DiffOptions {
+            tolerance: 1e-6,
+            sample_tolerance: 0.01,
+        }
+    }
+}
+
+/// Differences between two road networks
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diff {
+    pub header: Vec<FieldChange>,
+    /// by ascending road id
+    pub roads: Vec<Change<RoadId, RoadChanges>>,
+    /// by ascending junction id
+    pub junctions: Vec<Change<JunctionId, Vec<FieldChange>>>,
+}
+
+impl Diff {
+    /// Both networks are equal within the tolerance
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty() && self.roads.is_empty() && self.junctions.is_empty()
+    }
+}
+
+/// Change of an element matched by id
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<I, T> {
+    Added(I),
+    Removed(I),
+    Changed(I, T),
+}
+
+/// Changes of a road present in both networks
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoadChanges {
+    pub fields: Vec<FieldChange>,
+    pub deviation: Deviation,
+}
+
+/// Change of a single attribute, `None` if it is absent in one of the
+/// networks
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    /// path of the attribute with the names of the OpenDRIVE records, e.g.
+    /// `lanes.laneSection[0].right.lane[1].width[0].a`
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// Geometric deviation of two versions of a road
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Deviation {
+    /// m maximum distance between the reference lines
+    pub reference_line: f64,
+    /// m maximum distance between the borders of lanes with the same id
+    pub lane_boundaries: f64,
+}
+
+/// Compare road network `b` against `a`. Fails if the sample tolerance is
+/// not positive and finite.
+pub fn diff(a: &Root, b: &Root, options: &DiffOptions) -> Result<Diff, SampleError> {
+    let roads = matched(
+        a.roads.iter().map(|road| (&road.id, road)),
+        b.roads.iter().map(|road| (&road.id, road)),
+    )
+    .filter_map(|change| match change {
+        Change::Added(id) => Some(Ok(Change::Added(id))),
+        Change::Removed(id) => Some(Ok(Change::Removed(id))),
+        Change::Changed(id, (old, new)) => {
+            let fields = field_changes(old, new, options.tolerance);
+            if fields.is_empty() {
+                return None;
+            }
+            Some(
+                deviation(old, new, options.sample_tolerance)
+                    .map(|deviation| Change::Changed(id, RoadChanges { fields, deviation })),
+            )
+        }
+    })
+    .collect::<Result<_, _>>()?;
+
+    let junctions = matched(
+        a.junctions.iter().map(|junction| (&junction.id, junction)),
+        b.junctions.iter().map(|junction| (&junction.id, junction)),
+    )
+    .filter_map(|change| match change {
+        Change::Added(id) => Some(Change::Added(id)),
+        Change::Removed(id) => Some(Change::Removed(id)),
+        Change::Changed(id, (old, new)) => {
+            let fields = field_changes(old, new, options.tolerance);
+            if fields.is_empty() {
+                None
+            } else {
+                Some(Change::Changed(id, fields))
+            }
+        }
+    })
+    .collect();
+
+    // a missing date is no change of the date
+    let header = if a.header.has_date() && b.header.has_date() {
+        field_changes(&a.header, &b.header, options.tolerance)
+    } else {
+        let b = Header {
+            date: a.header.date,
+            ..b.header.clone()
+        };
+        field_changes(&a.header, &b, options.tolerance)
+    };
+
+    Ok(Diff {
+        header,
+        roads,
+        junctions,
+    })
+}
+
+/// Pair the elements of both networks by id. Of duplicate ids only the
+/// first element is compared.
+fn matched<'a, I: Ord + Clone + 'a, T: 'a>(
+    a: impl Iterator<Item = (&'a I, &'a T)>,
+    b: impl Iterator<Item = (&'a I, &'a T)>,
+) -> impl Iterator<Item = Change<I, (&'a T, &'a T)>> {
+    let mut pairs: BTreeMap<&I, (Option<&T>, Option<&T>)> = BTreeMap::new();
+    for (id, element) in a {
+        pairs.entry(id).or_default().0.get_or_insert(element);
+    }
+    for (id, element) in b {
+        pairs.entry(id).or_default().1.get_or_insert(element);
+    }
+    pairs.into_iter().map(|(id, pair)| {
+        let id = id.clone();
+        match pair {
+            (Some(old), Some(new)) => Change::Changed(id, (old, new)),
+            (Some(_), None) => Change::Removed(id),
+            (None, _) => Change::Added(id),
+        }
+    })
+}
+
+fn field_changes<T: Serialize>(a: &T, b: &T, tolerance: f64) -> Vec<FieldChange> {
+    let a = serde_json::to_value(a).expect("records serialize to JSON");
+    let b = serde_json::to_value(b).expect("records serialize to JSON");
+    let mut changes = Vec::new();
+    compare("", &a, &b, tolerance, &mut changes);
+    changes
+}
+
+fn compare(path: &str, a: &Value, b: &Value, tolerance: f64, changes: &mut Vec<FieldChange>) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            let keys: BTreeSet<_> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                // the element name of enum records like `<line/>` carries no
+                // information of its own
+                let path = match (key.as_str(), path) {
+                    ("$value", _) => path.to_string(),
+                    (_, "") => key.to_string(),
+                    _ => format!("{}.{}", path, key),
+                };
+                match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) => compare(&path, a, b, tolerance, changes),
+                    (old, new) => changes.push(FieldChange {
+                        path,
+                        old: old.cloned(),
+                        new: new.cloned(),
+                    }),
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                let path = format!("{}[{}]", path, i);
+                match (a.get(i), b.get(i)) {
+                    (Some(a), Some(b)) => compare(&path, a, b, tolerance, changes),
+                    (old, new) => changes.push(FieldChange {
+                        path,
+                        old: old.cloned(),
+                        new: new.cloned(),
+                    }),
+                }
+            }
+        }
+        _ => {
+            let field = path.rsplit('.').next().unwrap_or(path);
+            if !same_value(field, a, b, tolerance) {
+                changes.push(FieldChange {
+                    path: path.to_string(),
+                    old: Some(a.clone()),
+                    new: Some(b.clone()),
+                });
+            }
+        }
+    }
+}
+
+/// Fields holding numbers serialized as strings, see `parse_util::angle`
+const NUMERIC_STRING_FIELDS: &[&str] = &["hdg"];
+
+/// Numbers and the numeric strings of `NUMERIC_STRING_FIELDS` are compared
+/// with the tolerance, everything else exactly, so ids "007" and "7" differ
+fn same_value(field: &str, a: &Value, b: &Value, tolerance: f64) -> bool {
+    let number = |value: &Value| match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) if NUMERIC_STRING_FIELDS.contains(&field) => {
+            string.trim().parse::<f64>().ok()
+        }
+        _ => None,
+    };
+    match (number(a), number(b)) {
+        (Some(x), Some(y)) => (x - y).abs() <= tolerance,
+        _ => a == b,
+    }
+}
+
+/// Symmetric maximum distance between the reference lines and lane borders
+/// of two roads
+fn deviation(a: &Road, b: &Road, tolerance: f64) -> Result<Deviation, SampleError> {
+    let forward = directed_deviation(a, b, tolerance)?;
+    let backward = directed_deviation(b, a, tolerance)?;
+    Ok(Deviation {
+        reference_line: forward.reference_line.max(backward.reference_line),
+        lane_boundaries: forward.lane_boundaries.max(backward.lane_boundaries),
+    })
+}
+
+/// Maximum distance of the samples of road `a` to road `b`
+fn directed_deviation(a: &Road, b: &Road, tolerance: f64) -> Result<Deviation, SampleError> {
+    let mut deviation = Deviation::default();
+    let distance = |(x0, y0): (f64, f64), (x1, y1): (f64, f64)| (x1 - x0).hypot(y1 - y0);
+    for s in a.sample_s(0.0, a.length.get(), tolerance)? {
+        let point = a.position_at(s, 0.0);
+        let (sb, _) = b.project(point.0, point.1);
+        deviation.reference_line = deviation
+            .reference_line
+            .max(distance(point, b.position_at(sb, 0.0)));
+
+        let index = match a.lane_section_index_at(s) {
+            Some(index) => index,
+            None => continue,
+        };
+        for border in a.lane_borders(index, s) {
+            let point = a.position_at(s, border.outer);
+            let (sb, _) = b.project(point.0, point.1);
+            let index = match b.lane_section_index_at(sb) {
+                Some(index) => index,
+                None => continue,
+            };
+            // lanes missing in one of the roads are reported as field changes
+            if let Some(other) = b
+                .lane_borders(index, sb)
+                .into_iter()
+                .find(|other| other.id == border.id)
+            {
+                deviation.lane_boundaries = deviation
+                    .lane_boundaries
+                    .max(distance(point, b.position_at(sb, other.outer)));
+            }
+        }
+    }
+    Ok(deviation)
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = |value: &Option<Value>| match value {
+            Some(value) => value.to_string(),
+            None => "(none)".to_string(),
+        };
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.path,
+            value(&self.old),
+            value(&self.new)
+        )
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.header.is_empty() {
+            writeln!(f, "header changed")?;
+            for field in self.header.iter() {
+                writeln!(f, "  {}", field)?;
+            }
+        }
+        for change in self.roads.iter() {
+            match change {
+                Change::Added(id) => writeln!(f, "road {} added", id)?,
+                Change::Removed(id) => writeln!(f, "road {} removed", id)?,
+                Change::Changed(id, changes) => {
+                    writeln!(f, "road {} changed", id)?;
+                    for field in changes.fields.iter() {
+                        writeln!(f, "  {}", field)?;
+                    }
+                    writeln!(
+                        f,
+                        "  deviation: reference line {:.3} m, lane boundaries {:.3} m",
+                        changes.deviation.reference_line, changes.deviation.lane_boundaries
+                    )?;
+                }
+            }
+        }
+        for change in self.junctions.iter() {
+            match change {
+                Change::Added(id) => writeln!(f, "junction {} added", id)?,
+                Change::Removed(id) => writeln!(f, "junction {} removed", id)?,
+                Change::Changed(id, fields) => {
+                    writeln!(f, "junction {} changed", id)?;
+                    for field in fields.iter() {
+                        writeln!(f, "  {}", field)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
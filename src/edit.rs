@@ -0,0 +1,965 @@
+//! Editing operations on roads
+//!
+//! Roads can be split at an s position, trimmed to an s range, merged with
+//! the road linked to their end and reversed. All records along the road are
+//! carried over: the polynomials of elevation, superelevation, lane offset
+//! and lane width records valid at a cut are re-based to start there, road
+//! marks, heights and road types valid at a cut start there as well, and
+//! objects and signals move with their s co-ordinate.
+//!
+//! The `Root` variants also update the links and junction connections of
+//! the other roads of the network that refer to the edited road.
+use crate::arc_length::{self, ArcLengthCurve};
+use crate::errors::EditError;
+use crate::opendrive::*;
+use crate::types::{self, JunctionId, RoadId};
+use std::collections::HashSet;
+use std::f64::consts::PI;
+
+/// m distance below which s positions are considered equal
+const S_TOLERANCE: f64 = 1e-9;
+
+/// A record valid from its start position until the next record
+trait Record: Clone {
+    fn start(&self) -> f64;
+
+    fn set_start(&mut self, s: f64);
+
+    /// Move the start of the record by ds, keeping the values along s
+    fn rebase(&mut self, _ds: f64) {}
+
+    /// Reverse the direction of the values along a record of `length`
+    fn reverse(&mut self, _length: f64) {}
+
+    /// A record starting at s with the value the absence of records implies
+    fn neutral(s: f64) -> Self;
+}
+
+/// Records of a cubic polynomial a + b*ds + c*ds² + d*ds³
+macro_rules! cubic_record {
+    ($name:ident, $s:ident) => {
+        impl Record for $name {
+            fn start(&self) -> f64 {
+                self.$s
+            }
+
+            fn set_start(&mut self, s: f64) {
+                self.$s = s;
+            }
+
+            fn rebase(&mut self, ds: f64) {
+                let [a, b, c, d] = arc_length::shift([self.a, self.b, self.c, self.d], ds, 1.0);
+                *self = $name {
+                    a,
+                    b,
+                    c,
+                    d,
+                    ..self.clone()
+                };
+            }
+
+            fn reverse(&mut self, length: f64) {
+                let [a, b, c, d] =
+                    arc_length::shift([self.a, self.b, self.c, self.d], length, -1.0);
+                *self = $name {
+                    a,
+                    b,
+                    c,
+                    d,
+                    ..self.clone()
+                };
+            }
+
+            fn neutral(s: f64) -> $name {
+                $name {
+                    $s: s,
+                    a: 0.0,
+                    b: 0.0,
+                    c: 0.0,
+                    d: 0.0,
+                }
+            }
+        }
+    };
+}
+
+cubic_record!(Elevation, s);
+cubic_record!(Superelevation, s);
+cubic_record!(LaneOffset, s);
+cubic_record!(Width, s_offset);
+
+impl Record for RoadType {
+    fn start(&self) -> f64 {
+        self.s
+    }
+
+    fn set_start(&mut self, s: f64) {
+        self.s = s;
+    }
+
+    fn neutral(s: f64) -> RoadType {
+        RoadType {
+            s,
+            road_type: RoadTypeKind::Unknown,
+            speed: None,
+        }
+    }
+}
+
+impl Record for RoadMark {
+    fn start(&self) -> f64 {
+        self.s_offset
+    }
+
+    fn set_start(&mut self, s: f64) {
+        self.s_offset = s;
+    }
+
+    fn neutral(s: f64) -> RoadMark {
+        RoadMark {
+            s_offset: s,
+            road_mark_type: RoadMarkType::None,
+            weight: RoadMarkWeight::Standard,
+            color: RoadMarkColor::Standard,
+            material: None,
+            width: 0.0,
+            lane_change: Default::default(),
+            height: 0.0,
+        }
+    }
+}
+
+impl Record for LaneHeight {
+    fn start(&self) -> f64 {
+        self.s_offset
+    }
+
+    fn set_start(&mut self, s: f64) {
+        self.s_offset = s;
+    }
+
+    fn neutral(s: f64) -> LaneHeight {
+        LaneHeight {
+            s_offset: s,
+            inner: 0.0,
+            outer: 0.0,
+        }
+    }
+}
+
+/// Shape records at the same s form a cross section, the cross sections are
+/// interpolated linearly along s
+impl Record for Shape {
+    fn start(&self) -> f64 {
+        self.s
+    }
+
+    fn set_start(&mut self, s: f64) {
+        self.s = s;
+    }
+
+    fn neutral(s: f64) -> Shape {
+        Shape {
+            s,
+            t: 0.0,
+            a: 0.0,
+            b: 0.0,
+            c: 0.0,
+            d: 0.0,
+        }
+    }
+}
+
+/// The records valid between `start` and `end`, relative to `start`. All
+/// records starting at the last start position at or before `start` are
+/// kept, so cross sections of several records stay complete.
+fn slice_records<T: Record>(records: &[T], start: f64, end: f64) -> Vec<T> {
+    let first = records
+        .iter()
+        .map(Record::start)
+        .filter(|&s| s <= start + S_TOLERANCE)
+        .fold(None, |first: Option<f64>, s| {
+            Some(first.map_or(s, |first| first.max(s)))
+        });
+    records
+        .iter()
+        .filter(|r| first.iter().all(|&first| r.start() >= first) && r.start() < end - S_TOLERANCE)
+        .map(|r| {
+            let mut r = r.clone();
+            let s = r.start();
+            if s < start {
+                r.rebase(start - s);
+            }
+            r.set_start((s - start).max(0.0));
+            r
+        })
+        .collect()
+}
+
+/// The records of a range of `length` in the opposite direction
+fn reverse_records<T: Record>(records: &[T], length: f64) -> Vec<T> {
+    let mut reversed = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        let end = records.get(i + 1).map_or(length, Record::start).min(length);
+        // records replaced at the same position are never valid
+        if end - record.start() <= S_TOLERANCE && !(i + 1 == records.len() && reversed.is_empty()) {
+            continue;
+        }
+        let mut record = record.clone();
+        record.reverse(end - record.start());
+        record.set_start(length - end);
+        reversed.push(record);
+    }
+    reversed.reverse();
+    reversed
+}
+
+/// The records of `first` followed by those of `second` starting at
+/// `offset`
+fn append_records<T: Record>(first: &[T], second: &[T], offset: f64) -> Vec<T> {
+    let mut records = first.to_vec();
+    if !records.is_empty() && second.first().iter().all(|r| r.start() > S_TOLERANCE) {
+        records.push(T::neutral(offset));
+    }
+    records.extend(second.iter().map(|r| {
+        let mut r = r.clone();
+        r.set_start(r.start() + offset);
+        r
+    }));
+    records
+}
+
+/// The lanes of a lane section, left, center and right
+pub(crate) fn lanes_mut(section: &mut LaneSection) -> impl Iterator<Item = &mut Lane> {
+    section
+        .left
+        .iter_mut()
+        .chain(Some(&mut section.center))
+        .chain(section.right.iter_mut())
+        .flat_map(|side| side.lane.iter_mut())
+}
+
+fn link_mut(road: &mut Road) -> &mut Link {
+    road.link.get_or_insert_with(|| Link {
+        predecessor: None,
+        successor: None,
+        neighbors: Vec::new(),
+    })
+}
+
+/// Whether `element` links to the road with `id`
+fn links_to(element: &Option<LinkElement>, id: &RoadId) -> bool {
+    matches!(
+        element,
+        Some(LinkElement {
+            element_type: ElementType::Road,
+            element_id,
+            ..
+        }) if element_id == id
+    )
+}
+
+/// `id` with the first of the suffixes `_1`, `_2`, ... that makes it an id
+/// not `taken`
+pub(crate) fn unique_id(id: &str, taken: impl Fn(&str) -> bool) -> String {
+    (1..)
+        .map(|i| format!("{}_{}", id, i))
+        .find(|id| !taken(id))
+        .unwrap()
+}
+
+/// The ids of the objects along `roads`
+pub(crate) fn object_ids<'a>(roads: impl Iterator<Item = &'a Road>) -> HashSet<String> {
+    roads
+        .flat_map(|road| road.objects.iter())
+        .flat_map(|objects| objects.objects.iter())
+        .map(|object| object.id.clone())
+        .collect()
+}
+
+/// The junction the end of a road links to
+fn junction_at(element: &Option<LinkElement>) -> Option<JunctionId> {
+    match element {
+        Some(LinkElement {
+            element_type: ElementType::Junction,
+            element_id,
+            ..
+        }) => Some(element_id.clone().into()),
+        _ => None,
+    }
+}
+
+fn flip_contact_point(contact_point: &ContactPoint) -> ContactPoint {
+    match contact_point {
+        ContactPoint::Start => ContactPoint::End,
+        ContactPoint::End => ContactPoint::Start,
+    }
+}
+
+fn flip_orientation(orientation: Orientation) -> Orientation {
+    match orientation {
+        Orientation::Positive => Orientation::Negative,
+        Orientation::Negative => Orientation::Positive,
+        Orientation::None => Orientation::None,
+    }
+}
+
+/// The geometry traversed from its end to its start, placed at `s`
+fn reverse_geometry(g: &Geometry, s: f64) -> Geometry {
+    let length = g.length.get();
+    let (x, y, hdg) = g.pose_at(length);
+    let (hdg, element) = match g.element {
+        GeometryElement::Line => (hdg, GeometryElement::Line),
+        GeometryElement::Arc { curvature } => (
+            hdg,
+            GeometryElement::Arc {
+                curvature: -curvature,
+            },
+        ),
+        GeometryElement::Spiral {
+            curv_start,
+            curv_end,
+        } => (
+            hdg,
+            GeometryElement::Spiral {
+                curv_start: -curv_end,
+                curv_end: -curv_start,
+            },
+        ),
+        GeometryElement::Poly3 { .. } | GeometryElement::ParamPoly3 { .. } => {
+            let (u, v) = match g.element {
+                GeometryElement::Poly3 { a, b, c, d } => {
+                    let u = poly3_u_at_length(b, c, d, length);
+                    ([0.0, u, 0.0, 0.0], [a, b * u, c * u * u, d * u * u * u])
+                }
+                _ => g.param_poly3_coefficients().unwrap(),
+            };
+            // in the u/v system turned by π at the end point the reversed
+            // curve is the negated difference to the end point
+            let reverse = |f: [f64; 4]| {
+                let [_, b, c, d] = arc_length::shift(f, 1.0, -1.0);
+                [0.0, -b, -c, -d]
+            };
+            (
+                g.hdg.radians,
+                arc_length::param_poly3(reverse(u), reverse(v)),
+            )
+        }
+    };
+    Geometry {
+        s: types::Length::new(s),
+        x: types::Length::new(x),
+        y: types::Length::new(y),
+        hdg: types::Angle::radians(hdg + PI).signed(),
+        length: g.length,
+        element,
+    }
+}
+
+/// The road mark in the opposite direction of the road
+fn reverse_road_mark(mut mark: RoadMark, center: bool) -> RoadMark {
+    // compound marks of the center lane are listed from left to right, those
+    // of the other lanes from inside to outside
+    if center {
+        mark.road_mark_type = match mark.road_mark_type {
+            RoadMarkType::SolidBroken => RoadMarkType::BrokenSolid,
+            RoadMarkType::BrokenSolid => RoadMarkType::SolidBroken,
+            ref other => other.clone(),
+        };
+    }
+    // lane changes are in the direction of ascending lane ids, which are
+    // negated
+    mark.lane_change = match mark.lane_change {
+        LaneChangeType::Increase => LaneChangeType::Decrease,
+        LaneChangeType::Decrease => LaneChangeType::Increase,
+        ref other => other.clone(),
+    };
+    mark
+}
+
+/// The lane of a section of `length` in the opposite direction. Links to
+/// lanes of the same road follow their negated ids, links out of the first
+/// and last section of the road to other roads keep the ids.
+fn reverse_lane(lane: &Lane, length: f64, first: bool, last: bool) -> Lane {
+    let reverse = |element: &Option<LaneLinkElement>, outward: bool| {
+        element.as_ref().map(|element| LaneLinkElement {
+            id: if outward { element.id } else { -element.id },
+        })
+    };
+    Lane {
+        id: -lane.id,
+        widths: reverse_records(&lane.widths, length),
+        road_marks: reverse_records(&lane.road_marks, length)
+            .into_iter()
+            .map(|mark| reverse_road_mark(mark, lane.id == 0))
+            .collect(),
+        heights: reverse_records(&lane.heights, length),
+        link: lane.link.as_ref().map(|link| LaneLink {
+            predecessor: reverse(&link.successor, last),
+            successor: reverse(&link.predecessor, first),
+        }),
+        ..lane.clone()
+    }
+}
+
+/// The lanes of one side of the road on the other side, listed from left to
+/// right, see `reverse_lane`
+fn reverse_side(
+    side: &Option<LaneSubSection>,
+    length: f64,
+    first: bool,
+    last: bool,
+) -> Option<LaneSubSection> {
+    side.as_ref().map(|side| LaneSubSection {
+        lane: side
+            .lane
+            .iter()
+            .rev()
+            .map(|lane| reverse_lane(lane, length, first, last))
+            .collect(),
+    })
+}
+
+impl Road {
+    /// The part of the road between `start` and `end`, with the s positions
+    /// relative to `start` and the links of the road
+    fn slice(&self, start: f64, end: f64) -> Road {
+        let length = self.length.get();
+        let (_, rest) = self.plan_view.split_at_s(start);
+        let (plan_view, _) = rest.split_at_s(end - start);
+        // objects and signals at the cut belong to the part after it
+        let within = |s: f64| {
+            s >= start - S_TOLERANCE && (s < end - S_TOLERANCE || end >= length - S_TOLERANCE)
+        };
+
+        let lanes = self.lanes.as_ref().map(|lanes| {
+            let sections = &lanes.lane_sections;
+            let mut lane_sections = Vec::new();
+            for (i, section) in sections.iter().enumerate() {
+                let section_end = sections.get(i + 1).map_or(length, |next| next.s);
+                if section_end <= start + S_TOLERANCE || section.s >= end - S_TOLERANCE {
+                    continue;
+                }
+                let from = (start - section.s).max(0.0);
+                let to = end.min(section_end) - section.s;
+                let cut_end = end < section_end - S_TOLERANCE;
+                let mut section = section.clone();
+                section.s = (section.s - start).max(0.0);
+                for lane in lanes_mut(&mut section) {
+                    lane.widths = slice_records(&lane.widths, from, to);
+                    lane.road_marks = slice_records(&lane.road_marks, from, to);
+                    lane.heights = slice_records(&lane.heights, from, to);
+                    // a lane cut within its section continues in itself
+                    if lane.id != 0 && from > S_TOLERANCE {
+                        lane.link.get_or_insert_with(Default::default).predecessor =
+                            Some(LaneLinkElement { id: lane.id });
+                    }
+                    if lane.id != 0 && cut_end {
+                        lane.link.get_or_insert_with(Default::default).successor =
+                            Some(LaneLinkElement { id: lane.id });
+                    }
+                }
+                lane_sections.push(section);
+            }
+            Lanes {
+                lane_offsets: slice_records(&lanes.lane_offsets, start, end),
+                lane_sections,
+            }
+        });
+
+        Road {
+            length: types::Length::new(end - start),
+            road_types: slice_records(&self.road_types, start, end),
+            plan_view,
+            elevation_profile: self
+                .elevation_profile
+                .as_ref()
+                .map(|profile| ElevationProfile {
+                    elevations: slice_records(&profile.elevations, start, end),
+                }),
+            lateral_profile: self.lateral_profile.as_ref().map(|profile| LateralProfile {
+                superelevations: slice_records(&profile.superelevations, start, end),
+                shapes: slice_records(&profile.shapes, start, end),
+            }),
+            lanes,
+            objects: self.objects.as_ref().map(|objects| Objects {
+                objects: objects
+                    .objects
+                    .iter()
+                    .filter(|o| {
+                        within(o.s)
+                            || o.valid_length > 0.0
+                                && o.s < end - S_TOLERANCE
+                                && o.s + o.valid_length > start + S_TOLERANCE
+                    })
+                    .map(|o| {
+                        let s = o.s.max(start);
+                        Object {
+                            s: s - start,
+                            valid_length: ((o.s + o.valid_length).min(end) - s).max(0.0),
+                            ..o.clone()
+                        }
+                    })
+                    .collect(),
+            }),
+            signals: self.signals.as_ref().map(|signals| Signals {
+                signals: signals
+                    .signals
+                    .iter()
+                    .filter(|signal| within(signal.s))
+                    .map(|signal| Signal {
+                        s: signal.s - start,
+                        ..signal.clone()
+                    })
+                    .collect(),
+            }),
+            ..self.clone()
+        }
+    }
+
+    fn check_range(&self, s: f64) -> Result<(), EditError> {
+        if s < -S_TOLERANCE || s > self.length.get() + S_TOLERANCE {
+            return Err(EditError::OutOfRange(s));
+        }
+        Ok(())
+    }
+
+    /// The road split at s into the road from its start, which keeps the id,
+    /// and the road to its end with `id`, linked to each other. Objects whose
+    /// valid length spans s are in both roads, in the second one with a new
+    /// id.
+    pub fn split_at(&self, s: f64, id: RoadId) -> Result<(Road, Road), EditError> {
+        self.split(s, id, object_ids(Some(self).into_iter()))
+    }
+
+    /// `split_at` with the object ids `taken` in the database
+    fn split(
+        &self,
+        s: f64,
+        id: RoadId,
+        mut taken: HashSet<String>,
+    ) -> Result<(Road, Road), EditError> {
+        if s <= S_TOLERANCE || s >= self.length.get() - S_TOLERANCE {
+            return Err(EditError::OutOfRange(s));
+        }
+        let mut first = self.slice(0.0, s);
+        let mut second = self.slice(s, self.length.get());
+        second.id = id;
+        let first_ids = object_ids(Some(&first).into_iter());
+        for object in second.objects.iter_mut().flat_map(|o| o.objects.iter_mut()) {
+            if first_ids.contains(&object.id) {
+                object.id = unique_id(&object.id, |id| taken.contains(id));
+                taken.insert(object.id.clone());
+            }
+        }
+        link_mut(&mut first).successor = Some(LinkElement {
+            element_type: ElementType::Road,
+            element_id: second.id.clone().into(),
+            contact_point: ContactPoint::Start,
+        });
+        link_mut(&mut second).predecessor = Some(LinkElement {
+            element_type: ElementType::Road,
+            element_id: first.id.clone().into(),
+            contact_point: ContactPoint::End,
+        });
+        Ok((first, second))
+    }
+
+    /// The part of the road between `start` and `end`. The links at the ends
+    /// that are cut off are removed.
+    pub fn trimmed(&self, start: f64, end: f64) -> Result<Road, EditError> {
+        self.check_range(start)?;
+        self.check_range(end)?;
+        if end - start <= S_TOLERANCE {
+            return Err(EditError::OutOfRange(end));
+        }
+        let mut road = self.slice(start.max(0.0), end.min(self.length.get()));
+        let cut_start = start > S_TOLERANCE;
+        let cut_end = end < self.length.get() - S_TOLERANCE;
+        if let Some(link) = road.link.as_mut() {
+            if cut_start {
+                link.predecessor = None;
+            }
+            if cut_end {
+                link.successor = None;
+            }
+        }
+        if let Some(lanes) = road.lanes.as_mut() {
+            if let (true, Some(section)) = (cut_start, lanes.lane_sections.first_mut()) {
+                for lane in lanes_mut(section) {
+                    if let Some(link) = lane.link.as_mut() {
+                        link.predecessor = None;
+                    }
+                }
+            }
+            if let (true, Some(section)) = (cut_end, lanes.lane_sections.last_mut()) {
+                for lane in lanes_mut(section) {
+                    if let Some(link) = lane.link.as_mut() {
+                        link.successor = None;
+                    }
+                }
+            }
+        }
+        Ok(road)
+    }
+
+    /// The road continued by `other`, which must be linked to the end of the
+    /// road with its start. The merged road keeps the id, name and
+    /// predecessor of the road and the successor of `other`.
+    pub fn merge(&self, other: &Road) -> Result<Road, EditError> {
+        let linked = self.link.iter().any(|link| {
+            links_to(&link.successor, &other.id)
+                && link.successor.as_ref().unwrap().contact_point == ContactPoint::Start
+        }) && other.link.iter().any(|link| {
+            links_to(&link.predecessor, &self.id)
+                && link.predecessor.as_ref().unwrap().contact_point == ContactPoint::End
+        });
+        if !linked {
+            return Err(EditError::NotLinked(self.id.clone(), other.id.clone()));
+        }
+
+        let offset = self.length.get();
+        let mut road = self.clone();
+        road.length = self.length + other.length;
+        road.road_types = append_records(&self.road_types, &other.road_types, offset);
+        road.plan_view
+            .geometries
+            .extend(other.plan_view.geometries.iter().map(|g| Geometry {
+                s: g.s + self.length,
+                ..g.clone()
+            }));
+
+        if self.elevation_profile.is_some() || other.elevation_profile.is_some() {
+            fn elevations(road: &Road) -> &[Elevation] {
+                road.elevation_profile
+                    .as_ref()
+                    .map_or(&[], |profile| &profile.elevations[..])
+            }
+            road.elevation_profile = Some(ElevationProfile {
+                elevations: append_records(elevations(self), elevations(other), offset),
+            });
+        }
+        if self.lateral_profile.is_some() || other.lateral_profile.is_some() {
+            let empty = LateralProfile::default();
+            let first = self.lateral_profile.as_ref().unwrap_or(&empty);
+            let second = other.lateral_profile.as_ref().unwrap_or(&empty);
+            road.lateral_profile = Some(LateralProfile {
+                superelevations: append_records(
+                    &first.superelevations,
+                    &second.superelevations,
+                    offset,
+                ),
+                shapes: append_records(&first.shapes, &second.shapes, offset),
+            });
+        }
+        if let Some(lanes) = other.lanes.as_ref() {
+            let first = road.lanes.get_or_insert_with(|| Lanes {
+                lane_offsets: Vec::new(),
+                lane_sections: Vec::new(),
+            });
+            first.lane_offsets = append_records(&first.lane_offsets, &lanes.lane_offsets, offset);
+            // the lane links between the roads now link consecutive sections
+            first
+                .lane_sections
+                .extend(lanes.lane_sections.iter().map(|section| LaneSection {
+                    s: section.s + offset,
+                    ..section.clone()
+                }));
+        }
+        if let Some(objects) = other.objects.as_ref() {
+            road.objects
+                .get_or_insert_with(Default::default)
+                .objects
+                .extend(objects.objects.iter().map(|o| Object {
+                    s: o.s + offset,
+                    ..o.clone()
+                }));
+        }
+        if let Some(signals) = other.signals.as_ref() {
+            road.signals
+                .get_or_insert_with(Default::default)
+                .signals
+                .extend(signals.signals.iter().map(|signal| Signal {
+                    s: signal.s + offset,
+                    ..signal.clone()
+                }));
+        }
+        link_mut(&mut road).successor = other.link.as_ref().and_then(|l| l.successor.clone());
+        Ok(road)
+    }
+
+    /// The road in the opposite direction: s runs from the end to the start,
+    /// left and right lanes swap with negated ids and the predecessors and
+    /// successors of the road and its lanes swap. Compound center lane marks
+    /// and the directions of lane changes swap as well.
+    ///
+    /// Lateral shape records are not supported, as a cross section reversed
+    /// in t has no start for its outermost polynomial.
+    pub fn reversed(&self) -> Result<Road, EditError> {
+        if self
+            .lateral_profile
+            .iter()
+            .any(|profile| !profile.shapes.is_empty())
+        {
+            return Err(EditError::Unsupported("reversing lateral shape records"));
+        }
+        let length = self.length.get();
+        let negate = |mut offset: LaneOffset| {
+            offset.a = -offset.a;
+            offset.b = -offset.b;
+            offset.c = -offset.c;
+            offset.d = -offset.d;
+            offset
+        };
+
+        Ok(Road {
+            link: self.link.as_ref().map(|link| Link {
+                predecessor: link.successor.clone(),
+                successor: link.predecessor.clone(),
+                neighbors: link
+                    .neighbors
+                    .iter()
+                    .map(|neighbor| Neighbor {
+                        side: match neighbor.side {
+                            Side::Left => Side::Right,
+                            Side::Right => Side::Left,
+                        },
+                        element_id: neighbor.element_id.clone(),
+                        direction: match neighbor.direction {
+                            Direction::Same => Direction::Opposite,
+                            Direction::Opposite => Direction::Same,
+                        },
+                    })
+                    .collect(),
+            }),
+            road_types: reverse_records(&self.road_types, length),
+            plan_view: PlanView {
+                geometries: self
+                    .plan_view
+                    .geometries
+                    .iter()
+                    .rev()
+                    .map(|g| reverse_geometry(g, length - g.s.get() - g.length.get()))
+                    .collect(),
+            },
+            elevation_profile: self
+                .elevation_profile
+                .as_ref()
+                .map(|profile| ElevationProfile {
+                    elevations: reverse_records(&profile.elevations, length),
+                }),
+            // the road falls to the other side
+            lateral_profile: self.lateral_profile.as_ref().map(|profile| LateralProfile {
+                superelevations: reverse_records(&profile.superelevations, length)
+                    .into_iter()
+                    .map(|mut e| {
+                        e.a = -e.a;
+                        e.b = -e.b;
+                        e.c = -e.c;
+                        e.d = -e.d;
+                        e
+                    })
+                    .collect(),
+                shapes: Vec::new(),
+            }),
+            lanes: self.lanes.as_ref().map(|lanes| {
+                let sections = &lanes.lane_sections;
+                Lanes {
+                    lane_offsets: reverse_records(&lanes.lane_offsets, length)
+                        .into_iter()
+                        .map(negate)
+                        .collect(),
+                    lane_sections: sections
+                        .iter()
+                        .enumerate()
+                        .rev()
+                        .map(|(i, section)| {
+                            let end = sections.get(i + 1).map_or(length, |next| next.s);
+                            let section_length = end - section.s;
+                            let (first, last) = (i == 0, i + 1 == sections.len());
+                            let reverse = |side: &Option<LaneSubSection>| {
+                                reverse_side(side, section_length, first, last)
+                            };
+                            LaneSection {
+                                s: (length - end).max(0.0),
+                                single_side: section.single_side,
+                                left: reverse(&section.right),
+                                center: reverse(&Some(section.center.clone())).unwrap(),
+                                right: reverse(&section.left),
+                            }
+                        })
+                        .collect(),
+                }
+            }),
+            objects: self.objects.as_ref().map(|objects| Objects {
+                objects: objects
+                    .objects
+                    .iter()
+                    .rev()
+                    .map(|o| Object {
+                        s: (length - o.s - o.valid_length).max(0.0),
+                        t: -o.t,
+                        hdg: (o.hdg + PI).rem_euclid(2.0 * PI),
+                        orientation: flip_orientation(o.orientation),
+                        ..o.clone()
+                    })
+                    .collect(),
+            }),
+            signals: self.signals.as_ref().map(|signals| Signals {
+                signals: signals
+                    .signals
+                    .iter()
+                    .rev()
+                    .map(|signal| Signal {
+                        s: length - signal.s,
+                        t: -signal.t,
+                        orientation: flip_orientation(signal.orientation),
+                        ..signal.clone()
+                    })
+                    .collect(),
+            }),
+            ..self.clone()
+        })
+    }
+}
+
+impl Root {
+    fn road_index(&self, id: &RoadId) -> Result<usize, EditError> {
+        self.roads
+            .iter()
+            .position(|road| road.id == *id)
+            .ok_or_else(|| EditError::UnknownRoad(id.clone()))
+    }
+
+    /// Split the road with `id` at s, see `Road::split_at`. The links and
+    /// junction connections to the end of the road refer to the new road
+    /// with `new_id`, which is inserted after the road.
+    pub fn split_road(&mut self, id: &RoadId, s: f64, new_id: RoadId) -> Result<(), EditError> {
+        let index = self.road_index(id)?;
+        let taken = object_ids(self.roads.iter());
+        let (first, second) = self.roads[index].split(s, new_id.clone(), taken)?;
+        let junction = second
+            .link
+            .as_ref()
+            .and_then(|link| junction_at(&link.successor));
+        for road in self.roads.iter_mut() {
+            if let Some(link) = road.link.as_mut() {
+                for element in link.predecessor.iter_mut().chain(link.successor.iter_mut()) {
+                    if element.element_type == ElementType::Road
+                        && element.element_id == *id
+                        && element.contact_point == ContactPoint::End
+                    {
+                        element.element_id = new_id.clone().into();
+                    }
+                }
+            }
+        }
+        for junction_record in self.junctions.iter_mut() {
+            let at_end = junction.as_ref() == Some(&junction_record.id);
+            for connection in junction_record.connections.iter_mut() {
+                if connection.incoming_road == *id && at_end {
+                    connection.incoming_road = new_id.clone();
+                }
+                let at_end = connection.contact_point == ContactPoint::End;
+                match connection.connected_road_mut() {
+                    Some(connected) if *connected == *id && at_end => *connected = new_id.clone(),
+                    _ => (),
+                }
+            }
+        }
+        self.roads[index] = first;
+        self.roads.insert(index + 1, second);
+        Ok(())
+    }
+
+    /// Merge the road with `second` into the road with `first`, see
+    /// `Road::merge`. The links and junction connections to the second road
+    /// refer to the merged road.
+    pub fn merge_roads(&mut self, first: &RoadId, second: &RoadId) -> Result<(), EditError> {
+        let a = self.road_index(first)?;
+        let b = self.road_index(second)?;
+        let merged = self.roads[a].merge(&self.roads[b])?;
+        for road in self.roads.iter_mut() {
+            if let Some(link) = road.link.as_mut() {
+                for element in link.predecessor.iter_mut().chain(link.successor.iter_mut()) {
+                    if element.element_type == ElementType::Road && element.element_id == *second {
+                        element.element_id = first.clone().into();
+                    }
+                }
+            }
+        }
+        for junction in self.junctions.iter_mut() {
+            for connection in junction.connections.iter_mut() {
+                if connection.incoming_road == *second {
+                    connection.incoming_road = first.clone();
+                }
+                match connection.connected_road_mut() {
+                    Some(connected) if *connected == *second => *connected = first.clone(),
+                    _ => (),
+                }
+            }
+        }
+        self.roads[a] = merged;
+        self.roads.remove(b);
+        Ok(())
+    }
+
+    /// Reverse the road with `id`, see `Road::reversed`. The contact points
+    /// of the links and junction connections to the road and the lane links
+    /// to its lanes are updated.
+    pub fn reverse_road(&mut self, id: &RoadId) -> Result<(), EditError> {
+        let index = self.road_index(id)?;
+        let reversed = self.roads[index].reversed()?;
+        for road in self.roads.iter_mut() {
+            let (at_start, at_end) = match road.link.as_mut() {
+                Some(link) => {
+                    let flip = |element: &mut Option<LinkElement>| {
+                        if !links_to(element, id) {
+                            return false;
+                        }
+                        let element = element.as_mut().unwrap();
+                        element.contact_point = flip_contact_point(&element.contact_point);
+                        true
+                    };
+                    (flip(&mut link.predecessor), flip(&mut link.successor))
+                }
+                None => continue,
+            };
+            let lanes = match road.lanes.as_mut() {
+                Some(lanes) => lanes,
+                None => continue,
+            };
+            if let (true, Some(section)) = (at_start, lanes.lane_sections.first_mut()) {
+                for lane in lanes_mut(section) {
+                    if let Some(element) = lane.link.as_mut().and_then(|l| l.predecessor.as_mut()) {
+                        element.id = -element.id;
+                    }
+                }
+            }
+            if let (true, Some(section)) = (at_end, lanes.lane_sections.last_mut()) {
+                for lane in lanes_mut(section) {
+                    if let Some(element) = lane.link.as_mut().and_then(|l| l.successor.as_mut()) {
+                        element.id = -element.id;
+                    }
+                }
+            }
+        }
+        for junction in self.junctions.iter_mut() {
+            for connection in junction.connections.iter_mut() {
+                if connection.connected_road() == Some(id) {
+                    connection.contact_point = flip_contact_point(&connection.contact_point);
+                    for lane_link in connection.lane_links.iter_mut() {
+                        lane_link.to = -lane_link.to;
+                    }
+                }
+                if connection.incoming_road == *id {
+                    for lane_link in connection.lane_links.iter_mut() {
+                        lane_link.from = -lane_link.from;
+                    }
+                }
+            }
+        }
+        self.roads[index] = reversed;
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use crate::lane_graph::LaneKey;
+use crate::revision::Revision;
 use crate::types;
 use failure::Fail;
 
@@ -11,4 +13,157 @@ pub enum ValidationError {
         _0
     )]
     ReferenceLineLength((types::Length, types::Length)),
+
+    #[fail(display = "validation error: duplicate road id {}", _0)]
+    DuplicateRoadId(types::RoadId),
+
+    #[fail(display = "validation error: duplicate junction id {}", _0)]
+    DuplicateJunctionId(types::JunctionId),
+
+    #[fail(display = "validation error: link to missing road {}", _0)]
+    MissingRoad(types::RoadId),
+
+    #[fail(display = "validation error: link to missing junction {}", _0)]
+    MissingJunction(types::JunctionId),
+
+    #[fail(
+        display = "validation error: connection {} has neither a connecting nor a linked road",
+        _0
+    )]
+    MissingConnectedRoad(types::ConnectionId),
+
+    #[fail(display = "validation error: lane sections not in ascending order of s")]
+    LaneSectionOrder,
+
+    #[fail(display = "road {}: {}", _0, _1)]
+    Road(types::RoadId, Box<ValidationError>),
+
+    #[fail(display = "junction {}: {}", _0, _1)]
+    Junction(types::JunctionId, Box<ValidationError>),
+}
+
+#[derive(Debug, Fail)]
+pub enum RevisionError {
+    #[fail(display = "revision error: unsupported OpenDRIVE revision {}", _0)]
+    Unsupported(Revision),
+
+    #[fail(
+        display = "revision error: {} require OpenDRIVE {} but the file declares {}",
+        _0, _1, _2
+    )]
+    UndefinedFeature(&'static str, Revision, Revision),
+}
+
+#[derive(Debug, Fail)]
+pub enum GeoReferenceError {
+    #[fail(display = "geo reference error: no geoReference record in header")]
+    Missing,
+
+    #[fail(display = "geo reference error: unsupported projection \"{}\"", _0)]
+    UnsupportedProjection(String),
+
+    #[fail(display = "geo reference error: invalid parameter \"{}\"", _0)]
+    InvalidParameter(String),
+}
+
+#[derive(Debug, Fail)]
+pub enum Lanelet2Error {
+    #[fail(display = "lanelet2 error: lanelet {} has no {} bound", _0, _1)]
+    MissingBound(i64, &'static str),
+
+    #[fail(display = "lanelet2 error: way {} not found", _0)]
+    MissingWay(i64),
+
+    #[fail(display = "lanelet2 error: node {} not found", _0)]
+    MissingNode(i64),
+}
+
+#[derive(Debug, Fail)]
+pub enum TrajectoryError {
+    #[fail(display = "trajectory error: empty route")]
+    EmptyRoute,
+
+    #[fail(display = "trajectory error: lane {:?} not found", _0)]
+    UnknownLane(LaneKey),
+
+    #[fail(
+        display = "trajectory error: lane {:?} neither leads to nor is next to lane {:?}",
+        _0, _1
+    )]
+    NotConnected(LaneKey, LaneKey),
+
+    #[fail(display = "trajectory error: step {} is not positive and finite", _0)]
+    InvalidStep(f64),
+}
+
+#[derive(Debug, Fail)]
+pub enum CacheError {
+    #[fail(display = "cache error: {}", _0)]
+    Io(#[fail(cause)] std::io::Error),
+
+    #[fail(display = "cache error: not a cache file")]
+    NotACache,
+
+    #[fail(display = "cache error: unsupported format version {}", _0)]
+    UnsupportedVersion(u32),
+
+    #[fail(display = "cache error: cache of a different source file or options")]
+    Outdated,
+
+    #[fail(display = "cache error: checksum mismatch")]
+    Corrupt,
+
+    #[fail(display = "cache error: {}", _0)]
+    Format(String),
+}
+
+impl From<std::io::Error> for CacheError {
+    fn from(err: std::io::Error) -> CacheError {
+        CacheError::Io(err)
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum OffsetError {
+    #[fail(
+        display = "offset error: offset exceeds the radius of curvature at x {}, y {}",
+        _0, _1
+    )]
+    Cusp(f64, f64),
+
+    #[fail(display = "offset error: lane {} not found", _0)]
+    UnknownLane(i32),
+}
+
+#[derive(Debug, Fail)]
+pub enum EditError {
+    #[fail(display = "edit error: unknown road {}", _0)]
+    UnknownRoad(types::RoadId),
+
+    #[fail(display = "edit error: s {} outside of the road", _0)]
+    OutOfRange(f64),
+
+    #[fail(
+        display = "edit error: end of road {} not linked to start of road {}",
+        _0, _1
+    )]
+    NotLinked(types::RoadId, types::RoadId),
+
+    #[fail(display = "edit error: {} is not supported", _0)]
+    Unsupported(&'static str),
+}
+
+#[derive(Debug, Fail)]
+pub enum BuildError {
+    #[fail(
+        display = "build error: no biarc to x {}, y {}, heading {}",
+        _0, _1, _2
+    )]
+    NoBiarc(f64, f64, f64),
+}
+
+#[derive(Debug, Fail)]
+pub enum SampleError {
+    #[fail(display = "sample error: tolerance {} is not positive and finite", _0)]
+    InvalidTolerance(f64),
 }
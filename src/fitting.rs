@@ -0,0 +1,713 @@
+//! Fitting of reference line geometry to polylines
+//!
+//! A dense polyline, e.g. a surveyed center line, is approximated by a
+//! sequence of line, arc, spiral and paramPoly3 elements. The elements are
+//! placed greedily: each element starts at the end pose of the previous one
+//! and is extended over as many polyline points as possible while every
+//! point lies within the tolerance of the element and the element lies
+//! within the tolerance of the polyline. Of all element types the one
+//! reaching furthest is chosen, preferring the simpler type on ties.
+//!
+//! Consecutive elements always share their end and start position and
+//! heading (G1 continuity). With `Continuity::G2` the curvature is continuous
+//! as well: arcs and lines must continue the curvature of the previous
+//! element, spirals and paramPoly3 curves start with it. Two more element
+//! types are used then, a spiral ending with zero curvature to lead into a
+//! line and a pair of spirals which reaches the next point with the heading
+//! of the polyline.
+use crate::opendrive::*;
+use crate::types;
+
+/// Points closer to their predecessor are dropped
+const MIN_POINT_DISTANCE: f64 = 1e-6;
+/// m minimum distance of the neighbours through which the circle estimating
+/// the curvature at a point is drawn
+const CURVATURE_BASE: f64 = 10.0;
+/// Fraction of the tolerance allowed for the chord error when sampling
+/// elements to measure their distance to the polyline
+const SAMPLE_TOLERANCE_RATIO: f64 = 0.1;
+const MAX_ITERATIONS: usize = 50;
+/// m, rad convergence threshold of the residuals of the element equations
+const SOLVE_TOLERANCE: f64 = 1e-9;
+
+/// Continuity of the reference line at the joints of its elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Continuity {
+    /// continuous position and heading
+    G1,
+    /// continuous position, heading and curvature
+    G2,
+}
+
+#[derive(Debug, Clone)]
+pub struct FitOptions {
+    /// m maximum lateral distance between the polyline and the fitted
+    /// reference line
+    pub tolerance: f64,
+    pub continuity: Continuity,
+    /// Whether paramPoly3 elements may be used. Without them a stretch of
+    /// the polyline that no line, arc or spiral fits is bridged by the
+    /// closest one.
+    pub param_poly3: bool,
+}
+
+impl Default for FitOptions {
+    fn default() -> FitOptions {
+        FitOptions {
+            tolerance: 0.05,
+            continuity: Continuity::G1,
+            param_poly3: true,
+        }
+    }
+}
+
+/// Approximate a polyline of inertial x, y points by a plan view that starts
+/// at the first and ends within the tolerance of the last point
+pub fn fit_plan_view(points: &[(f64, f64)], options: &FitOptions) -> PlanView {
+    let mut points = points.to_vec();
+    points.dedup_by(|a, b| (a.0 - b.0).hypot(a.1 - b.1) < MIN_POINT_DISTANCE);
+    if points.len() < 2 {
+        return PlanView::default();
+    }
+    let headings = headings(&points);
+    let curvatures = curvatures(&points);
+
+    let mut kinds = vec![Kind::Line, Kind::Arc, Kind::Spiral];
+    if options.continuity == Continuity::G2 {
+        kinds.push(Kind::SpiralToLine);
+        kinds.push(Kind::SpiralPair);
+    }
+    if options.param_poly3 {
+        kinds.push(Kind::ParamPoly3);
+    }
+
+    let mut geometries = Vec::new();
+    let mut start = Start {
+        x: points[0].0,
+        y: points[0].1,
+        hdg: headings[0],
+        curvature: None,
+    };
+    let mut s = 0.0;
+    let mut i = 0;
+    while i + 1 < points.len() {
+        let fitter = Fitter {
+            points: &points,
+            headings: &headings,
+            curvatures: &curvatures,
+            options,
+            start,
+            i,
+        };
+        let best = kinds.iter().filter_map(|&kind| fitter.reach(kind)).fold(
+            None,
+            |best: Option<Fit>, (j, fit)| match best {
+                Some((reached, _)) if reached >= j => best,
+                _ => Some((j, fit)),
+            },
+        );
+        let (j, fit) = match best.or_else(|| fitter.closest(&kinds)) {
+            Some(fit) => fit,
+            None => {
+                // the start coincides with the next point
+                i += 1;
+                continue;
+            }
+        };
+
+        for mut g in fit {
+            g.s = types::Length::new(s);
+            s += g.length.get();
+            let (x, y, hdg) = g.pose_at(g.length.get());
+            start = Start {
+                x,
+                y,
+                hdg: wrap(hdg),
+                curvature: Some(g.curvature_at(g.length.get())),
+            };
+            geometries.push(g);
+        }
+        i = j;
+    }
+    PlanView { geometries }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Line,
+    Arc,
+    Spiral,
+    /// spiral ending with zero curvature
+    SpiralToLine,
+    /// two spirals of equal length
+    SpiralPair,
+    ParamPoly3,
+}
+
+/// The index of the last point reached by a sequence of elements
+type Fit = (usize, Vec<Geometry>);
+
+/// Pose and curvature at the end of the previous element
+#[derive(Debug, Clone, Copy)]
+struct Start {
+    x: f64,
+    y: f64,
+    hdg: f64,
+    curvature: Option<f64>,
+}
+
+/// Fits elements from a start pose at point `i` to later points
+struct Fitter<'a> {
+    points: &'a [(f64, f64)],
+    headings: &'a [f64],
+    curvatures: &'a [f64],
+    options: &'a FitOptions,
+    start: Start,
+    i: usize,
+}
+
+impl<'a> Fitter<'a> {
+    /// The curvature the next element has to start with
+    fn curvature(&self) -> Option<f64> {
+        match self.options.continuity {
+            Continuity::G1 => None,
+            Continuity::G2 => self.start.curvature,
+        }
+    }
+
+    /// The furthest point up to which an element of `kind` fits the polyline
+    /// and the element, found by doubling the number of points until an
+    /// element fits and no longer fits and bisecting
+    fn reach(&self, kind: Kind) -> Option<Fit> {
+        let tolerance = self.options.tolerance;
+        let last = self.points.len() - 1;
+        let fit = |j: usize| {
+            self.element(kind, j)
+                .filter(|g| deviation(g, &self.points[self.i..=j], tolerance) <= tolerance)
+        };
+
+        let mut good: Option<Fit> = None;
+        let mut bad = last + 1;
+        let mut step = 1;
+        loop {
+            let j = (self.i + step).min(last);
+            match fit(j) {
+                Some(g) => good = Some((j, g)),
+                None if good.is_some() => {
+                    bad = j;
+                    break;
+                }
+                None => {}
+            }
+            if j == last {
+                break;
+            }
+            step *= 2;
+        }
+        let mut good = good?;
+        while bad - good.0 > 1 {
+            let j = (good.0 + bad) / 2;
+            match fit(j) {
+                Some(g) => good = (j, g),
+                None => bad = j,
+            }
+        }
+        Some(good)
+    }
+
+    /// The element closest to the polyline up to the next point, used if no
+    /// element fits within the tolerance
+    fn closest(&self, kinds: &[Kind]) -> Option<Fit> {
+        let j = self.i + 1;
+        let tolerance = self.options.tolerance;
+        kinds
+            .iter()
+            .filter_map(|&kind| self.element(kind, j))
+            .map(|g| (deviation(&g, &self.points[self.i..=j], tolerance), g))
+            .fold(
+                None,
+                |best: Option<(f64, Vec<Geometry>)>, (d, g)| match best {
+                    Some((closest, _)) if closest <= d => best,
+                    _ => Some((d, g)),
+                },
+            )
+            .or_else(|| {
+                // a paramPoly3 curve always reaches the next point
+                self.element(Kind::ParamPoly3, j).map(|g| (0.0, g))
+            })
+            .map(|(_, g)| (j, g))
+    }
+
+    /// The elements of `kind` from the start pose towards point `j`
+    fn element(&self, kind: Kind, j: usize) -> Option<Vec<Geometry>> {
+        let start = self.start;
+        let (dx, dy) = (self.points[j].0 - start.x, self.points[j].1 - start.y);
+        let (sin, cos) = start.hdg.sin_cos();
+        // the target point and heading relative to the start pose
+        let (u, v) = (dx * cos + dy * sin, dy * cos - dx * sin);
+        let hdg = wrap(self.headings[j] - start.hdg);
+        let chord = u.hypot(v);
+
+        match kind {
+            Kind::Line => match self.curvature() {
+                Some(curvature) if curvature != 0.0 => None,
+                _ => self.geometry(u, GeometryElement::Line),
+            },
+            Kind::Arc => match self.curvature() {
+                // the arc tangent to the start heading through the point
+                None => {
+                    if v.abs() < 1e-12 * chord {
+                        return None;
+                    }
+                    let curvature = 2.0 * v / (chord * chord);
+                    let sweep = 2.0 * v.atan2(u);
+                    self.geometry(sweep / curvature, GeometryElement::Arc { curvature })
+                }
+                // the arc of the start curvature up to the point's angle
+                // around its center
+                Some(curvature) => {
+                    if curvature == 0.0 {
+                        return None;
+                    }
+                    let radius = 1.0 / curvature;
+                    let angle = (radius * u).atan2(radius * (radius - v));
+                    let sweep = (angle * curvature.signum()).rem_euclid(2.0 * std::f64::consts::PI);
+                    self.geometry(sweep * radius.abs(), GeometryElement::Arc { curvature })
+                }
+            },
+            Kind::Spiral => {
+                let (curvature, length) = arc_guess(u, v);
+                let spiral = |curv_start: f64, curv_end: f64, length: f64| {
+                    local_geometry(
+                        length,
+                        GeometryElement::Spiral {
+                            curv_start,
+                            curv_end,
+                        },
+                    )
+                };
+                let element = match self.curvature() {
+                    // through the point with its heading
+                    None => {
+                        let x = solve(
+                            |x| {
+                                let (pu, pv, phdg) = spiral(x[0], x[1], x[2])?;
+                                Some(vec![pu - u, pv - v, wrap(phdg - hdg)])
+                            },
+                            vec![curvature, curvature, length],
+                        )?;
+                        (x[2], x[0], x[1])
+                    }
+                    // through the point
+                    Some(curv_start) => {
+                        let x = solve(
+                            |x| {
+                                let (pu, pv, _) = spiral(curv_start, x[0], x[1])?;
+                                Some(vec![pu - u, pv - v])
+                            },
+                            vec![2.0 * curvature - curv_start, length],
+                        )?;
+                        (x[1], curv_start, x[0])
+                    }
+                };
+                let (length, curv_start, curv_end) = element;
+                self.geometry(
+                    length,
+                    GeometryElement::Spiral {
+                        curv_start,
+                        curv_end,
+                    },
+                )
+            }
+            Kind::SpiralToLine => {
+                // ends abeam of the point
+                let curv_start = self.curvature().filter(|&c| c != 0.0)?;
+                let (_, length) = arc_guess(u, v);
+                let x = solve(
+                    |x| {
+                        let element = GeometryElement::Spiral {
+                            curv_start,
+                            curv_end: 0.0,
+                        };
+                        let (pu, pv, phdg) = local_geometry(x[0], element)?;
+                        Some(vec![(pu - u) * phdg.cos() + (pv - v) * phdg.sin()])
+                    },
+                    vec![length],
+                )?;
+                self.geometry(
+                    x[0],
+                    GeometryElement::Spiral {
+                        curv_start,
+                        curv_end: 0.0,
+                    },
+                )
+            }
+            Kind::SpiralPair => {
+                // through the point with its heading and the curvature of
+                // the polyline, so that the next element starts in the state
+                // of the polyline
+                let curv_start = self.curvature()?;
+                let curv_end = self.curvatures[j];
+                let (_, length) = arc_guess(u, v);
+                let spirals = |curv_mid: f64, first: f64, second: f64| {
+                    vec![
+                        (
+                            first,
+                            GeometryElement::Spiral {
+                                curv_start,
+                                curv_end: curv_mid,
+                            },
+                        ),
+                        (
+                            second,
+                            GeometryElement::Spiral {
+                                curv_start: curv_mid,
+                                curv_end,
+                            },
+                        ),
+                    ]
+                };
+                let x = solve(
+                    |x| {
+                        let mut pose = (0.0, 0.0, 0.0f64);
+                        for (length, element) in spirals(x[0], x[1], x[2]) {
+                            let (pu, pv, phdg) = local_geometry(length, element)?;
+                            let (sin, cos) = pose.2.sin_cos();
+                            pose = (
+                                pose.0 + pu * cos - pv * sin,
+                                pose.1 + pu * sin + pv * cos,
+                                pose.2 + phdg,
+                            );
+                        }
+                        Some(vec![pose.0 - u, pose.1 - v, wrap(pose.2 - hdg)])
+                    },
+                    vec![
+                        (4.0 * hdg / length - curv_start - curv_end) / 2.0,
+                        length / 2.0,
+                        length / 2.0,
+                    ],
+                )?;
+                self.chain(spirals(x[0], x[1], x[2]))
+            }
+            Kind::ParamPoly3 => {
+                // Hermite curve with tangents of the length of the chord
+                let m = chord;
+                let (u, v) = match self.curvature() {
+                    None => {
+                        let (end_u, end_v) = (m * hdg.cos(), m * hdg.sin());
+                        (
+                            [0.0, m, 3.0 * u - 2.0 * m - end_u, m + end_u - 2.0 * u],
+                            [0.0, 0.0, 3.0 * v - end_v, end_v - 2.0 * v],
+                        )
+                    }
+                    // the second derivative yields the start curvature and
+                    // the length of the end tangent the end heading, which is
+                    // left free if that length is unreasonable
+                    Some(curvature) => {
+                        let c_v = curvature * m * m / 2.0;
+                        let end = (3.0 * v - c_v) / hdg.sin();
+                        if end > 0.1 * m && end < 10.0 * m {
+                            let end_u = end * hdg.cos();
+                            (
+                                [0.0, m, 3.0 * u - 2.0 * m - end_u, m + end_u - 2.0 * u],
+                                [0.0, 0.0, c_v, v - c_v],
+                            )
+                        } else {
+                            ([0.0, m, 0.0, u - m], [0.0, 0.0, c_v, v - c_v])
+                        }
+                    }
+                };
+                let length = param_poly3_length(&u, &v);
+                self.geometry(
+                    length,
+                    GeometryElement::ParamPoly3 {
+                        a_u: u[0],
+                        b_u: u[1],
+                        c_u: u[2],
+                        d_u: u[3],
+                        a_v: v[0],
+                        b_v: v[1],
+                        c_v: v[2],
+                        d_v: v[3],
+                        p_range: PRange::Normalized,
+                    },
+                )
+            }
+        }
+    }
+
+    /// A single element at the start pose if it has a positive length
+    fn geometry(&self, length: f64, element: GeometryElement) -> Option<Vec<Geometry>> {
+        self.chain(vec![(length, element)])
+    }
+
+    /// Elements following each other from the start pose if all of them
+    /// have a positive length
+    fn chain(&self, elements: Vec<(f64, GeometryElement)>) -> Option<Vec<Geometry>> {
+        let (mut x, mut y, mut hdg) = (self.start.x, self.start.y, self.start.hdg);
+        let mut geometries = Vec::new();
+        for (length, element) in elements {
+            if !(length > 0.0 && length.is_finite()) {
+                return None;
+            }
+            let g = Geometry {
+                s: types::Length::new(0.0),
+                x: types::Length::new(x),
+                y: types::Length::new(y),
+                hdg: types::Angle::radians(hdg),
+                length: types::Length::new(length),
+                element,
+            };
+            let end = g.pose_at(length);
+            x = end.0;
+            y = end.1;
+            hdg = end.2;
+            geometries.push(g);
+        }
+        Some(geometries)
+    }
+}
+
+/// End pose of an element starting at the origin with zero heading
+fn local_geometry(length: f64, element: GeometryElement) -> Option<(f64, f64, f64)> {
+    if !(length > 0.0 && length.is_finite()) {
+        return None;
+    }
+    let g = Geometry {
+        s: types::Length::new(0.0),
+        x: types::Length::new(0.0),
+        y: types::Length::new(0.0),
+        hdg: types::Angle::radians(0.0),
+        length: types::Length::new(length),
+        element,
+    };
+    Some(g.pose_at(length))
+}
+
+/// Curvature and length of the arc from the origin with zero heading through
+/// the point u, v
+fn arc_guess(u: f64, v: f64) -> (f64, f64) {
+    let chord = u.hypot(v);
+    if v.abs() < 1e-12 * chord {
+        return (0.0, chord);
+    }
+    let curvature = 2.0 * v / (chord * chord);
+    (curvature, 2.0 * v.atan2(u) / curvature)
+}
+
+/// Length of the parametric cubic curve over p in [0, 1] by composite
+/// Simpson integration
+fn param_poly3_length(u: &[f64; 4], v: &[f64; 4]) -> f64 {
+    let speed = |p: f64| {
+        let du = u[1] + p * (2.0 * u[2] + 3.0 * u[3] * p);
+        let dv = v[1] + p * (2.0 * v[2] + 3.0 * v[3] * p);
+        du.hypot(dv)
+    };
+    let n = 64;
+    let h = 1.0 / f64::from(n);
+    let mut sum = speed(0.0) + speed(1.0);
+    for i in 1..n {
+        let w = if i % 2 == 0 { 2.0 } else { 4.0 };
+        sum += w * speed(h * f64::from(i));
+    }
+    sum * h / 3.0
+}
+
+/// Newton iteration with a numerical Jacobian and backtracking on the
+/// residuals returned by `f`, which returns `None` for invalid arguments
+fn solve<F>(f: F, mut x: Vec<f64>) -> Option<Vec<f64>>
+where
+    F: Fn(&[f64]) -> Option<Vec<f64>>,
+{
+    let norm = |r: &[f64]| r.iter().map(|r| r * r).sum::<f64>().sqrt();
+    let mut r = f(&x)?;
+    for _ in 0..MAX_ITERATIONS {
+        if r.iter().all(|r| r.abs() < SOLVE_TOLERANCE) {
+            return Some(x);
+        }
+        let n = x.len();
+        let mut jacobian = vec![vec![0.0; n + 1]; n];
+        for k in 0..n {
+            let h = 1e-6 * x[k].abs().max(1e-2);
+            let mut xh = x.clone();
+            xh[k] += h;
+            let rh = f(&xh).or_else(|| {
+                xh[k] = x[k] - h;
+                f(&xh).map(|r0| r.iter().zip(r0).map(|(a, b)| 2.0 * a - b).collect())
+            })?;
+            for (row, (a, b)) in jacobian.iter_mut().zip(rh.iter().zip(r.iter())) {
+                row[k] = (a - b) / h;
+            }
+        }
+        for (row, r) in jacobian.iter_mut().zip(r.iter()) {
+            row[n] = *r;
+        }
+        let dx = gauss(jacobian)?;
+
+        let mut t = 1.0;
+        loop {
+            let candidate: Vec<f64> = x.iter().zip(dx.iter()).map(|(x, d)| x - t * d).collect();
+            if let Some(rc) = f(&candidate) {
+                if norm(&rc) < norm(&r) {
+                    x = candidate;
+                    r = rc;
+                    break;
+                }
+            }
+            t /= 2.0;
+            if t < 1e-6 {
+                return None;
+            }
+        }
+    }
+    None
+}
+
+/// Solve a linear system given as augmented matrix by Gaussian elimination
+/// with partial pivoting
+fn gauss(mut m: Vec<Vec<f64>>) -> Option<Vec<f64>> {
+    let n = m.len();
+    for col in 0..n {
+        let pivot =
+            (col..n).max_by(|&a, &b| m[a][col].abs().partial_cmp(&m[b][col].abs()).unwrap())?;
+        if m[pivot][col].abs() < 1e-15 {
+            return None;
+        }
+        m.swap(col, pivot);
+        let (upper, lower) = m.split_at_mut(col + 1);
+        let pivot = &upper[col];
+        for row in lower.iter_mut() {
+            let factor = row[col] / pivot[col];
+            for (a, b) in row.iter_mut().zip(pivot.iter()).skip(col) {
+                *a -= factor * b;
+            }
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| m[row][k] * x[k]).sum();
+        x[row] = (m[row][n] - sum) / m[row][row];
+    }
+    Some(x)
+}
+
+/// Maximum distance of the points to the elements and of the elements to the
+/// polyline through the points
+fn deviation(geometries: &[Geometry], points: &[(f64, f64)], tolerance: f64) -> f64 {
+    let mut samples = Vec::new();
+    for g in geometries {
+        let length = g.length.get();
+        let curvature = g.max_curvature();
+        // The distance between a chord of length l and an arc of curvature k
+        // is approximately k * l² / 8
+        let n = if curvature > 0.0 {
+            let step = (8.0 * SAMPLE_TOLERANCE_RATIO * tolerance / curvature).sqrt();
+            ((length / step).ceil() as usize).max(4)
+        } else {
+            4
+        };
+        samples.extend((0..=n).map(|i| {
+            let (x, y, _) = g.pose_at(length * i as f64 / n as f64);
+            (x, y)
+        }));
+    }
+
+    let forward = points
+        .iter()
+        .map(|&p| distance_to_polyline(p, &samples))
+        .fold(0.0, f64::max);
+    let backward = samples
+        .iter()
+        .map(|&p| distance_to_polyline(p, points))
+        .fold(0.0, f64::max);
+    forward.max(backward)
+}
+
+fn distance_to_polyline(p: (f64, f64), polyline: &[(f64, f64)]) -> f64 {
+    if polyline.len() == 1 {
+        return (p.0 - polyline[0].0).hypot(p.1 - polyline[0].1);
+    }
+    polyline
+        .windows(2)
+        .map(|w| {
+            let (a, b) = (w[0], w[1]);
+            let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+            let length2 = dx * dx + dy * dy;
+            let t = if length2 > 0.0 {
+                (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length2).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            (p.0 - a.0 - t * dx).hypot(p.1 - a.1 - t * dy)
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Tangent directions at the points of the parabolas through each point and
+/// its neighbours, parametrised by chord length
+fn headings(points: &[(f64, f64)]) -> Vec<f64> {
+    let n = points.len();
+    let direction = |a: (f64, f64), b: (f64, f64)| {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let length = dx.hypot(dy);
+        (dx / length, dy / length, length)
+    };
+    if n == 2 {
+        let (x, y, _) = direction(points[0], points[1]);
+        return vec![y.atan2(x); 2];
+    }
+    (0..n)
+        .map(|i| {
+            // the triple of points around i and the weights of the unit
+            // directions of its chords at the point of interest
+            let c = i.max(1).min(n - 2);
+            let (x1, y1, l1) = direction(points[c - 1], points[c]);
+            let (x2, y2, l2) = direction(points[c], points[c + 1]);
+            let sum = l1 + l2;
+            let (w1, w2) = if i < c {
+                ((2.0 * l1 + l2) / sum, -l1 / sum)
+            } else if i > c {
+                (-l2 / sum, (l1 + 2.0 * l2) / sum)
+            } else {
+                (l2 / sum, l1 / sum)
+            };
+            (w1 * y1 + w2 * y2).atan2(w1 * x1 + w2 * x2)
+        })
+        .collect()
+}
+
+/// Curvatures of the circles through each point and the nearest points at
+/// least `CURVATURE_BASE` before and after it
+fn curvatures(points: &[(f64, f64)]) -> Vec<f64> {
+    let n = points.len();
+    let distance =
+        |a: usize, b: usize| (points[a].0 - points[b].0).hypot(points[a].1 - points[b].1);
+    let mut curvatures: Vec<Option<f64>> = (0..n)
+        .map(|i| {
+            let a = (0..i)
+                .rev()
+                .find(|&a| distance(a, i) >= CURVATURE_BASE)
+                .unwrap_or(0);
+            let c = (i + 1..n)
+                .find(|&c| distance(i, c) >= CURVATURE_BASE)
+                .unwrap_or(n - 1);
+            if a == i || c == i {
+                return None;
+            }
+            let (pa, pb, pc) = (points[a], points[i], points[c]);
+            let cross = (pb.0 - pa.0) * (pc.1 - pb.1) - (pb.1 - pa.1) * (pc.0 - pb.0);
+            Some(2.0 * cross / (distance(a, i) * distance(i, c) * distance(a, c)))
+        })
+        .collect();
+    // the end points take the curvature of their neighbours
+    if n > 2 {
+        curvatures[0] = curvatures[1];
+        curvatures[n - 1] = curvatures[n - 2];
+    }
+    curvatures.into_iter().map(|c| c.unwrap_or(0.0)).collect()
+}
+
+/// Normalise an angle to ]-π, π]
+fn wrap(angle: f64) -> f64 {
+    angle.sin().atan2(angle.cos())
+}
@@ -0,0 +1,847 @@
+//! Procedural generators for canonical road network layouts
+//!
+//! Each generator takes a parameter struct and returns a complete `Root`
+//! including a header whose bounds enclose all reference lines. Roads are
+//! built with `builder::RoadBuilder`, junctions are connected with biarcs and
+//! all road and lane links are filled in.
+use crate::builder::{LaneBuilder, LaneSectionBuilder, RoadBuilder};
+use crate::opendrive::*;
+use crate::types::{ConnectionId, JunctionId, RoadId};
+use lyon_geom::Segment;
+use std::f64::consts::{FRAC_PI_2, PI};
+
+/// Number of points sampled per geometry element to compute the header bounds
+const BOUNDS_SAMPLES: usize = 16;
+
+/// Parameters of a straight multilane highway
+#[derive(Debug, Clone)]
+pub struct HighwayParams {
+    /// m length of the highway
+    pub length: f64,
+    /// number of lanes in each direction
+    pub lanes: usize,
+    /// m width of each lane
+    pub lane_width: f64,
+    /// km/h speed limit
+    pub speed: f64,
+}
+impl Default for HighwayParams {
+    fn default() -> HighwayParams {
+        HighwayParams {
+            length: 1000.0,
+            lanes: 3,
+            lane_width: 3.75,
+            speed: 130.0,
+        }
+    }
+}
+
+/// A straight highway with `lanes` driving lanes in each direction
+pub fn straight_highway(params: &HighwayParams) -> Root {
+    let road = RoadBuilder::new()
+        .name("highway")
+        .id(1)
+        .road_type(0.0, RoadTypeKind::Motorway, Some(kmh(params.speed)))
+        .line(params.length)
+        .lanes(two_way_lanes(params.lanes, params.lane_width))
+        .build();
+
+    root("straight highway", vec![road], vec![])
+}
+
+/// Parameters of a constant-radius curve
+#[derive(Debug, Clone)]
+pub struct CurveParams {
+    /// m length of the straight sections before and after the curve
+    pub approach_length: f64,
+    /// m radius of the curve, positive for left and negative for right turns
+    pub radius: f64,
+    /// rad angle the road turns by
+    pub angle: f64,
+    /// number of lanes in each direction
+    pub lanes: usize,
+    /// m width of each lane
+    pub lane_width: f64,
+    /// km/h speed limit
+    pub speed: f64,
+}
+impl Default for CurveParams {
+    fn default() -> CurveParams {
+        CurveParams {
+            approach_length: 100.0,
+            radius: 200.0,
+            angle: FRAC_PI_2,
+            lanes: 1,
+            lane_width: 3.5,
+            speed: 80.0,
+        }
+    }
+}
+
+/// A straight approach, an arc of constant radius and a straight exit
+pub fn curve(params: &CurveParams) -> Root {
+    let road = RoadBuilder::new()
+        .name("curve")
+        .id(1)
+        .road_type(0.0, RoadTypeKind::Rural, Some(kmh(params.speed)))
+        .line(params.approach_length)
+        .arc(params.radius.abs() * params.angle, 1.0 / params.radius)
+        .line(params.approach_length)
+        .lanes(two_way_lanes(params.lanes, params.lane_width))
+        .build();
+
+    root("curve", vec![road], vec![])
+}
+
+/// An S-curve: two arcs of opposite direction, each turning by `angle`
+pub fn s_curve(params: &CurveParams) -> Root {
+    let length = params.radius.abs() * params.angle;
+    let road = RoadBuilder::new()
+        .name("s-curve")
+        .id(1)
+        .road_type(0.0, RoadTypeKind::Rural, Some(kmh(params.speed)))
+        .line(params.approach_length)
+        .arc(length, 1.0 / params.radius)
+        .arc(length, -1.0 / params.radius)
+        .line(params.approach_length)
+        .lanes(two_way_lanes(params.lanes, params.lane_width))
+        .build();
+
+    root("s-curve", vec![road], vec![])
+}
+
+/// Parameters of an at-grade junction
+#[derive(Debug, Clone)]
+pub struct JunctionParams {
+    /// m distance from the center of the junction to the start of each arm
+    pub size: f64,
+    /// m length of each arm
+    pub arm_length: f64,
+    /// number of lanes in each direction
+    pub lanes: usize,
+    /// m width of each lane
+    pub lane_width: f64,
+    /// km/h speed limit
+    pub speed: f64,
+}
+impl Default for JunctionParams {
+    fn default() -> JunctionParams {
+        JunctionParams {
+            size: 15.0,
+            arm_length: 100.0,
+            lanes: 1,
+            lane_width: 3.5,
+            speed: 50.0,
+        }
+    }
+}
+
+/// A T-junction: a through road along the x-axis with a side road to the south
+pub fn t_junction(params: &JunctionParams) -> Root {
+    junction("T-junction", &[0.0, PI, -FRAC_PI_2], params)
+}
+
+/// An X-junction (four way crossing)
+pub fn x_junction(params: &JunctionParams) -> Root {
+    junction("X-junction", &[0.0, FRAC_PI_2, PI, -FRAC_PI_2], params)
+}
+
+/// A junction with one arm in each of the inertial headings `angles`.
+///
+/// The arms start `size` metres from the origin and point away from it, so
+/// traffic enters the junction on their left lanes. A connecting road links
+/// every arm to every other arm.
+pub fn junction(name: &str, angles: &[f64], params: &JunctionParams) -> Root {
+    let junction_id = 1;
+    let mut roads = Vec::new();
+
+    for (i, &angle) in angles.iter().enumerate() {
+        roads.push(
+            RoadBuilder::new()
+                .name(format!("arm {}", i))
+                .id(i as u32 + 1)
+                .road_type(0.0, RoadTypeKind::Town, Some(kmh(params.speed)))
+                .start(params.size * angle.cos(), params.size * angle.sin(), angle)
+                .line(params.arm_length)
+                .lanes(two_way_lanes(params.lanes, params.lane_width))
+                .predecessor(ElementType::Junction, junction_id, ContactPoint::Start)
+                .build(),
+        );
+    }
+
+    let mut connections = Vec::new();
+    for (i, &from) in angles.iter().enumerate() {
+        for (j, &to) in angles.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            let id = roads.len() as u32 + 1;
+            let mut section = LaneSectionBuilder::new();
+            for lane in 1..=params.lanes as i32 {
+                section = section.right(
+                    LaneBuilder::new(LaneType::Driving, params.lane_width)
+                        .predecessor(lane)
+                        .successor(-lane),
+                );
+            }
+
+            roads.push(
+                RoadBuilder::new()
+                    .name(format!("arm {} to arm {}", i, j))
+                    .id(id)
+                    .junction(junction_id)
+                    .road_type(0.0, RoadTypeKind::Town, Some(kmh(params.speed)))
+                    .start(
+                        params.size * from.cos(),
+                        params.size * from.sin(),
+                        from + PI,
+                    )
+                    .connect_to(params.size * to.cos(), params.size * to.sin(), to)
+                    .expect("junction arms face the centre")
+                    .lanes(section)
+                    .predecessor(ElementType::Road, i as u32 + 1, ContactPoint::Start)
+                    .successor(ElementType::Road, j as u32 + 1, ContactPoint::Start)
+                    .build(),
+            );
+            connections.push(Connection {
+                id: ConnectionId::from(connections.len() as u32),
+                incoming_road: RoadId::from(i as u32 + 1),
+                connecting_road: Some(RoadId::from(id)),
+                linked_road: None,
+                contact_point: ContactPoint::Start,
+                lane_links: (1..=params.lanes as i32)
+                    .map(|lane| JunctionLaneLink {
+                        from: lane,
+                        to: -lane,
+                    })
+                    .collect(),
+            });
+        }
+    }
+
+    let junction = Junction {
+        name: name.to_string(),
+        id: JunctionId::from(junction_id),
+        junction_type: JunctionType::Default,
+        connections,
+    };
+    root(name, roads, vec![junction])
+}
+
+/// Parameters of a single-lane roundabout
+#[derive(Debug, Clone)]
+pub struct RoundaboutParams {
+    /// m radius of the ring's reference line, i.e. its inner edge
+    pub radius: f64,
+    /// number of arms, evenly distributed around the ring
+    pub arms: usize,
+    /// m length of each arm
+    pub arm_length: f64,
+    /// m width of each lane
+    pub lane_width: f64,
+    /// km/h speed limit
+    pub speed: f64,
+}
+impl Default for RoundaboutParams {
+    fn default() -> RoundaboutParams {
+        RoundaboutParams {
+            radius: 20.0,
+            arms: 4,
+            arm_length: 100.0,
+            lane_width: 3.5,
+            speed: 30.0,
+        }
+    }
+}
+
+/// A roundabout with counter-clockwise traffic on a single lane ring.
+///
+/// The ring is split into one road between each pair of neighbouring arms.
+/// At every arm there is a junction which connects the arm to the ring and
+/// continues the ring through the junction.
+pub fn roundabout(params: &RoundaboutParams) -> Root {
+    let n = params.arms;
+    let r = params.radius;
+    let gap = 3.0 * params.lane_width;
+    // half the angle of the ring covered by each junction
+    let delta = (gap / r).min(PI / (2 * n) as f64);
+    let arm_start = r + params.lane_width + gap;
+    let angle = |k: usize| 2.0 * PI * (k % n) as f64 / n as f64;
+    let ring_pose = |a: f64| (r * a.cos(), r * a.sin(), a + FRAC_PI_2);
+    let speed = || Some(kmh(params.speed));
+
+    let arm_id = |k: usize| (k % n + 1) as u32;
+    let ring_id = |k: usize| (n + k % n + 1) as u32;
+    let junction_id = |k: usize| (k % n + 1) as u32;
+    let single_lane = |predecessor: i32, successor: i32| {
+        LaneSectionBuilder::new().right(
+            LaneBuilder::new(LaneType::Driving, params.lane_width)
+                .predecessor(predecessor)
+                .successor(successor),
+        )
+    };
+
+    let mut roads = Vec::new();
+    for k in 0..n {
+        let a = angle(k);
+        roads.push(
+            RoadBuilder::new()
+                .name(format!("arm {}", k))
+                .id(arm_id(k))
+                .road_type(0.0, RoadTypeKind::Town, speed())
+                .start(arm_start * a.cos(), arm_start * a.sin(), a)
+                .line(params.arm_length)
+                .lanes(two_way_lanes(1, params.lane_width))
+                .predecessor(ElementType::Junction, junction_id(k), ContactPoint::Start)
+                .build(),
+        );
+    }
+
+    for k in 0..n {
+        let (x, y, hdg) = ring_pose(angle(k) + delta);
+        let sweep = 2.0 * PI / n as f64 - 2.0 * delta;
+        roads.push(
+            RoadBuilder::new()
+                .name(format!("ring {}", k))
+                .id(ring_id(k))
+                .road_type(0.0, RoadTypeKind::Town, speed())
+                .start(x, y, hdg)
+                .arc(r * sweep, 1.0 / r)
+                .lanes(
+                    LaneSectionBuilder::new().right(
+                        LaneBuilder::new(LaneType::Driving, params.lane_width)
+                            .mark(RoadMarkType::Solid),
+                    ),
+                )
+                .predecessor(ElementType::Junction, junction_id(k), ContactPoint::Start)
+                .successor(
+                    ElementType::Junction,
+                    junction_id(k + 1),
+                    ContactPoint::Start,
+                )
+                .build(),
+        );
+    }
+
+    let mut junctions = Vec::new();
+    for k in 0..n {
+        let a = angle(k);
+        let ring_in = ring_id(k + n - 1);
+        let ring_out = ring_id(k);
+        let arm = arm_id(k);
+        let (in_x, in_y, in_hdg) = ring_pose(a - delta);
+        let (out_x, out_y, out_hdg) = ring_pose(a + delta);
+        let (arm_x, arm_y) = (arm_start * a.cos(), arm_start * a.sin());
+
+        let id = roads.len() as u32 + 1;
+        let connecting = vec![
+            // continue on the ring
+            RoadBuilder::new()
+                .name(format!("ring through {}", k))
+                .start(in_x, in_y, in_hdg)
+                .connect_to(out_x, out_y, out_hdg)
+                .expect("ring poses lie on a circle")
+                .lanes(single_lane(-1, -1))
+                .predecessor(ElementType::Road, ring_in, ContactPoint::End)
+                .successor(ElementType::Road, ring_out, ContactPoint::Start),
+            // leave the ring
+            RoadBuilder::new()
+                .name(format!("exit {}", k))
+                .start(in_x, in_y, in_hdg)
+                .connect_to(arm_x, arm_y, a)
+                .expect("arms start outside of the ring")
+                .lanes(single_lane(-1, -1))
+                .predecessor(ElementType::Road, ring_in, ContactPoint::End)
+                .successor(ElementType::Road, arm, ContactPoint::Start),
+            // enter the ring
+            RoadBuilder::new()
+                .name(format!("entry {}", k))
+                .start(arm_x, arm_y, a + PI)
+                .connect_to(out_x, out_y, out_hdg)
+                .expect("arms start outside of the ring")
+                .lanes(single_lane(1, -1))
+                .predecessor(ElementType::Road, arm, ContactPoint::Start)
+                .successor(ElementType::Road, ring_out, ContactPoint::Start),
+        ];
+        let incoming = [(ring_in, -1), (ring_in, -1), (arm, 1)];
+
+        let mut connections = Vec::new();
+        for (i, (builder, &(incoming_road, from))) in
+            connecting.into_iter().zip(incoming.iter()).enumerate()
+        {
+            let connecting_road = id + i as u32;
+            roads.push(
+                builder
+                    .id(connecting_road)
+                    .junction(junction_id(k))
+                    .road_type(0.0, RoadTypeKind::Town, speed())
+                    .build(),
+            );
+            connections.push(Connection {
+                id: ConnectionId::from(i as u32),
+                incoming_road: RoadId::from(incoming_road),
+                connecting_road: Some(RoadId::from(connecting_road)),
+                linked_road: None,
+                contact_point: ContactPoint::Start,
+                lane_links: vec![JunctionLaneLink { from, to: -1 }],
+            });
+        }
+
+        junctions.push(Junction {
+            name: format!("roundabout entry {}", k),
+            id: JunctionId::from(junction_id(k)),
+            junction_type: JunctionType::Default,
+            connections,
+        });
+    }
+
+    root("roundabout", roads, junctions)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RampKind {
+    /// a ramp leaving the mainline
+    Off,
+    /// a ramp joining the mainline
+    On,
+}
+
+/// Parameters of a motorway on- or off-ramp
+#[derive(Debug, Clone)]
+pub struct RampParams {
+    pub kind: RampKind,
+    /// m length of the mainline before and after the junction
+    pub mainline_length: f64,
+    /// m length of the junction along the mainline
+    pub junction_length: f64,
+    /// m length of the ramp road
+    pub ramp_length: f64,
+    /// rad angle between the ramp and the mainline
+    pub ramp_angle: f64,
+    /// m lateral distance between the ramp and the outermost mainline lane
+    /// where the ramp meets the junction
+    pub ramp_offset: f64,
+    /// number of mainline lanes
+    pub lanes: usize,
+    /// m width of each lane
+    pub lane_width: f64,
+    /// km/h mainline speed limit
+    pub speed: f64,
+    /// km/h ramp speed limit
+    pub ramp_speed: f64,
+}
+impl Default for RampParams {
+    fn default() -> RampParams {
+        RampParams {
+            kind: RampKind::Off,
+            mainline_length: 300.0,
+            junction_length: 120.0,
+            ramp_length: 200.0,
+            ramp_angle: 0.15,
+            ramp_offset: 6.0,
+            lanes: 2,
+            lane_width: 3.75,
+            speed: 120.0,
+            ramp_speed: 80.0,
+        }
+    }
+}
+
+/// One carriageway of a motorway with a single lane on- or off-ramp on the
+/// right hand side
+pub fn ramp(params: &RampParams) -> Root {
+    const MAIN_IN: u32 = 1;
+    const MAIN_OUT: u32 = 2;
+    const RAMP: u32 = 3;
+    const MAIN_CONNECTING: u32 = 4;
+    const RAMP_CONNECTING: u32 = 5;
+    const JUNCTION: u32 = 1;
+
+    let n = params.lanes as i32;
+    let w = params.lane_width;
+    let x0 = params.mainline_length;
+    let x1 = x0 + params.junction_length;
+    // lateral position of the outermost lane's inner border
+    let t_outer = -(n - 1) as f64 * w;
+    let ramp_hdg = match params.kind {
+        RampKind::Off => -params.ramp_angle,
+        RampKind::On => params.ramp_angle,
+    };
+    let ramp_type = || match params.kind {
+        RampKind::Off => LaneType::OffRamp,
+        RampKind::On => LaneType::OnRamp,
+    };
+    let ramp_lane = || {
+        LaneSectionBuilder::new().right(LaneBuilder::new(ramp_type(), w).mark(RoadMarkType::Solid))
+    };
+    let motorway = || Some(kmh(params.speed));
+    let ramp_speed = || Some(kmh(params.ramp_speed));
+
+    let mut connecting_lanes = LaneSectionBuilder::new();
+    for lane in 1..=n {
+        connecting_lanes = connecting_lanes.right(
+            LaneBuilder::new(LaneType::Driving, w)
+                .predecessor(-lane)
+                .successor(-lane),
+        );
+    }
+
+    let mut roads = vec![
+        RoadBuilder::new()
+            .name("mainline in")
+            .id(MAIN_IN)
+            .road_type(0.0, RoadTypeKind::Motorway, motorway())
+            .line(params.mainline_length)
+            .lanes(one_way_lanes(params.lanes, w))
+            .successor(ElementType::Junction, JUNCTION, ContactPoint::Start)
+            .build(),
+        RoadBuilder::new()
+            .name("mainline out")
+            .id(MAIN_OUT)
+            .road_type(0.0, RoadTypeKind::Motorway, motorway())
+            .start(x1, 0.0, 0.0)
+            .line(params.mainline_length)
+            .lanes(one_way_lanes(params.lanes, w))
+            .predecessor(ElementType::Junction, JUNCTION, ContactPoint::Start)
+            .build(),
+        RoadBuilder::new()
+            .name("mainline through")
+            .id(MAIN_CONNECTING)
+            .junction(JUNCTION)
+            .road_type(0.0, RoadTypeKind::Motorway, motorway())
+            .start(x0, 0.0, 0.0)
+            .line(params.junction_length)
+            .lanes(connecting_lanes)
+            .predecessor(ElementType::Road, MAIN_IN, ContactPoint::End)
+            .successor(ElementType::Road, MAIN_OUT, ContactPoint::Start)
+            .build(),
+    ];
+    let mut connections = vec![Connection {
+        id: ConnectionId::from(0),
+        incoming_road: RoadId::from(MAIN_IN),
+        connecting_road: Some(RoadId::from(MAIN_CONNECTING)),
+        linked_road: None,
+        contact_point: ContactPoint::Start,
+        lane_links: (1..=n)
+            .map(|lane| JunctionLaneLink {
+                from: -lane,
+                to: -lane,
+            })
+            .collect(),
+    }];
+
+    match params.kind {
+        RampKind::Off => {
+            let (rx, ry) = (x1, t_outer - params.ramp_offset);
+            roads.push(
+                RoadBuilder::new()
+                    .name("off-ramp")
+                    .id(RAMP)
+                    .road_type(0.0, RoadTypeKind::Motorway, ramp_speed())
+                    .start(rx, ry, ramp_hdg)
+                    .line(params.ramp_length)
+                    .lanes(ramp_lane())
+                    .predecessor(ElementType::Junction, JUNCTION, ContactPoint::Start)
+                    .build(),
+            );
+            roads.push(
+                RoadBuilder::new()
+                    .name("diverge")
+                    .id(RAMP_CONNECTING)
+                    .junction(JUNCTION)
+                    .road_type(0.0, RoadTypeKind::Motorway, ramp_speed())
+                    .start(x0, 0.0, 0.0)
+                    .connect_to(rx, ry, ramp_hdg)
+                    .expect("ramp starts ahead of the diverge")
+                    .apply(|b| lane_offset_transition(b, t_outer, 0.0))
+                    .lanes(
+                        LaneSectionBuilder::new().right(
+                            LaneBuilder::new(ramp_type(), w)
+                                .predecessor(-n)
+                                .successor(-1),
+                        ),
+                    )
+                    .predecessor(ElementType::Road, MAIN_IN, ContactPoint::End)
+                    .successor(ElementType::Road, RAMP, ContactPoint::Start)
+                    .build(),
+            );
+            connections.push(Connection {
+                id: ConnectionId::from(1),
+                incoming_road: RoadId::from(MAIN_IN),
+                connecting_road: Some(RoadId::from(RAMP_CONNECTING)),
+                linked_road: None,
+                contact_point: ContactPoint::Start,
+                lane_links: vec![JunctionLaneLink { from: -n, to: -1 }],
+            });
+        }
+        RampKind::On => {
+            let (rx, ry) = (x0, t_outer - params.ramp_offset);
+            roads.push(
+                RoadBuilder::new()
+                    .name("on-ramp")
+                    .id(RAMP)
+                    .road_type(0.0, RoadTypeKind::Motorway, ramp_speed())
+                    .start(
+                        rx - params.ramp_length * ramp_hdg.cos(),
+                        ry - params.ramp_length * ramp_hdg.sin(),
+                        ramp_hdg,
+                    )
+                    .line(params.ramp_length)
+                    .lanes(ramp_lane())
+                    .successor(ElementType::Junction, JUNCTION, ContactPoint::Start)
+                    .build(),
+            );
+            roads.push(
+                RoadBuilder::new()
+                    .name("merge")
+                    .id(RAMP_CONNECTING)
+                    .junction(JUNCTION)
+                    .road_type(0.0, RoadTypeKind::Motorway, ramp_speed())
+                    .start(rx, ry, ramp_hdg)
+                    .connect_to(x1, 0.0, 0.0)
+                    .expect("merge ends ahead of the ramp")
+                    .apply(|b| lane_offset_transition(b, 0.0, t_outer))
+                    .lanes(
+                        LaneSectionBuilder::new().right(
+                            LaneBuilder::new(ramp_type(), w)
+                                .predecessor(-1)
+                                .successor(-n),
+                        ),
+                    )
+                    .predecessor(ElementType::Road, RAMP, ContactPoint::End)
+                    .successor(ElementType::Road, MAIN_OUT, ContactPoint::Start)
+                    .build(),
+            );
+            connections.push(Connection {
+                id: ConnectionId::from(1),
+                incoming_road: RoadId::from(RAMP),
+                connecting_road: Some(RoadId::from(RAMP_CONNECTING)),
+                linked_road: None,
+                contact_point: ContactPoint::Start,
+                lane_links: vec![JunctionLaneLink { from: -1, to: -1 }],
+            });
+        }
+    }
+
+    let junction = Junction {
+        name: "ramp".to_string(),
+        id: JunctionId::from(JUNCTION),
+        junction_type: JunctionType::Default,
+        connections,
+    };
+    let name = match params.kind {
+        RampKind::Off => "off-ramp",
+        RampKind::On => "on-ramp",
+    };
+    root(name, roads, vec![junction])
+}
+
+/// Parameters of a cul-de-sac
+#[derive(Debug, Clone)]
+pub struct CulDeSacParams {
+    /// m length of the two-way approach road
+    pub approach_length: f64,
+    /// m radius of the turning circle's reference line
+    pub bulb_radius: f64,
+    /// m radius of the right turn leading into the turning circle
+    pub turn_radius: f64,
+    /// rad angle of the right turn leading into the turning circle
+    pub turn_angle: f64,
+    /// m length of the spirals between straight and curved elements
+    pub transition_length: f64,
+    /// m width of each lane
+    pub lane_width: f64,
+    /// km/h speed limit
+    pub speed: f64,
+}
+impl Default for CulDeSacParams {
+    fn default() -> CulDeSacParams {
+        CulDeSacParams {
+            approach_length: 100.0,
+            bulb_radius: 12.5,
+            turn_radius: 17.0,
+            turn_angle: 0.7,
+            transition_length: 5.0,
+            lane_width: 3.25,
+            speed: 30.0,
+        }
+    }
+}
+
+/// A two-way road ending in a one-way turning loop, similar to the bundled
+/// `CulDeSac.xodr`.
+///
+/// The loop turns right, around the turning circle to the left and back right
+/// onto the approach road. It is symmetric about the axis of the approach
+/// road, which determines the length of the straight between the turns.
+pub fn cul_de_sac(params: &CulDeSacParams) -> Root {
+    let w = params.lane_width;
+    let k1 = -1.0 / params.turn_radius;
+    let k2 = 1.0 / params.bulb_radius;
+    let l = params.transition_length;
+    let turn_arc = (params.turn_angle / k1.abs() - l).max(0.0);
+
+    let right_turn = |b: RoadBuilder| b.spiral(l, 0.0, k1).arc(turn_arc, k1).spiral(l, k1, 0.0);
+    let loop_start = || RoadBuilder::new().start(params.approach_length, 0.0, 0.0);
+    let end_hdg = |road: &Road| road.plan_view.geometries.last().unwrap().end_hdg().radians;
+
+    // Trace the first half of the loop without the straight between the turns,
+    // up to the point where its heading is perpendicular to the approach road.
+    let turn_hdg = end_hdg(&right_turn(loop_start()).build());
+    let half_arc = (FRAC_PI_2 - end_hdg(&right_turn(loop_start()).spiral(l, 0.0, k2).build())) / k2;
+    let half = right_turn(loop_start())
+        .spiral(l, 0.0, k2)
+        .arc(half_arc, k2)
+        .build();
+    let mid = half.plan_view.geometries.last().unwrap().as_segment().to();
+    // The straight moves the midpoint onto the axis of the approach road
+    let straight = (mid.y / -turn_hdg.sin()).max(0.0);
+
+    let approach = RoadBuilder::new()
+        .name("approach")
+        .id(1)
+        .road_type(0.0, RoadTypeKind::Town, Some(kmh(params.speed)))
+        .line(params.approach_length)
+        .lanes(
+            LaneSectionBuilder::new()
+                .center_mark(RoadMarkType::Broken)
+                .left(LaneBuilder::new(LaneType::Driving, w).mark(RoadMarkType::Solid))
+                .right(
+                    LaneBuilder::new(LaneType::Driving, w)
+                        .successor(-1)
+                        .mark(RoadMarkType::Solid),
+                ),
+        )
+        .successor(ElementType::Road, 2, ContactPoint::Start)
+        .build();
+
+    let lp = right_turn(
+        RoadBuilder::new()
+            .name("loop")
+            .id(2)
+            .road_type(0.0, RoadTypeKind::Town, Some(kmh(params.speed)))
+            .start(params.approach_length, 0.0, 0.0),
+    )
+    .line(straight)
+    .spiral(l, 0.0, k2)
+    .arc(2.0 * half_arc, k2)
+    .spiral(l, k2, 0.0)
+    .line(straight);
+    let lp = right_turn(lp)
+        .lanes(
+            LaneSectionBuilder::new().right(
+                LaneBuilder::new(LaneType::Driving, w)
+                    .predecessor(-1)
+                    .successor(1)
+                    .mark(RoadMarkType::Solid),
+            ),
+        )
+        .predecessor(ElementType::Road, 1, ContactPoint::End)
+        .successor(ElementType::Road, 1, ContactPoint::End)
+        .build();
+
+    root("cul-de-sac", vec![approach, lp], vec![])
+}
+
+/// Add a lane offset record that moves the lanes smoothly from `from` at the
+/// start to `to` at the current end of the reference line
+fn lane_offset_transition(builder: RoadBuilder, from: f64, to: f64) -> RoadBuilder {
+    let l = builder.s();
+    let delta = to - from;
+    builder.lane_offset(
+        0.0,
+        from,
+        0.0,
+        3.0 * delta / (l * l),
+        -2.0 * delta / (l * l * l),
+    )
+}
+
+fn kmh(speed: f64) -> Speed {
+    Speed {
+        max: speed,
+        unit: Some(SpeedUnit::KilometersPerHour),
+    }
+}
+
+/// `lanes` driving lanes in each direction with road marks
+fn two_way_lanes(lanes: usize, width: f64) -> LaneSectionBuilder {
+    let center = if lanes > 1 {
+        RoadMarkType::Solid
+    } else {
+        RoadMarkType::Broken
+    };
+    let mut section = LaneSectionBuilder::new().center_mark(center);
+    for i in 1..=lanes {
+        section = section
+            .left(driving_lane(i == lanes, width))
+            .right(driving_lane(i == lanes, width));
+    }
+    section
+}
+
+/// `lanes` driving lanes on the right side, i.e. in direction of the
+/// reference line
+fn one_way_lanes(lanes: usize, width: f64) -> LaneSectionBuilder {
+    let mut section = LaneSectionBuilder::new().center_mark(RoadMarkType::Solid);
+    for i in 1..=lanes {
+        section = section.right(driving_lane(i == lanes, width));
+    }
+    section
+}
+
+fn driving_lane(outermost: bool, width: f64) -> LaneBuilder {
+    let mark = if outermost {
+        RoadMarkType::Solid
+    } else {
+        RoadMarkType::Broken
+    };
+    LaneBuilder::new(LaneType::Driving, width).mark(mark)
+}
+
+/// Assemble a `Root` with a header describing `roads`, dated at the Unix
+/// epoch so that the same parameters always generate the same network
+pub(crate) fn root(name: &str, roads: Vec<Road>, junctions: Vec<Junction>) -> Root {
+    let mut north = f64::MIN;
+    let mut south = f64::MAX;
+    let mut east = f64::MIN;
+    let mut west = f64::MAX;
+    for geometry in roads.iter().flat_map(|r| r.plan_view.geometries.iter()) {
+        let segment = geometry.as_segment();
+        for i in 0..=BOUNDS_SAMPLES {
+            let p = segment.sample(i as f64 / BOUNDS_SAMPLES as f64);
+            north = north.max(p.y);
+            south = south.min(p.y);
+            east = east.max(p.x);
+            west = west.min(p.x);
+        }
+    }
+    if roads.is_empty() {
+        north = 0.0;
+        south = 0.0;
+        east = 0.0;
+        west = 0.0;
+    }
+
+    Root {
+        header: Header {
+            rev_major: 1,
+            rev_minor: 4,
+            name: name.to_string(),
+            version: "1.00".to_string(),
+            date: std::time::UNIX_EPOCH.into(),
+            north,
+            south,
+            east,
+            west,
+            max_road: roads.len() as u32,
+            max_junc: junctions.len() as u32,
+            max_prg: 0,
+            vendor: None,
+            geo_reference: None,
+            offset: None,
+        },
+        roads,
+        junctions,
+    }
+}
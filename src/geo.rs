@@ -0,0 +1,428 @@
+//! Conversion between OpenDRIVE inertial coordinates and geodetic WGS84
+//! coordinates.
+//!
+//! The `<geoReference>` record holds a PROJ definition string. The common
+//! projections used for road networks are supported without linking to PROJ:
+//!
+//! - `+proj=tmerc` using the fourth order Krüger series, accurate to well
+//!   below a millimetre within 3000 km of the central meridian
+//! - `+proj=utm` with `+zone` and `+south`
+//! - `+proj=merc` (ellipsoidal) with either `+k` or `+lat_ts`
+//! - `+proj=longlat`
+//!
+//! Datum shifts (`+towgs84`, `+nadgrids`) are not applied, i.e. the datum of
+//! the projection is assumed to coincide with WGS84.
+use crate::errors::GeoReferenceError;
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+/// A reference ellipsoid given by its semi-major axis and flattening
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipsoid {
+    /// m semi-major axis
+    pub a: f64,
+    /// flattening
+    pub f: f64,
+}
+
+impl Ellipsoid {
+    pub const WGS84: Ellipsoid = Ellipsoid {
+        a: 6_378_137.0,
+        f: 1.0 / 298.257_223_563,
+    };
+    pub const GRS80: Ellipsoid = Ellipsoid {
+        a: 6_378_137.0,
+        f: 1.0 / 298.257_222_101,
+    };
+    pub const INTERNATIONAL: Ellipsoid = Ellipsoid {
+        a: 6_378_388.0,
+        f: 1.0 / 297.0,
+    };
+    pub const BESSEL: Ellipsoid = Ellipsoid {
+        a: 6_377_397.155,
+        f: 1.0 / 299.152_812_8,
+    };
+    pub const CLARKE_1866: Ellipsoid = Ellipsoid {
+        a: 6_378_206.4,
+        f: 1.0 / 294.978_698_2,
+    };
+    pub const SPHERE: Ellipsoid = Ellipsoid {
+        a: 6_370_997.0,
+        f: 0.0,
+    };
+
+    /// Look up an ellipsoid by its PROJ `+ellps` name
+    pub fn from_proj_name(name: &str) -> Option<Ellipsoid> {
+        match name {
+            "WGS84" => Some(Ellipsoid::WGS84),
+            "GRS80" => Some(Ellipsoid::GRS80),
+            "intl" => Some(Ellipsoid::INTERNATIONAL),
+            "bessel" => Some(Ellipsoid::BESSEL),
+            "clrk66" => Some(Ellipsoid::CLARKE_1866),
+            "sphere" => Some(Ellipsoid::SPHERE),
+            _ => None,
+        }
+    }
+
+    /// Look up the ellipsoid of a datum by its PROJ `+datum` name
+    pub fn from_proj_datum(name: &str) -> Option<Ellipsoid> {
+        match name {
+            "WGS84" => Some(Ellipsoid::WGS84),
+            "NAD83" | "ETRS89" => Some(Ellipsoid::GRS80),
+            "NAD27" => Some(Ellipsoid::CLARKE_1866),
+            "potsdam" | "hermannskogel" => Some(Ellipsoid::BESSEL),
+            _ => None,
+        }
+    }
+
+    /// first eccentricity
+    pub fn e(&self) -> f64 {
+        (self.f * (2.0 - self.f)).sqrt()
+    }
+}
+
+/// A map projection parsed from a PROJ definition string
+#[derive(Debug, Clone, PartialEq)]
+pub enum Projection {
+    TransverseMercator(TransverseMercator),
+    Mercator(Mercator),
+    /// Coordinates are longitude and latitude in degrees
+    LongLat,
+}
+
+impl Projection {
+    /// Parse a PROJ definition string such as
+    /// `+proj=utm +zone=32 +ellps=WGS84 +datum=WGS84 +units=m +no_defs`
+    pub fn from_proj_string(definition: &str) -> Result<Projection, GeoReferenceError> {
+        let params = ProjParams::parse(definition)?;
+
+        let proj = params
+            .get("proj")
+            .ok_or_else(|| GeoReferenceError::InvalidParameter("missing +proj".to_string()))?;
+        if let Some(units) = params.get("units") {
+            if units != "m" {
+                return Err(GeoReferenceError::InvalidParameter(format!(
+                    "unsupported +units={}",
+                    units
+                )));
+            }
+        }
+
+        let ellipsoid = params.ellipsoid()?;
+        match proj {
+            "tmerc" => Ok(Projection::TransverseMercator(TransverseMercator::new(
+                ellipsoid,
+                params.number("lat_0")?.unwrap_or(0.0),
+                params.number("lon_0")?.unwrap_or(0.0),
+                params.scale()?.unwrap_or(1.0),
+                params.number("x_0")?.unwrap_or(0.0),
+                params.number("y_0")?.unwrap_or(0.0),
+            ))),
+            "utm" => {
+                let zone = params
+                    .number("zone")?
+                    .filter(|zone| *zone >= 1.0 && *zone <= 60.0 && zone.fract() == 0.0)
+                    .ok_or_else(|| {
+                        GeoReferenceError::InvalidParameter("missing or invalid +zone".to_string())
+                    })?;
+                let y_0 = if params.has("south") {
+                    10_000_000.0
+                } else {
+                    0.0
+                };
+                Ok(Projection::TransverseMercator(TransverseMercator::new(
+                    ellipsoid,
+                    0.0,
+                    zone * 6.0 - 183.0,
+                    0.9996,
+                    500_000.0,
+                    y_0,
+                )))
+            }
+            "merc" => {
+                let k_0 = match (params.scale()?, params.number("lat_ts")?) {
+                    (Some(k), _) => k,
+                    (None, Some(lat_ts)) => {
+                        let (sin, cos) = lat_ts.to_radians().sin_cos();
+                        let e = ellipsoid.e();
+                        cos / (1.0 - e * e * sin * sin).sqrt()
+                    }
+                    (None, None) => 1.0,
+                };
+                Ok(Projection::Mercator(Mercator {
+                    ellipsoid,
+                    lon_0: params.number("lon_0")?.unwrap_or(0.0),
+                    k_0,
+                    x_0: params.number("x_0")?.unwrap_or(0.0),
+                    y_0: params.number("y_0")?.unwrap_or(0.0),
+                }))
+            }
+            "longlat" | "latlong" | "lonlat" | "latlon" => Ok(Projection::LongLat),
+            other => Err(GeoReferenceError::UnsupportedProjection(other.to_string())),
+        }
+    }
+
+    /// Project geodetic latitude and longitude in degrees to x/y
+    pub fn forward(&self, lat: f64, lon: f64) -> (f64, f64) {
+        match self {
+            Projection::TransverseMercator(p) => p.forward(lat, lon),
+            Projection::Mercator(p) => p.forward(lat, lon),
+            Projection::LongLat => (lon, lat),
+        }
+    }
+
+    /// Convert projected x/y to geodetic latitude and longitude in degrees
+    pub fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        match self {
+            Projection::TransverseMercator(p) => p.inverse(x, y),
+            Projection::Mercator(p) => p.inverse(x, y),
+            Projection::LongLat => (y, x),
+        }
+    }
+}
+
+/// Transverse Mercator projection using the Krüger series
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransverseMercator {
+    pub ellipsoid: Ellipsoid,
+    /// deg latitude of origin
+    pub lat_0: f64,
+    /// deg central meridian
+    pub lon_0: f64,
+    /// scale factor on the central meridian
+    pub k_0: f64,
+    /// m false easting
+    pub x_0: f64,
+    /// m false northing
+    pub y_0: f64,
+    /// rectifying radius
+    big_a: f64,
+    alpha: [f64; 4],
+    beta: [f64; 4],
+    /// northing of the latitude of origin on the central meridian
+    m_0: f64,
+}
+
+impl TransverseMercator {
+    pub fn new(
+        ellipsoid: Ellipsoid,
+        lat_0: f64,
+        lon_0: f64,
+        k_0: f64,
+        x_0: f64,
+        y_0: f64,
+    ) -> TransverseMercator {
+        let n = ellipsoid.f / (2.0 - ellipsoid.f);
+        let (n2, n3, n4) = (n * n, n * n * n, n * n * n * n);
+        let mut tm = TransverseMercator {
+            ellipsoid,
+            lat_0,
+            lon_0,
+            k_0,
+            x_0,
+            y_0,
+            big_a: ellipsoid.a / (1.0 + n) * (1.0 + n2 / 4.0 + n2 * n2 / 64.0),
+            alpha: [
+                n / 2.0 - 2.0 * n2 / 3.0 + 5.0 * n3 / 16.0 + 41.0 * n4 / 180.0,
+                13.0 * n2 / 48.0 - 3.0 * n3 / 5.0 + 557.0 * n4 / 1440.0,
+                61.0 * n3 / 240.0 - 103.0 * n4 / 140.0,
+                49561.0 * n4 / 161_280.0,
+            ],
+            beta: [
+                n / 2.0 - 2.0 * n2 / 3.0 + 37.0 * n3 / 96.0 - n4 / 360.0,
+                n2 / 48.0 + n3 / 15.0 - 437.0 * n4 / 1440.0,
+                17.0 * n3 / 480.0 - 37.0 * n4 / 840.0,
+                4397.0 * n4 / 161_280.0,
+            ],
+            m_0: 0.0,
+        };
+        tm.m_0 = tm.series(lat_0, lon_0).1;
+        tm
+    }
+
+    /// Unscaled easting and northing relative to the central meridian and
+    /// the equator
+    fn series(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let n = self.ellipsoid.f / (2.0 - self.ellipsoid.f);
+        let c = 2.0 * n.sqrt() / (1.0 + n);
+        let phi = lat.to_radians();
+        let dlambda = (lon - self.lon_0).to_radians();
+
+        let t = (phi.sin().atanh() - c * (c * phi.sin()).atanh()).sinh();
+        let xi = t.atan2(dlambda.cos());
+        let eta = (dlambda.sin() / (1.0 + t * t).sqrt()).atanh();
+
+        let mut e = eta;
+        let mut n = xi;
+        for (j, alpha) in self.alpha.iter().enumerate() {
+            let j2 = 2.0 * (j + 1) as f64;
+            e += alpha * (j2 * xi).cos() * (j2 * eta).sinh();
+            n += alpha * (j2 * xi).sin() * (j2 * eta).cosh();
+        }
+        (self.big_a * e, self.big_a * n)
+    }
+
+    pub fn forward(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let (e, n) = self.series(lat, lon);
+        (
+            self.x_0 + self.k_0 * e,
+            self.y_0 + self.k_0 * (n - self.m_0),
+        )
+    }
+
+    pub fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let xi = ((y - self.y_0) / self.k_0 + self.m_0) / self.big_a;
+        let eta = (x - self.x_0) / self.k_0 / self.big_a;
+
+        let mut xi_p = xi;
+        let mut eta_p = eta;
+        for (j, beta) in self.beta.iter().enumerate() {
+            let j2 = 2.0 * (j + 1) as f64;
+            xi_p -= beta * (j2 * xi).sin() * (j2 * eta).cosh();
+            eta_p -= beta * (j2 * xi).cos() * (j2 * eta).sinh();
+        }
+
+        let chi = (xi_p.sin() / eta_p.cosh()).asin();
+        // invert the conformal latitude by fixed-point iteration, which
+        // converges by a factor of about e² per step
+        let e = self.ellipsoid.e();
+        let mut phi = chi;
+        for _ in 0..15 {
+            let es = e * phi.sin();
+            let next = 2.0
+                * ((FRAC_PI_4 + chi / 2.0).tan() * ((1.0 + es) / (1.0 - es)).powf(e / 2.0)).atan()
+                - FRAC_PI_2;
+            let done = (next - phi).abs() < 1e-15;
+            phi = next;
+            if done {
+                break;
+            }
+        }
+        let dlambda = eta_p.sinh().atan2(xi_p.cos());
+
+        (phi.to_degrees(), self.lon_0 + dlambda.to_degrees())
+    }
+}
+
+/// Ellipsoidal Mercator projection
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mercator {
+    pub ellipsoid: Ellipsoid,
+    /// deg central meridian
+    pub lon_0: f64,
+    /// scale factor on the equator
+    pub k_0: f64,
+    /// m false easting
+    pub x_0: f64,
+    /// m false northing
+    pub y_0: f64,
+}
+
+impl Mercator {
+    pub fn forward(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let a = self.ellipsoid.a;
+        let e = self.ellipsoid.e();
+        let phi = lat.to_radians();
+        let es = e * phi.sin();
+        let y = ((FRAC_PI_4 + phi / 2.0).tan() * ((1.0 - es) / (1.0 + es)).powf(e / 2.0)).ln();
+        (
+            self.x_0 + self.k_0 * a * (lon - self.lon_0).to_radians(),
+            self.y_0 + self.k_0 * a * y,
+        )
+    }
+
+    pub fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let a = self.ellipsoid.a;
+        let e = self.ellipsoid.e();
+        let t = (-(y - self.y_0) / (self.k_0 * a)).exp();
+
+        let mut phi = FRAC_PI_2 - 2.0 * t.atan();
+        for _ in 0..15 {
+            let es = e * phi.sin();
+            let next = FRAC_PI_2 - 2.0 * (t * ((1.0 - es) / (1.0 + es)).powf(e / 2.0)).atan();
+            let done = (next - phi).abs() < 1e-14;
+            phi = next;
+            if done {
+                break;
+            }
+        }
+
+        let lon = self.lon_0 + ((x - self.x_0) / (self.k_0 * a)).to_degrees();
+        (phi.to_degrees(), lon)
+    }
+}
+
+/// The `+key=value` and `+flag` parameters of a PROJ definition string
+struct ProjParams<'a> {
+    params: Vec<(&'a str, Option<&'a str>)>,
+}
+
+impl<'a> ProjParams<'a> {
+    fn parse(definition: &'a str) -> Result<ProjParams<'a>, GeoReferenceError> {
+        let mut params = Vec::new();
+        for token in definition.split_whitespace() {
+            if !token.starts_with('+') {
+                return Err(GeoReferenceError::InvalidParameter(token.to_string()));
+            }
+            let mut kv = token[1..].splitn(2, '=');
+            let key = kv.next().unwrap_or("");
+            params.push((key, kv.next()));
+        }
+        Ok(ProjParams { params })
+    }
+
+    fn get(&self, key: &str) -> Option<&'a str> {
+        self.params
+            .iter()
+            .find(|(k, _)| *k == key)
+            .and_then(|(_, v)| *v)
+    }
+
+    fn has(&self, key: &str) -> bool {
+        self.params.iter().any(|(k, _)| *k == key)
+    }
+
+    fn number(&self, key: &str) -> Result<Option<f64>, GeoReferenceError> {
+        match self.get(key) {
+            Some(value) => value
+                .parse::<f64>()
+                .map(Some)
+                .map_err(|_| GeoReferenceError::InvalidParameter(format!("+{}={}", key, value))),
+            None => Ok(None),
+        }
+    }
+
+    fn scale(&self) -> Result<Option<f64>, GeoReferenceError> {
+        match self.number("k_0")? {
+            Some(k) => Ok(Some(k)),
+            None => self.number("k"),
+        }
+    }
+
+    /// The ellipsoid given by `+a`/`+b`/`+rf`/`+f`, `+ellps` or `+datum`, in
+    /// that order of precedence. Defaults to WGS84.
+    fn ellipsoid(&self) -> Result<Ellipsoid, GeoReferenceError> {
+        if let Some(a) = self.number("a")? {
+            let f = if let Some(rf) = self.number("rf")? {
+                1.0 / rf
+            } else if let Some(f) = self.number("f")? {
+                f
+            } else if let Some(b) = self.number("b")? {
+                (a - b) / a
+            } else {
+                0.0
+            };
+            return Ok(Ellipsoid { a, f });
+        }
+        if let Some(name) = self.get("ellps") {
+            return Ellipsoid::from_proj_name(name).ok_or_else(|| {
+                GeoReferenceError::InvalidParameter(format!("unknown +ellps={}", name))
+            });
+        }
+        if let Some(name) = self.get("datum") {
+            return Ellipsoid::from_proj_datum(name).ok_or_else(|| {
+                GeoReferenceError::InvalidParameter(format!("unknown +datum={}", name))
+            });
+        }
+        Ok(Ellipsoid::WGS84)
+    }
+}
@@ -0,0 +1,277 @@
+//! Export of a road network as a GeoJSON `FeatureCollection`
+//!
+//! The collection contains
+//!
+//! - the reference line of every road as a `LineString`
+//! - every lane of every lane section as a `Polygon`
+//! - every road mark as a `LineString` along the outer border of its lane
+//! - objects and signals as `Point`s
+//!
+//! Each feature carries a `kind` property (`referenceLine`, `lane`,
+//! `roadMark`, `object` or `signal`) together with the ids needed to look the
+//! element up in the OpenDRIVE file.
+use crate::errors::{GeoReferenceError, SampleError};
+use crate::geo::Projection;
+use crate::opendrive::*;
+use crate::parallel;
+use serde_json::{json, Value};
+
+/// Coordinate system of the exported geometry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coordinates {
+    /// OpenDRIVE inertial x/y
+    Local,
+    /// WGS84 longitude/latitude using the `geoReference` of the header
+    Geodetic,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeoJsonOptions {
+    pub coordinates: Coordinates,
+    /// m maximum deviation of the exported polylines from the exact curves
+    pub tolerance: f64,
+}
+
+impl Default for GeoJsonOptions {
+    fn default() -> GeoJsonOptions {
+        GeoJsonOptions {
+            coordinates: Coordinates::Local,
+            tolerance: 0.05,
+        }
+    }
+}
+
+/// Convert the road network into a GeoJSON `FeatureCollection`. Fails
+/// without a usable geo reference or if the tolerance is not positive and
+/// finite.
+pub fn to_geojson(root: &Root, options: &GeoJsonOptions) -> Result<Value, failure::Error> {
+    let transform = Transform::new(root, options.coordinates)?;
+    let mut features = Vec::new();
+    for road in parallel::map(&root.roads, |road| {
+        let mut features = Vec::new();
+        road_features(road, &transform, options.tolerance, &mut features).map(|_| features)
+    }) {
+        features.extend(road?);
+    }
+    Ok(json!({
+        "type": "FeatureCollection",
+        "features": features,
+    }))
+}
+
+/// Write the road network as GeoJSON
+pub fn write_geojson<W: std::io::Write>(
+    root: &Root,
+    options: &GeoJsonOptions,
+    writer: W,
+) -> Result<(), failure::Error> {
+    let collection = to_geojson(root, options)?;
+    serde_json::to_writer(writer, &collection)?;
+    Ok(())
+}
+
+fn road_features(
+    road: &Road,
+    transform: &Transform,
+    tolerance: f64,
+    features: &mut Vec<Value>,
+) -> Result<(), SampleError> {
+    let length = road.length.get();
+    let line: Vec<Value> = road
+        .sample_s(0.0, length, tolerance)?
+        .into_iter()
+        .map(|s| transform.point(road.position_at(s, 0.0)))
+        .collect();
+    features.push(feature(
+        json!({ "type": "LineString", "coordinates": line }),
+        json!({
+            "kind": "referenceLine",
+            "road": road.id,
+            "name": road.name,
+            "junction": road.junction,
+            "length": length,
+        }),
+    ));
+
+    let sections = road
+        .lanes
+        .as_ref()
+        .map_or(0, |lanes| lanes.lane_sections.len());
+    for index in 0..sections {
+        lane_features(road, index, transform, tolerance, features)?;
+    }
+
+    for object in road.objects.iter().flat_map(|o| o.objects.iter()) {
+        features.push(feature(
+            json!({
+                "type": "Point",
+                "coordinates": transform.point(road.position_at(object.s, object.t)),
+            }),
+            json!({
+                "kind": "object",
+                "road": road.id,
+                "id": object.id,
+                "name": object.name,
+                "type": object.object_type,
+                "s": object.s,
+                "t": object.t,
+            }),
+        ));
+    }
+
+    for signal in road.signals.iter().flat_map(|s| s.signals.iter()) {
+        features.push(feature(
+            json!({
+                "type": "Point",
+                "coordinates": transform.point(road.position_at(signal.s, signal.t)),
+            }),
+            json!({
+                "kind": "signal",
+                "road": road.id,
+                "id": signal.id,
+                "name": signal.name,
+                "country": signal.country,
+                "type": signal.signal_type,
+                "subtype": signal.subtype,
+                "value": signal.value,
+                "dynamic": signal.dynamic,
+                "s": signal.s,
+                "t": signal.t,
+            }),
+        ));
+    }
+    Ok(())
+}
+
+fn lane_features(
+    road: &Road,
+    index: usize,
+    transform: &Transform,
+    tolerance: f64,
+    features: &mut Vec<Value>,
+) -> Result<(), SampleError> {
+    let section = &road.lanes.as_ref().unwrap().lane_sections[index];
+    let (s0, s1) = road.lane_section_range(index);
+    let stations = road.sample_s(s0, s1, tolerance)?;
+    let borders: Vec<Vec<LaneBorders>> = stations
+        .iter()
+        .map(|&s| road.lane_borders(index, s))
+        .collect();
+    let speed = road.speed_at(s0);
+
+    let lanes = section
+        .left_lanes()
+        .chain(Some(&section.center.lane[..]).into_iter().flatten())
+        .chain(section.right_lanes());
+    for lane in lanes {
+        let border_of = |b: &[LaneBorders]| *b.iter().find(|b| b.id == lane.id).unwrap();
+
+        if lane.id != 0 {
+            let mut ring: Vec<(f64, f64)> = stations
+                .iter()
+                .zip(borders.iter())
+                .map(|(&s, b)| road.position_at(s, border_of(b).outer))
+                .collect();
+            ring.extend(
+                stations
+                    .iter()
+                    .zip(borders.iter())
+                    .rev()
+                    .map(|(&s, b)| road.position_at(s, border_of(b).inner)),
+            );
+            ring.push(ring[0]);
+            // RFC 7946 exterior rings are counterclockwise, the outer border
+            // of left lanes comes first clockwise
+            if signed_area(&ring) < 0.0 {
+                ring.reverse();
+            }
+            let ring: Vec<Value> = ring.into_iter().map(|p| transform.point(p)).collect();
+            features.push(feature(
+                json!({ "type": "Polygon", "coordinates": [ring] }),
+                json!({
+                    "kind": "lane",
+                    "road": road.id,
+                    "section": index,
+                    "lane": lane.id,
+                    "type": lane.lane_type,
+                    "speed": speed,
+                }),
+            ));
+        }
+
+        for (i, mark) in lane.road_marks.iter().enumerate() {
+            if mark.road_mark_type == RoadMarkType::None {
+                continue;
+            }
+            let start = s0 + mark.s_offset;
+            let end = lane
+                .road_marks
+                .get(i + 1)
+                .map_or(s1, |next| s0 + next.s_offset);
+            let line: Vec<Value> = road
+                .sample_s(start, end, tolerance)?
+                .into_iter()
+                .map(|s| {
+                    let t = border_of(&road.lane_borders(index, s)).outer;
+                    transform.point(road.position_at(s, t))
+                })
+                .collect();
+            features.push(feature(
+                json!({ "type": "LineString", "coordinates": line }),
+                json!({
+                    "kind": "roadMark",
+                    "road": road.id,
+                    "section": index,
+                    "lane": lane.id,
+                    "type": mark.road_mark_type,
+                    "weight": mark.weight,
+                    "color": mark.color,
+                    "width": mark.width,
+                }),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn feature(geometry: Value, properties: Value) -> Value {
+    json!({
+        "type": "Feature",
+        "geometry": geometry,
+        "properties": properties,
+    })
+}
+
+/// Conversion from inertial co-ordinates to output co-ordinates
+enum Transform {
+    Local,
+    Geodetic(Projection, Option<Offset>),
+}
+
+/// Area enclosed by the closed `ring`, positive if it is counterclockwise
+fn signed_area(ring: &[(f64, f64)]) -> f64 {
+    ring.windows(2)
+        .map(|pair| pair[0].0 * pair[1].1 - pair[1].0 * pair[0].1)
+        .sum::<f64>()
+        / 2.0
+}
+
+impl Transform {
+    fn new(root: &Root, coordinates: Coordinates) -> Result<Transform, GeoReferenceError> {
+        Ok(match coordinates {
+            Coordinates::Local => Transform::Local,
+            Coordinates::Geodetic => Transform::Geodetic(root.projection()?, root.header.offset),
+        })
+    }
+
+    fn point(&self, (x, y): (f64, f64)) -> Value {
+        match self {
+            Transform::Local => json!([x, y]),
+            Transform::Geodetic(projection, offset) => {
+                let (x, y) = offset.map_or((x, y), |offset| offset.apply(x, y));
+                let (lat, lon) = projection.inverse(x, y);
+                json!([lon, lat])
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Connectivity of lanes across lane sections, roads and junctions
+//!
+//! Lanes are followed in their driving direction. In right hand traffic
+//! right lanes (negative ids) are driven in the direction of increasing s and
+//! left lanes (positive ids) in the direction of decreasing s, in left hand
+//! traffic it is the other way round. A lane with a reversed direction
+//! attribute is driven against the convention for its side of the road. The
+//! successors of a lane are found from
+//!
+//! - the lane link records within a road, defaulting to the lane with the
+//!   same id in the next lane section
+//! - the road link records together with the lane link records at the start
+//!   and end of a road
+//! - the connection and lane link records of a junction
+use crate::opendrive::*;
+use crate::types::RoadId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A lane identified by the index of its road in `Root::roads`, the index of
+/// its lane section and its lane id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct LaneKey {
+    pub road: usize,
+    pub section: usize,
+    pub lane: i32,
+}
+
+/// Whether a lane of `road` is driven in the direction of increasing s
+pub fn is_forward(road: &Road, lane: &Lane) -> bool {
+    let right_hand = road.traffic_rule() == TrafficRule::RightHand;
+    let forward = (lane.id < 0) == right_hand;
+    match lane.direction {
+        Some(LaneDirection::Reversed) => !forward,
+        _ => forward,
+    }
+}
+
+/// Directed graph of lanes in driving direction
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LaneGraph {
+    successors: BTreeMap<LaneKey, Vec<LaneKey>>,
+    predecessors: BTreeMap<LaneKey, Vec<LaneKey>>,
+}
+
+impl LaneGraph {
+    pub fn new(root: &Root) -> LaneGraph {
+        let mut graph = LaneGraph::default();
+        for (road_index, road) in root.roads.iter().enumerate() {
+            let sections = match road.lanes {
+                Some(ref lanes) => &lanes.lane_sections,
+                None => continue,
+            };
+            for (section_index, section) in sections.iter().enumerate() {
+                for lane in section.left_lanes().chain(section.right_lanes()) {
+                    let key = LaneKey {
+                        road: road_index,
+                        section: section_index,
+                        lane: lane.id,
+                    };
+                    graph.successors.entry(key).or_default();
+                    graph.predecessors.entry(key).or_default();
+                    for next in successors(root, key, lane) {
+                        graph.add_edge(key, next);
+                    }
+                }
+            }
+        }
+        graph
+    }
+
+    fn add_edge(&mut self, from: LaneKey, to: LaneKey) {
+        let successors = self.successors.entry(from).or_default();
+        if !successors.contains(&to) {
+            successors.push(to);
+        }
+        let predecessors = self.predecessors.entry(to).or_default();
+        if !predecessors.contains(&from) {
+            predecessors.push(from);
+        }
+    }
+
+    /// All lanes of the network in ascending order
+    pub fn lanes(&self) -> impl Iterator<Item = &LaneKey> {
+        self.successors.keys()
+    }
+
+    /// The lanes that can be entered at the end of `lane`
+    pub fn successors(&self, lane: &LaneKey) -> &[LaneKey] {
+        self.successors.get(lane).map_or(&[], |s| &s[..])
+    }
+
+    /// The lanes from which `lane` can be entered
+    pub fn predecessors(&self, lane: &LaneKey) -> &[LaneKey] {
+        self.predecessors.get(lane).map_or(&[], |s| &s[..])
+    }
+}
+
+/// The successors of a single lane in driving direction
+fn successors(root: &Root, key: LaneKey, lane: &Lane) -> Vec<LaneKey> {
+    let road = &root.roads[key.road];
+    let sections = &road.lanes.as_ref().unwrap().lane_sections;
+    let forward = is_forward(road, lane);
+
+    let linked_id = lane.link.as_ref().and_then(|link| {
+        if forward {
+            link.successor.as_ref()
+        } else {
+            link.predecessor.as_ref()
+        }
+        .map(|element| element.id)
+    });
+
+    // next lane section within the same road
+    let next_section = if forward {
+        Some(key.section + 1).filter(|&i| i < sections.len())
+    } else {
+        key.section.checked_sub(1)
+    };
+    if let Some(next_section) = next_section {
+        let id = linked_id.unwrap_or(key.lane);
+        return lane_at(root, key.road, next_section, id, forward)
+            .into_iter()
+            .collect();
+    }
+
+    let element = road.link.as_ref().and_then(|link| {
+        if forward {
+            link.successor.as_ref()
+        } else {
+            link.predecessor.as_ref()
+        }
+    });
+    let element = match element {
+        Some(element) => element,
+        None => return vec![],
+    };
+    match element.element_type {
+        ElementType::Road => {
+            let id = match linked_id {
+                Some(id) => id,
+                None => return vec![],
+            };
+            road_index(root, &RoadId::from(element.element_id.clone()))
+                .and_then(|next| enter_road(root, next, &element.contact_point, id))
+                .into_iter()
+                .collect()
+        }
+        ElementType::Junction => {
+            let junction = match root.junctions.iter().find(|j| j.id == element.element_id) {
+                Some(junction) => junction,
+                None => return vec![],
+            };
+            let mut next = Vec::new();
+            for connection in junction.connections.iter() {
+                if connection.incoming_road != road.id {
+                    continue;
+                }
+                let connecting = match connection
+                    .connected_road()
+                    .and_then(|connected| road_index(root, connected))
+                {
+                    Some(connecting) => connecting,
+                    None => continue,
+                };
+                for lane_link in connection.lane_links.iter() {
+                    if lane_link.from != key.lane {
+                        continue;
+                    }
+                    next.extend(enter_road(
+                        root,
+                        connecting,
+                        &connection.contact_point,
+                        lane_link.to,
+                    ));
+                }
+            }
+            next
+        }
+    }
+}
+
+/// The lane `id` of road `road` entered at `contact_point`, if it is driven
+/// away from the contact point
+fn enter_road(root: &Root, road: usize, contact_point: &ContactPoint, id: i32) -> Option<LaneKey> {
+    let sections = root.roads[road].lanes.as_ref()?.lane_sections.len();
+    match contact_point {
+        ContactPoint::Start => lane_at(root, road, 0, id, true),
+        ContactPoint::End => lane_at(root, road, sections.checked_sub(1)?, id, false),
+    }
+}
+
+/// The lane `id` in the given lane section if it exists and its driving
+/// direction is `forward`
+fn lane_at(root: &Root, road: usize, section: usize, id: i32, forward: bool) -> Option<LaneKey> {
+    if id == 0 {
+        return None;
+    }
+    let record = &root.roads[road];
+    let lane = record
+        .lanes
+        .as_ref()?
+        .lane_sections
+        .get(section)?
+        .lane(id)?;
+    if is_forward(record, lane) != forward {
+        return None;
+    }
+    Some(LaneKey {
+        road,
+        section,
+        lane: id,
+    })
+}
+
+fn road_index(root: &Root, id: &RoadId) -> Option<usize> {
+    root.roads.iter().position(|road| road.id == *id)
+}
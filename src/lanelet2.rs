@@ -0,0 +1,1365 @@
+//! Export of a road network to the OSM XML format of Lanelet2
+//!
+//! Every lane section is cut at the start of every road mark and road type
+//! record. Each cut is exported as
+//!
+//! - one boundary linestring per lane border, oriented in the driving
+//!   direction of the adjacent lanes and tagged according to the road mark of
+//!   the border. The center line is exported twice, once per direction, if
+//!   there are lanes on both sides.
+//! - one lanelet per lane of a drivable, biking or walking lane type with the
+//!   inner border as left bound and the outer border as right bound
+//!
+//! Lanes are driven according to right hand traffic. Lanelet2 connects
+//! lanelets which share the end and start points of their bounds, so the
+//! border points at the end of a lane are merged with the border points at
+//! the start of its successors in the `LaneGraph`.
+//!
+//! Road type records with a speed become `speed_limit` regulatory elements
+//! and traffic light signals (type `1000xxx` of the German catalogue used by
+//! OpenDRIVE) become `traffic_light` regulatory elements, both referenced by
+//! the lanelets they apply to.
+//!
+//! The import reads a Lanelet2 OSM document and groups lanelets that share
+//! their bounds into the cross section of a road: the right lanes follow each
+//! other through shared left/right bounds and the left lanes are the
+//! lanelets driving in the opposite direction along the left bound of the
+//! innermost right lane. That left bound becomes the reference line of the
+//! road, fitted with line, arc, spiral and paramPoly3 elements, and the widths
+//! of the lanes are fitted with cubic polynomials to the lateral distance of
+//! their bounds. Roads are linked where lanelets succeed each other. Roads
+//! branching off an end shared with other roads become connecting roads of a
+//! junction.
+use crate::builder::{self, LaneBuilder, LaneSectionBuilder, RoadBuilder};
+use crate::errors::{GeoReferenceError, Lanelet2Error, SampleError};
+use crate::fitting::{fit_plan_view, FitOptions};
+use crate::generators;
+use crate::geo::{Ellipsoid, Projection, TransverseMercator};
+use crate::lane_graph::{is_forward, LaneGraph, LaneKey};
+use crate::opendrive::*;
+use crate::types::{ConnectionId, ElementId, JunctionId, RoadId};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+
+/// Width of the bottom edge of a traffic light if the signal doesn't specify
+/// one [m]
+const DEFAULT_TRAFFIC_LIGHT_WIDTH: f64 = 0.5;
+/// Distance below which two s positions are considered equal [m]
+const S_EPSILON: f64 = 1e-9;
+/// Distance between the reference line samples used as start values when
+/// projecting points onto a reference line [m]
+const PROJECTION_STEP: f64 = 0.5;
+/// Distance between the samples the lane widths are fitted to [m]
+const WIDTH_SAMPLE_STEP: f64 = 1.0;
+
+#[derive(Debug, Clone)]
+pub struct Lanelet2Options {
+    /// m maximum deviation of the boundary linestrings from the exact lane
+    /// borders
+    pub tolerance: f64,
+    /// latitude and longitude in degrees of the inertial origin, used with a
+    /// local transverse mercator projection if the header has no
+    /// `geoReference`
+    pub origin: (f64, f64),
+}
+
+impl Default for Lanelet2Options {
+    fn default() -> Lanelet2Options {
+        Lanelet2Options {
+            tolerance: 0.1,
+            origin: (0.0, 0.0),
+        }
+    }
+}
+
+/// Convert the road network into a Lanelet2 OSM document. Fails without a
+/// usable geo reference or if the tolerance is not positive and finite.
+pub fn to_lanelet2(root: &Root, options: &Lanelet2Options) -> Result<String, failure::Error> {
+    let (projection, offset) = match root.projection() {
+        Ok(projection) => (projection, root.header.offset),
+        Err(GeoReferenceError::Missing) => {
+            let (lat, lon) = options.origin;
+            let tm = TransverseMercator::new(Ellipsoid::WGS84, lat, lon, 1.0, 0.0, 0.0);
+            (Projection::TransverseMercator(tm), None)
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut map = Map::default();
+    let mut ends = BTreeMap::new();
+    for (index, road) in root.roads.iter().enumerate() {
+        map.add_road(index, road, options.tolerance, &mut ends)?;
+    }
+    map.connect(&LaneGraph::new(root), &ends);
+    Ok(map.to_osm(|x, y| {
+        let (x, y) = offset.map_or((x, y), |offset| offset.apply(x, y));
+        projection.inverse(x, y)
+    }))
+}
+
+/// Write the road network as Lanelet2 OSM document
+pub fn write_lanelet2<W: std::io::Write>(
+    root: &Root,
+    options: &Lanelet2Options,
+    mut writer: W,
+) -> Result<(), failure::Error> {
+    writer.write_all(to_lanelet2(root, options)?.as_bytes())?;
+    Ok(())
+}
+
+type Tags = Vec<(&'static str, String)>;
+
+struct Way {
+    nodes: Vec<usize>,
+    tags: Tags,
+}
+
+struct Relation {
+    /// (is way, index, role)
+    members: Vec<(bool, usize, &'static str)>,
+    tags: Tags,
+}
+
+#[derive(Default)]
+struct Map {
+    nodes: Vec<(f64, f64, f64)>,
+    ways: Vec<Way>,
+    relations: Vec<Relation>,
+}
+
+impl Map {
+    fn node(&mut self, point: (f64, f64, f64)) -> usize {
+        self.nodes.push(point);
+        self.nodes.len() - 1
+    }
+
+    fn way(&mut self, nodes: Vec<usize>, tags: Tags) -> usize {
+        self.ways.push(Way { nodes, tags });
+        self.ways.len() - 1
+    }
+
+    fn relation(&mut self, members: Vec<(bool, usize, &'static str)>, tags: Tags) -> usize {
+        self.relations.push(Relation { members, tags });
+        self.relations.len() - 1
+    }
+
+    /// Add the lanelets of a road, recording the first and last lanelet of
+    /// every lane in driving direction in `ends`
+    fn add_road(
+        &mut self,
+        road_index: usize,
+        road: &Road,
+        tolerance: f64,
+        ends: &mut BTreeMap<LaneKey, (usize, usize)>,
+    ) -> Result<(), SampleError> {
+        let sections = match road.lanes {
+            Some(ref lanes) => lanes.lane_sections.len(),
+            None => return Ok(()),
+        };
+
+        let speed_limits: Vec<(f64, Option<usize>)> = road
+            .road_types
+            .iter()
+            .map(|road_type| {
+                let limit = road_type
+                    .speed
+                    .as_ref()
+                    .map(|speed| self.speed_limit(road, road_type.s, speed));
+                (road_type.s, limit)
+            })
+            .collect();
+        let traffic_lights: Vec<(&Signal, usize)> = road
+            .signals
+            .iter()
+            .flat_map(|signals| signals.signals.iter())
+            .filter(|signal| signal.signal_type.starts_with("1000"))
+            .map(|signal| (signal, self.traffic_light(road, signal)))
+            .collect();
+
+        for index in 0..sections {
+            let key = LaneKey {
+                road: road_index,
+                section: index,
+                lane: 0,
+            };
+            let lanelets =
+                self.add_section(key, road, tolerance, &speed_limits, &traffic_lights)?;
+            let section = &road.lanes.as_ref().unwrap().lane_sections[index];
+            for (lane, lanelets) in lanelets {
+                let (first, last) = (lanelets[0], *lanelets.last().unwrap());
+                let ends_of_lane = if is_forward(road, section.lane(lane).unwrap()) {
+                    (first, last)
+                } else {
+                    (last, first)
+                };
+                ends.insert(LaneKey { lane, ..key }, ends_of_lane);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the boundaries and lanelets of a lane section, returning the
+    /// lanelets of every lane in order of increasing s
+    fn add_section(
+        &mut self,
+        key: LaneKey,
+        road: &Road,
+        tolerance: f64,
+        speed_limits: &[(f64, Option<usize>)],
+        traffic_lights: &[(&Signal, usize)],
+    ) -> Result<BTreeMap<i32, Vec<usize>>, SampleError> {
+        let section = &road.lanes.as_ref().unwrap().lane_sections[key.section];
+        let (s0, s1) = road.lane_section_range(key.section);
+        let lanes: Vec<&Lane> = section
+            .left_lanes()
+            .chain(section.center.lane.iter())
+            .chain(section.right_lanes())
+            .collect();
+
+        let mut cuts: Vec<f64> = lanes
+            .iter()
+            .flat_map(|lane| lane.road_marks.iter().map(|mark| s0 + mark.s_offset))
+            .chain(road.road_types.iter().map(|road_type| road_type.s))
+            .filter(|&s| s > s0 + S_EPSILON && s < s1 - S_EPSILON)
+            .collect();
+        cuts.push(s0);
+        cuts.push(s1);
+        sort_dedup(&mut cuts);
+        let mut stations = road.sample_s(s0, s1, tolerance)?;
+        stations.extend(cuts.iter().cloned());
+        sort_dedup(&mut stations);
+
+        // Points along every lane border, shared by all cuts
+        let mut border_nodes: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+        for &s in stations.iter() {
+            for border in road.lane_borders(key.section, s) {
+                let node = self.node(road.surface_point(s, border.outer));
+                border_nodes.entry(border.id).or_default().push(node);
+            }
+        }
+        // Borders bounding a lane in its driving direction, by lane id of
+        // the border and driving direction
+        let mut needed: BTreeSet<(i32, bool)> = BTreeSet::new();
+        for lane in lanes.iter().filter(|lane| lane.id != 0) {
+            let forward = is_forward(road, lane);
+            needed.insert((lane.id, forward));
+            needed.insert((lane.id - lane.id.signum(), forward));
+        }
+
+        let mut lanelets: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+        for (&a, &b) in cuts.iter().zip(cuts.iter().skip(1)) {
+            let station = |s: f64| {
+                stations
+                    .iter()
+                    .position(|&x| (x - s).abs() < S_EPSILON)
+                    .unwrap()
+            };
+            let (i0, i1) = (station(a), station(b));
+
+            // Boundaries keyed by lane id of the border and driving direction
+            let mut bounds: BTreeMap<(i32, bool), usize> = BTreeMap::new();
+            for lane in lanes.iter() {
+                let mark = lane
+                    .road_marks
+                    .iter()
+                    .take_while(|mark| s0 + mark.s_offset <= a + S_EPSILON)
+                    .last();
+                let nodes = &border_nodes[&lane.id][i0..=i1];
+                if needed.contains(&(lane.id, true)) {
+                    let way = self.way(nodes.to_vec(), line_tags(mark, lane.id > 0));
+                    bounds.insert((lane.id, true), way);
+                }
+                if needed.contains(&(lane.id, false)) {
+                    let reversed = nodes.iter().rev().cloned().collect();
+                    let way = self.way(reversed, line_tags(mark, lane.id <= 0));
+                    bounds.insert((lane.id, false), way);
+                }
+            }
+
+            let road_type = road
+                .road_types
+                .iter()
+                .take_while(|road_type| road_type.s <= a + S_EPSILON)
+                .last();
+            let speed_limit = speed_limits
+                .iter()
+                .take_while(|(s, _)| *s <= a + S_EPSILON)
+                .last()
+                .and_then(|(_, limit)| *limit);
+            let speed = road.speed_at(a);
+
+            for lane in lanes.iter().filter(|lane| lane.id != 0) {
+                let subtype = match lanelet_subtype(&lane.lane_type, road_type) {
+                    Some(subtype) => subtype,
+                    None => continue,
+                };
+                let forward = is_forward(road, lane);
+                let inner = lane.id - lane.id.signum();
+                // the inner border is on the left of right lanes driven in
+                // s direction and of left lanes driven against it
+                let (left, right) = if (lane.id < 0) == forward {
+                    (inner, lane.id)
+                } else {
+                    (lane.id, inner)
+                };
+                let left = bounds[&(left, forward)];
+                let right = bounds[&(right, forward)];
+
+                let mut members = vec![(true, left, "left"), (true, right, "right")];
+                let mut tags: Tags = vec![
+                    ("type", "lanelet".to_string()),
+                    ("subtype", subtype.to_string()),
+                    ("location", location(road_type).to_string()),
+                ];
+                let one_way = match lane.lane_type {
+                    LaneType::Bidirectional | LaneType::Sidewalk => "no",
+                    _ => "yes",
+                };
+                tags.push(("one_way", one_way.to_string()));
+                if subtype == "road" || subtype == "highway" {
+                    if let Some(speed) = speed {
+                        tags.push(("speed_limit", kilometers_per_hour(speed)));
+                    }
+                    members.extend(speed_limit.map(|limit| (false, limit, "regulatory_element")));
+                    for (signal, light) in traffic_lights.iter() {
+                        let direction = match signal.orientation {
+                            Orientation::Positive => forward,
+                            Orientation::Negative => !forward,
+                            Orientation::None => true,
+                        };
+                        let at_end = b >= road.length.get() - S_EPSILON && signal.s >= b;
+                        if direction && ((signal.s >= a && signal.s < b) || at_end) {
+                            members.push((false, *light, "regulatory_element"));
+                        }
+                    }
+                }
+                tags.push(("opendrive:road", road.id.to_string()));
+                tags.push(("opendrive:lane_section", key.section.to_string()));
+                tags.push(("opendrive:lane", lane.id.to_string()));
+
+                let lanelet = self.relation(members, tags);
+                lanelets.entry(lane.id).or_default().push(lanelet);
+            }
+        }
+        Ok(lanelets)
+    }
+
+    /// Add a speed limit regulatory element referring to a virtual traffic
+    /// sign on the reference line at s
+    fn speed_limit(&mut self, road: &Road, s: f64, speed: &Speed) -> usize {
+        let value = kilometers_per_hour(speed.max_meters_per_second());
+        let (x, y) = road.position_at(s, 0.0);
+        let z = road.elevation_at(s);
+        let a = self.node((x, y, z));
+        let b = self.node((x, y, z + 1.0));
+        let sign = self.way(
+            vec![a, b],
+            vec![
+                ("type", "traffic_sign".to_string()),
+                ("subtype", "speed_limit".to_string()),
+                ("speed_limit", value.clone()),
+                ("virtual", "yes".to_string()),
+            ],
+        );
+        self.relation(
+            vec![(true, sign, "refers")],
+            vec![
+                ("type", "regulatory_element".to_string()),
+                ("subtype", "speed_limit".to_string()),
+                ("speed_limit", value),
+            ],
+        )
+    }
+
+    /// Add a traffic light regulatory element referring to the bottom edge
+    /// of the light, oriented from left to right as seen by the driver
+    fn traffic_light(&mut self, road: &Road, signal: &Signal) -> usize {
+        let half = signal.width.unwrap_or(DEFAULT_TRAFFIC_LIGHT_WIDTH) / 2.0;
+        let z = road.elevation_at(signal.s) + signal.z_offset;
+        let mut edge = [signal.t + half, signal.t - half];
+        if signal.orientation == Orientation::Negative {
+            edge.reverse();
+        }
+        let nodes = edge
+            .iter()
+            .map(|&t| {
+                let (x, y) = road.position_at(signal.s, t);
+                self.node((x, y, z))
+            })
+            .collect();
+        let mut tags: Tags = vec![
+            ("type", "traffic_light".to_string()),
+            ("subtype", "red_yellow_green".to_string()),
+        ];
+        if let Some(height) = signal.height {
+            tags.push(("height", height.to_string()));
+        }
+        let light = self.way(nodes, tags);
+        self.relation(
+            vec![(true, light, "refers")],
+            vec![
+                ("type", "regulatory_element".to_string()),
+                ("subtype", "traffic_light".to_string()),
+                ("opendrive:signal", signal.id.to_string()),
+            ],
+        )
+    }
+
+    /// The left and right bound of a lanelet
+    fn bounds(&self, lanelet: usize) -> (&Way, &Way) {
+        let members = &self.relations[lanelet].members;
+        let bound = |role| {
+            let &(_, way, _) = members.iter().find(|m| m.2 == role).unwrap();
+            &self.ways[way]
+        };
+        (bound("left"), bound("right"))
+    }
+
+    /// Merge the end points of the bounds of every lanelet with the start
+    /// points of the bounds of its successors
+    fn connect(&mut self, graph: &LaneGraph, ends: &BTreeMap<LaneKey, (usize, usize)>) {
+        let mut parent: Vec<usize> = (0..self.nodes.len()).collect();
+        for (key, &(_, last)) in ends.iter() {
+            for next in graph.successors(key) {
+                let first = match ends.get(next) {
+                    Some(&(first, _)) => first,
+                    None => continue,
+                };
+                let (left0, right0) = self.bounds(last);
+                let (left1, right1) = self.bounds(first);
+                union(&mut parent, *left0.nodes.last().unwrap(), left1.nodes[0]);
+                union(&mut parent, *right0.nodes.last().unwrap(), right1.nodes[0]);
+            }
+        }
+        for way in self.ways.iter_mut() {
+            for node in way.nodes.iter_mut() {
+                *node = find(&mut parent, *node);
+            }
+            way.nodes.dedup();
+        }
+    }
+
+    fn to_osm<F: Fn(f64, f64) -> (f64, f64)>(&self, to_geodetic: F) -> String {
+        let mut used = vec![false; self.nodes.len()];
+        for way in self.ways.iter() {
+            for &node in way.nodes.iter() {
+                used[node] = true;
+            }
+        }
+        // Node, way and relation ids are unique across all primitives
+        let node_id = |i: usize| i + 1;
+        let way_id = |i: usize| self.nodes.len() + i + 1;
+        let relation_id = |i: usize| self.nodes.len() + self.ways.len() + i + 1;
+
+        let mut osm = String::new();
+        osm.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        osm.push_str("<osm version=\"0.6\" generator=\"opendrive\">\n");
+        for (i, &(x, y, z)) in self.nodes.iter().enumerate() {
+            if !used[i] {
+                continue;
+            }
+            let (lat, lon) = to_geodetic(x, y);
+            writeln!(
+                osm,
+                r#"  <node id="{}" lat="{:.10}" lon="{:.10}">"#,
+                node_id(i),
+                lat,
+                lon
+            )
+            .unwrap();
+            write_tag(&mut osm, "ele", &format!("{:.3}", z));
+            write_tag(&mut osm, "local_x", &format!("{:.4}", x));
+            write_tag(&mut osm, "local_y", &format!("{:.4}", y));
+            osm.push_str("  </node>\n");
+        }
+        for (i, way) in self.ways.iter().enumerate() {
+            writeln!(osm, r#"  <way id="{}">"#, way_id(i)).unwrap();
+            for &node in way.nodes.iter() {
+                writeln!(osm, r#"    <nd ref="{}"/>"#, node_id(node)).unwrap();
+            }
+            for (k, v) in way.tags.iter() {
+                write_tag(&mut osm, k, v);
+            }
+            osm.push_str("  </way>\n");
+        }
+        for (i, relation) in self.relations.iter().enumerate() {
+            writeln!(osm, r#"  <relation id="{}">"#, relation_id(i)).unwrap();
+            for &(is_way, index, role) in relation.members.iter() {
+                let (member_type, id) = if is_way {
+                    ("way", way_id(index))
+                } else {
+                    ("relation", relation_id(index))
+                };
+                writeln!(
+                    osm,
+                    r#"    <member type="{}" ref="{}" role="{}"/>"#,
+                    member_type, id, role
+                )
+                .unwrap();
+            }
+            for (k, v) in relation.tags.iter() {
+                write_tag(&mut osm, k, v);
+            }
+            osm.push_str("  </relation>\n");
+        }
+        osm.push_str("</osm>\n");
+        osm
+    }
+}
+
+fn write_tag(osm: &mut String, key: &str, value: &str) {
+    writeln!(osm, r#"    <tag k="{}" v="{}"/>"#, key, escape(value)).unwrap();
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn sort_dedup(values: &mut Vec<f64>) {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    values.dedup_by(|a, b| (*a - *b).abs() < S_EPSILON);
+}
+
+fn find(parent: &mut [usize], mut node: usize) -> usize {
+    while parent[node] != node {
+        parent[node] = parent[parent[node]];
+        node = parent[node];
+    }
+    node
+}
+
+/// Merge two sets of nodes, keeping the node that was created first
+fn union(parent: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(parent, a), find(parent, b));
+    parent[a.max(b)] = a.min(b);
+}
+
+fn kilometers_per_hour(meters_per_second: f64) -> String {
+    ((meters_per_second * 3.6 * 100.0).round() / 100.0).to_string()
+}
+
+/// Lanelet2 type and subtype tags of a lane border. Double lines are given
+/// from left to right in linestring direction, `flip` swaps them for center
+/// lines oriented against the s direction.
+fn line_tags(mark: Option<&RoadMark>, flip: bool) -> Tags {
+    let mark = match mark {
+        Some(mark) if mark.road_mark_type != RoadMarkType::None => mark,
+        _ => return vec![("type", "virtual".to_string())],
+    };
+    let line = match mark.weight {
+        RoadMarkWeight::Standard => "line_thin",
+        RoadMarkWeight::Bold => "line_thick",
+    };
+    let (line_type, subtype) = match mark.road_mark_type {
+        RoadMarkType::None => unreachable!(),
+        RoadMarkType::Solid => (line, "solid"),
+        RoadMarkType::Broken | RoadMarkType::BrokenBroken => (line, "dashed"),
+        RoadMarkType::SolidSolid => (line, "solid_solid"),
+        RoadMarkType::SolidBroken if flip => (line, "dashed_solid"),
+        RoadMarkType::SolidBroken => (line, "solid_dashed"),
+        RoadMarkType::BrokenSolid if flip => (line, "solid_dashed"),
+        RoadMarkType::BrokenSolid => (line, "dashed_solid"),
+        RoadMarkType::BottsDots => ("line_thin", "dashed"),
+        RoadMarkType::Grass => ("road_border", ""),
+        RoadMarkType::Curb => ("curbstone", "high"),
+        RoadMarkType::Edge => ("road_border", ""),
+        RoadMarkType::Custom => (line, "solid"),
+    };
+    let mut tags = vec![("type", line_type.to_string())];
+    if !subtype.is_empty() {
+        tags.push(("subtype", subtype.to_string()));
+    }
+    let color = match mark.color {
+        RoadMarkColor::Standard | RoadMarkColor::White => None,
+        RoadMarkColor::Blue => Some("blue"),
+        RoadMarkColor::Green => Some("green"),
+        RoadMarkColor::Red => Some("red"),
+        RoadMarkColor::Yellow => Some("yellow"),
+    };
+    tags.extend(color.map(|color| ("color", color.to_string())));
+    tags
+}
+
+/// Lanelet subtype of a lane, `None` for lanes that are not exported
+fn lanelet_subtype(lane_type: &LaneType, road_type: Option<&RoadType>) -> Option<&'static str> {
+    match lane_type {
+        LaneType::Driving
+        | LaneType::Entry
+        | LaneType::Exit
+        | LaneType::OnRamp
+        | LaneType::OffRamp
+        | LaneType::Bidirectional => match road_type.map(|road_type| &road_type.road_type) {
+            Some(RoadTypeKind::Motorway) => Some("highway"),
+            _ => Some("road"),
+        },
+        LaneType::Biking => Some("bicycle_lane"),
+        LaneType::Sidewalk => Some("walkway"),
+        _ => None,
+    }
+}
+
+fn location(road_type: Option<&RoadType>) -> &'static str {
+    match road_type.map(|road_type| &road_type.road_type) {
+        Some(RoadTypeKind::Rural) | Some(RoadTypeKind::Motorway) => "nonurban",
+        _ => "urban",
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Lanelet2ImportOptions {
+    /// m maximum deviation of the fitted reference lines and of the
+    /// fitted lane widths from the lanelet bounds
+    pub tolerance: f64,
+    /// latitude and longitude in degrees of the origin of the local
+    /// transverse mercator projection, the first node of the map if `None`
+    pub origin: Option<(f64, f64)>,
+}
+
+impl Default for Lanelet2ImportOptions {
+    fn default() -> Lanelet2ImportOptions {
+        Lanelet2ImportOptions {
+            tolerance: 0.05,
+            origin: None,
+        }
+    }
+}
+
+/// Read a Lanelet2 OSM document and synthesise an OpenDRIVE road network
+pub fn from_lanelet2<R: std::io::Read>(
+    input: R,
+    options: &Lanelet2ImportOptions,
+) -> Result<Root, failure::Error> {
+    let osm: Osm =
+        serde_xml_rs::from_reader(input).map_err(|err| failure::err_msg(err.to_string()))?;
+    Ok(import(&osm, options)?)
+}
+
+#[derive(Debug, Deserialize)]
+struct Osm {
+    #[serde(default, rename = "node")]
+    nodes: Vec<OsmNode>,
+    #[serde(default, rename = "way")]
+    ways: Vec<OsmWay>,
+    #[serde(default, rename = "relation")]
+    relations: Vec<OsmRelation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsmNode {
+    id: i64,
+    lat: f64,
+    lon: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsmWay {
+    id: i64,
+    #[serde(default, rename = "nd")]
+    nodes: Vec<OsmNodeRef>,
+    #[serde(default, rename = "tag")]
+    tags: Vec<OsmTag>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsmNodeRef {
+    #[serde(rename = "ref")]
+    node: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsmRelation {
+    id: i64,
+    #[serde(default, rename = "member")]
+    members: Vec<OsmMember>,
+    #[serde(default, rename = "tag")]
+    tags: Vec<OsmTag>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsmMember {
+    #[serde(rename = "type")]
+    member_type: String,
+    #[serde(rename = "ref")]
+    id: i64,
+    role: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsmTag {
+    k: String,
+    v: String,
+}
+
+fn tag<'a>(tags: &'a [OsmTag], key: &str) -> Option<&'a str> {
+    tags.iter()
+        .find(|tag| tag.k == key)
+        .map(|tag| tag.v.as_str())
+}
+
+/// A lanelet bound oriented in the driving direction of the lanelet
+struct Bound {
+    nodes: Vec<i64>,
+    points: Vec<(f64, f64)>,
+    mark: RoadMark,
+}
+
+impl Bound {
+    fn invert(&mut self) {
+        self.nodes.reverse();
+        self.points.reverse();
+        // Double lines are given from left to right in linestring direction
+        self.mark.road_mark_type = match self.mark.road_mark_type {
+            RoadMarkType::SolidBroken => RoadMarkType::BrokenSolid,
+            RoadMarkType::BrokenSolid => RoadMarkType::SolidBroken,
+            ref other => other.clone(),
+        };
+    }
+}
+
+struct Lanelet {
+    id: i64,
+    left: Bound,
+    right: Bound,
+    lane_type: LaneType,
+    highway: bool,
+    urban: bool,
+    /// km/h
+    speed: Option<f64>,
+}
+
+/// End of the reference line of a road: (index of the road, at the end)
+type End = (usize, bool);
+
+/// Lane links in s direction, (predecessor, successor) by (road, lane id)
+type LaneLinks = HashMap<(usize, i32), (Option<i32>, Option<i32>)>;
+
+/// The lanelets of a road ordered from the center outwards
+struct Group {
+    right: Vec<usize>,
+    left: Vec<usize>,
+}
+
+fn import(osm: &Osm, options: &Lanelet2ImportOptions) -> Result<Root, Lanelet2Error> {
+    let (lat_0, lon_0) = options
+        .origin
+        .or_else(|| osm.nodes.first().map(|node| (node.lat, node.lon)))
+        .unwrap_or((0.0, 0.0));
+    let projection = TransverseMercator::new(Ellipsoid::WGS84, lat_0, lon_0, 1.0, 0.0, 0.0);
+    let nodes: HashMap<i64, (f64, f64)> = osm
+        .nodes
+        .iter()
+        .map(|node| (node.id, projection.forward(node.lat, node.lon)))
+        .collect();
+    let ways: HashMap<i64, &OsmWay> = osm.ways.iter().map(|way| (way.id, way)).collect();
+    let relations: HashMap<i64, &OsmRelation> = osm
+        .relations
+        .iter()
+        .map(|relation| (relation.id, relation))
+        .collect();
+
+    let mut lanelets = Vec::new();
+    for relation in osm.relations.iter() {
+        if tag(&relation.tags, "type") == Some("lanelet") {
+            lanelets.push(lanelet(relation, &nodes, &ways, &relations)?);
+        }
+    }
+    lanelets.sort_by_key(|lanelet| lanelet.id);
+
+    let groups = group_lanelets(&lanelets);
+    let mut lane_of = vec![(0, 0); lanelets.len()];
+    for (g, group) in groups.iter().enumerate() {
+        for (k, &l) in group.right.iter().enumerate() {
+            lane_of[l] = (g, -(k as i32 + 1));
+        }
+        for (k, &l) in group.left.iter().enumerate() {
+            lane_of[l] = (g, k as i32 + 1);
+        }
+    }
+
+    // Lanelets succeed each other if the end points of their bounds are the
+    // start points of the bounds of the successor
+    let mut starts: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, lanelet) in lanelets.iter().enumerate() {
+        starts
+            .entry((lanelet.left.nodes[0], lanelet.right.nodes[0]))
+            .or_default()
+            .push(i);
+    }
+    // (end left by the lane, lane id, end entered by the successor, lane id)
+    let mut transitions: Vec<(End, i32, End, i32)> = Vec::new();
+    let mut partners: BTreeMap<End, BTreeSet<End>> = BTreeMap::new();
+    for (x, lanelet) in lanelets.iter().enumerate() {
+        let end = (
+            *lanelet.left.nodes.last().unwrap(),
+            *lanelet.right.nodes.last().unwrap(),
+        );
+        for &m in starts.get(&end).into_iter().flatten() {
+            let ((g, from), (h, to)) = (lane_of[x], lane_of[m]);
+            if m == x || g == h {
+                continue;
+            }
+            // imported roads use right hand traffic, so right lanes leave at
+            // the end and left lanes at the start
+            let exit = (g, from < 0);
+            let entry = (h, to > 0);
+            partners.entry(exit).or_default().insert(entry);
+            partners.entry(entry).or_default().insert(exit);
+            transitions.push((exit, from, entry, to));
+        }
+    }
+
+    // Roads branching off a shared end become connecting roads
+    let count = |end: &End| partners.get(end).map_or(0, BTreeSet::len);
+    let connecting: Vec<bool> = (0..groups.len())
+        .map(|g| {
+            let ends = [(g, false), (g, true)];
+            ends.iter().all(|end| count(end) <= 1)
+                && ends.iter().any(|end| {
+                    partners
+                        .get(end)
+                        .into_iter()
+                        .flatten()
+                        .any(|partner| count(partner) > 1)
+                })
+        })
+        .collect();
+    let mut parent: Vec<usize> = (0..groups.len()).collect();
+    for ends in partners.values().filter(|ends| ends.len() > 1) {
+        let mut members = ends.iter().map(|&(h, _)| h).filter(|&h| connecting[h]);
+        if let Some(first) = members.next() {
+            for other in members {
+                union(&mut parent, first, other);
+            }
+        }
+    }
+    let mut junction_ids: BTreeMap<usize, u32> = BTreeMap::new();
+    let mut junction_of = vec![None; groups.len()];
+    for g in (0..groups.len()).filter(|&g| connecting[g]) {
+        let next_id = junction_ids.len() as u32 + 1;
+        let id = *junction_ids.entry(find(&mut parent, g)).or_insert(next_id);
+        junction_of[g] = Some(id);
+    }
+
+    let link_of = |(g, at_end): End| -> Option<LinkElement> {
+        let ends = partners.get(&(g, at_end))?;
+        let &(h, h_at_end) = ends.iter().next()?;
+        let road = LinkElement {
+            element_type: ElementType::Road,
+            element_id: ElementId::from(h as u32 + 1),
+            contact_point: contact_point(h_at_end),
+        };
+        if connecting[g] {
+            return Some(road);
+        }
+        match ends.iter().find_map(|&(h, _)| junction_of[h]) {
+            Some(junction) => Some(LinkElement {
+                element_type: ElementType::Junction,
+                element_id: ElementId::from(junction),
+                contact_point: ContactPoint::Start,
+            }),
+            None if ends.len() == 1 => Some(road),
+            None => None,
+        }
+    };
+    let is_road_link = |end: End| {
+        matches!(
+            link_of(end),
+            Some(LinkElement {
+                element_type: ElementType::Road,
+                ..
+            })
+        )
+    };
+
+    let mut lane_links = LaneLinks::new();
+    let mut connections: BTreeMap<(usize, usize, bool), Vec<JunctionLaneLink>> = BTreeMap::new();
+    for &(exit, from, entry, to) in transitions.iter() {
+        for &(end, lane, other) in [(exit, from, to), (entry, to, from)].iter() {
+            if is_road_link(end) {
+                let link = lane_links.entry((end.0, lane)).or_default();
+                if end.1 {
+                    link.1 = Some(other);
+                } else {
+                    link.0 = Some(other);
+                }
+            }
+        }
+        if !connecting[exit.0] && connecting[entry.0] {
+            connections
+                .entry((exit.0, entry.0, entry.1))
+                .or_default()
+                .push(JunctionLaneLink { from, to });
+        }
+    }
+
+    let mut roads = Vec::new();
+    for (g, group) in groups.iter().enumerate() {
+        let reference = &lanelets[group.right[0]];
+        let mut road = fit_reference_line(&reference.left.points, options.tolerance);
+        road.id = RoadId::from(g as u32 + 1);
+        road.junction = junction_of[g].map(JunctionId::from);
+        let (predecessor, successor) = (link_of((g, false)), link_of((g, true)));
+        if predecessor.is_some() || successor.is_some() {
+            road.link = Some(Link {
+                predecessor,
+                successor,
+                neighbors: vec![],
+            });
+        }
+        road.road_types = vec![RoadType {
+            s: 0.0,
+            road_type: if reference.highway {
+                RoadTypeKind::Motorway
+            } else if reference.urban {
+                RoadTypeKind::Town
+            } else {
+                RoadTypeKind::Rural
+            },
+            speed: reference.speed.map(|max| Speed {
+                max,
+                unit: Some(SpeedUnit::KilometersPerHour),
+            }),
+        }];
+        let section = lane_section(&road, group, &lanelets, &lane_links, g, options.tolerance);
+        road.lanes = Some(Lanes {
+            lane_offsets: vec![],
+            lane_sections: vec![section.build(0.0)],
+        });
+        roads.push(road);
+    }
+
+    let mut junctions: Vec<Junction> = junction_ids
+        .values()
+        .map(|&id| Junction {
+            name: format!("junction {}", id),
+            id: JunctionId::from(id),
+            junction_type: JunctionType::Default,
+            connections: vec![],
+        })
+        .collect();
+    for ((incoming, connecting_road, at_end), lane_links) in connections {
+        let junction = &mut junctions[junction_of[connecting_road].unwrap() as usize - 1];
+        junction.connections.push(Connection {
+            id: ConnectionId::from(junction.connections.len() as u32),
+            incoming_road: RoadId::from(incoming as u32 + 1),
+            connecting_road: Some(RoadId::from(connecting_road as u32 + 1)),
+            linked_road: None,
+            contact_point: contact_point(at_end),
+            lane_links,
+        });
+    }
+
+    let mut root = generators::root("lanelet2", roads, junctions);
+    root.header.geo_reference = Some(GeoReference {
+        projection: format!(
+            "+proj=tmerc +lat_0={} +lon_0={} +k=1 +x_0=0 +y_0=0 +datum=WGS84 +units=m",
+            lat_0, lon_0
+        ),
+    });
+    Ok(root)
+}
+
+fn contact_point(at_end: bool) -> ContactPoint {
+    if at_end {
+        ContactPoint::End
+    } else {
+        ContactPoint::Start
+    }
+}
+
+fn lanelet(
+    relation: &OsmRelation,
+    nodes: &HashMap<i64, (f64, f64)>,
+    ways: &HashMap<i64, &OsmWay>,
+    relations: &HashMap<i64, &OsmRelation>,
+) -> Result<Lanelet, Lanelet2Error> {
+    let bound = |role: &'static str| -> Result<Bound, Lanelet2Error> {
+        let member = relation
+            .members
+            .iter()
+            .find(|member| member.role == role && member.member_type == "way")
+            .ok_or(Lanelet2Error::MissingBound(relation.id, role))?;
+        let way = ways
+            .get(&member.id)
+            .ok_or(Lanelet2Error::MissingWay(member.id))?;
+        if way.nodes.len() < 2 {
+            return Err(Lanelet2Error::MissingBound(relation.id, role));
+        }
+        let points = way
+            .nodes
+            .iter()
+            .map(|n| {
+                nodes
+                    .get(&n.node)
+                    .cloned()
+                    .ok_or(Lanelet2Error::MissingNode(n.node))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Bound {
+            nodes: way.nodes.iter().map(|n| n.node).collect(),
+            points,
+            mark: road_mark(&way.tags),
+        })
+    };
+    let mut left = bound("left")?;
+    let mut right = bound("right")?;
+
+    // Lanelet2 allows bounds to be referenced against their direction: orient
+    // the right bound like the left one and both such that the left bound is
+    // on the left
+    let distance = |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).hypot(a.1 - b.1);
+    let (l0, l1) = (left.points[0], *left.points.last().unwrap());
+    let (r0, r1) = (right.points[0], *right.points.last().unwrap());
+    if distance(l0, r0) + distance(l1, r1) > distance(l0, r1) + distance(l1, r0) {
+        right.invert();
+    }
+    let centroid = |points: &[(f64, f64)]| {
+        let n = points.len() as f64;
+        let (x, y) = points
+            .iter()
+            .fold((0.0, 0.0), |(x, y), p| (x + p.0, y + p.1));
+        (x / n, y / n)
+    };
+    let (lc, rc) = (centroid(&left.points), centroid(&right.points));
+    let (dx, dy) = (l1.0 - l0.0, l1.1 - l0.1);
+    if dx * (rc.1 - lc.1) - dy * (rc.0 - lc.0) > 0.0 {
+        left.invert();
+        right.invert();
+    }
+
+    let subtype = tag(&relation.tags, "subtype").unwrap_or("road");
+    let lane_type = match subtype {
+        "bicycle_lane" => LaneType::Biking,
+        "walkway" | "shared_walkway" | "crosswalk" | "stairs" => LaneType::Sidewalk,
+        "emergency_lane" => LaneType::Shoulder,
+        _ if tag(&relation.tags, "one_way") == Some("no") => LaneType::Bidirectional,
+        _ => LaneType::Driving,
+    };
+    let speed = tag(&relation.tags, "speed_limit")
+        .and_then(parse_speed)
+        .or_else(|| {
+            relation
+                .members
+                .iter()
+                .filter(|member| member.role == "regulatory_element")
+                .filter_map(|member| relations.get(&member.id))
+                .filter(|element| tag(&element.tags, "subtype") == Some("speed_limit"))
+                .filter_map(|element| tag(&element.tags, "speed_limit"))
+                .find_map(parse_speed)
+        });
+
+    Ok(Lanelet {
+        id: relation.id,
+        left,
+        right,
+        lane_type,
+        highway: subtype == "highway",
+        urban: tag(&relation.tags, "location") != Some("nonurban"),
+        speed,
+    })
+}
+
+/// Speed in km/h of a `speed_limit` tag, e.g. "50", "50 km/h" or "30 mph"
+fn parse_speed(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let number: String = value
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let speed: f64 = number.parse().ok()?;
+    if value.ends_with("mph") {
+        Some(speed * 1.609_344)
+    } else {
+        Some(speed)
+    }
+}
+
+/// Road mark of a boundary linestring
+fn road_mark(tags: &[OsmTag]) -> RoadMark {
+    let line_type = tag(tags, "type").unwrap_or("");
+    let road_mark_type = match line_type {
+        "line_thin" | "line_thick" => match tag(tags, "subtype").unwrap_or("solid") {
+            "dashed" => RoadMarkType::Broken,
+            "solid_solid" => RoadMarkType::SolidSolid,
+            "solid_dashed" => RoadMarkType::SolidBroken,
+            "dashed_solid" => RoadMarkType::BrokenSolid,
+            "dashed_dashed" => RoadMarkType::BrokenBroken,
+            _ => RoadMarkType::Solid,
+        },
+        "curbstone" => RoadMarkType::Curb,
+        _ => RoadMarkType::None,
+    };
+    let mut mark = builder::road_mark(0.0, road_mark_type);
+    if line_type == "line_thick" {
+        mark.weight = RoadMarkWeight::Bold;
+    }
+    mark.color = match tag(tags, "color") {
+        Some("yellow") => RoadMarkColor::Yellow,
+        Some("blue") => RoadMarkColor::Blue,
+        Some("green") => RoadMarkColor::Green,
+        Some("red") => RoadMarkColor::Red,
+        _ => RoadMarkColor::Standard,
+    };
+    mark
+}
+
+/// Group lanelets that share their bounds into road cross sections
+fn group_lanelets(lanelets: &[Lanelet]) -> Vec<Group> {
+    let by_left: HashMap<&[i64], usize> = lanelets
+        .iter()
+        .enumerate()
+        .map(|(i, lanelet)| (&lanelet.left.nodes[..], i))
+        .collect();
+    let by_right: HashMap<&[i64], usize> = lanelets
+        .iter()
+        .enumerate()
+        .map(|(i, lanelet)| (&lanelet.right.nodes[..], i))
+        .collect();
+
+    // Lanelets from `start` outwards through shared right/left bounds
+    let chain = |start: usize, assigned: &mut Vec<bool>| {
+        let mut lanes = Vec::new();
+        let mut next = Some(start);
+        while let Some(lane) = next.filter(|&lane| !assigned[lane]) {
+            assigned[lane] = true;
+            lanes.push(lane);
+            next = by_left.get(&lanelets[lane].right.nodes[..]).cloned();
+        }
+        lanes
+    };
+
+    let mut assigned = vec![false; lanelets.len()];
+    let mut groups = Vec::new();
+    for i in 0..lanelets.len() {
+        if assigned[i] {
+            continue;
+        }
+        let mut first = i;
+        for _ in 0..lanelets.len() {
+            match by_right.get(&lanelets[first].left.nodes[..]) {
+                Some(&lane) if lane != i && !assigned[lane] => first = lane,
+                _ => break,
+            }
+        }
+        let right = chain(first, &mut assigned);
+        let center: Vec<i64> = lanelets[first].left.nodes.iter().rev().cloned().collect();
+        let left = match by_left.get(&center[..]) {
+            Some(&opposite) => chain(opposite, &mut assigned),
+            None => vec![],
+        };
+        groups.push(Group { right, left });
+    }
+    groups
+}
+
+/// A road whose reference line follows the polyline `points` within
+/// `tolerance`
+fn fit_reference_line(points: &[(f64, f64)], tolerance: f64) -> Road {
+    let options = FitOptions {
+        tolerance,
+        ..Default::default()
+    };
+    let mut road = RoadBuilder::new().build();
+    road.plan_view = fit_plan_view(points, &options);
+    road.length = road.plan_view.sum_length();
+    road
+}
+
+/// Projects points onto the reference line of a road
+struct Projector<'a> {
+    road: &'a Road,
+    samples: Vec<(f64, f64, f64)>,
+}
+
+impl<'a> Projector<'a> {
+    fn new(road: &'a Road) -> Projector<'a> {
+        let length = road.length.get();
+        let n = (length / PROJECTION_STEP).ceil().max(1.0) as usize;
+        let samples = (0..=n)
+            .map(|i| {
+                let s = length * i as f64 / n as f64;
+                let (x, y) = road.position_at(s, 0.0);
+                (s, x, y)
+            })
+            .collect();
+        Projector { road, samples }
+    }
+
+    /// s and t of the point closest to `point` on the reference line
+    fn project(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let length = self.road.length.get();
+        let mut s = self
+            .samples
+            .iter()
+            .map(|&(s, sx, sy)| (s, (x - sx).hypot(y - sy)))
+            .fold((0.0, f64::INFINITY), |a, b| if b.1 < a.1 { b } else { a })
+            .0;
+        for _ in 0..16 {
+            let (px, py, hdg) = self.road.plan_view.pose_at(s);
+            let next = (s + (x - px) * hdg.cos() + (y - py) * hdg.sin())
+                .max(0.0)
+                .min(length);
+            let converged = (next - s).abs() < 1e-9;
+            s = next;
+            if converged {
+                break;
+            }
+        }
+        let (px, py, hdg) = self.road.plan_view.pose_at(s);
+        (s, (y - py) * hdg.cos() - (x - px) * hdg.sin())
+    }
+
+    /// Lateral offset of a polyline as function of s, sampled at its vertices
+    fn offsets(&self, points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        let mut offsets: Vec<(f64, f64)> = points.iter().map(|&p| self.project(p)).collect();
+        offsets.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        offsets
+    }
+}
+
+/// Piecewise linear interpolation of (s, t) samples sorted by s
+fn interpolate(offsets: &[(f64, f64)], s: f64) -> f64 {
+    let i = offsets.iter().take_while(|o| o.0 <= s).count();
+    if i == 0 {
+        return offsets[0].1;
+    }
+    if i == offsets.len() {
+        return offsets[i - 1].1;
+    }
+    let (a, b) = (offsets[i - 1], offsets[i]);
+    a.1 + (b.1 - a.1) * (s - a.0) / (b.0 - a.0)
+}
+
+/// The lanes of a road fitted to the bounds of its lanelets
+fn lane_section(
+    road: &Road,
+    group: &Group,
+    lanelets: &[Lanelet],
+    lane_links: &LaneLinks,
+    road_index: usize,
+    tolerance: f64,
+) -> LaneSectionBuilder {
+    let projector = Projector::new(road);
+    let length = road.length.get();
+    let n = (length / WIDTH_SAMPLE_STEP).ceil().max(1.0) as usize;
+    let stations: Vec<f64> = (0..=n).map(|i| length * i as f64 / n as f64).collect();
+
+    let reference = &lanelets[group.right[0]];
+    let mut section = LaneSectionBuilder::new().center_road_mark(reference.left.mark.clone());
+    for (side, lanes) in [(-1, &group.right), (1, &group.left)].iter() {
+        let mut inner = projector.offsets(&reference.left.points);
+        for (k, &l) in lanes.iter().enumerate() {
+            let lanelet = &lanelets[l];
+            let outer = projector.offsets(&lanelet.right.points);
+            let widths: Vec<(f64, f64)> = stations
+                .iter()
+                .map(|&s| {
+                    let width = (interpolate(&outer, s) - interpolate(&inner, s)) * *side as f64;
+                    (s, width.max(0.0))
+                })
+                .collect();
+            let mut lane = LaneBuilder::new(lanelet.lane_type.clone(), 0.0)
+                .road_mark(lanelet.right.mark.clone());
+            for (s_offset, [a, b, c, d]) in fit_cubics(&widths, tolerance) {
+                lane = lane.width(s_offset, a, b, c, d);
+            }
+            let id = side * (k as i32 + 1);
+            if let Some(&(predecessor, successor)) = lane_links.get(&(road_index, id)) {
+                if let Some(predecessor) = predecessor {
+                    lane = lane.predecessor(predecessor);
+                }
+                if let Some(successor) = successor {
+                    lane = lane.successor(successor);
+                }
+            }
+            section = if *side < 0 {
+                section.right(lane)
+            } else {
+                section.left(lane)
+            };
+            inner = outer;
+        }
+    }
+    section
+}
+
+/// Piecewise cubic polynomials (start, [a, b, c, d]) approximating the
+/// samples within `tolerance`
+fn fit_cubics(samples: &[(f64, f64)], tolerance: f64) -> Vec<(f64, [f64; 4])> {
+    let coefficients = least_squares_cubic(samples);
+    let s0 = samples[0].0;
+    let [a, b, c, d] = coefficients;
+    let error = samples
+        .iter()
+        .map(|&(s, w)| {
+            let ds = s - s0;
+            (a + ds * (b + ds * (c + ds * d)) - w).abs()
+        })
+        .fold(0.0, f64::max);
+    if error <= tolerance || samples.len() <= 4 {
+        return vec![(s0, coefficients)];
+    }
+    let mid = samples.len() / 2;
+    let mut pieces = fit_cubics(&samples[..=mid], tolerance);
+    pieces.extend(fit_cubics(&samples[mid..], tolerance));
+    pieces
+}
+
+/// Least squares fit of a polynomial of up to third degree in s - s0
+fn least_squares_cubic(samples: &[(f64, f64)]) -> [f64; 4] {
+    let s0 = samples[0].0;
+    let span = (samples[samples.len() - 1].0 - s0).max(1e-9);
+    let m = samples.len().min(4);
+
+    // Normal equations in the normalised parameter u = (s - s0) / span
+    let mut system = [[0.0; 5]; 4];
+    for &(s, w) in samples.iter() {
+        let u = (s - s0) / span;
+        let powers = [1.0, u, u * u, u * u * u];
+        for r in 0..m {
+            for c in 0..m {
+                system[r][c] += powers[r] * powers[c];
+            }
+            system[r][4] += powers[r] * w;
+        }
+    }
+    for col in 0..m {
+        let pivot = (col..m)
+            .max_by(|&a, &b| {
+                system[a][col]
+                    .abs()
+                    .partial_cmp(&system[b][col].abs())
+                    .unwrap()
+            })
+            .unwrap();
+        system.swap(col, pivot);
+        if system[col][col].abs() < 1e-12 {
+            continue;
+        }
+        for row in 0..m {
+            if row != col {
+                let factor = system[row][col] / system[col][col];
+                let pivot_row = system[col];
+                for (value, pivot) in system[row].iter_mut().zip(pivot_row.iter()).skip(col) {
+                    *value -= factor * pivot;
+                }
+            }
+        }
+    }
+    let mut q = [0.0; 4];
+    for (i, q) in q.iter_mut().enumerate().take(m) {
+        if system[i][i].abs() >= 1e-12 {
+            *q = system[i][4] / system[i][i];
+        }
+    }
+    [
+        q[0],
+        q[1] / span,
+        q[2] / (span * span),
+        q[3] / (span * span * span),
+    ]
+}
@@ -1,10 +1,32 @@
-mod errors;
+pub mod arc_length;
+pub mod builder;
+pub mod cache;
+pub mod crop;
+pub mod curvature;
+pub mod diff;
+pub mod edit;
+pub mod errors;
+pub mod fitting;
+pub mod generators;
+pub mod geo;
+pub mod geojson;
+pub mod lane_graph;
+pub mod lanelet2;
+pub mod mesh;
+pub mod offset;
 mod opendrive;
+mod parallel;
 mod parse_util;
+pub mod revision;
+pub mod spatial_index;
+pub mod stream;
+pub mod svg;
 #[cfg(test)]
+#[macro_use]
 mod tests;
 #[cfg(test)]
 mod tests2;
+pub mod trajectory;
 pub mod types;
 
 pub use crate::opendrive::*;
@@ -23,6 +45,17 @@ impl<I: Iterator> Monotonic for I {}
 
 /// Deserializes LVM file data from the specified reader
 pub fn from_reader<R: std::io::Read>(input: R) -> Result<opendrive::Root, failure::Error> {
-    let root: opendrive::Root = serde_xml_rs::from_reader(input).unwrap();
-    return Ok(root);
+    from_reader_with(input, &Default::default())
+}
+
+/// Deserializes an OpenDRIVE file and checks it against the rules of the
+/// revision it declares
+pub fn from_reader_with<R: std::io::Read>(
+    input: R,
+    options: &revision::ParseOptions,
+) -> Result<opendrive::Root, failure::Error> {
+    let root: opendrive::Root =
+        serde_xml_rs::from_reader(input).map_err(|err| failure::err_msg(err.to_string()))?;
+    revision::check(&root, options)?;
+    Ok(root)
 }
@@ -0,0 +1,439 @@
+//! Triangulated road surface meshes
+//!
+//! Every lane of every lane section is triangulated into a strip between its
+//! inner and outer border. The surface follows the elevation, superelevation
+//! and lateral shape of the road as well as the height records of the lane.
+//! Every lane of every lane section is a group of its own, named
+//! `road_<id>_s<section>_lane_<id>`, whose material is the `LaneType` of the
+//! lane. The texture coordinates run across the lane from 0 to 1 and along
+//! the lane in meters of s.
+//!
+//! The mesh can be written as Wavefront OBJ, which keeps the OpenDRIVE axes
+//! (z up), or as binary glTF 2.0 which by convention is y up, i.e. the
+//! OpenDRIVE x/y/z axes map to glTF x/-z/y.
+use crate::errors::SampleError;
+use crate::opendrive::*;
+use crate::parallel;
+use crate::svg;
+use serde_json::json;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone)]
+pub struct MeshOptions {
+    /// m maximum deviation of the lane borders from the exact curves
+    pub tolerance: f64,
+    /// m height by which sidewalks without lane height records are raised,
+    /// together with a curb face towards the road. `None` leaves them at road
+    /// level.
+    pub sidewalk_height: Option<f64>,
+}
+
+impl Default for MeshOptions {
+    fn default() -> MeshOptions {
+        MeshOptions {
+            tolerance: 0.05,
+            sidewalk_height: None,
+        }
+    }
+}
+
+/// An indexed triangle mesh with per-vertex normals and texture coordinates
+#[derive(Debug, Default, Clone)]
+pub struct Mesh {
+    pub positions: Vec<[f64; 3]>,
+    pub normals: Vec<[f64; 3]>,
+    pub uvs: Vec<[f64; 2]>,
+    pub groups: Vec<MeshGroup>,
+}
+
+/// Triangles of a lane or curb
+#[derive(Debug, Clone)]
+pub struct MeshGroup {
+    pub name: String,
+    pub material: String,
+    /// rgb base color of the material
+    pub color: [f64; 3],
+    pub triangles: Vec<[u32; 3]>,
+}
+
+/// Name of the material group of curb faces
+pub const CURB_MATERIAL: &str = "Curb";
+
+/// Triangulate the surface of all roads. Fails if the tolerance is not
+/// positive and finite.
+pub fn road_mesh(root: &Root, options: &MeshOptions) -> Result<Mesh, SampleError> {
+    let roads = parallel::map(&root.roads, |road| -> Result<Mesh, SampleError> {
+        let mut mesh = Mesh::default();
+        let sections = road
+            .lanes
+            .as_ref()
+            .map_or(0, |lanes| lanes.lane_sections.len());
+        for index in 0..sections {
+            add_lane_section(&mut mesh, road, index, options)?;
+        }
+        Ok(mesh)
+    });
+    let mut mesh = Mesh::default();
+    for road in roads {
+        mesh.append(road?);
+    }
+    mesh.compute_normals();
+    Ok(mesh)
+}
+
+fn add_lane_section(
+    mesh: &mut Mesh,
+    road: &Road,
+    index: usize,
+    options: &MeshOptions,
+) -> Result<(), SampleError> {
+    let section = &road.lanes.as_ref().unwrap().lane_sections[index];
+    let (s0, s1) = road.lane_section_range(index);
+    let stations = road.sample_s(s0, s1, options.tolerance)?;
+    let borders: Vec<Vec<LaneBorders>> = stations
+        .iter()
+        .map(|&s| road.lane_borders(index, s))
+        .collect();
+
+    for lane in section.left_lanes().chain(section.right_lanes()) {
+        let raise = match options.sidewalk_height {
+            Some(height) if lane.lane_type == LaneType::Sidewalk && lane.heights.is_empty() => {
+                height
+            }
+            _ => 0.0,
+        };
+
+        // inner and outer border vertices of the lane at every station
+        let mut inner = Vec::with_capacity(stations.len());
+        let mut outer = Vec::with_capacity(stations.len());
+        for (&s, b) in stations.iter().zip(borders.iter()) {
+            let border = *b.iter().find(|b| b.id == lane.id).unwrap();
+            let (h_inner, h_outer) = lane.height_at(s - s0);
+            let (xi, yi, zi) = road.surface_point(s, border.inner);
+            let (xo, yo, zo) = if lane.level {
+                // continue horizontally from the inner border
+                let (x, y) = road.position_at(s, border.outer);
+                (x, y, zi)
+            } else {
+                road.surface_point(s, border.outer)
+            };
+            inner.push(mesh.push_vertex([xi, yi, zi + h_inner + raise], [0.0, s]));
+            outer.push(mesh.push_vertex([xo, yo, zo + h_outer + raise], [1.0, s]));
+        }
+
+        let left = lane.id > 0;
+        let name = format!(
+            "road_{}_s{}_lane_{}",
+            road.id.to_string().replace(char::is_whitespace, "_"),
+            index,
+            lane.id
+        );
+        let material = format!("{:?}", lane.lane_type);
+        let color = hex_color(svg::lane_fill(&lane.lane_type));
+        let triangles = &mut mesh.group(&name, &material, color).triangles;
+        for i in 0..stations.len().saturating_sub(1) {
+            let (a, b, c, d) = (inner[i], outer[i], outer[i + 1], inner[i + 1]);
+            // counter-clockwise when seen from above
+            if left {
+                triangles.push([a, d, c]);
+                triangles.push([a, c, b]);
+            } else {
+                triangles.push([a, b, c]);
+                triangles.push([a, c, d]);
+            }
+        }
+
+        if raise > 0.0 {
+            add_curb(mesh, &format!("{}_curb", name), &inner, raise, left);
+        }
+    }
+    Ok(())
+}
+
+/// Add a vertical face below the raised inner border of a lane, facing
+/// towards the center of the road
+fn add_curb(mesh: &mut Mesh, name: &str, top: &[u32], height: f64, left: bool) {
+    let base: Vec<u32> = top
+        .iter()
+        .map(|&v| {
+            let [x, y, z] = mesh.positions[v as usize];
+            let [_, s] = mesh.uvs[v as usize];
+            mesh.push_vertex([x, y, z - height], [0.0, s])
+        })
+        .collect();
+    let top: Vec<u32> = top
+        .iter()
+        .map(|&v| {
+            let position = mesh.positions[v as usize];
+            let [_, s] = mesh.uvs[v as usize];
+            mesh.push_vertex(position, [1.0, s])
+        })
+        .collect();
+
+    let triangles = &mut mesh.group(name, CURB_MATERIAL, [0.7, 0.7, 0.7]).triangles;
+    for i in 0..top.len().saturating_sub(1) {
+        let (b0, b1, t0, t1) = (base[i], base[i + 1], top[i], top[i + 1]);
+        if left {
+            triangles.push([b0, b1, t1]);
+            triangles.push([b0, t1, t0]);
+        } else {
+            triangles.push([b0, t1, b1]);
+            triangles.push([b0, t0, t1]);
+        }
+    }
+}
+
+impl Mesh {
+    fn push_vertex(&mut self, position: [f64; 3], uv: [f64; 2]) -> u32 {
+        self.positions.push(position);
+        self.uvs.push(uv);
+        (self.positions.len() - 1) as u32
+    }
+
+    /// Add the vertices and triangles of `other`, merging groups of the same
+    /// name
+    fn append(&mut self, other: Mesh) {
+        let offset = self.positions.len() as u32;
+        self.positions.extend(other.positions);
+        self.uvs.extend(other.uvs);
+        for group in other.groups {
+            let triangles = &mut self
+                .group(&group.name, &group.material, group.color)
+                .triangles;
+            triangles.extend(
+                group
+                    .triangles
+                    .iter()
+                    .map(|t| [t[0] + offset, t[1] + offset, t[2] + offset]),
+            );
+        }
+    }
+
+    /// The group `name`, created with `material` if it doesn't exist yet
+    fn group(&mut self, name: &str, material: &str, color: [f64; 3]) -> &mut MeshGroup {
+        match self.groups.iter().position(|g| g.name == name) {
+            Some(i) => &mut self.groups[i],
+            None => {
+                self.groups.push(MeshGroup {
+                    name: name.to_string(),
+                    material: material.to_string(),
+                    color,
+                    triangles: vec![],
+                });
+                self.groups.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// Area weighted vertex normals
+    fn compute_normals(&mut self) {
+        let mut normals = vec![[0.0; 3]; self.positions.len()];
+        for triangle in self.groups.iter().flat_map(|g| g.triangles.iter()) {
+            let [a, b, c] = [
+                self.positions[triangle[0] as usize],
+                self.positions[triangle[1] as usize],
+                self.positions[triangle[2] as usize],
+            ];
+            let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let n = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            for &i in triangle.iter() {
+                for k in 0..3 {
+                    normals[i as usize][k] += n[k];
+                }
+            }
+        }
+        for n in normals.iter_mut() {
+            let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            if length > 0.0 {
+                *n = [n[0] / length, n[1] / length, n[2] / length];
+            } else {
+                *n = [0.0, 0.0, 1.0];
+            }
+        }
+        self.normals = normals;
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.groups.iter().map(|g| g.triangles.len()).sum()
+    }
+
+    /// The distinct materials of the groups with their colors in order of
+    /// first use
+    fn materials(&self) -> Vec<(&str, [f64; 3])> {
+        let mut materials: Vec<(&str, [f64; 3])> = Vec::new();
+        for group in self.groups.iter() {
+            if materials.iter().all(|&(m, _)| m != group.material) {
+                materials.push((&group.material, group.color));
+            }
+        }
+        materials
+    }
+
+    /// Write the mesh as Wavefront OBJ. `mtl_file` is referenced with
+    /// `mtllib` if given, see `write_mtl`.
+    pub fn write_obj<W: Write>(&self, mut writer: W, mtl_file: Option<&str>) -> io::Result<()> {
+        writeln!(writer, "# OpenDRIVE road surface")?;
+        if let Some(mtl_file) = mtl_file {
+            writeln!(writer, "mtllib {}", mtl_file)?;
+        }
+        for p in self.positions.iter() {
+            writeln!(writer, "v {:.4} {:.4} {:.4}", p[0], p[1], p[2])?;
+        }
+        for t in self.uvs.iter() {
+            writeln!(writer, "vt {:.4} {:.4}", t[0], t[1])?;
+        }
+        for n in self.normals.iter() {
+            writeln!(writer, "vn {:.4} {:.4} {:.4}", n[0], n[1], n[2])?;
+        }
+        for group in self.groups.iter() {
+            writeln!(writer, "g {}", group.name)?;
+            writeln!(writer, "usemtl {}", group.material)?;
+            for triangle in group.triangles.iter() {
+                let [a, b, c] = [triangle[0] + 1, triangle[1] + 1, triangle[2] + 1];
+                writeln!(writer, "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}", a, b, c)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the materials of the mesh as a Wavefront MTL file
+    pub fn write_mtl<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (material, [r, g, b]) in self.materials() {
+            writeln!(writer, "newmtl {}", material)?;
+            writeln!(writer, "Kd {:.3} {:.3} {:.3}", r, g, b)?;
+        }
+        Ok(())
+    }
+
+    /// Write the mesh as binary glTF 2.0 (.glb)
+    pub fn write_glb<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        // glTF is y up with the z axis pointing towards the viewer
+        let to_gltf = |p: &[f64; 3]| [p[0] as f32, p[2] as f32, -p[1] as f32];
+
+        let mut bin: Vec<u8> = Vec::new();
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for p in self.positions.iter().map(to_gltf) {
+            for k in 0..3 {
+                min[k] = min[k].min(p[k]);
+                max[k] = max[k].max(p[k]);
+                bin.extend_from_slice(&p[k].to_le_bytes());
+            }
+        }
+        let normals_offset = bin.len();
+        for n in self.normals.iter().map(to_gltf) {
+            n.iter()
+                .for_each(|v| bin.extend_from_slice(&v.to_le_bytes()));
+        }
+        let uvs_offset = bin.len();
+        for t in self.uvs.iter() {
+            bin.extend_from_slice(&(t[0] as f32).to_le_bytes());
+            bin.extend_from_slice(&(t[1] as f32).to_le_bytes());
+        }
+        let vertex_count = self.positions.len();
+
+        let mut buffer_views = vec![
+            json!({ "buffer": 0, "byteOffset": 0, "byteLength": normals_offset, "target": 34962 }),
+            json!({ "buffer": 0, "byteOffset": normals_offset, "byteLength": uvs_offset - normals_offset, "target": 34962 }),
+            json!({ "buffer": 0, "byteOffset": uvs_offset, "byteLength": bin.len() - uvs_offset, "target": 34962 }),
+        ];
+        let mut accessors = vec![
+            json!({ "bufferView": 0, "componentType": 5126, "count": vertex_count, "type": "VEC3", "min": min, "max": max }),
+            json!({ "bufferView": 1, "componentType": 5126, "count": vertex_count, "type": "VEC3" }),
+            json!({ "bufferView": 2, "componentType": 5126, "count": vertex_count, "type": "VEC2" }),
+        ];
+        let materials = self.materials();
+        // one node and mesh per group to keep the group names
+        let mut meshes = Vec::new();
+        for group in self.groups.iter() {
+            let offset = bin.len();
+            for triangle in group.triangles.iter() {
+                triangle
+                    .iter()
+                    .for_each(|v| bin.extend_from_slice(&v.to_le_bytes()));
+            }
+            buffer_views.push(json!({
+                "buffer": 0,
+                "byteOffset": offset,
+                "byteLength": bin.len() - offset,
+                "target": 34963,
+            }));
+            accessors.push(json!({
+                "bufferView": buffer_views.len() - 1,
+                "componentType": 5125,
+                "count": group.triangles.len() * 3,
+                "type": "SCALAR",
+            }));
+            meshes.push(json!({
+                "name": group.name,
+                "primitives": [{
+                    "attributes": { "POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2 },
+                    "indices": accessors.len() - 1,
+                    "material": materials.iter().position(|&(m, _)| m == group.material),
+                }],
+            }));
+        }
+        let nodes: Vec<_> = self
+            .groups
+            .iter()
+            .enumerate()
+            .map(|(i, group)| json!({ "mesh": i, "name": group.name }))
+            .collect();
+        let materials: Vec<_> = materials
+            .iter()
+            .map(|&(material, [r, g, b])| {
+                json!({
+                    "name": material,
+                    "pbrMetallicRoughness": {
+                        "baseColorFactor": [r, g, b, 1.0],
+                        "metallicFactor": 0.0,
+                        "roughnessFactor": 0.9,
+                    },
+                })
+            })
+            .collect();
+        let padding = (4 - bin.len() % 4) % 4;
+        bin.resize(bin.len() + padding, 0);
+
+        let document = json!({
+            "asset": { "version": "2.0", "generator": "opendrive-rs" },
+            "scene": 0,
+            "scenes": [{ "nodes": (0..nodes.len()).collect::<Vec<_>>() }],
+            "nodes": nodes,
+            "meshes": meshes,
+            "materials": materials,
+            "accessors": accessors,
+            "bufferViews": buffer_views,
+            "buffers": [{ "byteLength": bin.len() }],
+        });
+        let mut json = serde_json::to_vec(&document)?;
+        let padding = (4 - json.len() % 4) % 4;
+        json.resize(json.len() + padding, b' ');
+
+        let length = 12 + 8 + json.len() + 8 + bin.len();
+        writer.write_all(b"glTF")?;
+        writer.write_all(&2u32.to_le_bytes())?;
+        writer.write_all(&(length as u32).to_le_bytes())?;
+        writer.write_all(&(json.len() as u32).to_le_bytes())?;
+        writer.write_all(b"JSON")?;
+        writer.write_all(&json)?;
+        writer.write_all(&(bin.len() as u32).to_le_bytes())?;
+        writer.write_all(b"BIN\0")?;
+        writer.write_all(&bin)?;
+        Ok(())
+    }
+}
+
+/// Parse a "#rrggbb" color into linear rgb components
+fn hex_color(hex: &str) -> [f64; 3] {
+    let channel = |i: usize| {
+        let srgb = f64::from(u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0)) / 255.0;
+        srgb.powf(2.2)
+    };
+    [channel(1), channel(3), channel(5)]
+}
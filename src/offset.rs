@@ -0,0 +1,308 @@
+//! Offset curves of road geometry
+//!
+//! Lane boundaries are curves at a lateral offset from the reference line.
+//! Flattening them into polylines needs many points on long curves, so this
+//! module computes the offset curves as `types::Segment`s instead. The offset
+//! of a line is a parallel line and the offset of a circular arc a concentric
+//! arc with the radius changed by the offset, both exact. Clothoids, cubic
+//! Bezier curves (poly3 and paramPoly3 elements) and offsets varying along
+//! the road have no closed form and are approximated by cubic Bezier curves
+//! through points and tangents of the exact offset curve, subdivided until
+//! they deviate less than a tolerance from it.
+//!
+//! Offsets are positive to the left of the direction of travel. An offset
+//! towards the center of curvature that reaches the radius of curvature
+//! collapses the offset curve into a cusp, which is reported as
+//! `OffsetError::Cusp`.
+use crate::arc_length::ArcLengthCurve;
+use crate::errors::OffsetError;
+use crate::opendrive::*;
+use crate::types;
+use euclid::{Point2D, Vector2D};
+
+/// Points at which an approximation is compared with the exact offset curve
+const CHECK_SAMPLES: usize = 8;
+
+/// Subdivisions of an approximation after which it is accepted regardless of
+/// its deviation
+const MAX_DEPTH: u32 = 16;
+
+/// m step of the difference quotient of offsets varying along the road
+const DERIVATIVE_STEP: f64 = 1e-5;
+
+/// The curve at `distance` to the left of `segment`, with Bezier curves and
+/// clothoids approximated within `tolerance`
+pub fn offset_segment(
+    segment: &types::Segment<f64>,
+    distance: f64,
+    tolerance: f64,
+) -> Result<Vec<types::Segment<f64>>, OffsetError> {
+    let mut segments = Vec::new();
+    match *segment {
+        types::Segment::Line(line) => {
+            let direction = line.to - line.from;
+            let length = direction.length();
+            let shift = if length > 0.0 {
+                left_normal(direction) * (distance / length)
+            } else {
+                Vector2D::zero()
+            };
+            segments.push(types::Segment::Line(lyon_geom::LineSegment {
+                from: line.from + shift,
+                to: line.to + shift,
+            }));
+        }
+        types::Segment::Arc(arc) if arc.radii.x == arc.radii.y => {
+            // the center is on the left of counterclockwise arcs
+            let radius = if arc.sweep_angle.get() >= 0.0 {
+                arc.radii.x - distance
+            } else {
+                arc.radii.x + distance
+            };
+            if radius <= 0.0 {
+                let cusp = arc.from();
+                return Err(OffsetError::Cusp(cusp.x, cusp.y));
+            }
+            segments.push(types::Segment::Arc(lyon_geom::Arc {
+                radii: euclid::vec2(radius, radius),
+                ..arc
+            }));
+        }
+        types::Segment::Arc(arc) => {
+            let sweep = arc.sweep_angle.get();
+            let rotation = types::Rotation::new(arc.x_rotation);
+            approximate(
+                &|t| {
+                    let angle = arc.get_angle(t).get();
+                    let (sin, cos) = angle.sin_cos();
+                    let d1 = rotation.transform_vector(&euclid::vec2(
+                        -arc.radii.x * sin * sweep,
+                        arc.radii.y * cos * sweep,
+                    ));
+                    let d2 = rotation.transform_vector(&euclid::vec2(
+                        -arc.radii.x * cos * sweep * sweep,
+                        -arc.radii.y * sin * sweep * sweep,
+                    ));
+                    offset_point(arc.sample(t), d1, curvature(d1, d2), distance)
+                },
+                0.0,
+                1.0,
+                tolerance,
+                0,
+                &mut segments,
+            )?;
+        }
+        types::Segment::Clothoid(clothoid) => approximate(
+            &|s| {
+                let (sin, cos) = clothoid.angle_at_length(s).get().sin_cos();
+                offset_point(
+                    clothoid.sample_at_length(s),
+                    euclid::vec2(cos, sin),
+                    clothoid.curvature_at_length(s),
+                    distance,
+                )
+            },
+            0.0,
+            clothoid.length,
+            tolerance,
+            0,
+            &mut segments,
+        )?,
+        types::Segment::Quadratic(quadratic) => {
+            return offset_segment(
+                &types::Segment::Cubic(quadratic.to_cubic()),
+                distance,
+                tolerance,
+            );
+        }
+        types::Segment::Cubic(cubic) => approximate(
+            &|t| {
+                let d1 = cubic.derivative(t);
+                let d2 = ((cubic.ctrl2 - cubic.ctrl1 * 2.0 + cubic.from.to_vector()) * (1.0 - t)
+                    + (cubic.to - cubic.ctrl2 * 2.0 + cubic.ctrl1.to_vector()) * t)
+                    * 6.0;
+                offset_point(cubic.sample(t), d1, curvature(d1, d2), distance)
+            },
+            0.0,
+            1.0,
+            tolerance,
+            0,
+            &mut segments,
+        )?,
+    }
+    Ok(segments)
+}
+
+/// The curve at `distance` to the left of the reference line
+pub fn offset_plan_view(
+    plan_view: &PlanView,
+    distance: f64,
+    tolerance: f64,
+) -> Result<Vec<types::Segment<f64>>, OffsetError> {
+    let mut segments = Vec::new();
+    for g in plan_view.geometries.iter() {
+        segments.extend(offset_segment(&g.as_segment(), distance, tolerance)?);
+    }
+    Ok(segments)
+}
+
+/// The outer border of `lane` in the lane section with `index`, the lane
+/// offset line for the center lane, approximated within `tolerance`
+///
+/// The road is split at the start of every geometry, lane offset and lane
+/// width record. Where the border keeps a constant offset, it is the offset
+/// of the geometry, so lanes of constant width along lines and arcs are
+/// exact.
+pub fn lane_boundary(
+    road: &Road,
+    index: usize,
+    lane: i32,
+    tolerance: f64,
+) -> Result<Vec<types::Segment<f64>>, OffsetError> {
+    let (start, end) = road.lane_section_range(index);
+    let offset = |s: f64| {
+        road.lane_borders(index, s)
+            .into_iter()
+            .find(|borders| borders.id == lane)
+            .map(|borders| borders.outer)
+    };
+    if offset(start).is_none() {
+        return Err(OffsetError::UnknownLane(lane));
+    }
+    let offset = |s: f64| offset(s).unwrap_or(0.0);
+
+    let lanes = road.lanes.as_ref().unwrap();
+    let section = &lanes.lane_sections[index];
+    let mut stations = vec![start, end];
+    stations.extend(road.plan_view.geometries.iter().map(|g| g.s.get()));
+    stations.extend(lanes.lane_offsets.iter().map(|o| o.s));
+    stations.extend(
+        section
+            .left_lanes()
+            .chain(section.right_lanes())
+            .flat_map(|lane| lane.widths.iter())
+            .map(|width| section.s + width.s_offset),
+    );
+    stations.retain(|&s| s >= start && s <= end);
+    stations.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    stations.dedup_by(|a, b| (*a - *b).abs() < 1e-9);
+
+    let mut segments = Vec::new();
+    for pair in stations.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let g = match road.plan_view.geometry_at(a) {
+            Some(g) => g,
+            None => continue,
+        };
+        // between the stations the offset is a cubic polynomial, constant if
+        // it has the same value at four points
+        let t = offset(a);
+        let constant = [1.0 / 3.0, 2.0 / 3.0, 1.0]
+            .iter()
+            .all(|f| (offset(a + (b - a) * f) - t).abs() < 1e-9);
+        if constant {
+            let (_, rest) = g.split_at_s(a - g.s.get());
+            let (piece, _) = rest.split_at_s(b - a);
+            segments.extend(offset_segment(&piece.as_segment(), t, tolerance)?);
+            continue;
+        }
+        let slope = |s: f64| {
+            let (s0, s1) = if s + DERIVATIVE_STEP > b {
+                (s - DERIVATIVE_STEP, s)
+            } else {
+                (s, s + DERIVATIVE_STEP)
+            };
+            (offset(s1) - offset(s0)) / (s1 - s0)
+        };
+        approximate(
+            &|s| {
+                let ds = s - g.s.get();
+                let (x, y, hdg) = g.pose_at(ds);
+                let (sin, cos) = hdg.sin_cos();
+                let (tangent, normal) = (euclid::vec2(cos, sin), euclid::vec2(-sin, cos));
+                let t = offset(s);
+                let (point, derivative) =
+                    offset_point(euclid::point2(x, y), tangent, g.curvature_at(ds), t)?;
+                Ok((point, derivative + normal * slope(s)))
+            },
+            a,
+            b,
+            tolerance,
+            0,
+            &mut segments,
+        )?;
+    }
+    Ok(segments)
+}
+
+/// Counterclockwise perpendicular of `v`
+fn left_normal(v: Vector2D<f64>) -> Vector2D<f64> {
+    euclid::vec2(-v.y, v.x)
+}
+
+/// Signed curvature of a curve with the first and second derivatives `d1`
+/// and `d2`
+fn curvature(d1: Vector2D<f64>, d2: Vector2D<f64>) -> f64 {
+    let speed = d1.length();
+    if speed > 0.0 {
+        d1.cross(d2) / (speed * speed * speed)
+    } else {
+        0.0
+    }
+}
+
+/// Point and derivative of the offset curve at `distance` from the point
+/// `point` of a curve with the derivative `derivative` and `curvature`
+fn offset_point(
+    point: Point2D<f64>,
+    derivative: Vector2D<f64>,
+    curvature: f64,
+    distance: f64,
+) -> Result<(Point2D<f64>, Vector2D<f64>), OffsetError> {
+    let speed = derivative.length();
+    if speed == 0.0 {
+        return Ok((point, derivative));
+    }
+    // the offset curve runs backwards beyond the center of curvature
+    let scale = 1.0 - curvature * distance;
+    if scale <= 0.0 {
+        return Err(OffsetError::Cusp(point.x, point.y));
+    }
+    let normal = left_normal(derivative) / speed;
+    Ok((point + normal * distance, derivative * scale))
+}
+
+/// Append cubic Bezier curves approximating the curve with points and
+/// derivatives `curve` between the parameters `start` and `end`, which match
+/// `curve` in position and tangent at their ends
+fn approximate(
+    curve: &dyn Fn(f64) -> Result<(Point2D<f64>, Vector2D<f64>), OffsetError>,
+    start: f64,
+    end: f64,
+    tolerance: f64,
+    depth: u32,
+    segments: &mut Vec<types::Segment<f64>>,
+) -> Result<(), OffsetError> {
+    let (from, d0) = curve(start)?;
+    let (to, d1) = curve(end)?;
+    let third = (end - start) / 3.0;
+    let cubic = lyon_geom::CubicBezierSegment {
+        from,
+        ctrl1: from + d0 * third,
+        ctrl2: to - d1 * third,
+        to,
+    };
+    let mut error: f64 = 0.0;
+    for i in 1..CHECK_SAMPLES {
+        let f = i as f64 / CHECK_SAMPLES as f64;
+        let (point, _) = curve(start + (end - start) * f)?;
+        error = error.max((cubic.sample(f) - point).length());
+    }
+    if error <= tolerance || depth >= MAX_DEPTH {
+        segments.push(types::Segment::Cubic(cubic));
+        return Ok(());
+    }
+    let middle = (start + end) / 2.0;
+    approximate(curve, start, middle, tolerance, depth + 1, segments)?;
+    approximate(curve, middle, end, tolerance, depth + 1, segments)
+}
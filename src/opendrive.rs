@@ -1,5 +1,7 @@
 use crate::errors;
+use crate::geo;
 use crate::parse_util;
+use crate::revision::Revision;
 use chrono;
 use lyon_geom;
 use lyon_path;
@@ -19,9 +21,11 @@ pub struct Root {
     pub header: Header,
     #[serde(default, rename = "road")]
     pub roads: Vec<Road>,
+    #[serde(default, rename = "junction")]
+    pub junctions: Vec<Junction>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename = "header", rename_all = "camelCase")]
 pub struct Header {
     /// 1 major revision number of OpenDRIVE® format
@@ -29,32 +33,59 @@ pub struct Header {
     /// 4 minor revision number of OpenDRIVE® format
     pub rev_minor: u16,
     /// database name
+    #[serde(default)]
     pub name: String,
-    /// version number of this database (format: a.bb)
-    pub version: f32,
+    /// version number of this database (format: a.bb, any string since
+    /// OpenDRIVE 1.6)
+    #[serde(default)]
+    pub version: String,
     #[serde(with = "parse_util::odr_dateformat", default = "Header::default_date")]
     /// time/date of database creation according to ISO 8601 (preference: YYYY-MM-DDThh:mm:ss)
     pub date: chrono::DateTime<chrono::Utc>,
     /// maximum inertial y value [m]
+    #[serde(default)]
     pub north: f64,
     /// minimum inertial y value [m]
+    #[serde(default)]
     pub south: f64,
     /// maximum inertial x value [m]
+    #[serde(default)]
     pub east: f64,
     /// minimum inertial x value [m]
+    #[serde(default)]
     pub west: f64,
 
+    /// maximum road id, only written before OpenDRIVE 1.4
+    #[serde(default)]
     pub max_road: u32,
+    /// maximum junction id, only written before OpenDRIVE 1.4
+    #[serde(default)]
     pub max_junc: u32,
+    /// maximum program id, only written before OpenDRIVE 1.4
+    #[serde(default)]
     pub max_prg: u32,
 
-    /// vendor string - - vendor name
-    //pub vendor: String,
+    /// vendor name
+    pub vendor: Option<String>,
     pub geo_reference: Option<GeoReference>,
+    pub offset: Option<Offset>,
 }
 impl Header {
+    /// Placeholder for a missing date, the Unix epoch so that parsing the
+    /// same file twice gives the same header
     fn default_date() -> chrono::DateTime<chrono::Utc> {
-        chrono::Utc::now()
+        std::time::UNIX_EPOCH.into()
+    }
+
+    /// Whether the file declares the date, i.e. the date isn't the
+    /// placeholder for a missing one
+    pub fn has_date(&self) -> bool {
+        self.date != Header::default_date()
+    }
+
+    /// Revision of the OpenDRIVE format the file declares
+    pub fn revision(&self) -> Revision {
+        Revision::new(self.rev_major, self.rev_minor)
     }
 }
 
@@ -63,18 +94,342 @@ impl Header {
 /// necessary to convert OpenDRIVE's cartesian x/y/z co-ordinates into a
 /// corresponding geographic reference system. There must be no more than one
 /// definition of the geographic projection.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct GeoReference {
     #[serde(rename = "$value")]
     pub projection: String,
 }
+impl GeoReference {
+    /// Parse the PROJ definition string of this record
+    pub fn parse(&self) -> Result<geo::Projection, errors::GeoReferenceError> {
+        geo::Projection::from_proj_string(&self.projection)
+    }
+}
+
+/// The offset record (OpenDRIVE 1.6) shifts and rotates the inertial
+/// co-ordinates relative to the projected co-ordinates of the geographic
+/// reference. A local point is first rotated by `hdg` and then translated by
+/// `x`/`y`/`z` to obtain its projected co-ordinates.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename = "offset")]
+pub struct Offset {
+    /// inertial x offset [m]
+    #[serde(default)]
+    pub x: f64,
+    /// inertial y offset [m]
+    #[serde(default)]
+    pub y: f64,
+    /// inertial z offset [m]
+    #[serde(default)]
+    pub z: f64,
+    /// heading offset (rotation around resulting z-axis) [rad]
+    #[serde(default)]
+    pub hdg: f64,
+}
+impl Offset {
+    /// Transform local inertial co-ordinates to projected co-ordinates
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let (sin, cos) = self.hdg.sin_cos();
+        (self.x + cos * x - sin * y, self.y + sin * x + cos * y)
+    }
+
+    /// Transform projected co-ordinates to local inertial co-ordinates
+    pub fn unapply(&self, x: f64, y: f64) -> (f64, f64) {
+        let (sin, cos) = self.hdg.sin_cos();
+        let (dx, dy) = (x - self.x, y - self.y);
+        (cos * dx + sin * dy, -sin * dx + cos * dy)
+    }
+}
+
+impl Root {
+    /// The projection of the geographic reference record of the header
+    pub fn projection(&self) -> Result<geo::Projection, errors::GeoReferenceError> {
+        self.header
+            .geo_reference
+            .as_ref()
+            .ok_or(errors::GeoReferenceError::Missing)?
+            .parse()
+    }
+
+    /// Convert inertial x/y co-ordinates to WGS84 latitude and longitude in
+    /// degrees, honouring the header offset
+    pub fn to_geodetic(&self, x: f64, y: f64) -> Result<(f64, f64), errors::GeoReferenceError> {
+        let projection = self.projection()?;
+        let (x, y) = match self.header.offset {
+            Some(offset) => offset.apply(x, y),
+            None => (x, y),
+        };
+        Ok(projection.inverse(x, y))
+    }
+
+    /// Convert WGS84 latitude and longitude in degrees to inertial x/y
+    /// co-ordinates, honouring the header offset
+    pub fn from_geodetic(
+        &self,
+        lat: f64,
+        lon: f64,
+    ) -> Result<(f64, f64), errors::GeoReferenceError> {
+        let projection = self.projection()?;
+        let (x, y) = projection.forward(lat, lon);
+        Ok(match self.header.offset {
+            Some(offset) => offset.unapply(x, y),
+            None => (x, y),
+        })
+    }
+
+    /// Extent of the road network including the lanes, sampled such that
+    /// the sampled polylines deviate less than `tolerance` from the curves.
+    /// `None` if there are no roads.
+    pub fn bounding_box(&self, tolerance: f64) -> Result<Option<BoundingBox>, errors::SampleError> {
+        let boxes = crate::parallel::map(&self.roads, |road| road.bounding_box(tolerance));
+        let mut bounds: Option<BoundingBox> = None;
+        for b in boxes {
+            if let Some(b) = b? {
+                bounds = Some(bounds.map_or(b, |bounds| bounds.union(&b)));
+            }
+        }
+        Ok(bounds)
+    }
+
+    /// The lane containing the inertial position x, y. Where lanes of
+    /// several roads overlap, e.g. in junctions, the road whose reference
+    /// line is closest is chosen.
+    pub fn lane_position_at(&self, x: f64, y: f64) -> Option<LanePosition> {
+        self.lane_position_in(x, y, 0..self.roads.len())
+    }
+
+    /// Like `lane_position_at`, considering only the roads with the given
+    /// indices
+    pub(crate) fn lane_position_in(
+        &self,
+        x: f64,
+        y: f64,
+        roads: impl IntoIterator<Item = usize>,
+    ) -> Option<LanePosition> {
+        self.lane_positions_in(x, y, roads)
+            .fold(None, |best: Option<LanePosition>, p| match best {
+                Some(best) if best.t.abs() <= p.t.abs() => Some(best),
+                _ => Some(p),
+            })
+    }
+
+    /// The lanes of all roads containing the inertial position x, y
+    fn lane_positions_at(&self, x: f64, y: f64) -> impl Iterator<Item = LanePosition> + '_ {
+        self.lane_positions_in(x, y, 0..self.roads.len())
+    }
+
+    /// The lanes of the roads with the given indices containing the inertial
+    /// position x, y
+    fn lane_positions_in<'a>(
+        &'a self,
+        x: f64,
+        y: f64,
+        roads: impl IntoIterator<Item = usize> + 'a,
+    ) -> impl Iterator<Item = LanePosition> + 'a {
+        roads.into_iter().filter_map(move |index| {
+            let road = &self.roads[index];
+            let (s, t) = road.project(x, y);
+            // beyond the ends of the reference line
+            let (px, py) = road.position_at(s, t);
+            if (px - x).hypot(py - y) > 1e-6 {
+                return None;
+            }
+            let (section, lane) = road.lane_at(s, t)?;
+            Some(LanePosition {
+                road: index,
+                section,
+                lane,
+                s,
+                t,
+            })
+        })
+    }
+
+    /// Localize a vehicle at the inertial position x, y with heading `yaw`
+    /// relative to the lane it occupies. Where lanes of several roads
+    /// overlap, e.g. in junctions, the lane whose driving direction is
+    /// closest to the heading is chosen.
+    pub fn localize(&self, x: f64, y: f64, yaw: f64) -> Option<Localization> {
+        self.lane_positions_at(x, y)
+            .filter_map(|position| self.roads[position.road].localize(position, yaw))
+            .fold(None, |best: Option<Localization>, l| match best {
+                Some(best) if best.heading.abs() <= l.heading.abs() => Some(best),
+                _ => Some(l),
+            })
+    }
+
+    /// The road with the given id
+    pub fn road(&self, id: &types::RoadId) -> Option<&Road> {
+        self.roads.iter().find(|road| road.id == *id)
+    }
+
+    /// The junction with the given id
+    pub fn junction(&self, id: &types::JunctionId) -> Option<&Junction> {
+        self.junctions.iter().find(|junction| junction.id == *id)
+    }
+
+    /// Check the consistency of the road network, returning all findings
+    pub fn validate(&self) -> Vec<errors::ValidationError> {
+        use super::Monotonic;
+        use std::collections::HashSet;
+
+        let mut findings = Vec::new();
+        let mut road_ids = HashSet::new();
+        for road in self.roads.iter() {
+            if !road_ids.insert(&road.id) {
+                findings.push(errors::ValidationError::DuplicateRoadId(road.id.clone()));
+            }
+        }
+        let mut junction_ids = HashSet::new();
+        for junction in self.junctions.iter() {
+            if !junction_ids.insert(&junction.id) {
+                findings.push(errors::ValidationError::DuplicateJunctionId(
+                    junction.id.clone(),
+                ));
+            }
+        }
+
+        for road in self.roads.iter() {
+            let finding = |err| errors::ValidationError::Road(road.id.clone(), Box::new(err));
+            if let Err(err) = road.validate() {
+                findings.push(finding(err));
+            }
+            if let Some(ref junction) = road.junction {
+                if !junction_ids.contains(junction) {
+                    findings.push(finding(errors::ValidationError::MissingJunction(
+                        junction.clone(),
+                    )));
+                }
+            }
+            let sections = road.lanes.as_ref().map(|lanes| &lanes.lane_sections);
+            if let Some(sections) = sections {
+                if !sections.iter().map(|section| section.s).is_monotonic() {
+                    findings.push(finding(errors::ValidationError::LaneSectionOrder));
+                }
+            }
+            let links = road
+                .link
+                .iter()
+                .flat_map(|link| link.predecessor.iter().chain(link.successor.iter()));
+            for element in links {
+                let missing = match element.element_type {
+                    ElementType::Road => {
+                        let id = types::RoadId::from(element.element_id.clone());
+                        if road_ids.contains(&id) {
+                            continue;
+                        }
+                        errors::ValidationError::MissingRoad(id)
+                    }
+                    ElementType::Junction => {
+                        let id = types::JunctionId::from(element.element_id.clone());
+                        if junction_ids.contains(&id) {
+                            continue;
+                        }
+                        errors::ValidationError::MissingJunction(id)
+                    }
+                };
+                findings.push(finding(missing));
+            }
+        }
+
+        for junction in self.junctions.iter() {
+            for connection in junction.connections.iter() {
+                let connected = match connection.connected_road() {
+                    Some(road) => road,
+                    None => {
+                        findings.push(errors::ValidationError::Junction(
+                            junction.id.clone(),
+                            Box::new(errors::ValidationError::MissingConnectedRoad(
+                                connection.id.clone(),
+                            )),
+                        ));
+                        continue;
+                    }
+                };
+                for road in &[&connection.incoming_road, connected] {
+                    if !road_ids.contains(road) {
+                        findings.push(errors::ValidationError::Junction(
+                            junction.id.clone(),
+                            Box::new(errors::ValidationError::MissingRoad((*road).clone())),
+                        ));
+                    }
+                }
+            }
+        }
+        findings
+    }
+}
+
+/// Position of a point relative to a lane
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LanePosition {
+    /// index of the road in `Root::roads`
+    pub road: usize,
+    /// index of the lane section
+    pub section: usize,
+    /// lane id
+    pub lane: i32,
+    /// m position along the reference line
+    pub s: f64,
+    /// m lateral offset from the reference line
+    pub t: f64,
+}
+
+/// Position and heading of a vehicle relative to the lane it occupies. Left
+/// and right refer to the driving direction of the lane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Localization {
+    pub position: LanePosition,
+    pub road_id: types::RoadId,
+    /// m lateral offset from the lane center, positive to the left
+    pub offset: f64,
+    /// rad heading relative to the driving direction of the lane in
+    /// ]-π, π], positive to the left
+    pub heading: f64,
+    /// m distance to the left lane border
+    pub left: f64,
+    /// m distance to the right lane border
+    pub right: f64,
+}
+
+/// Axis aligned extent in inertial co-ordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    /// minimum x
+    pub west: f64,
+    /// minimum y
+    pub south: f64,
+    /// maximum x
+    pub east: f64,
+    /// maximum y
+    pub north: f64,
+}
+impl BoundingBox {
+    /// The smallest box containing both boxes
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox {
+            west: self.west.min(other.west),
+            south: self.south.min(other.south),
+            east: self.east.max(other.east),
+            north: self.north.max(other.north),
+        }
+    }
+
+    /// Both boxes overlap or touch
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.west <= other.east
+            && other.west <= self.east
+            && self.south <= other.north
+            && other.south <= self.north
+    }
+}
 
 /// 5.3.1 Road Header Record
 /// The road header record defines the basic parameters of an individual road.
 /// It is followed immediately by other records defining geometry and logical
 /// properties of the road.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename = "road", rename_all = "camelCase")]
 pub struct Road {
     /// name of the road
@@ -82,11 +437,19 @@ pub struct Road {
     /// total length of the reference line in the xy-plane
     pub length: types::Length,
     /// unique ID within database
-    pub id: u8,
-    /// ID of the junction to which the road belongs as a connecting road (= -1 for none)
-    pub junction: i8,
+    pub id: types::RoadId,
+    /// ID of the junction to which the road belongs as a connecting road,
+    /// written as -1 for none
+    #[serde(with = "parse_util::junction_ref")]
+    pub junction: Option<types::JunctionId>,
+    /// traffic rule of the road (OpenDRIVE 1.5), right-hand traffic if
+    /// omitted
+    pub rule: Option<TrafficRule>,
     /// Road link record
     pub link: Option<Link>,
+    /// Road type records
+    #[serde(default, rename = "type")]
+    pub road_types: Vec<RoadType>,
     pub plan_view: PlanView,
     pub elevation_profile: Option<ElevationProfile>,
     pub lateral_profile: Option<LateralProfile>,
@@ -96,12 +459,17 @@ pub struct Road {
     /// along the reference line.
     #[serde(default)]
     pub lanes: Option<Lanes>,
+
+    /// Objects record
+    pub objects: Option<Objects>,
+    /// Signals record
+    pub signals: Option<Signals>,
 }
 impl Road {
     pub fn validate(&self) -> Result<(), errors::ValidationError> {
         //self.plan_view.validate()?;
         let sum_length = self.plan_view.sum_length();
-        if self.length != sum_length {
+        if (self.length.get() - sum_length.get()).abs() > LENGTH_TOLERANCE {
             return Err(errors::ValidationError::ReferenceLineLength((
                 self.length,
                 sum_length,
@@ -110,9 +478,512 @@ impl Road {
 
         Ok(())
     }
+
+    /// Inertial x/y position of the point at s along the reference line with
+    /// lateral offset t
+    pub fn position_at(&self, s: f64, t: f64) -> (f64, f64) {
+        let (x, y, hdg) = self.plan_view.pose_at(s);
+        let (sin, cos) = hdg.sin_cos();
+        (x - t * sin, y + t * cos)
+    }
+
+    /// Extent of the road including the lanes, sampled such that the sampled
+    /// polylines deviate less than `tolerance` from the curves
+    pub fn bounding_box(&self, tolerance: f64) -> Result<Option<BoundingBox>, errors::SampleError> {
+        let mut bounds: Option<BoundingBox> = None;
+        let mut add = |(x, y): (f64, f64)| {
+            let point = BoundingBox {
+                west: x,
+                south: y,
+                east: x,
+                north: y,
+            };
+            bounds = Some(bounds.map_or(point, |b| b.union(&point)));
+        };
+        for s in self.sample_s(0.0, self.length.get(), tolerance)? {
+            match self.lane_section_index_at(s) {
+                Some(index) => {
+                    let borders = self.lane_borders(index, s);
+                    add(self.position_at(s, borders.first().unwrap().outer));
+                    add(self.position_at(s, borders.last().unwrap().outer));
+                }
+                None => add(self.position_at(s, 0.0)),
+            }
+        }
+        Ok(bounds)
+    }
+
+    /// Traffic rule of the road, right-hand traffic unless specified
+    pub fn traffic_rule(&self) -> TrafficRule {
+        self.rule.unwrap_or_default()
+    }
+
+    /// s and t of the point on the reference line closest to the inertial
+    /// position x, y. Points beyond the ends of the reference line are
+    /// projected onto the ends.
+    pub fn project(&self, x: f64, y: f64) -> (f64, f64) {
+        let length = self.length.get();
+        // start at the closest sample and refine the foot point iteratively
+        let mut s = self
+            .plan_view
+            .sample_s(PROJECTION_SAMPLE_TOLERANCE)
+            .expect("positive sample tolerance")
+            .into_iter()
+            .map(|s| {
+                let (px, py) = self.position_at(s, 0.0);
+                (s, (x - px).hypot(y - py))
+            })
+            .fold((0.0, f64::INFINITY), |a, b| if b.1 < a.1 { b } else { a })
+            .0;
+        for _ in 0..32 {
+            let (px, py, hdg) = self.plan_view.pose_at(s);
+            let next = (s + (x - px) * hdg.cos() + (y - py) * hdg.sin()).clamp(0.0, length);
+            let converged = (next - s).abs() < 1e-9;
+            s = next;
+            if converged {
+                break;
+            }
+        }
+        let (px, py, hdg) = self.plan_view.pose_at(s);
+        (s, (y - py) * hdg.cos() - (x - px) * hdg.sin())
+    }
+
+    /// Index of the lane section and id of the lane containing the point at
+    /// s with lateral offset t
+    pub fn lane_at(&self, s: f64, t: f64) -> Option<(usize, i32)> {
+        let index = self.lane_section_index_at(s)?;
+        self.lane_borders(index, s)
+            .into_iter()
+            .find(|b| b.id != 0 && (t - b.inner) * (t - b.outer) <= 0.0)
+            .map(|b| (index, b.id))
+    }
+
+    /// Localize a vehicle with heading `yaw` in the lane at `position`.
+    /// Lanes without a driving direction, like sidewalks or lanes of
+    /// bidirectional traffic, are measured in the direction of the reference
+    /// line or against it, whichever is closer to the heading.
+    fn localize(&self, position: LanePosition, yaw: f64) -> Option<Localization> {
+        let LanePosition {
+            section,
+            lane,
+            s,
+            t,
+            ..
+        } = position;
+        let record = self
+            .lanes
+            .as_ref()?
+            .lane_sections
+            .get(section)?
+            .lane(lane)?;
+        let borders = |s: f64| {
+            self.lane_borders(section, s)
+                .into_iter()
+                .find(|borders| borders.id == lane)
+        };
+        let here = borders(s)?;
+        let (lower, upper) = (here.inner.min(here.outer), here.inner.max(here.outer));
+
+        // heading of the lane center line relative to the reference line
+        let (start, end) = self.lane_section_range(section);
+        let (s0, s1) = (
+            (s - LANE_HEADING_STEP).max(start),
+            (s + LANE_HEADING_STEP).min(end),
+        );
+        let center = |b: LaneBorders| (b.inner + b.outer) / 2.0;
+        let slope = match (borders(s0), borders(s1)) {
+            (Some(b0), Some(b1)) if s1 > s0 => (center(b1) - center(b0)) / (s1 - s0),
+            _ => 0.0,
+        };
+        let (_, _, hdg) = self.plan_view.pose_at(s);
+        let along = normalize_angle(yaw - hdg - slope.atan());
+        let against = normalize_angle(along + std::f64::consts::PI);
+
+        let forward = match record.lane_type {
+            LaneType::Driving
+            | LaneType::Entry
+            | LaneType::Exit
+            | LaneType::OnRamp
+            | LaneType::OffRamp
+            | LaneType::ConnectingRamp
+            | LaneType::Bus
+            | LaneType::Taxi
+            | LaneType::Hov
+            | LaneType::MwyEntry
+            | LaneType::MwyExit
+            | LaneType::Biking
+            | LaneType::Restricted
+            | LaneType::Tram => match record.direction {
+                Some(LaneDirection::Both) => None,
+                _ => Some(crate::lane_graph::is_forward(self, record)),
+            },
+            _ => None,
+        };
+        let forward = forward.unwrap_or_else(|| along.abs() <= against.abs());
+
+        let center = (lower + upper) / 2.0;
+        let (heading, offset, left, right) = if forward {
+            (along, t - center, upper - t, t - lower)
+        } else {
+            (against, center - t, t - lower, upper - t)
+        };
+        Some(Localization {
+            position,
+            road_id: self.id.clone(),
+            offset,
+            heading,
+            left,
+            right,
+        })
+    }
+
+    /// Lateral offset of the lane reference line (laneOffset) at s
+    pub fn lane_offset_at(&self, s: f64) -> f64 {
+        self.lanes.as_ref().map_or(0.0, |lanes| lanes.offset_at(s))
+    }
+
+    /// Index of the lane section that is valid at s
+    pub fn lane_section_index_at(&self, s: f64) -> Option<usize> {
+        let sections = &self.lanes.as_ref()?.lane_sections;
+        if sections.is_empty() {
+            return None;
+        }
+        let count = sections.iter().take_while(|section| section.s <= s).count();
+        Some(count.saturating_sub(1))
+    }
+
+    /// Start and end s of the lane section with the given index
+    pub fn lane_section_range(&self, index: usize) -> (f64, f64) {
+        let sections = &self
+            .lanes
+            .as_ref()
+            .expect("road has no lanes")
+            .lane_sections;
+        let end = sections
+            .get(index + 1)
+            .map_or(self.length.get(), |next| next.s);
+        (sections[index].s, end)
+    }
+
+    /// Lateral offsets of the inner and outer border of every lane of a lane
+    /// section at s, relative to the road reference line. The lanes are
+    /// ordered from left to right and include the center lane.
+    pub fn lane_borders(&self, index: usize, s: f64) -> Vec<LaneBorders> {
+        let section = &self
+            .lanes
+            .as_ref()
+            .expect("road has no lanes")
+            .lane_sections[index];
+        let ds = s - section.s;
+        let offset = self.lane_offset_at(s);
+
+        let mut borders = Vec::new();
+        let mut t = offset;
+        for lane in section.left_lanes() {
+            let width = lane.width_at(ds);
+            borders.push(LaneBorders {
+                id: lane.id,
+                inner: t,
+                outer: t + width,
+            });
+            t += width;
+        }
+        borders.reverse();
+        borders.push(LaneBorders {
+            id: 0,
+            inner: offset,
+            outer: offset,
+        });
+        let mut t = offset;
+        for lane in section.right_lanes() {
+            let width = lane.width_at(ds);
+            borders.push(LaneBorders {
+                id: lane.id,
+                inner: t,
+                outer: t - width,
+            });
+            t -= width;
+        }
+        borders
+    }
+
+    /// Elevation of the reference line at s
+    pub fn elevation_at(&self, s: f64) -> f64 {
+        self.elevation_record_at(s)
+            .map_or(0.0, |e| cubic(e.a, e.b, e.c, e.d, s - e.s))
+    }
+
+    /// Slope dz/ds of the reference line at s
+    pub fn elevation_slope_at(&self, s: f64) -> f64 {
+        self.elevation_record_at(s).map_or(0.0, |e| {
+            let ds = s - e.s;
+            e.b + ds * (2.0 * e.c + 3.0 * e.d * ds)
+        })
+    }
+
+    fn elevation_record_at(&self, s: f64) -> Option<&Elevation> {
+        self.elevation_profile
+            .as_ref()?
+            .elevations
+            .iter()
+            .take_while(|e| e.s <= s)
+            .last()
+    }
+
+    /// Superelevation (roll angle around the reference line) at s
+    pub fn superelevation_at(&self, s: f64) -> f64 {
+        self.lateral_profile
+            .as_ref()
+            .and_then(|profile| {
+                profile
+                    .superelevations
+                    .iter()
+                    .take_while(|e| e.s <= s)
+                    .last()
+            })
+            .map_or(0.0, |e| cubic(e.a, e.b, e.c, e.d, s - e.s))
+    }
+
+    /// Height of the lateral shape above the superelevated reference plane
+    /// at s, t
+    pub fn shape_at(&self, s: f64, t: f64) -> f64 {
+        let shapes = match self.lateral_profile {
+            Some(ref profile) if !profile.shapes.is_empty() => &profile.shapes,
+            _ => return 0.0,
+        };
+        // The shape records of one s position form a piecewise cubic in t
+        let at = |s: f64| {
+            shapes
+                .iter()
+                .filter(|shape| shape.s == s)
+                .take_while(|shape| shape.t <= t)
+                .last()
+                .or_else(|| shapes.iter().find(|shape| shape.s == s))
+                .map_or(0.0, |p| cubic(p.a, p.b, p.c, p.d, t - p.t))
+        };
+        let before = shapes.iter().map(|shape| shape.s).rev().find(|&x| x <= s);
+        let after = shapes.iter().map(|shape| shape.s).find(|&x| x > s);
+        match (before, after) {
+            (Some(s0), Some(s1)) => {
+                let f = (s - s0) / (s1 - s0);
+                at(s0) * (1.0 - f) + at(s1) * f
+            }
+            (Some(s0), None) => at(s0),
+            (None, Some(s1)) => at(s1),
+            (None, None) => 0.0,
+        }
+    }
+
+    /// Inertial x/y/z position of the point at s, t on the road surface,
+    /// taking elevation, superelevation and lateral shape into account
+    pub fn surface_point(&self, s: f64, t: f64) -> (f64, f64, f64) {
+        let (sin, cos) = self.superelevation_at(s).sin_cos();
+        let (x, y) = self.position_at(s, t * cos);
+        (x, y, self.elevation_at(s) + t * sin + self.shape_at(s, t))
+    }
+
+    /// Pose of the local co-ordinate system at s, t and height h above the
+    /// road surface. The heading follows the reference line, the pitch the
+    /// elevation and the roll the superelevation and the slope of the
+    /// lateral shape, so the local z axis is normal to the road surface.
+    pub fn pose_at(&self, s: f64, t: f64, h: f64) -> Pose {
+        let (_, _, heading) = self.plan_view.pose_at(s);
+        let shape_slope = (self.shape_at(s, t + SHAPE_SLOPE_STEP)
+            - self.shape_at(s, t - SHAPE_SLOPE_STEP))
+            / (2.0 * SHAPE_SLOPE_STEP);
+        let (x, y, z) = self.surface_point(s, t);
+        let surface = Pose {
+            x,
+            y,
+            z,
+            heading,
+            pitch: -self.elevation_slope_at(s).atan(),
+            roll: self.superelevation_at(s) + shape_slope.atan(),
+        };
+        let (x, y, z) = surface.to_world(0.0, 0.0, h);
+        Pose { x, y, z, ..surface }
+    }
+
+    /// s, t and h of the inertial point x, y, z, the inverse of `pose_at`.
+    /// Points beyond the ends of the reference line get the s of the
+    /// closest end.
+    pub fn track_position(&self, x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+        let (mut s, mut t) = self.project(x, y);
+        let mut h = z - self.surface_point(s, t).2;
+        // refine in the local co-ordinates of the current estimate
+        for _ in 0..32 {
+            let pose = self.pose_at(s, t, h);
+            let (du, dv, dw) = pose.to_local(x, y, z);
+            let next = (s + du * pose.pitch.cos()).clamp(0.0, self.length.get());
+            let converged = (next - s).abs() < 1e-9 && dv.abs() < 1e-9 && dw.abs() < 1e-9;
+            s = next;
+            t += dv;
+            h += dw;
+            if converged {
+                break;
+            }
+        }
+        (s, t, h)
+    }
+
+    /// Maximum speed in m/s of the road type record valid at s
+    pub fn speed_at(&self, s: f64) -> Option<f64> {
+        self.road_types
+            .iter()
+            .take_while(|road_type| road_type.s <= s)
+            .last()
+            .and_then(|road_type| road_type.speed.as_ref())
+            .map(Speed::max_meters_per_second)
+    }
+
+    /// s positions between s0 and s1 at which the road can be sampled such
+    /// that the polyline through the sampled reference line points deviates
+    /// from the reference line by less than `tolerance`. The positions
+    /// include the start of every geometry, lane offset, lane section and
+    /// lane width record. Fails if `tolerance` is not positive and finite.
+    pub fn sample_s(
+        &self,
+        s0: f64,
+        s1: f64,
+        tolerance: f64,
+    ) -> Result<Vec<f64>, errors::SampleError> {
+        let mut stations = self.plan_view.sample_s(tolerance)?;
+        if let Some(ref lanes) = self.lanes {
+            stations.extend(lanes.lane_offsets.iter().map(|offset| offset.s));
+            for section in lanes.lane_sections.iter() {
+                stations.push(section.s);
+                for lane in section.left_lanes().chain(section.right_lanes()) {
+                    stations.extend(lane.widths.iter().map(|w| section.s + w.s_offset));
+                    stations.extend(lane.road_marks.iter().map(|m| section.s + m.s_offset));
+                }
+            }
+        }
+        stations.push(s0);
+        stations.push(s1);
+        stations.retain(|s| *s >= s0 && *s <= s1);
+        stations.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        stations.dedup_by(|a, b| (*a - *b).abs() < 1e-9);
+
+        // Cubic lane offsets and widths also need sampling on straight lines
+        let curved_lanes = self.lanes.iter().any(|lanes| {
+            lanes.lane_offsets.iter().any(|o| o.c != 0.0 || o.d != 0.0)
+                || lanes.lane_sections.iter().any(|section| {
+                    section
+                        .left_lanes()
+                        .chain(section.right_lanes())
+                        .flat_map(|lane| lane.widths.iter())
+                        .any(|w| w.c != 0.0 || w.d != 0.0)
+                })
+        });
+        if !curved_lanes {
+            return Ok(stations);
+        }
+        let mut dense = Vec::with_capacity(stations.len());
+        for (a, b) in stations.iter().zip(stations.iter().skip(1)) {
+            let n = ((b - a) / MAX_LANE_SAMPLE_STEP).ceil().max(1.0);
+            dense.extend((0..n as usize).map(|i| a + (b - a) * i as f64 / n));
+        }
+        dense.extend(stations.last());
+        Ok(dense)
+    }
+}
+
+/// Maximum distance between samples along roads with cubic lane offsets or
+/// widths [m]
+const MAX_LANE_SAMPLE_STEP: f64 = 1.0;
+/// m allowed difference between the road length and the length of its
+/// geometry elements
+const LENGTH_TOLERANCE: f64 = 1e-6;
+/// m chord error of the reference line samples used as start of projections
+const PROJECTION_SAMPLE_TOLERANCE: f64 = 0.1;
+
+/// Lateral offsets of the borders of a lane relative to the road reference
+/// line. For left lanes the outer border lies to the left of the inner
+/// border, for right lanes to the right.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LaneBorders {
+    pub id: i32,
+    pub inner: f64,
+    pub outer: f64,
+}
+
+/// m step of the central difference the heading of a lane center line is
+/// computed with
+const LANE_HEADING_STEP: f64 = 1e-3;
+
+/// Angle in ]-π, π]
+fn normalize_angle(angle: f64) -> f64 {
+    use std::f64::consts::PI;
+    let angle = angle.rem_euclid(2.0 * PI);
+    if angle > PI {
+        angle - 2.0 * PI
+    } else {
+        angle
+    }
+}
+
+/// m lateral step of the central difference the slope of the lateral shape
+/// is computed with
+const SHAPE_SLOPE_STEP: f64 = 1e-4;
+
+/// Position and orientation of a local co-ordinate system in inertial
+/// co-ordinates. The orientation rotates by `heading` around the z axis,
+/// then by `pitch` around the new y axis and finally by `roll` around the
+/// new x axis, so an ascending road has a negative pitch and a road falling
+/// to the right a positive roll.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Pose {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// rad yaw around the z axis
+    pub heading: f64,
+    /// rad around the y axis
+    pub pitch: f64,
+    /// rad around the x axis
+    pub roll: f64,
+}
+impl Pose {
+    /// Rows of the rotation matrix from local to inertial co-ordinates
+    fn rotation(&self) -> [[f64; 3]; 3] {
+        let (sy, cy) = self.heading.sin_cos();
+        let (sp, cp) = self.pitch.sin_cos();
+        let (sr, cr) = self.roll.sin_cos();
+        [
+            [cy * cp, cy * sp * sr - sy * cr, cy * sp * cr + sy * sr],
+            [sy * cp, sy * sp * sr + cy * cr, sy * sp * cr - cy * sr],
+            [-sp, cp * sr, cp * cr],
+        ]
+    }
+
+    /// Inertial co-ordinates of the local point u, v, w
+    pub fn to_world(self, u: f64, v: f64, w: f64) -> (f64, f64, f64) {
+        let r = self.rotation();
+        (
+            self.x + r[0][0] * u + r[0][1] * v + r[0][2] * w,
+            self.y + r[1][0] * u + r[1][1] * v + r[1][2] * w,
+            self.z + r[2][0] * u + r[2][1] * v + r[2][2] * w,
+        )
+    }
+
+    /// Local co-ordinates of the inertial point x, y, z
+    pub fn to_local(self, x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+        let r = self.rotation();
+        let (dx, dy, dz) = (x - self.x, y - self.y, z - self.z);
+        (
+            r[0][0] * dx + r[1][0] * dy + r[2][0] * dz,
+            r[0][1] * dx + r[1][1] * dy + r[2][1] * dz,
+            r[0][2] * dx + r[1][2] * dy + r[2][2] * dz,
+        )
+    }
+}
+
+/// Evaluate the cubic polynomial a + b*ds + c*ds² + d*ds³
+fn cubic(a: f64, b: f64, c: f64, d: f64, ds: f64) -> f64 {
+    a + ds * (b + ds * (c + ds * d))
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename = "link", rename_all = "camelCase")]
 pub struct Link {
     //#[serde(flatten)]
@@ -123,23 +994,23 @@ pub struct Link {
     pub neighbors: Vec<Neighbor>,
 }
 
-#[derive(Default, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct LinkElement {
     pub element_type: ElementType,
-    pub element_id: u32,
+    pub element_id: types::ElementId,
     pub contact_point: ContactPoint,
 }
 
-#[derive(Default, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename = "neighbor", rename_all = "camelCase")]
 pub struct Neighbor {
     pub side: Side,
-    pub element_id: u32,
+    pub element_id: types::RoadId,
     pub direction: Direction,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum ElementType {
     Road,
@@ -151,7 +1022,7 @@ impl Default for ElementType {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum ContactPoint {
     Start,
@@ -163,7 +1034,7 @@ impl Default for ContactPoint {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum Side {
     Left,
@@ -175,7 +1046,7 @@ impl Default for Side {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum Direction {
     Same,
@@ -187,9 +1058,90 @@ impl Default for Direction {
     }
 }
 
+/// Side of the road traffic keeps to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TrafficRule {
+    /// right-hand traffic
+    #[serde(rename = "RHT")]
+    RightHand,
+    /// left-hand traffic
+    #[serde(rename = "LHT")]
+    LeftHand,
+}
+impl Default for TrafficRule {
+    fn default() -> TrafficRule {
+        TrafficRule::RightHand
+    }
+}
+
+/// 5.3.2 Road Type Record
+///
+/// The road type record is used to indicate the type of road (e.g. motorway,
+/// rural road etc.) along the reference line. It is valid until a new road
+/// type record is given.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "type", rename_all = "camelCase")]
+pub struct RoadType {
+    /// m [0,∞[ start position (s-coordinate)
+    pub s: f64,
+    /// type of the road
+    #[serde(rename = "type")]
+    pub road_type: RoadTypeKind,
+    /// 5.3.2.1 Road Type Speed Record
+    pub speed: Option<Speed>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RoadTypeKind {
+    Unknown,
+    Rural,
+    Motorway,
+    Town,
+    /// In Germany, lowSpeed is equivalent to a 30km/h zone
+    LowSpeed,
+    Pedestrian,
+    Bicycle,
+}
+impl Default for RoadTypeKind {
+    fn default() -> RoadTypeKind {
+        RoadTypeKind::Unknown
+    }
+}
+
+/// The speed record defines the maximum allowed speed for a given road type.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "speed", rename_all = "camelCase")]
+pub struct Speed {
+    /// - [0,∞[ maximum allowed speed
+    pub max: f64,
+    /// unit of the attribute max (default: m/s)
+    pub unit: Option<SpeedUnit>,
+}
+impl Speed {
+    /// The maximum allowed speed in m/s
+    pub fn max_meters_per_second(&self) -> f64 {
+        match self.unit {
+            None | Some(SpeedUnit::MetersPerSecond) => self.max,
+            Some(SpeedUnit::KilometersPerHour) => self.max / 3.6,
+            Some(SpeedUnit::MilesPerHour) => self.max * 0.44704,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum SpeedUnit {
+    #[serde(rename = "m/s")]
+    MetersPerSecond,
+    #[serde(rename = "km/h")]
+    KilometersPerHour,
+    #[serde(rename = "mph")]
+    MilesPerHour,
+}
+
 /// The plan view record contains a series of geometry records which define the
 /// layout of the road's reference line in the x/y-plane (plan view).
-#[derive(Default, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct PlanView {
     #[serde(default, rename = "geometry")]
@@ -228,6 +1180,49 @@ impl PlanView {
     pub fn sum_length(&self) -> types::Length {
         types::Length::new(self.geometries.iter().fold(0.0, |acc, g| g.length.0 + acc))
     }
+
+    /// The geometry element that is valid at s
+    pub fn geometry_at(&self, s: f64) -> Option<&Geometry> {
+        self.geometries
+            .iter()
+            .take_while(|g| g.s.get() <= s)
+            .last()
+            .or_else(|| self.geometries.first())
+    }
+
+    /// Inertial x, y and heading of the reference line at s
+    pub fn pose_at(&self, s: f64) -> (f64, f64, f64) {
+        match self.geometry_at(s) {
+            Some(g) => g.pose_at(s - g.s.get()),
+            None => (0.0, 0.0, 0.0),
+        }
+    }
+
+    /// s positions at which the reference line can be sampled such that the
+    /// polyline through the sampled points deviates from it by less than
+    /// `tolerance`. Fails if `tolerance` is not positive and finite.
+    pub fn sample_s(&self, tolerance: f64) -> Result<Vec<f64>, errors::SampleError> {
+        if !(tolerance > 0.0 && tolerance.is_finite()) {
+            return Err(errors::SampleError::InvalidTolerance(tolerance));
+        }
+        let mut stations = Vec::new();
+        for g in self.geometries.iter() {
+            let (s, length) = (g.s.get(), g.length.get());
+            // The distance between a chord of length l and an arc of curvature
+            // k is approximately k * l² / 8
+            let k = g.max_curvature();
+            let n = if k > 1e-12 && length > 0.0 {
+                (length / (8.0 * tolerance / k).sqrt()).ceil()
+            } else {
+                1.0
+            };
+            stations.extend((0..n as usize).map(|i| s + length * i as f64 / n));
+        }
+        if let Some(last) = self.geometries.last() {
+            stations.push(last.s.get() + last.length.get());
+        }
+        Ok(stations)
+    }
 }
 
 /// 5.3.4.1 Road Geometry Header Record
@@ -240,12 +1235,13 @@ impl PlanView {
 /// geometric elements and a subsequent bead containing the actual geometric
 /// element’s data (depending on the type of geometric element).
 ///
-/// Currently, four types of geometric elements are supported:
+/// Currently, five types of geometric elements are supported:
 /// - straight lines
 /// - spirals
 /// - arcs
 /// - cubic polynomials
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// - parametric cubic curves
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Geometry {
     /// m [0,∞[ start position (s-coordinate)
@@ -278,15 +1274,24 @@ impl Geometry {
                 })
             }
             GeometryElement::Spiral {
-                curv_start: _,
-                curv_end: _,
-            } => types::Segment::Line(lyon_geom::LineSegment {
-                from: euclid::point2(0.0, 0.0),
-                to: euclid::point2(0.0, 0.0),
-            }),
+                curv_start,
+                curv_end,
+            } => types::Segment::Clothoid(lyon_geom::Clothoid::from_opendrive(
+                euclid::point2(self.x.get(), self.y.get()),
+                curv_start,
+                curv_end,
+                self.length.get(),
+                self.hdg,
+            )),
             GeometryElement::Arc { curvature } => {
                 let radius = 1.0 / curvature;
-                let start_angle = self.hdg;
+                // lyon_geom needs positive radii, a right turn starts on the
+                // opposite side of the center
+                let start_angle = if curvature < 0.0 {
+                    self.hdg + euclid::Angle::pi()
+                } else {
+                    self.hdg
+                };
                 let sweep_angle = types::Angle::radians((self.length * curvature).get());
 
                 // Find the center by rotating the vector (0.0, radius) by the heading
@@ -295,27 +1300,267 @@ impl Geometry {
                     + euclid::TypedVector2D::from_lengths(self.x, self.y);
 
                 types::Segment::Arc(lyon_geom::Arc {
-                    center: center,
-                    radii: euclid::vec2(radius, radius),
-                    start_angle: start_angle,
-                    sweep_angle: sweep_angle,
+                    center,
+                    radii: euclid::vec2(radius.abs(), radius.abs()),
+                    start_angle,
+                    sweep_angle,
                     x_rotation: -euclid::Angle::frac_pi_2(), // OpenDRIVE zero-heading is pi/2 rotated from the lyon_geom::Arc
                 })
             }
-            GeometryElement::Poly3 {
-                a: _,
-                b: _,
-                c: _,
-                d: _,
-            } => types::Segment::Line(lyon_geom::LineSegment {
-                from: euclid::point2(0.0, 0.0),
-                to: euclid::point2(0.0, 0.0),
-            }),
+            GeometryElement::Poly3 { a, b, c, d } => {
+                // (u, v(u)) is a cubic polynomial curve and therefore exactly
+                // representable as a cubic bezier curve
+                let u = poly3_u_at_length(b, c, d, self.length.get());
+                let rot = types::Rotation::new(self.hdg);
+                let origin = euclid::vec2(self.x.get(), self.y.get());
+                let local = |x: f64, y: f64| rot.transform_point(&euclid::point2(x, y)) + origin;
+                let end = cubic(a, b, c, d, u);
+                let end_slope = b + u * (2.0 * c + 3.0 * d * u);
+                types::Segment::Cubic(lyon_geom::CubicBezierSegment {
+                    from: local(0.0, a),
+                    ctrl1: local(u / 3.0, a + b * u / 3.0),
+                    ctrl2: local(2.0 * u / 3.0, end - end_slope * u / 3.0),
+                    to: local(u, end),
+                })
+            }
+            GeometryElement::ParamPoly3 { .. } => {
+                // (u(p), v(p)) is a cubic polynomial curve as well
+                let (u, v) = self.param_poly3_coefficients().unwrap();
+                let rot = types::Rotation::new(self.hdg);
+                let origin = euclid::vec2(self.x.get(), self.y.get());
+                let local = |x: f64, y: f64| rot.transform_point(&euclid::point2(x, y)) + origin;
+                let ctrl = |c: [f64; 4]| {
+                    [
+                        c[0],
+                        c[0] + c[1] / 3.0,
+                        c[0] + (2.0 * c[1] + c[2]) / 3.0,
+                        c[0] + c[1] + c[2] + c[3],
+                    ]
+                };
+                let (u, v) = (ctrl(u), ctrl(v));
+                types::Segment::Cubic(lyon_geom::CubicBezierSegment {
+                    from: local(u[0], v[0]),
+                    ctrl1: local(u[1], v[1]),
+                    ctrl2: local(u[2], v[2]),
+                    to: local(u[3], v[3]),
+                })
+            }
+        }
+    }
+
+    /// The coefficients of u and v of a paramPoly3 element as polynomials of
+    /// the normalized parameter in [0, 1]
+    pub(crate) fn param_poly3_coefficients(&self) -> Option<([f64; 4], [f64; 4])> {
+        match self.element {
+            GeometryElement::ParamPoly3 {
+                a_u,
+                b_u,
+                c_u,
+                d_u,
+                a_v,
+                b_v,
+                c_v,
+                d_v,
+                p_range,
+            } => {
+                let p = match p_range {
+                    PRange::ArcLength => self.length.get(),
+                    PRange::Normalized => 1.0,
+                };
+                Some((
+                    [a_u, b_u * p, c_u * p * p, d_u * p * p * p],
+                    [a_v, b_v * p, c_v * p * p, d_v * p * p * p],
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Inertial x, y and heading at a distance ds along the element
+    pub fn pose_at(&self, ds: f64) -> (f64, f64, f64) {
+        let (x, y, hdg) = (self.x.get(), self.y.get(), self.hdg.radians);
+        match self.element {
+            GeometryElement::Line => (x + ds * hdg.cos(), y + ds * hdg.sin(), hdg),
+            GeometryElement::Arc { curvature } => {
+                if curvature == 0.0 {
+                    return (x + ds * hdg.cos(), y + ds * hdg.sin(), hdg);
+                }
+                let end = hdg + curvature * ds;
+                (
+                    x + (end.sin() - hdg.sin()) / curvature,
+                    y - (end.cos() - hdg.cos()) / curvature,
+                    end,
+                )
+            }
+            GeometryElement::Spiral {
+                curv_start,
+                curv_end,
+            } => {
+                let clothoid = lyon_geom::Clothoid::from_opendrive(
+                    euclid::point2(x, y),
+                    curv_start,
+                    curv_end,
+                    self.length.get(),
+                    self.hdg,
+                );
+                let p = clothoid.sample_at_length(ds);
+                (p.x, p.y, clothoid.angle_at_length(ds).radians)
+            }
+            GeometryElement::Poly3 { a, b, c, d } => {
+                let u = poly3_u_at_length(b, c, d, ds);
+                let v = cubic(a, b, c, d, u);
+                let dv = b + u * (2.0 * c + 3.0 * d * u);
+                let (sin, cos) = hdg.sin_cos();
+                (
+                    x + u * cos - v * sin,
+                    y + u * sin + v * cos,
+                    hdg + dv.atan(),
+                )
+            }
+            GeometryElement::ParamPoly3 { .. } => {
+                // The parameter is assumed to be proportional to the length
+                let (u, v) = self.param_poly3_coefficients().unwrap();
+                let p = if self.length.get() > 0.0 {
+                    ds / self.length.get()
+                } else {
+                    0.0
+                };
+                let (pu, pv) = (
+                    cubic(u[0], u[1], u[2], u[3], p),
+                    cubic(v[0], v[1], v[2], v[3], p),
+                );
+                let du = u[1] + p * (2.0 * u[2] + 3.0 * u[3] * p);
+                let dv = v[1] + p * (2.0 * v[2] + 3.0 * v[3] * p);
+                let (sin, cos) = hdg.sin_cos();
+                (
+                    x + pu * cos - pv * sin,
+                    y + pu * sin + pv * cos,
+                    hdg + dv.atan2(du),
+                )
+            }
+        }
+    }
+
+    /// Signed curvature at a distance ds along the element
+    pub fn curvature_at(&self, ds: f64) -> f64 {
+        let length = self.length.get();
+        match self.element {
+            GeometryElement::Line => 0.0,
+            GeometryElement::Arc { curvature } => curvature,
+            GeometryElement::Spiral {
+                curv_start,
+                curv_end,
+            } => {
+                if length > 0.0 {
+                    curv_start + (curv_end - curv_start) * ds / length
+                } else {
+                    curv_start
+                }
+            }
+            GeometryElement::Poly3 { b, c, d, .. } => {
+                let u = poly3_u_at_length(b, c, d, ds);
+                let dv = b + u * (2.0 * c + 3.0 * d * u);
+                (2.0 * c + 6.0 * d * u) / (1.0 + dv * dv).powf(1.5)
+            }
+            GeometryElement::ParamPoly3 { .. } => {
+                let (u, v) = self.param_poly3_coefficients().unwrap();
+                let p = if length > 0.0 { ds / length } else { 0.0 };
+                let du = u[1] + p * (2.0 * u[2] + 3.0 * u[3] * p);
+                let dv = v[1] + p * (2.0 * v[2] + 3.0 * v[3] * p);
+                let ddu = 2.0 * u[2] + 6.0 * u[3] * p;
+                let ddv = 2.0 * v[2] + 6.0 * v[3] * p;
+                let speed = (du * du + dv * dv).sqrt();
+                if speed > 0.0 {
+                    (du * ddv - dv * ddu) / speed.powi(3)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    /// Upper bound of the absolute curvature along the element
+    pub fn max_curvature(&self) -> f64 {
+        match self.element {
+            GeometryElement::Line => 0.0,
+            GeometryElement::Arc { curvature } => curvature.abs(),
+            GeometryElement::Spiral {
+                curv_start,
+                curv_end,
+            } => curv_start.abs().max(curv_end.abs()),
+            // the curvature of v(u) is bounded by |v''|
+            GeometryElement::Poly3 { c, d, .. } => (2.0 * c)
+                .abs()
+                .max((2.0 * c + 6.0 * d * self.length.get()).abs()),
+            GeometryElement::ParamPoly3 { .. } => {
+                // sampled, as the curvature has no closed form bound
+                let length = self.length.get();
+                (0..=32)
+                    .map(|i| self.curvature_at(length * f64::from(i) / 32.0).abs())
+                    .fold(0.0, f64::max)
+            }
+        }
+    }
+
+    /// Inertial heading at the end of the element
+    pub fn end_hdg(&self) -> types::Angle {
+        let length = self.length.get();
+        match self.element {
+            GeometryElement::Line => self.hdg,
+            GeometryElement::Spiral {
+                curv_start,
+                curv_end,
+            } => self.hdg + types::Angle::radians((curv_start + curv_end) * length / 2.0),
+            GeometryElement::Arc { curvature } => {
+                self.hdg + types::Angle::radians(curvature * length)
+            }
+            GeometryElement::Poly3 { a: _, b, c, d } => {
+                // the length is measured along the curve
+                let u = poly3_u_at_length(b, c, d, length);
+                let dv = b + 2.0 * c * u + 3.0 * d * u * u;
+                self.hdg + types::Angle::radians(dv.atan())
+            }
+            GeometryElement::ParamPoly3 { .. } => {
+                let (u, v) = self.param_poly3_coefficients().unwrap();
+                let du = u[1] + 2.0 * u[2] + 3.0 * u[3];
+                let dv = v[1] + 2.0 * v[2] + 3.0 * v[3];
+                self.hdg + types::Angle::radians(dv.atan2(du))
+            }
+        }
+    }
+}
+
+/// The parameter u of a poly3 element at which the arc length along the curve
+/// v(u) = a + b*u + c*u² + d*u³ equals `length`
+pub(crate) fn poly3_u_at_length(b: f64, c: f64, d: f64, length: f64) -> f64 {
+    let speed = |u: f64| (1.0 + (b + u * (2.0 * c + 3.0 * d * u)).powi(2)).sqrt();
+    // Arc length from 0 to u by composite 5 point Gauss-Legendre integration
+    let arc_length = |u: f64| {
+        let n = 8;
+        let h = u / n as f64;
+        let mut sum = 0.0;
+        for i in 0..n {
+            let mid = h * (i as f64 + 0.5);
+            for &(node, weight) in lyon_geom::clothoid::GAUSS_LEGENDRE_5.iter() {
+                sum += weight * speed(mid + h * 0.5 * node);
+            }
+        }
+        sum * h * 0.5
+    };
+
+    // Newton iteration on arc_length(u) = length
+    let mut u = length / speed(0.0);
+    for _ in 0..10 {
+        let du = (arc_length(u) - length) / speed(u);
+        u -= du;
+        if du.abs() < 1e-10 {
+            break;
         }
     }
+    u
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum GeometryElement {
     /// This record describes a straight line as part of the road’s reference line
@@ -335,26 +1580,129 @@ pub enum GeometryElement {
     },
     /// This record describes a cubic polynomial as part of the road’s reference line.
     Poly3 { a: f64, b: f64, c: f64, d: f64 },
+    /// This record describes a parametric cubic curve as part of the road’s
+    /// reference line in a local u/v co-ordinate system. Both u and v are
+    /// cubic polynomials of the parameter p.
+    #[serde(rename_all = "camelCase")]
+    ParamPoly3 {
+        a_u: f64,
+        b_u: f64,
+        c_u: f64,
+        d_u: f64,
+        a_v: f64,
+        b_v: f64,
+        c_v: f64,
+        d_v: f64,
+        /// range of the parameter p
+        #[serde(default)]
+        p_range: PRange,
+    },
+}
+
+/// 5.3.4.1.5 Range of the parameter p of a parametric cubic curve
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum PRange {
+    /// p in [0, length of the element]
+    ArcLength,
+    /// p in [0, 1]
+    Normalized,
+}
+impl Default for PRange {
+    fn default() -> PRange {
+        PRange::Normalized
+    }
 }
 
 /// 5.3.5 Road Elevation Profile Record
 ///
 /// The elevation profile record contains a series of elevation records which
 /// define the characteristics of the road's elevation along the reference line.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ElevationProfile {
+    #[serde(default, rename = "elevation")]
+    pub elevations: Vec<Elevation>,
+}
+
+/// 5.3.5.1 Road Elevation Record
+///
+/// The elevation record defines an elevation entry at a given reference line
+/// position. If multiple elevation entries are defined, they must be listed in
+/// ascending order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub struct ElevationProfile {}
+pub struct Elevation {
+    /// m [0,∞[ start position (s-coordinate)
+    pub s: f64,
+    /// m ]-∞,∞[ parameter A, elevation at s=0
+    pub a: f64,
+    /// 1 ]-∞,∞[ parameter B
+    pub b: f64,
+    /// 1/m ]-∞,∞[ parameter C
+    pub c: f64,
+    /// 1/m² ]-∞,∞[ parameter D
+    pub d: f64,
+}
 
 /// 5.3.6 Road Lateral Profile Record
 ///
 /// The lateral profile record contains a series of superelevation and crossfall
 /// records which define the characteristics of the road surface's banking along
 /// the reference line.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub struct LateralProfile {}
+pub struct LateralProfile {
+    #[serde(default, rename = "superelevation")]
+    pub superelevations: Vec<Superelevation>,
+    #[serde(default, rename = "shape")]
+    pub shapes: Vec<Shape>,
+}
+
+/// 5.3.6.1 Superelevation Record
+///
+/// The superelevation of the road is defined as the road section’s roll angle
+/// around the s-axis. Superelevation is positive for roads falling to the
+/// right side and negative for roads falling to the left side.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Superelevation {
+    /// m [0,∞[ start position (s-coordinate)
+    pub s: f64,
+    /// rad ]-∞,∞[ parameter A, superelevation at s=0
+    pub a: f64,
+    /// 1/m ]-∞,∞[ parameter B
+    pub b: f64,
+    /// rad/m² ]-∞,∞[ parameter C
+    pub c: f64,
+    /// rad/m³ ]-∞,∞[ parameter D
+    pub d: f64,
+}
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// 5.3.6.3 Shape Record
+///
+/// The shape of the road is defined as the road section’s surface relative to
+/// the reference plane. There may be multiple shape definitions at a given
+/// s-position in order to describe complex road profiles. Between two
+/// s-positions the shape is interpolated linearly.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Shape {
+    /// m [0,∞[ start position (s-coordinate)
+    pub s: f64,
+    /// m ]-∞,∞[ start position (t-coordinate)
+    pub t: f64,
+    /// m ]-∞,∞[ parameter A, relative height at t=0
+    pub a: f64,
+    /// 1 ]-∞,∞[ parameter B
+    pub b: f64,
+    /// 1/m ]-∞,∞[ parameter C
+    pub c: f64,
+    /// 1/m² ]-∞,∞[ parameter D
+    pub d: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename = "lanes", rename_all = "camelCase")]
 pub struct Lanes {
     #[serde(default, rename = "laneOffset")]
@@ -362,6 +1710,16 @@ pub struct Lanes {
     #[serde(default, rename = "laneSection")]
     pub lane_sections: Vec<LaneSection>,
 }
+impl Lanes {
+    /// Lateral offset of the lane reference line at s
+    pub fn offset_at(&self, s: f64) -> f64 {
+        self.lane_offsets
+            .iter()
+            .take_while(|offset| offset.s <= s)
+            .last()
+            .map_or(0.0, |o| cubic(o.a, o.b, o.c, o.d, s - o.s))
+    }
+}
 
 /// 5.3.7.1 Road Lane Offset Record
 ///
@@ -371,7 +1729,7 @@ pub struct Lanes {
 /// to the road’s reference line. Especially the modeling of inner-city layouts
 /// or "2+1" crosscountry road layouts can be facilitated considerably by this
 /// feature.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct LaneOffset {
     /// double m [0,∞[ start position (s-coordinate)
@@ -394,7 +1752,7 @@ pub struct LaneOffset {
 /// section record is valid until a new lane section record is defined. If
 /// multiple lane section records are defined, they must be listed in ascending
 /// order.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct LaneSection {
     /// m - start position (s-coordinate)
@@ -408,13 +1766,38 @@ pub struct LaneSection {
     pub center: LaneSubSection,
     pub right: Option<LaneSubSection>,
 }
+impl LaneSection {
+    /// Left lanes ordered from the center outwards
+    pub fn left_lanes(&self) -> impl DoubleEndedIterator<Item = &Lane> {
+        let mut lanes: Vec<&Lane> = self.left.iter().flat_map(|l| l.lane.iter()).collect();
+        lanes.sort_by_key(|lane| lane.id);
+        lanes.into_iter()
+    }
+
+    /// Right lanes ordered from the center outwards
+    pub fn right_lanes(&self) -> impl DoubleEndedIterator<Item = &Lane> {
+        let mut lanes: Vec<&Lane> = self.right.iter().flat_map(|l| l.lane.iter()).collect();
+        lanes.sort_by_key(|lane| -lane.id);
+        lanes.into_iter()
+    }
+
+    /// The lane with the given id
+    pub fn lane(&self, id: i32) -> Option<&Lane> {
+        self.left
+            .iter()
+            .chain(Some(&self.center))
+            .chain(self.right.iter())
+            .flat_map(|l| l.lane.iter())
+            .find(|lane| lane.id == id)
+    }
+}
 
 /// 5.3.7.2.1 Left / Center / Right Records
 ///
 /// For easier navigation through a road description, the lanes under a lane
 /// section are grouped into left, center and right lanes. At least one entry
 /// (left, center or right) must be present.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct LaneSubSection {
     pub lane: Vec<Lane>,
@@ -427,7 +1810,7 @@ pub struct LaneSubSection {
 /// conventions in 3). In order to prevent confusion, lane records should
 /// represent the lanes from left to right (i.e. with descending ID). All
 /// properties of the lanes are defined as children of the lane records.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Lane {
     /// - ]-∞,∞[ id of the lane (according to convention)
@@ -438,8 +1821,14 @@ pub struct Lane {
     /// "true" = keep lane on level, .i.e. do not apply superelevation or
     /// crossfall. "false" = apply superelevation and crossfall to this lane
     /// (default, also used if argument level is missing)
-    #[serde(with = "parse_util::flexible_boolean")]
+    #[serde(default, with = "parse_util::flexible_boolean")]
     pub level: bool,
+    /// driving direction of the lane relative to the convention for its
+    /// side of the road (OpenDRIVE 1.7)
+    pub direction: Option<LaneDirection>,
+    /// side of the lane on which driving assistance systems are advised to
+    /// keep it (OpenDRIVE 1.7)
+    pub advisory: Option<LaneAdvisory>,
 
     /// In order to facilitate navigation through a road network on a per-lane
     /// basis, lanes should be provided with predecessor/successor information.
@@ -452,6 +1841,30 @@ pub struct Lane {
 
     #[serde(default, rename = "roadMark")]
     pub road_marks: Vec<RoadMark>,
+
+    #[serde(default, rename = "height")]
+    pub heights: Vec<LaneHeight>,
+}
+impl Lane {
+    /// Width of the lane at ds from the start of its lane section
+    pub fn width_at(&self, ds: f64) -> f64 {
+        self.widths
+            .iter()
+            .take_while(|w| w.s_offset <= ds)
+            .last()
+            .or_else(|| self.widths.first())
+            .map_or(0.0, |w| cubic(w.a, w.b, w.c, w.d, ds - w.s_offset))
+    }
+
+    /// Height of the inner and outer border of the lane above the road
+    /// surface at ds from the start of its lane section
+    pub fn height_at(&self, ds: f64) -> (f64, f64) {
+        self.heights
+            .iter()
+            .take_while(|h| h.s_offset <= ds)
+            .last()
+            .map_or((0.0, 0.0), |h| (h.inner, h.outer))
+    }
 }
 impl Default for Lane {
     fn default() -> Lane {
@@ -459,14 +1872,17 @@ impl Default for Lane {
             id: 0,
             lane_type: LaneType::None,
             level: false,
+            direction: None,
+            advisory: None,
             link: None,
             widths: vec![],
             road_marks: vec![],
+            heights: vec![],
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum LaneType {
     None,
@@ -491,21 +1907,55 @@ pub enum LaneType {
     Exit,
     OffRamp,
     OnRamp,
+    /// (OpenDRIVE 1.5)
+    Curb,
+    /// (OpenDRIVE 1.5)
+    ConnectingRamp,
+    /// (OpenDRIVE 1.6)
+    Bus,
+    /// (OpenDRIVE 1.6)
+    Taxi,
+    /// high-occupancy vehicle lane (OpenDRIVE 1.6)
+    #[serde(rename = "HOV")]
+    Hov,
+    /// motorway entry (OpenDRIVE 1.8)
+    MwyEntry,
+    /// motorway exit (OpenDRIVE 1.8)
+    MwyExit,
+}
+/// Driving direction of a lane relative to the convention for its side
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum LaneDirection {
+    Standard,
+    Reversed,
+    Both,
+}
+
+/// Lane border driving assistance systems are advised to keep to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum LaneAdvisory {
+    None,
+    Inner,
+    Outer,
+    Both,
 }
+
 impl Default for LaneType {
     fn default() -> LaneType {
         LaneType::None
     }
 }
 
-#[derive(Default, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename = "link", rename_all = "camelCase")]
 pub struct LaneLink {
     pub predecessor: Option<LaneLinkElement>,
     pub successor: Option<LaneLinkElement>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct LaneLinkElement {
     pub id: i32,
@@ -518,7 +1968,7 @@ pub struct LaneLinkElement {
 /// center lane which is, per convention, of zero width (see 3.2). Each entry
 /// is valid until a new entry is defined. If multiple entries are defined for
 /// a lane, they must be listed in ascending order.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Width {
     /// start position (s-coordinate) relative to the position of the preceding
@@ -541,7 +1991,7 @@ pub struct Width {
 /// lane’s outer border. For left lanes, this is the left border, for right
 /// lanes the right one. The style of the line separating left and right lanes
 /// is determined by the road mark entry for lane zero (i.e. the center lane)
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct RoadMark {
     /// m [0,∞[ start position (s-coordinate) relative to the position of the preceding laneSection record
@@ -566,27 +2016,58 @@ pub struct RoadMark {
     pub height: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// 5.3.7.2.1.1.9 Lane Height Record
+///
+/// The surface of a lane may be offset from the plane defined by the reference
+/// line and the corresponding elevation and crossfall entries (e.g. pedestrian
+/// walkways are typically a few centimeters above road level). The height
+/// record provides a simplified method to describe this offset by setting an
+/// inner and outer offset from road level at discrete positions along the
+/// lane profile.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LaneHeight {
+    /// m [0,∞[ start position (s-coordinate) relative to the position of the
+    /// preceding laneSection record
+    pub s_offset: f64,
+    /// m ]-∞,∞[ inner offset from road level
+    #[serde(default)]
+    pub inner: f64,
+    /// m ]-∞,∞[ outer offset from road level
+    #[serde(default)]
+    pub outer: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum RoadMarkType {
     None,
     Solid,
     Broken,
     /// (for double solid line),
+    #[serde(rename = "solid solid", alias = "solidSolid")]
     SolidSolid,
     /// (from inside to outside, exception: center lane - from left to right)
+    #[serde(rename = "solid broken", alias = "solidBroken")]
     SolidBroken,
     ///  (from inside to outside, exception: center lane - from left to right)
+    #[serde(rename = "broken solid", alias = "brokenSolid")]
     BrokenSolid,
     ///  (from inside to outside, exception: center lane - from left to right)
+    #[serde(rename = "broken broken", alias = "brokenBroken")]
     BrokenBroken,
+    #[serde(rename = "botts dots", alias = "bottsDots")]
     BottsDots,
     /// (meaning a grass edge)
     Grass,
     Curb,
+    /// (OpenDRIVE 1.5) described by sub-records not modelled here
+    Custom,
+    /// (OpenDRIVE 1.5) edge of the road surface
+    Edge,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum RoadMarkWeight {
     Standard,
@@ -598,7 +2079,7 @@ impl Default for RoadMarkWeight {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum RoadMarkColor {
     /// (equivalent to "white")
@@ -615,7 +2096,7 @@ impl Default for RoadMarkColor {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum LaneChangeType {
     Increase,
@@ -628,3 +2109,235 @@ impl Default for LaneChangeType {
         LaneChangeType::Both
     }
 }
+
+/// 5.3.8 Objects Record
+///
+/// Objects are items that influence a road by expanding, delimiting or
+/// supplementing its course. The most common examples are parking spaces,
+/// crosswalks and traffic barriers.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "objects", rename_all = "camelCase")]
+pub struct Objects {
+    #[serde(default, rename = "object")]
+    pub objects: Vec<Object>,
+}
+
+/// 5.3.8.1 Object Record
+///
+/// The object record describes common properties for an object. Objects are
+/// positioned relative to the road reference line.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "object", rename_all = "camelCase")]
+pub struct Object {
+    /// type of object
+    #[serde(rename = "type")]
+    pub object_type: Option<String>,
+    /// subtype of object (OpenDRIVE 1.7)
+    pub subtype: Option<String>,
+    /// whether the object is dynamic (OpenDRIVE 1.7)
+    #[serde(default, with = "parse_util::optional_flexible_boolean")]
+    pub dynamic: Option<bool>,
+    /// whether the heading of the object is perpendicular to the road
+    /// (OpenDRIVE 1.7)
+    #[serde(default, with = "parse_util::optional_flexible_boolean")]
+    pub perp_to_road: Option<bool>,
+    /// name of the object
+    pub name: Option<String>,
+    /// unique ID within database
+    pub id: String,
+    /// m [0,∞[ s-coordinate of object’s origin
+    pub s: f64,
+    /// m ]-∞,∞[ t-coordinate of object’s origin
+    pub t: f64,
+    /// m ]-∞,∞[ z-offset of object’s origin relative to the elevation of the
+    /// reference line
+    #[serde(default)]
+    pub z_offset: f64,
+    /// m [0,∞[ validity of object along s-axis (0.0 for point object)
+    #[serde(default)]
+    pub valid_length: f64,
+    /// "+" = valid in positive track direction, "-" = valid in negative track
+    /// direction, "none" = valid in both directions
+    #[serde(default)]
+    pub orientation: Orientation,
+    /// m [0,∞[ length of the object's bounding box
+    pub length: Option<f64>,
+    /// m [0,∞[ width of the object's bounding box
+    pub width: Option<f64>,
+    /// m [0,∞[ radius of a circular object's bounding box
+    pub radius: Option<f64>,
+    /// m [0,∞[ height of the object's bounding box
+    pub height: Option<f64>,
+    /// rad [0;2π[ heading angle of the object relative to road direction
+    #[serde(default)]
+    pub hdg: f64,
+    /// rad [0;2π[ pitch angle relative to the x/y-plane
+    #[serde(default)]
+    pub pitch: f64,
+    /// rad [0;2π[ roll angle relative to the x/y-plane
+    #[serde(default)]
+    pub roll: f64,
+}
+
+/// 5.3.9 Signals Record
+///
+/// The signals record contains a series of signal records which define the
+/// traffic signals along the road.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "signals", rename_all = "camelCase")]
+pub struct Signals {
+    #[serde(default, rename = "signal")]
+    pub signals: Vec<Signal>,
+}
+
+/// 5.3.9.1 Signal Record
+///
+/// The signal record is used to provide information about signals along a
+/// road. The type of signal is given by a country code and a type/subtype.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "signal", rename_all = "camelCase")]
+pub struct Signal {
+    /// m [0,∞[ s-coordinate
+    pub s: f64,
+    /// m ]-∞,∞[ t-coordinate
+    pub t: f64,
+    /// unique ID of the signal within the database
+    pub id: types::SignalId,
+    /// name of the signal
+    pub name: Option<String>,
+    /// indicates whether the signal is dynamic or static
+    #[serde(with = "parse_util::flexible_boolean")]
+    pub dynamic: bool,
+    /// "+" = valid in positive track direction, "-" = valid in negative track
+    /// direction, "none" = valid in both directions
+    #[serde(default)]
+    pub orientation: Orientation,
+    /// m ]-∞,∞[ z offset from the road to bottom edge of the signal
+    #[serde(default)]
+    pub z_offset: f64,
+    /// country code of the signal
+    pub country: Option<String>,
+    /// type identifier according to country code or "-1" / "none"
+    #[serde(rename = "type")]
+    pub signal_type: String,
+    /// subtype identifier according to country code or "-1" / "none"
+    #[serde(default)]
+    pub subtype: String,
+    /// value of the signal, if value is given, unit is mandatory
+    pub value: Option<f64>,
+    /// unit of the value
+    pub unit: Option<String>,
+    /// m [0,∞[ height of the signal, measured from bottom edge of the signal
+    pub height: Option<f64>,
+    /// m [0,∞[ width of the signal
+    pub width: Option<f64>,
+    /// additional text associated with the signal
+    pub text: Option<String>,
+    /// rad ]-∞,∞[ heading offset of the signal relative to orientation
+    #[serde(default)]
+    pub h_offset: f64,
+    /// rad ]-∞,∞[ pitch angle of the signal relative to the x/y-plane
+    #[serde(default)]
+    pub pitch: f64,
+    /// rad ]-∞,∞[ roll angle of the signal relative to the x/y-plane
+    #[serde(default)]
+    pub roll: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum Orientation {
+    #[serde(rename = "+")]
+    Positive,
+    #[serde(rename = "-")]
+    Negative,
+    #[serde(rename = "none")]
+    None,
+}
+impl Default for Orientation {
+    fn default() -> Orientation {
+        Orientation::None
+    }
+}
+
+/// 5.4 Junction Header Record
+///
+/// The junction record is the first record within a junction definition. It
+/// is followed by connection records which define which incoming roads are
+/// linked to which connecting roads.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "junction", rename_all = "camelCase")]
+pub struct Junction {
+    /// name of the junction
+    pub name: String,
+    /// unique ID within database
+    pub id: types::JunctionId,
+    /// type of the junction (OpenDRIVE 1.6)
+    #[serde(default, rename = "type")]
+    pub junction_type: JunctionType,
+    #[serde(default, rename = "connection")]
+    pub connections: Vec<Connection>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum JunctionType {
+    Default,
+    /// (OpenDRIVE 1.6) junction within a road without connecting roads of its
+    /// own, e.g. to access driveways
+    Virtual,
+    /// (OpenDRIVE 1.7) roads are linked directly without connecting roads,
+    /// e.g. at motorway entries and exits
+    Direct,
+}
+impl Default for JunctionType {
+    fn default() -> JunctionType {
+        JunctionType::Default
+    }
+}
+
+/// 5.4.1 Junction Connection Record
+///
+/// The junction connection record provides information about a single
+/// connection within a junction.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "connection", rename_all = "camelCase")]
+pub struct Connection {
+    /// unique ID within the junction
+    pub id: types::ConnectionId,
+    /// ID of the incoming road
+    pub incoming_road: types::RoadId,
+    /// ID of the connecting path, absent in direct junctions
+    pub connecting_road: Option<types::RoadId>,
+    /// ID of the road the incoming road is linked to directly in direct
+    /// junctions (OpenDRIVE 1.7)
+    pub linked_road: Option<types::RoadId>,
+    /// contact point on the connecting or linked road
+    pub contact_point: ContactPoint,
+    #[serde(default, rename = "laneLink")]
+    pub lane_links: Vec<JunctionLaneLink>,
+}
+
+impl Connection {
+    /// The road the incoming road leads to, i.e. the connecting road or the
+    /// linked road of a direct junction
+    pub fn connected_road(&self) -> Option<&types::RoadId> {
+        self.connecting_road.as_ref().or(self.linked_road.as_ref())
+    }
+
+    pub fn connected_road_mut(&mut self) -> Option<&mut types::RoadId> {
+        self.connecting_road.as_mut().or(self.linked_road.as_mut())
+    }
+}
+
+/// 5.4.1.1 Junction Lane Link Record
+///
+/// The junction lane link record provides information about the lanes which
+/// are linked between an incoming road and a connecting road.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "laneLink", rename_all = "camelCase")]
+pub struct JunctionLaneLink {
+    /// ID of the incoming lane
+    pub from: i32,
+    /// ID of the connecting lane
+    pub to: i32,
+}
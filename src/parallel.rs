@@ -0,0 +1,33 @@
+//! Concurrent computation of per-road data
+//!
+//! Sampling, tessellation and the other geometry derived from a road only
+//! depend on the road itself. With the `parallel` feature these
+//! computations run on the rayon thread pool, otherwise sequentially. The
+//! results are in the order of the input either way, so the output does not
+//! depend on the feature or on scheduling.
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Apply `f` to every item, concurrently with the `parallel` feature, and
+/// collect the results in the order of the items
+#[cfg(feature = "parallel")]
+pub(crate) fn map<T, U, F>(items: &[T], f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync + Send,
+{
+    items.par_iter().map(f).collect()
+}
+
+/// Apply `f` to every item, concurrently with the `parallel` feature, and
+/// collect the results in the order of the items
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn map<T, U, F>(items: &[T], f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync + Send,
+{
+    items.iter().map(f).collect()
+}
@@ -5,17 +5,28 @@ pub mod flexible_boolean {
     where
         S: Serializer,
     {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_bool(*value);
+        }
         let s = format!("{}", value);
         serializer.serialize_str(&s)
     }
 
-    /// Custom deserializer that can accept strings [0, 1, true, false]
+    /// Custom deserializer that can accept strings [0, 1, true, false, yes, no]
     pub fn deserialize<'de, D>(deserializer: D) -> Result<bool, D::Error>
     where
         D: Deserializer<'de>,
     {
         use std::str::FromStr;
+        if !deserializer.is_human_readable() {
+            return bool::deserialize(deserializer);
+        }
         let s: String = String::deserialize(deserializer)?;
+        match s.as_str() {
+            "yes" => return Ok(true),
+            "no" => return Ok(false),
+            _ => {}
+        }
         bool::from_str(&s).or_else(|_e| {
             i8::from_str(&s)
                 .and_then(|x| Ok(x > 0))
@@ -24,15 +35,48 @@ pub mod flexible_boolean {
     }
 }
 
+/// Like `flexible_boolean` for optional attributes
+pub mod optional_flexible_boolean {
+    use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(value: &Option<bool>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if !serializer.is_human_readable() {
+            return value.serialize(serializer);
+        }
+        match value {
+            Some(value) => super::flexible_boolean::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if !deserializer.is_human_readable() {
+            return Option::deserialize(deserializer);
+        }
+        super::flexible_boolean::deserialize(deserializer).map(Some)
+    }
+}
+
 pub mod odr_dateformat {
     use chrono::{DateTime, TimeZone, Utc};
     use serde::{self, Deserialize, Deserializer, Serializer};
     const DATE_FORMAT: &'static str = "%a %b %d %H:%M:%S %Y";
+    const ISO_DATE_FORMAT: &'static str = "%Y-%m-%dT%H:%M:%S";
 
     pub fn serialize<S>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
+        // the XML date format drops the fraction of a second
+        if !serializer.is_human_readable() {
+            return serializer.serialize_str(&date.to_rfc3339());
+        }
         let s = format!("{}", date.format(DATE_FORMAT));
         serializer.serialize_str(&s)
     }
@@ -42,6 +86,13 @@ pub mod odr_dateformat {
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
+        // OpenDRIVE 1.6 and later write ISO 8601, possibly without time zone
+        if let Ok(date) = DateTime::parse_from_rfc3339(s.trim()) {
+            return Ok(date.with_timezone(&Utc));
+        }
+        if let Ok(date) = Utc.datetime_from_str(s.trim(), ISO_DATE_FORMAT) {
+            return Ok(date);
+        }
         Utc.datetime_from_str(&s, DATE_FORMAT)
             .map_err(serde::de::Error::custom)
     }
@@ -57,6 +108,9 @@ pub mod angle {
     where
         S: Serializer,
     {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_f64(angle.radians);
+        }
         let s = format!("{}", angle.radians);
         serializer.serialize_str(&s)
     }
@@ -70,3 +124,37 @@ pub mod angle {
         ))))
     }
 }
+
+/// The junction a road belongs to, written as "-1" if it belongs to none
+pub mod junction_ref {
+    use crate::types::JunctionId;
+    use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(junction: &Option<JunctionId>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if !serializer.is_human_readable() {
+            return junction.serialize(serializer);
+        }
+        match junction {
+            Some(id) => id.serialize(serializer),
+            None => serializer.serialize_i64(-1),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<JunctionId>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if !deserializer.is_human_readable() {
+            return Option::deserialize(deserializer);
+        }
+        let id = JunctionId::deserialize(deserializer)?;
+        if id == JunctionId::from("-1") {
+            Ok(None)
+        } else {
+            Ok(Some(id))
+        }
+    }
+}
@@ -0,0 +1,620 @@
+//! OpenDRIVE format revisions and the features of a road network that
+//! depend on them
+//!
+//! The data model accepts the elements and attributes of all revisions from
+//! 1.4 to 1.8, and files of earlier revisions, so maps from different
+//! toolchains parse alike. Records whose syntax changed between revisions,
+//! like the header date, version and bounds, are read in every variant.
+//! `feature_report` lists the revision dependent features a road network
+//! uses together with the revision that introduced or deprecated them.
+//! Parsing with `ParseOptions::strict` rejects files that use features newer
+//! than the revision they declare. `migrate` rewrites a road network for
+//! another revision.
+use crate::errors::RevisionError;
+use crate::opendrive::*;
+use crate::types;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Revision of the OpenDRIVE format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Revision {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl Revision {
+    pub const V1_4: Revision = Revision::new(1, 4);
+    pub const V1_5: Revision = Revision::new(1, 5);
+    pub const V1_6: Revision = Revision::new(1, 6);
+    pub const V1_7: Revision = Revision::new(1, 7);
+    pub const V1_8: Revision = Revision::new(1, 8);
+
+    pub const fn new(major: u16, minor: u16) -> Revision {
+        Revision { major, minor }
+    }
+
+    /// Files of this revision are read according to its specification.
+    /// Earlier minor revisions are read like 1.4.
+    pub fn is_supported(self) -> bool {
+        self.major == 1 && self <= Revision::V1_8
+    }
+}
+
+impl fmt::Display for Revision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// A revision dependent element, attribute or attribute value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Feature {
+    /// `maxRoad`, `maxJunc` and `maxPrg` header attributes
+    HeaderMaxIds,
+    /// `<poly3>` geometry
+    Poly3,
+    /// `<geoReference>` header record
+    GeoReference,
+    /// `<paramPoly3>` geometry
+    ParamPoly3,
+    /// `<laneOffset>` records
+    LaneOffset,
+    /// road `rule` attribute
+    TrafficRule,
+    /// road and junction ids that aren't plain numbers
+    StringIds,
+    /// `curb` and `connectingRamp` lane types
+    CurbLanes,
+    /// `custom` and `edge` road mark types
+    CustomRoadMarks,
+    /// `<offset>` header record
+    HeaderOffset,
+    /// `virtual` junctions
+    VirtualJunctions,
+    /// `bus`, `taxi` and `HOV` lane types
+    RestrictedUseLanes,
+    /// `direct` junctions
+    DirectJunctions,
+    /// lane `direction` attribute
+    LaneDirection,
+    /// lane `advisory` attribute
+    LaneAdvisory,
+    /// object `subtype`, `dynamic` and `perpToRoad` attributes
+    ObjectAttributes,
+    /// `mwyEntry` and `mwyExit` lane types
+    MotorwayLanes,
+}
+
+impl Feature {
+    /// The first revision the feature is defined in, `None` for features of
+    /// revisions before 1.4
+    pub fn introduced(self) -> Option<Revision> {
+        match self {
+            Feature::HeaderMaxIds | Feature::Poly3 => None,
+            Feature::GeoReference | Feature::ParamPoly3 | Feature::LaneOffset => {
+                Some(Revision::V1_4)
+            }
+            Feature::TrafficRule
+            | Feature::StringIds
+            | Feature::CurbLanes
+            | Feature::CustomRoadMarks => Some(Revision::V1_5),
+            Feature::HeaderOffset | Feature::VirtualJunctions | Feature::RestrictedUseLanes => {
+                Some(Revision::V1_6)
+            }
+            Feature::DirectJunctions
+            | Feature::LaneDirection
+            | Feature::LaneAdvisory
+            | Feature::ObjectAttributes => Some(Revision::V1_7),
+            Feature::MotorwayLanes => Some(Revision::V1_8),
+        }
+    }
+
+    /// The revision from which on the feature is deprecated or removed
+    pub fn deprecated(self) -> Option<Revision> {
+        match self {
+            Feature::HeaderMaxIds => Some(Revision::V1_4),
+            Feature::Poly3 => Some(Revision::V1_6),
+            _ => None,
+        }
+    }
+
+    /// The feature is defined in `revision`
+    pub fn is_defined_in(self, revision: Revision) -> bool {
+        self.introduced()
+            .into_iter()
+            .all(|introduced| introduced <= revision)
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Feature::HeaderMaxIds => "maxRoad/maxJunc/maxPrg header attributes",
+            Feature::Poly3 => "poly3 geometry",
+            Feature::GeoReference => "geoReference record",
+            Feature::ParamPoly3 => "paramPoly3 geometry",
+            Feature::LaneOffset => "laneOffset records",
+            Feature::TrafficRule => "road traffic rule",
+            Feature::StringIds => "non-numeric ids",
+            Feature::CurbLanes => "curb and connectingRamp lanes",
+            Feature::CustomRoadMarks => "custom and edge road marks",
+            Feature::HeaderOffset => "header offset record",
+            Feature::VirtualJunctions => "virtual junctions",
+            Feature::RestrictedUseLanes => "bus, taxi and HOV lanes",
+            Feature::DirectJunctions => "direct junctions",
+            Feature::LaneDirection => "lane direction",
+            Feature::LaneAdvisory => "lane advisory",
+            Feature::ObjectAttributes => "object subtype/dynamic/perpToRoad",
+            Feature::MotorwayLanes => "mwyEntry and mwyExit lanes",
+        }
+    }
+}
+
+/// How often a road network uses a feature
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeatureUsage {
+    pub feature: Feature,
+    /// number of records using the feature
+    pub count: usize,
+}
+
+/// The revision dependent features a road network uses
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureReport {
+    /// revision declared in the header
+    pub revision: Revision,
+    /// by feature, in order of the revision that introduced them
+    pub usages: Vec<FeatureUsage>,
+}
+
+impl FeatureReport {
+    /// Features used although the declared revision doesn't define them yet
+    pub fn undefined(&self) -> impl Iterator<Item = &FeatureUsage> {
+        let revision = self.revision;
+        self.usages
+            .iter()
+            .filter(move |usage| !usage.feature.is_defined_in(revision))
+    }
+
+    /// Features used although they are deprecated in the declared revision
+    pub fn deprecated(&self) -> impl Iterator<Item = &FeatureUsage> {
+        let revision = self.revision;
+        self.usages.iter().filter(move |usage| {
+            usage
+                .feature
+                .deprecated()
+                .into_iter()
+                .any(|deprecated| deprecated <= revision)
+        })
+    }
+
+    /// The earliest revision defining all used features, `None` if all of
+    /// them predate 1.4
+    pub fn required_revision(&self) -> Option<Revision> {
+        self.usages
+            .iter()
+            .filter_map(|usage| usage.feature.introduced())
+            .max()
+    }
+}
+
+impl fmt::Display for FeatureReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for usage in self.usages.iter() {
+            let feature = usage.feature;
+            let introduced = feature.introduced().map_or_else(
+                || format!("before {}", Revision::V1_4),
+                |revision| revision.to_string(),
+            );
+            let note = if !feature.is_defined_in(self.revision) {
+                format!(", newer than {}", self.revision)
+            } else if let Some(deprecated) = feature.deprecated().filter(|&d| d <= self.revision) {
+                format!(", deprecated since {}", deprecated)
+            } else {
+                String::new()
+            };
+            writeln!(
+                f,
+                "{} (since {}{}): {}",
+                feature.description(),
+                introduced,
+                note,
+                usage.count
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Collect the revision dependent features used by the road network
+pub fn feature_report(root: &Root) -> FeatureReport {
+    let mut counts: BTreeMap<Feature, usize> = BTreeMap::new();
+    let mut count = |feature: Feature, n: usize| {
+        if n > 0 {
+            *counts.entry(feature).or_default() += n;
+        }
+    };
+
+    let header = &root.header;
+    count(
+        Feature::HeaderMaxIds,
+        (header.max_road > 0 || header.max_junc > 0 || header.max_prg > 0) as usize,
+    );
+    count(
+        Feature::GeoReference,
+        header.geo_reference.is_some() as usize,
+    );
+    count(Feature::HeaderOffset, header.offset.is_some() as usize);
+
+    for road in root.roads.iter() {
+        let string_ids = road.id.as_u64().is_none() as usize
+            + road
+                .junction
+                .as_ref()
+                .map_or(0, |id| id.as_u64().is_none() as usize);
+        count(Feature::StringIds, string_ids);
+        count(Feature::TrafficRule, road.rule.is_some() as usize);
+        for geometry in road.plan_view.geometries.iter() {
+            match geometry.element {
+                GeometryElement::Poly3 { .. } => count(Feature::Poly3, 1),
+                GeometryElement::ParamPoly3 { .. } => count(Feature::ParamPoly3, 1),
+                _ => {}
+            }
+        }
+        let lanes = match road.lanes {
+            Some(ref lanes) => lanes,
+            None => continue,
+        };
+        count(Feature::LaneOffset, lanes.lane_offsets.len());
+        for section in lanes.lane_sections.iter() {
+            let all_lanes = section
+                .left_lanes()
+                .chain(section.center.lane.iter())
+                .chain(section.right_lanes());
+            for lane in all_lanes {
+                count(Feature::LaneDirection, lane.direction.is_some() as usize);
+                count(Feature::LaneAdvisory, lane.advisory.is_some() as usize);
+                match lane.lane_type {
+                    LaneType::Curb | LaneType::ConnectingRamp => count(Feature::CurbLanes, 1),
+                    LaneType::Bus | LaneType::Taxi | LaneType::Hov => {
+                        count(Feature::RestrictedUseLanes, 1)
+                    }
+                    LaneType::MwyEntry | LaneType::MwyExit => count(Feature::MotorwayLanes, 1),
+                    _ => {}
+                }
+                let custom = lane.road_marks.iter().filter(|mark| {
+                    matches!(
+                        mark.road_mark_type,
+                        RoadMarkType::Custom | RoadMarkType::Edge
+                    )
+                });
+                count(Feature::CustomRoadMarks, custom.count());
+            }
+        }
+        for object in road
+            .objects
+            .iter()
+            .flat_map(|objects| objects.objects.iter())
+        {
+            let new_attributes = object.subtype.is_some()
+                || object.dynamic.is_some()
+                || object.perp_to_road.is_some();
+            count(Feature::ObjectAttributes, new_attributes as usize);
+        }
+    }
+
+    for junction in root.junctions.iter() {
+        count(Feature::StringIds, junction.id.as_u64().is_none() as usize);
+        match junction.junction_type {
+            JunctionType::Virtual => count(Feature::VirtualJunctions, 1),
+            JunctionType::Direct => count(Feature::DirectJunctions, 1),
+            JunctionType::Default => {}
+        }
+    }
+
+    let mut usages: Vec<FeatureUsage> = counts
+        .into_iter()
+        .map(|(feature, count)| FeatureUsage { feature, count })
+        .collect();
+    usages.sort_by_key(|usage| usage.feature.introduced());
+    FeatureReport {
+        revision: header.revision(),
+        usages,
+    }
+}
+
+/// Options for reading OpenDRIVE files
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// reject files using features their declared revision doesn't define
+    pub strict: bool,
+}
+
+/// Check a parsed road network against the rules of its declared revision
+pub fn check(root: &Root, options: &ParseOptions) -> Result<(), RevisionError> {
+    let revision = root.header.revision();
+    if revision.major != 1 {
+        return Err(RevisionError::Unsupported(revision));
+    }
+    if options.strict {
+        let report = feature_report(root);
+        let undefined = report.undefined().next().copied();
+        if let Some(usage) = undefined {
+            return Err(RevisionError::UndefinedFeature(
+                usage.feature.description(),
+                usage.feature.introduced().unwrap_or(revision),
+                revision,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// How `migrate` dealt with data the target revision can't represent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    /// replaced by the closest equivalent of the target revision
+    Replaced(&'static str),
+    /// removed from the road network
+    Removed,
+    /// kept although the target revision doesn't define it
+    Kept,
+}
+
+/// Data of a feature that didn't survive the migration unchanged
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MigrationWarning {
+    pub feature: Feature,
+    /// number of affected records
+    pub count: usize,
+    pub resolution: Resolution,
+}
+
+impl fmt::Display for MigrationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}): ", self.feature.description(), self.count)?;
+        match self.resolution {
+            Resolution::Replaced(by) => write!(f, "replaced by {}", by),
+            Resolution::Removed => write!(f, "removed"),
+            Resolution::Kept => write!(f, "kept, not defined in the target revision"),
+        }
+    }
+}
+
+/// Rewrite the road network for the `target` revision
+///
+/// Upgrades are lossless:
+/// - poly3 geometries become paramPoly3 geometries describing the same
+///   curve from 1.6 on
+/// - the `standard` road mark color becomes `white`, its equivalent, from
+///   1.6 on
+/// - the deprecated `maxRoad`, `maxJunc` and `maxPrg` header attributes are
+///   cleared from 1.4 on
+///
+/// Downgrades replace newer lane types, road mark types and junction types
+/// by the closest older ones and remove newer attributes. Below 1.6 the
+/// header offset is applied to the geometry, so the projected co-ordinates
+/// stay the same. Below 1.4 the `maxRoad` and `maxJunc` attributes are
+/// filled in. Every lossy change and all data the target revision doesn't
+/// define but that is kept is reported as a warning. Finally the header
+/// declares the target revision.
+pub fn migrate(root: &mut Root, target: Revision) -> Result<Vec<MigrationWarning>, RevisionError> {
+    if !target.is_supported() {
+        return Err(RevisionError::Unsupported(target));
+    }
+    let mut warnings: BTreeMap<Feature, (usize, Resolution)> = BTreeMap::new();
+    let mut warn = |feature: Feature, resolution: Resolution| {
+        warnings.entry(feature).or_insert((0, resolution)).0 += 1;
+    };
+    let below = |feature: Feature| !feature.is_defined_in(target);
+
+    migrate_header(root, target);
+
+    for road in root.roads.iter_mut() {
+        if below(Feature::TrafficRule) {
+            if let Some(rule) = road.rule.take() {
+                // right-hand traffic is implied by older revisions
+                if rule == TrafficRule::LeftHand {
+                    warn(Feature::TrafficRule, Resolution::Removed);
+                }
+            }
+        }
+        if target >= Revision::V1_6 {
+            for geometry in road.plan_view.geometries.iter_mut() {
+                if let Some(element) = poly3_as_param_poly3(geometry) {
+                    geometry.element = element;
+                }
+            }
+        }
+        if let Some(ref mut objects) = road.objects {
+            for object in objects.objects.iter_mut() {
+                if below(Feature::ObjectAttributes) {
+                    let subtype = object.subtype.take().is_some();
+                    let dynamic = object.dynamic.take().is_some();
+                    let perp_to_road = object.perp_to_road.take().is_some();
+                    if subtype || dynamic || perp_to_road {
+                        warn(Feature::ObjectAttributes, Resolution::Removed);
+                    }
+                }
+            }
+        }
+        let lanes = match road.lanes {
+            Some(ref mut lanes) => lanes,
+            None => continue,
+        };
+        for section in lanes.lane_sections.iter_mut() {
+            let all_lanes = section
+                .left
+                .iter_mut()
+                .chain(Some(&mut section.center))
+                .chain(section.right.iter_mut())
+                .flat_map(|sub_section| sub_section.lane.iter_mut());
+            for lane in all_lanes {
+                migrate_lane(lane, target, &mut warn);
+            }
+        }
+    }
+
+    for junction in root.junctions.iter_mut() {
+        let feature = match junction.junction_type {
+            JunctionType::Virtual => Feature::VirtualJunctions,
+            JunctionType::Direct => Feature::DirectJunctions,
+            JunctionType::Default => continue,
+        };
+        if below(feature) {
+            junction.junction_type = JunctionType::Default;
+            // older revisions refer to the linked road as connecting road
+            for connection in junction.connections.iter_mut() {
+                if connection.connecting_road.is_none() {
+                    connection.connecting_road = connection.linked_road.take();
+                }
+            }
+            warn(feature, Resolution::Replaced("default junctions"));
+        }
+    }
+
+    // newer data without an older equivalent
+    for usage in feature_report(root).undefined() {
+        warnings.insert(usage.feature, (usage.count, Resolution::Kept));
+    }
+    Ok(warnings
+        .into_iter()
+        .map(|(feature, (count, resolution))| MigrationWarning {
+            feature,
+            count,
+            resolution,
+        })
+        .collect())
+}
+
+fn migrate_header(root: &mut Root, target: Revision) {
+    if target < Revision::V1_6 {
+        if let Some(offset) = root.header.offset.take() {
+            apply_offset(root, &offset);
+        }
+    }
+    let header = &mut root.header;
+    if target >= Revision::V1_4 {
+        header.max_road = 0;
+        header.max_junc = 0;
+        header.max_prg = 0;
+    } else {
+        let max_id = |ids: &mut dyn Iterator<Item = Option<u64>>| {
+            ids.flatten().max().unwrap_or(0).min(u32::MAX as u64) as u32
+        };
+        header.max_road = max_id(&mut root.roads.iter().map(|road| road.id.as_u64()));
+        header.max_junc = max_id(&mut root.junctions.iter().map(|junction| junction.id.as_u64()));
+    }
+    header.rev_major = target.major;
+    header.rev_minor = target.minor;
+}
+
+/// Move the reference lines and bounds of the road network from local to
+/// projected co-ordinates
+fn apply_offset(root: &mut Root, offset: &Offset) {
+    let header = &mut root.header;
+    if header.north != header.south || header.east != header.west {
+        let corners = [
+            offset.apply(header.west, header.south),
+            offset.apply(header.east, header.south),
+            offset.apply(header.east, header.north),
+            offset.apply(header.west, header.north),
+        ];
+        let (x, y): (Vec<f64>, Vec<f64>) = corners.iter().cloned().unzip();
+        header.west = x.iter().cloned().fold(f64::INFINITY, f64::min);
+        header.east = x.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        header.south = y.iter().cloned().fold(f64::INFINITY, f64::min);
+        header.north = y.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    }
+
+    for road in root.roads.iter_mut() {
+        for geometry in road.plan_view.geometries.iter_mut() {
+            let (x, y) = offset.apply(geometry.x.get(), geometry.y.get());
+            geometry.x = types::Length::new(x);
+            geometry.y = types::Length::new(y);
+            geometry.hdg += types::Angle::radians(offset.hdg);
+        }
+        if offset.z != 0.0 {
+            let profile = road.elevation_profile.get_or_insert_with(Default::default);
+            if profile.elevations.is_empty() {
+                profile.elevations.push(Elevation {
+                    s: 0.0,
+                    a: 0.0,
+                    b: 0.0,
+                    c: 0.0,
+                    d: 0.0,
+                });
+            }
+            for elevation in profile.elevations.iter_mut() {
+                elevation.a += offset.z;
+            }
+        }
+    }
+}
+
+/// The paramPoly3 element describing the same curve as a poly3 geometry
+fn poly3_as_param_poly3(geometry: &Geometry) -> Option<GeometryElement> {
+    match geometry.element {
+        GeometryElement::Poly3 { a, b, c, d } => {
+            // u(p) = u_end * p, v(p) = v(u_end * p) for p in [0, 1]
+            let u = poly3_u_at_length(b, c, d, geometry.length.get());
+            Some(GeometryElement::ParamPoly3 {
+                a_u: 0.0,
+                b_u: u,
+                c_u: 0.0,
+                d_u: 0.0,
+                a_v: a,
+                b_v: b * u,
+                c_v: c * u * u,
+                d_v: d * u * u * u,
+                p_range: PRange::Normalized,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn migrate_lane(lane: &mut Lane, target: Revision, warn: &mut impl FnMut(Feature, Resolution)) {
+    let below = |feature: Feature| !feature.is_defined_in(target);
+    if below(Feature::LaneDirection) && lane.direction.take().is_some() {
+        warn(Feature::LaneDirection, Resolution::Removed);
+    }
+    if below(Feature::LaneAdvisory) && lane.advisory.take().is_some() {
+        warn(Feature::LaneAdvisory, Resolution::Removed);
+    }
+
+    let replacement = match lane.lane_type {
+        LaneType::Curb => Some((Feature::CurbLanes, LaneType::Border, "border lanes")),
+        LaneType::ConnectingRamp => Some((Feature::CurbLanes, LaneType::Driving, "driving lanes")),
+        LaneType::Bus | LaneType::Taxi | LaneType::Hov => Some((
+            Feature::RestrictedUseLanes,
+            LaneType::Restricted,
+            "restricted lanes",
+        )),
+        LaneType::MwyEntry => Some((Feature::MotorwayLanes, LaneType::Entry, "entry/exit lanes")),
+        LaneType::MwyExit => Some((Feature::MotorwayLanes, LaneType::Exit, "entry/exit lanes")),
+        _ => None,
+    };
+    if let Some((feature, lane_type, by)) = replacement {
+        if below(feature) {
+            lane.lane_type = lane_type;
+            warn(feature, Resolution::Replaced(by));
+        }
+    }
+
+    for mark in lane.road_marks.iter_mut() {
+        let custom = matches!(
+            mark.road_mark_type,
+            RoadMarkType::Custom | RoadMarkType::Edge
+        );
+        if custom && below(Feature::CustomRoadMarks) {
+            mark.road_mark_type = RoadMarkType::Solid;
+            warn(
+                Feature::CustomRoadMarks,
+                Resolution::Replaced("solid road marks"),
+            );
+        }
+        if target >= Revision::V1_6 && mark.color == RoadMarkColor::Standard {
+            mark.color = RoadMarkColor::White;
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Uniform grid over the extent of the lanes of a road network
+//!
+//! Every cell of the grid lists the roads whose lanes may cover it. The
+//! cells of a road are found from boxes around its lanes between
+//! consecutive samples of the road, so curved roads only occupy the cells
+//! along them. Looking up a position then only needs to project it onto the
+//! roads of its cell instead of onto every road of the network. Only the
+//! cells covered by a road are stored, so sparse networks spread over a
+//! large area do not need memory for the empty cells between their roads.
+use crate::errors::SampleError;
+use crate::opendrive::*;
+use crate::parallel;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpatialIndex {
+    /// m minimum x of the grid
+    west: f64,
+    /// m minimum y of the grid
+    south: f64,
+    /// m edge length of the square cells
+    cell_size: f64,
+    columns: usize,
+    rows: usize,
+    /// indices of the roads in `Root::roads` in ascending order, by column
+    /// and row of the non-empty cells
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl SpatialIndex {
+    /// Index the roads of `root` in cells of `cell_size`, sampling the roads
+    /// such that the samples deviate less than `tolerance` from the curves
+    pub fn new(root: &Root, cell_size: f64, tolerance: f64) -> Result<SpatialIndex, SampleError> {
+        let boxes: Vec<(usize, BoundingBox)> =
+            parallel::map(&root.roads, |road| road_boxes(road, tolerance))
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .enumerate()
+                .flat_map(|(index, boxes)| boxes.into_iter().map(move |b| (index, b)))
+                .collect();
+        let bounds = boxes
+            .iter()
+            .map(|&(_, b)| b)
+            .fold(None, |bounds: Option<BoundingBox>, b| match bounds {
+                Some(bounds) => Some(bounds.union(&b)),
+                None => Some(b),
+            });
+        let mut index = SpatialIndex {
+            west: 0.0,
+            south: 0.0,
+            cell_size,
+            columns: 0,
+            rows: 0,
+            cells: HashMap::new(),
+        };
+        let bounds = match bounds {
+            Some(bounds) => bounds,
+            None => return Ok(index),
+        };
+        index.west = bounds.west;
+        index.south = bounds.south;
+        index.columns = ((bounds.east - bounds.west) / cell_size) as usize + 1;
+        index.rows = ((bounds.north - bounds.south) / cell_size) as usize + 1;
+        for (road, b) in boxes {
+            let (columns, rows) = index.cell_range(&b);
+            for row in rows {
+                for column in columns.clone() {
+                    let cell = index.cells.entry((column as i64, row as i64)).or_default();
+                    // the boxes of a road are consecutive
+                    if cell.last() != Some(&road) {
+                        cell.push(road);
+                    }
+                }
+            }
+        }
+        Ok(index)
+    }
+
+    /// Column and row ranges of the cells overlapping `b`, clamped to the
+    /// grid
+    fn cell_range(&self, b: &BoundingBox) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        // first cell and one past the last cell along an axis
+        let first = |value: f64, min: f64, count: usize| {
+            ((value - min) / self.cell_size)
+                .floor()
+                .max(0.0)
+                .min(count as f64) as usize
+        };
+        let end = |value: f64, min: f64, count: usize| {
+            (((value - min) / self.cell_size).floor() + 1.0)
+                .max(0.0)
+                .min(count as f64) as usize
+        };
+        (
+            first(b.west, self.west, self.columns)..end(b.east, self.west, self.columns),
+            first(b.south, self.south, self.rows)..end(b.north, self.south, self.rows),
+        )
+    }
+
+    /// Indices of the roads whose lanes may contain the inertial position
+    /// x, y in ascending order
+    pub fn roads_at(&self, x: f64, y: f64) -> &[usize] {
+        let column = ((x - self.west) / self.cell_size).floor() as i64;
+        let row = ((y - self.south) / self.cell_size).floor() as i64;
+        self.cells.get(&(column, row)).map_or(&[], |cell| &cell[..])
+    }
+
+    /// Indices of the roads whose lanes may intersect `bounds` in ascending
+    /// order
+    pub fn roads_in(&self, bounds: &BoundingBox) -> Vec<usize> {
+        if self.cells.is_empty() {
+            return Vec::new();
+        }
+        let (columns, rows) = self.cell_range(bounds);
+        let mut roads: Vec<usize> = rows
+            .flat_map(|row| {
+                columns
+                    .clone()
+                    .filter_map(move |column| self.cells.get(&(column as i64, row as i64)))
+                    .flatten()
+            })
+            .cloned()
+            .collect();
+        roads.sort_unstable();
+        roads.dedup();
+        roads
+    }
+}
+
+/// Boxes around the lanes of a road between consecutive samples, grown by
+/// the sampling tolerance
+fn road_boxes(road: &Road, tolerance: f64) -> Result<Vec<BoundingBox>, SampleError> {
+    let edges: Vec<[(f64, f64); 2]> = road
+        .sample_s(0.0, road.length.get(), tolerance)?
+        .into_iter()
+        .map(|s| match road.lane_section_index_at(s) {
+            Some(index) => {
+                let borders = road.lane_borders(index, s);
+                [
+                    road.position_at(s, borders.first().unwrap().outer),
+                    road.position_at(s, borders.last().unwrap().outer),
+                ]
+            }
+            None => [road.position_at(s, 0.0); 2],
+        })
+        .collect();
+    let pairs: Vec<&[[(f64, f64); 2]]> = if edges.len() == 1 {
+        vec![&edges[..]]
+    } else {
+        edges.windows(2).collect()
+    };
+    Ok(pairs
+        .into_iter()
+        .map(|pair| {
+            let mut points = pair.iter().flat_map(|edge| edge.iter());
+            let &(x, y) = points.next().unwrap();
+            let mut b = BoundingBox {
+                west: x,
+                south: y,
+                east: x,
+                north: y,
+            };
+            for &(x, y) in points {
+                b.west = b.west.min(x);
+                b.south = b.south.min(y);
+                b.east = b.east.max(x);
+                b.north = b.north.max(y);
+            }
+            BoundingBox {
+                west: b.west - tolerance,
+                south: b.south - tolerance,
+                east: b.east + tolerance,
+                north: b.north + tolerance,
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,235 @@
+//! Streaming reader for large OpenDRIVE files
+//!
+//! `from_reader` builds the whole document in memory before converting it.
+//! `RoadIterator` instead pulls XML events from the input and converts one
+//! top level record at a time, so the memory needed is bounded by the size
+//! of the largest road. The header, roads and junctions are yielded in file
+//! order. Roads and junctions can be filtered by id, which skips the
+//! filtered records without converting them, and roads by their extent.
+//!
+//! The revision declared by the header is checked as by `from_reader_with`,
+//! the records following the header one at a time.
+use crate::opendrive::*;
+use crate::revision::{self, ParseOptions};
+use crate::types::{JunctionId, RoadId};
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::io::Read;
+use xml::reader::{EventReader, XmlEvent};
+use xml::writer::EmitterConfig;
+
+/// A top level record of an OpenDRIVE file
+#[derive(Debug, PartialEq)]
+pub enum Record {
+    Header(Header),
+    Road(Road),
+    Junction(Junction),
+}
+
+/// Records yielded by `RoadIterator`
+#[derive(Debug, Clone)]
+pub struct RecordFilter {
+    /// ids of the roads to read, all roads if `None`
+    pub road_ids: Option<HashSet<RoadId>>,
+    /// ids of the junctions to read, all junctions if `None`
+    pub junction_ids: Option<HashSet<JunctionId>>,
+    /// only roads whose extent intersects the box are read
+    pub bounding_box: Option<BoundingBox>,
+    /// m chord error of the samples the extent of a road is computed from
+    pub tolerance: f64,
+    /// checks of the records against the revision of the file
+    pub parse_options: ParseOptions,
+}
+
+impl Default for RecordFilter {
+    fn default() -> RecordFilter {
+        RecordFilter {
+            road_ids: None,
+            junction_ids: None,
+            bounding_box: None,
+            tolerance: 0.1,
+            parse_options: Default::default(),
+        }
+    }
+}
+
+/// Iterator over the records of an OpenDRIVE file
+pub struct RoadIterator<R: Read> {
+    events: EventReader<R>,
+    filter: RecordFilter,
+    /// the header once it is read
+    header: Option<Header>,
+    /// depth of the current element, 1 for the children of `<OpenDRIVE>`
+    depth: usize,
+    done: bool,
+}
+
+impl<R: Read> RoadIterator<R> {
+    pub fn new(input: R) -> RoadIterator<R> {
+        RoadIterator::with_filter(input, Default::default())
+    }
+
+    pub fn with_filter(input: R, filter: RecordFilter) -> RoadIterator<R> {
+        RoadIterator {
+            events: EventReader::new(input),
+            filter,
+            header: None,
+            depth: 0,
+            done: false,
+        }
+    }
+
+    /// Skip the remainder of the element whose start was read last
+    fn skip_element(&mut self) -> Result<(), failure::Error> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.events.next()? {
+                XmlEvent::StartElement { .. } => depth += 1,
+                XmlEvent::EndElement { .. } => depth -= 1,
+                XmlEvent::EndDocument => failure::bail!("unexpected end of document"),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// The XML text of the element starting with `start`
+    fn read_element(&mut self, start: &XmlEvent) -> Result<String, failure::Error> {
+        let mut text = Vec::new();
+        {
+            let mut writer = EmitterConfig::new()
+                .write_document_declaration(false)
+                .perform_indent(false)
+                .create_writer(&mut text);
+            let mut depth = 0;
+            let mut event = start.clone();
+            loop {
+                match event {
+                    XmlEvent::StartElement { .. } => depth += 1,
+                    XmlEvent::EndElement { .. } => depth -= 1,
+                    XmlEvent::EndDocument => failure::bail!("unexpected end of document"),
+                    _ => {}
+                }
+                if let Some(event) = event.as_writer_event() {
+                    writer.write(event)?;
+                }
+                if depth == 0 {
+                    break;
+                }
+                event = self.events.next()?;
+            }
+        }
+        Ok(String::from_utf8(text)?)
+    }
+
+    /// A network of the header and the records read after it, checked
+    /// against the declared revision
+    fn check(&self, roads: Vec<Road>, junctions: Vec<Junction>) -> Result<Root, failure::Error> {
+        let header = match self.header {
+            Some(ref header) => header.clone(),
+            None => failure::bail!("<road> or <junction> before <header>"),
+        };
+        let root = Root {
+            header,
+            roads,
+            junctions,
+        };
+        revision::check(&root, &self.filter.parse_options)?;
+        Ok(root)
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>, failure::Error> {
+        loop {
+            let event = self.events.next()?;
+            let (name, wanted) = match event {
+                XmlEvent::EndDocument => return Ok(None),
+                XmlEvent::EndElement { .. } => {
+                    self.depth -= 1;
+                    continue;
+                }
+                XmlEvent::StartElement {
+                    ref name,
+                    ref attributes,
+                    ..
+                } => {
+                    if self.depth != 1 {
+                        self.depth += 1;
+                        continue;
+                    }
+                    let id = attributes
+                        .iter()
+                        .find(|attribute| attribute.name.local_name == "id")
+                        .map(|attribute| attribute.value.as_str());
+                    let wanted = match (name.local_name.as_str(), id) {
+                        ("header", _) => true,
+                        ("road", Some(id)) => self
+                            .filter
+                            .road_ids
+                            .iter()
+                            .all(|ids| ids.contains(&RoadId::from(id))),
+                        ("junction", Some(id)) => self
+                            .filter
+                            .junction_ids
+                            .iter()
+                            .all(|ids| ids.contains(&JunctionId::from(id))),
+                        // conversion fails on the missing id
+                        ("road", None) | ("junction", None) => true,
+                        _ => false,
+                    };
+                    (name.local_name.clone(), wanted)
+                }
+                _ => continue,
+            };
+            if !wanted {
+                self.skip_element()?;
+                continue;
+            }
+
+            let text = self.read_element(&event)?;
+            match name.as_str() {
+                "header" => {
+                    let header: Header = parse(&text)?;
+                    self.header = Some(header.clone());
+                    self.check(vec![], vec![])?;
+                    return Ok(Some(Record::Header(header)));
+                }
+                "road" => {
+                    let road = self.check(vec![parse(&text)?], vec![])?.roads.remove(0);
+                    let inside = match self.filter.bounding_box {
+                        Some(ref bounds) => matches!(
+                            road.bounding_box(self.filter.tolerance)?,
+                            Some(b) if b.intersects(bounds)
+                        ),
+                        None => true,
+                    };
+                    if inside {
+                        return Ok(Some(Record::Road(road)));
+                    }
+                }
+                _ => {
+                    let junction = self.check(vec![], vec![parse(&text)?])?.junctions.remove(0);
+                    return Ok(Some(Record::Junction(junction)));
+                }
+            }
+        }
+    }
+}
+
+fn parse<T: DeserializeOwned>(text: &str) -> Result<T, failure::Error> {
+    serde_xml_rs::from_str(text).map_err(|err| failure::err_msg(err.to_string()))
+}
+
+impl<R: Read> Iterator for RoadIterator<R> {
+    type Item = Result<Record, failure::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = self.next_record();
+        if !matches!(record, Ok(Some(_))) {
+            self.done = true;
+        }
+        record.transpose()
+    }
+}
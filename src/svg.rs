@@ -0,0 +1,432 @@
+//! Rendering of a road network to SVG
+//!
+//! Lanes are filled according to their `LaneType`, road marks are stroked
+//! according to their type, color and weight and every reference line is
+//! drawn with tick marks at regular s intervals. Reference lines are
+//! flattened with `lyon_geom`, lane borders are sampled along s with the same
+//! tolerance.
+use crate::builder::DEFAULT_ROAD_MARK_WIDTH;
+use crate::errors::SampleError;
+use crate::opendrive::*;
+use crate::parallel;
+use lyon_geom::Segment;
+use std::fmt::Write;
+
+/// Length of the painted part and the gap of broken road marks [m]
+const BROKEN_DASH: (f64, f64) = (3.0, 6.0);
+/// Distance between botts dots [m]
+const BOTTS_DOTS_SPACING: f64 = 1.5;
+
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    /// m maximum deviation of the drawn polylines from the exact curves
+    pub tolerance: f64,
+    /// pixels per meter
+    pub scale: f64,
+    /// m empty border around the network
+    pub margin: f64,
+    /// m distance between s tick marks on the reference lines, 0 disables
+    /// the tick marks
+    pub tick_spacing: f64,
+    /// draw road and lane id labels
+    pub labels: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> SvgOptions {
+        SvgOptions {
+            tolerance: 0.05,
+            scale: 10.0,
+            margin: 10.0,
+            tick_spacing: 10.0,
+            labels: true,
+        }
+    }
+}
+
+/// Render the road network to an SVG document. Fails if the tolerance is
+/// not positive and finite.
+pub fn to_svg(root: &Root, options: &SvgOptions) -> Result<String, SampleError> {
+    let bounds = root
+        .bounding_box(options.tolerance)?
+        .unwrap_or(BoundingBox {
+            west: 0.0,
+            south: 0.0,
+            east: 0.0,
+            north: 0.0,
+        });
+    let canvas = Canvas {
+        west: bounds.west - options.margin,
+        north: bounds.north + options.margin,
+        scale: options.scale,
+    };
+    let width = (bounds.east - bounds.west + 2.0 * options.margin) * options.scale;
+    let height = (bounds.north - bounds.south + 2.0 * options.margin) * options.scale;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.1}" height="{h:.1}" viewBox="0 0 {w:.1} {h:.1}">"#,
+        w = width,
+        h = height
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r##"<rect width="100%" height="100%" fill="#e8ecd8"/>"##
+    )
+    .unwrap();
+
+    // the layers of every road, drawn concurrently
+    let layers = parallel::map(&root.roads, |road| -> Result<_, SampleError> {
+        let mut lanes = String::new();
+        let mut road_marks = String::new();
+        let sections = road
+            .lanes
+            .as_ref()
+            .map_or(0, |lanes| lanes.lane_sections.len());
+        for index in 0..sections {
+            draw_lanes(&mut lanes, &canvas, road, index, options)?;
+            draw_road_marks(&mut road_marks, &canvas, road, index, options)?;
+        }
+        let mut reference_line = String::new();
+        draw_reference_line(&mut reference_line, &canvas, road, options);
+        let mut labels = String::new();
+        if options.labels {
+            draw_labels(&mut labels, &canvas, road);
+        }
+        Ok([lanes, road_marks, reference_line, labels])
+    })
+    .into_iter()
+    .collect::<Result<Vec<[String; 4]>, _>>()?;
+
+    svg.push_str("<g id=\"lanes\">\n");
+    layers.iter().for_each(|layers| svg.push_str(&layers[0]));
+    svg.push_str("</g>\n<g id=\"road-marks\">\n");
+    layers.iter().for_each(|layers| svg.push_str(&layers[1]));
+    svg.push_str("</g>\n<g id=\"reference-lines\">\n");
+    layers.iter().for_each(|layers| svg.push_str(&layers[2]));
+    svg.push_str("</g>\n");
+
+    if options.labels {
+        svg.push_str("<g id=\"labels\" font-family=\"sans-serif\" text-anchor=\"middle\">\n");
+        layers.iter().for_each(|layers| svg.push_str(&layers[3]));
+        svg.push_str("</g>\n");
+    }
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// Render the road network to SVG
+pub fn write_svg<W: std::io::Write>(
+    root: &Root,
+    options: &SvgOptions,
+    mut writer: W,
+) -> Result<(), failure::Error> {
+    writer.write_all(to_svg(root, options)?.as_bytes())?;
+    Ok(())
+}
+
+fn for_each_section<F: FnMut(usize)>(road: &Road, f: F) {
+    let sections = road
+        .lanes
+        .as_ref()
+        .map_or(0, |lanes| lanes.lane_sections.len());
+    (0..sections).for_each(f)
+}
+
+fn draw_lanes(
+    svg: &mut String,
+    canvas: &Canvas,
+    road: &Road,
+    index: usize,
+    options: &SvgOptions,
+) -> Result<(), SampleError> {
+    let section = &road.lanes.as_ref().unwrap().lane_sections[index];
+    let (s0, s1) = road.lane_section_range(index);
+    let stations = road.sample_s(s0, s1, options.tolerance)?;
+    let borders: Vec<Vec<LaneBorders>> = stations
+        .iter()
+        .map(|&s| road.lane_borders(index, s))
+        .collect();
+
+    for lane in section.left_lanes().chain(section.right_lanes()) {
+        let border_of = |b: &[LaneBorders]| *b.iter().find(|b| b.id == lane.id).unwrap();
+        let mut points: Vec<(f64, f64)> = stations
+            .iter()
+            .zip(borders.iter())
+            .map(|(&s, b)| road.position_at(s, border_of(b).outer))
+            .collect();
+        points.extend(
+            stations
+                .iter()
+                .zip(borders.iter())
+                .rev()
+                .map(|(&s, b)| road.position_at(s, border_of(b).inner)),
+        );
+        writeln!(
+            svg,
+            r#"<path d="{}Z" fill="{}"/>"#,
+            canvas.path(&points),
+            lane_fill(&lane.lane_type)
+        )
+        .unwrap();
+    }
+    Ok(())
+}
+
+fn draw_road_marks(
+    svg: &mut String,
+    canvas: &Canvas,
+    road: &Road,
+    index: usize,
+    options: &SvgOptions,
+) -> Result<(), SampleError> {
+    let section = &road.lanes.as_ref().unwrap().lane_sections[index];
+    let (s0, s1) = road.lane_section_range(index);
+
+    let lanes = section
+        .left_lanes()
+        .chain(section.center.lane.iter())
+        .chain(section.right_lanes());
+    for lane in lanes {
+        // Direction in which the second line of a double road mark is
+        // placed: outwards for left and right lanes, from left to right for
+        // the center lane
+        let outward = if lane.id > 0 { 1.0 } else { -1.0 };
+
+        for (i, mark) in lane.road_marks.iter().enumerate() {
+            let start = s0 + mark.s_offset;
+            let end = lane
+                .road_marks
+                .get(i + 1)
+                .map_or(s1, |next| s0 + next.s_offset);
+            let width = if mark.width > 0.0 {
+                mark.width
+            } else {
+                DEFAULT_ROAD_MARK_WIDTH
+            };
+            let width = match mark.weight {
+                RoadMarkWeight::Standard => width,
+                RoadMarkWeight::Bold => 2.0 * width,
+            };
+            let color = road_mark_color(&mark.color);
+
+            use self::Stroke::*;
+            let lines: &[Stroke] = match mark.road_mark_type {
+                RoadMarkType::None => &[],
+                RoadMarkType::Solid => &[Solid],
+                RoadMarkType::Broken => &[Broken],
+                RoadMarkType::SolidSolid => &[Solid, Solid],
+                RoadMarkType::SolidBroken => &[Solid, Broken],
+                RoadMarkType::BrokenSolid => &[Broken, Solid],
+                RoadMarkType::BrokenBroken => &[Broken, Broken],
+                RoadMarkType::BottsDots => &[Dots],
+                RoadMarkType::Grass => &[Grass],
+                RoadMarkType::Curb => &[Curb],
+                RoadMarkType::Edge | RoadMarkType::Custom => &[Solid],
+            };
+            for (j, stroke) in lines.iter().enumerate() {
+                // Double lines are separated by one line width
+                let shift = if lines.len() == 2 {
+                    outward * width * (j as f64 * 2.0 - 1.0)
+                } else {
+                    0.0
+                };
+                let points: Vec<(f64, f64)> = road
+                    .sample_s(start, end, options.tolerance)?
+                    .into_iter()
+                    .map(|s| {
+                        let border = road
+                            .lane_borders(index, s)
+                            .into_iter()
+                            .find(|b| b.id == lane.id)
+                            .unwrap();
+                        road.position_at(s, border.outer + shift)
+                    })
+                    .collect();
+                let style = match stroke {
+                    Solid => format!(
+                        r#"stroke="{}" stroke-width="{:.2}""#,
+                        color,
+                        width * canvas.scale
+                    ),
+                    Broken => format!(
+                        r#"stroke="{}" stroke-width="{:.2}" stroke-dasharray="{:.2} {:.2}""#,
+                        color,
+                        width * canvas.scale,
+                        BROKEN_DASH.0 * canvas.scale,
+                        BROKEN_DASH.1 * canvas.scale
+                    ),
+                    Dots => format!(
+                        r#"stroke="{}" stroke-width="{:.2}" stroke-linecap="round" stroke-dasharray="0 {:.2}""#,
+                        color,
+                        2.0 * width * canvas.scale,
+                        BOTTS_DOTS_SPACING * canvas.scale
+                    ),
+                    Grass => format!(
+                        r##"stroke="#5f9a3c" stroke-width="{:.2}""##,
+                        0.5 * canvas.scale
+                    ),
+                    Curb => format!(
+                        r##"stroke="#b0b0b0" stroke-width="{:.2}""##,
+                        0.2 * canvas.scale
+                    ),
+                };
+                writeln!(
+                    svg,
+                    r#"<path d="{}" fill="none" {}/>"#,
+                    canvas.path(&points),
+                    style
+                )
+                .unwrap();
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+enum Stroke {
+    Solid,
+    Broken,
+    Dots,
+    Grass,
+    Curb,
+}
+
+fn draw_reference_line(svg: &mut String, canvas: &Canvas, road: &Road, options: &SvgOptions) {
+    let mut points = Vec::new();
+    for g in road.plan_view.geometries.iter() {
+        let segment = g.as_segment();
+        if points.is_empty() {
+            let from = segment.from();
+            points.push((from.x, from.y));
+        }
+        segment.for_each_flattened(options.tolerance, &mut |p| points.push((p.x, p.y)));
+    }
+    if points.is_empty() {
+        return;
+    }
+    writeln!(
+        svg,
+        r##"<path d="{}" fill="none" stroke="#1f5fbf" stroke-width="1"/>"##,
+        canvas.path(&points)
+    )
+    .unwrap();
+
+    if options.tick_spacing > 0.0 {
+        let length = road.length.get();
+        let mut ticks = String::new();
+        let mut s = 0.0;
+        while s <= length {
+            let a = canvas.point(road.position_at(s, -0.5));
+            let b = canvas.point(road.position_at(s, 0.5));
+            write!(ticks, "M{:.2},{:.2}L{:.2},{:.2}", a.0, a.1, b.0, b.1).unwrap();
+            s += options.tick_spacing;
+        }
+        writeln!(
+            svg,
+            r##"<path d="{}" stroke="#1f5fbf" stroke-width="1"/>"##,
+            ticks
+        )
+        .unwrap();
+    }
+}
+
+fn draw_labels(svg: &mut String, canvas: &Canvas, road: &Road) {
+    let font_size = 1.5 * canvas.scale;
+    let length = road.length.get();
+    let (x, y) = canvas.point(road.position_at(length / 2.0, 0.0));
+    writeln!(
+        svg,
+        r##"<text x="{:.2}" y="{:.2}" font-size="{:.1}" fill="#1f5fbf">road {}</text>"##,
+        x,
+        y,
+        font_size,
+        escape(&road.id.to_string())
+    )
+    .unwrap();
+
+    for_each_section(road, |index| {
+        let (s0, s1) = road.lane_section_range(index);
+        let s = (s0 + s1) / 2.0;
+        for border in road.lane_borders(index, s) {
+            if border.id == 0 {
+                continue;
+            }
+            let (x, y) = canvas.point(road.position_at(s, (border.inner + border.outer) / 2.0));
+            writeln!(
+                svg,
+                r##"<text x="{:.2}" y="{:.2}" font-size="{:.1}" fill="#ffffff" dominant-baseline="middle">{}</text>"##,
+                x,
+                y,
+                0.6 * font_size,
+                border.id
+            )
+            .unwrap();
+        }
+    });
+}
+
+pub(crate) fn lane_fill(lane_type: &LaneType) -> &'static str {
+    match lane_type {
+        LaneType::Driving
+        | LaneType::Entry
+        | LaneType::Exit
+        | LaneType::OnRamp
+        | LaneType::OffRamp
+        | LaneType::ConnectingRamp
+        | LaneType::MwyEntry
+        | LaneType::MwyExit
+        | LaneType::Bidirectional => "#505050",
+        LaneType::Stop | LaneType::Shoulder => "#7a7a7a",
+        LaneType::Border | LaneType::Curb => "#959595",
+        LaneType::Restricted | LaneType::Bus | LaneType::Taxi | LaneType::Hov => "#8a6a6a",
+        LaneType::Parking => "#5a6a8a",
+        LaneType::Biking => "#a05050",
+        LaneType::Sidewalk => "#c8bca8",
+        LaneType::Median | LaneType::None => "#9bbf8a",
+        LaneType::RoadWorks => "#d09030",
+        LaneType::Tram | LaneType::Rail => "#8a7a5a",
+        LaneType::Special1 | LaneType::Special2 | LaneType::Special3 => "#6a6a6a",
+    }
+}
+
+fn road_mark_color(color: &RoadMarkColor) -> &'static str {
+    match color {
+        RoadMarkColor::Standard | RoadMarkColor::White => "#ffffff",
+        RoadMarkColor::Yellow => "#f0c000",
+        RoadMarkColor::Blue => "#3070e0",
+        RoadMarkColor::Green => "#30a040",
+        RoadMarkColor::Red => "#d03030",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Conversion from inertial co-ordinates to SVG pixels
+struct Canvas {
+    west: f64,
+    north: f64,
+    scale: f64,
+}
+
+impl Canvas {
+    fn point(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        ((x - self.west) * self.scale, (self.north - y) * self.scale)
+    }
+
+    fn path(&self, points: &[(f64, f64)]) -> String {
+        let mut d = String::new();
+        for (i, &p) in points.iter().enumerate() {
+            let (x, y) = self.point(p);
+            write!(d, "{}{:.2},{:.2}", if i == 0 { 'M' } else { 'L' }, x, y).unwrap();
+        }
+        d
+    }
+}
@@ -26,6 +26,19 @@ macro_rules! assert_approx_eq {
     }};
 }
 
+/// A geometry record of 20 m at s = 0, x = 1, y = 2 with a heading of
+/// 0.5 rad
+fn geometry(element: crate::GeometryElement) -> crate::Geometry {
+    crate::Geometry {
+        s: crate::types::Length::new(0.0),
+        x: crate::types::Length::new(1.0),
+        y: crate::types::Length::new(2.0),
+        hdg: crate::types::Angle::radians(0.5),
+        length: crate::types::Length::new(20.0),
+        element,
+    }
+}
+
 #[test]
 fn test_monotonic() {
     use super::Monotonic;
@@ -43,6 +56,7 @@ fn test_monotonic() {
 #[cfg(test)]
 mod parsing {
     use crate::opendrive;
+    use crate::types;
 
     #[test]
     fn test_header() {
@@ -64,7 +78,7 @@ mod parsing {
                 rev_minor: 1,
                 date: Utc.ymd(2014, 3, 11).and_hms(8, 53, 30),
                 name: "test".to_string(),
-                version: 1.0,
+                version: "1.00".to_string(),
                 max_road: 3,
                 max_junc: 0,
                 max_prg: 0,
@@ -72,8 +86,9 @@ mod parsing {
                 south: 1.0,
                 east: 2.0,
                 west: 3.0,
-                //vendor: "".to_string(),
+                vendor: None,
                 geo_reference: None,
+                offset: None,
             }
         );
     }
@@ -109,17 +124,64 @@ mod parsing {
             opendrive::Road {
                 name: "test_road".to_string(),
                 length: opendrive::types::Length::new(1.0478122375188772e+02),
-                id: 1,
-                junction: -1,
+                id: types::RoadId::from(1),
+                junction: None,
+                rule: None,
                 link: None,
+                road_types: vec![],
                 plan_view: Default::default(),
                 elevation_profile: None,
                 lateral_profile: None,
                 lanes: None,
+                objects: None,
+                signals: None,
             }
         );
     }
 
+    #[test]
+    fn test_string_ids() {
+        let s = r##"
+        <OpenDRIVE>
+            <header revMajor="1" revMinor="5" name="" version="1.00" date="Tue Mar 11 08:53:30 2014" north="0.0" south="0.0" east="0.0" west="0.0" maxRoad="3" maxJunc="1" maxPrg="0"></header>
+                <road name="" length="10.0" id="road_12a" junction="j1">
+                    <link>
+                        <predecessor elementType="road" elementId="300" contactPoint="end"/>
+                        <successor elementType="junction" elementId="007" contactPoint="start"/>
+                    </link>
+                    <planView></planView>
+                </road>
+                <road name="" length="10.0" id="300" junction="-1">
+                    <planView></planView>
+                </road>
+                <junction name="" id="j1">
+                    <connection id="c_left" incomingRoad="300" connectingRoad="road_12a" contactPoint="start"/>
+                </junction>
+        </OpenDRIVE>
+        "##;
+        use serde_xml_rs::from_str;
+        let od: opendrive::Root = from_str(s).unwrap();
+        let road = &od.roads[0];
+        assert_eq!(road.id, types::RoadId::from("road_12a"));
+        assert_eq!(road.id.to_string(), "road_12a");
+        assert_eq!(road.id.as_u64(), None);
+        assert_eq!(road.junction, Some(types::JunctionId::from("j1")));
+        let link = road.link.as_ref().unwrap();
+        let predecessor = &link.predecessor.as_ref().unwrap().element_id;
+        assert_eq!(*predecessor, od.roads[1].id);
+        assert_eq!(od.roads[1].id.as_u64(), Some(300));
+        assert_eq!(od.roads[1].junction, None);
+        // only plain decimal numbers take the numeric path
+        let successor = &link.successor.as_ref().unwrap().element_id;
+        assert_eq!(successor.to_string(), "007");
+        assert!(*successor != types::ElementId::from(7));
+        assert!(types::RoadId::from(9) < types::RoadId::from(10));
+        let connection = &od.junctions[0].connections[0];
+        assert_eq!(connection.id, types::ConnectionId::from("c_left"));
+        assert_eq!(connection.connecting_road.as_ref(), Some(&road.id));
+        assert_eq!(connection.linked_road, None);
+    }
+
     #[test]
     fn test_geometry() {
         let s = r##"
@@ -155,7 +217,7 @@ mod parsing {
                 predecessor: None,
                 successor: Some(opendrive::LinkElement {
                     element_type: opendrive::ElementType::Road,
-                    element_id: 3,
+                    element_id: types::ElementId::from(3),
                     contact_point: opendrive::ContactPoint::Start,
                 }),
                 neighbors: vec![],
@@ -247,6 +309,9 @@ mod parsing {
                         lane_change: opendrive::LaneChangeType::Both,
                         height: 0.0,
                     }],
+                    heights: vec![],
+                    direction: None,
+                    advisory: None,
                 }],
             })
         );
@@ -345,6 +410,82 @@ mod parsing {
         assert_approx_eq!(s0.y(1.0), 1.0);
     }
 
+    #[test]
+    fn test_road_type() {
+        let s = r##"
+        <type s="0.0" type="town">
+            <speed max="50" unit="km/h"/>
+        </type>
+        "##;
+        use serde_xml_rs::from_str;
+        let road_type: opendrive::RoadType = from_str(s).unwrap();
+        assert_eq!(road_type.road_type, opendrive::RoadTypeKind::Town);
+        let speed = road_type.speed.unwrap();
+        assert_eq!(speed.unit, Some(opendrive::SpeedUnit::KilometersPerHour));
+        assert_approx_eq!(speed.max_meters_per_second(), 50.0 / 3.6);
+    }
+
+    #[test]
+    fn test_junction() {
+        let s = r##"
+        <junction name="" id="1">
+            <connection id="0" incomingRoad="1" connectingRoad="2" contactPoint="start">
+                <laneLink from="-1" to="-1"/>
+                <laneLink from="-2" to="-2"/>
+            </connection>
+            <connection id="1" incomingRoad="3" connectingRoad="4" contactPoint="end">
+                <laneLink from="1" to="-1"/>
+            </connection>
+        </junction>
+        "##;
+        use serde_xml_rs::from_str;
+        let junction: opendrive::Junction = from_str(s).unwrap();
+        assert_eq!(junction.id, types::JunctionId::from(1));
+        assert_eq!(junction.connections.len(), 2);
+        assert_eq!(
+            junction.connections[1],
+            opendrive::Connection {
+                id: types::ConnectionId::from(1),
+                incoming_road: types::RoadId::from(3),
+                connecting_road: Some(types::RoadId::from(4)),
+                linked_road: None,
+                contact_point: opendrive::ContactPoint::End,
+                lane_links: vec![opendrive::JunctionLaneLink { from: 1, to: -1 }],
+            }
+        );
+    }
+
+    #[test]
+    fn test_objects_signals() {
+        let s = r##"
+        <road name="" length="100.0" id="1" junction="-1">
+            <planView>
+                <geometry s="0.0" x="0.0" y="0.0" hdg="0.0" length="100.0"><line/></geometry>
+            </planView>
+            <objects>
+                <object type="pole" name="lamp" id="7" s="10.0" t="-5.0" zOffset="0.0" orientation="+" radius="0.1" height="6.0"/>
+            </objects>
+            <signals>
+                <signal s="50.0" t="-4.0" id="12" name="limit" dynamic="no" orientation="+" zOffset="2.0" country="DE" type="274" subtype="-1" value="80" unit="km/h"/>
+            </signals>
+        </road>
+        "##;
+        use serde_xml_rs::from_str;
+        let road: opendrive::Road = from_str(s).unwrap();
+        let objects = &road.objects.unwrap().objects;
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].id, "7");
+        assert_eq!(objects[0].orientation, opendrive::Orientation::Positive);
+        assert_eq!(objects[0].radius, Some(0.1));
+        assert_eq!(objects[0].length, None);
+
+        let signals = &road.signals.unwrap().signals;
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].signal_type, "274");
+        assert_eq!(signals[0].value, Some(80.0));
+        assert!(!signals[0].dynamic);
+    }
+
     #[test]
     fn test_file1() {
         use serde_xml_rs;
@@ -355,3 +496,3386 @@ mod parsing {
         //println!("{:#?}", root);
     }
 }
+
+#[cfg(test)]
+mod builder {
+    use crate::builder::*;
+    use crate::opendrive::*;
+    use crate::types::ElementId;
+    use lyon_geom::Segment;
+
+    #[test]
+    fn test_chained_geometry() {
+        let road = RoadBuilder::new()
+            .start(10.0, -5.0, 0.5)
+            .line(50.0)
+            .arc(30.0, 0.02)
+            .spiral(20.0, 0.02, 0.0)
+            .build();
+
+        assert_eq!(road.plan_view.geometries.len(), 3);
+        assert_eq!(road.length, road.plan_view.sum_length());
+        assert_approx_eq!(road.length.get(), 100.0);
+        assert!(road.validate().is_ok());
+
+        let g = &road.plan_view.geometries;
+        assert_approx_eq!(g[1].s.get(), 50.0);
+        assert_approx_eq!(g[2].s.get(), 80.0);
+        assert_approx_eq!(g[1].hdg.radians, 0.5);
+        assert_approx_eq!(g[2].hdg.radians, 0.5 + 30.0 * 0.02);
+        assert_approx_eq!(g[2].end_hdg().radians, 0.5 + 30.0 * 0.02 + 20.0 * 0.01);
+
+        // Each element starts where the previous one ends
+        for (a, b) in g.iter().zip(g.iter().skip(1)) {
+            let end = a.as_segment().to();
+            assert_approx_eq!(end.x, b.x.get());
+            assert_approx_eq!(end.y, b.y.get());
+            assert_approx_eq!(a.end_hdg().radians, b.hdg.radians);
+        }
+    }
+
+    #[test]
+    fn test_connect_to() {
+        let road = RoadBuilder::new()
+            .start(0.0, 0.0, 0.0)
+            .connect_to(30.0, 20.0, 1.0)
+            .unwrap()
+            .build();
+        let g = road.plan_view.geometries.last().unwrap();
+        let end = g.as_segment().to();
+        assert_approx_eq!(end.x, 30.0);
+        assert_approx_eq!(end.y, 20.0);
+        assert_approx_eq!(g.end_hdg().radians, 1.0);
+        assert!(road.validate().is_ok());
+
+        // Sideways and behind the start there is no biarc with equal tangents
+        assert!(RoadBuilder::new().connect_to(0.0, 10.0, 0.0).is_err());
+        assert!(RoadBuilder::new().connect_to(-20.0, 0.0, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_lane_ids() {
+        let road = RoadBuilder::new()
+            .line(10.0)
+            .lanes(
+                LaneSectionBuilder::new()
+                    .center_mark(RoadMarkType::Broken)
+                    .left(LaneBuilder::new(LaneType::Driving, 3.5))
+                    .left(LaneBuilder::new(LaneType::Sidewalk, 2.0))
+                    .right(LaneBuilder::new(LaneType::Driving, 3.5).mark(RoadMarkType::Solid)),
+            )
+            .build();
+
+        let section = &road.lanes.as_ref().unwrap().lane_sections[0];
+        let left: Vec<i32> = section
+            .left
+            .as_ref()
+            .unwrap()
+            .lane
+            .iter()
+            .map(|l| l.id)
+            .collect();
+        assert_eq!(left, vec![2, 1]);
+        assert_eq!(
+            section.left.as_ref().unwrap().lane[0].lane_type,
+            LaneType::Sidewalk
+        );
+        assert_eq!(section.right.as_ref().unwrap().lane[0].id, -1);
+        assert_eq!(section.right.as_ref().unwrap().lane[0].road_marks.len(), 1);
+        assert_eq!(
+            section.center.lane[0].road_marks[0].road_mark_type,
+            RoadMarkType::Broken
+        );
+    }
+
+    #[test]
+    fn test_link_roads() {
+        let lanes = || {
+            LaneSectionBuilder::new()
+                .left(LaneBuilder::new(LaneType::Driving, 3.5))
+                .right(LaneBuilder::new(LaneType::Driving, 3.5))
+        };
+        let mut a = RoadBuilder::new().id(1).line(10.0).lanes(lanes()).build();
+        let mut b = RoadBuilder::new()
+            .id(2)
+            .start_at_end_of(&a)
+            .arc(10.0, 0.1)
+            .lanes(lanes())
+            .build();
+        link_roads(&mut a, &mut b);
+
+        assert_approx_eq!(b.plan_view.geometries[0].x.get(), 10.0);
+        assert_eq!(
+            a.link.as_ref().unwrap().successor,
+            Some(LinkElement {
+                element_type: ElementType::Road,
+                element_id: ElementId::from(2),
+                contact_point: ContactPoint::Start,
+            })
+        );
+        assert_eq!(
+            b.link
+                .as_ref()
+                .unwrap()
+                .predecessor
+                .as_ref()
+                .unwrap()
+                .element_id,
+            ElementId::from(1)
+        );
+
+        let b_section = &b.lanes.as_ref().unwrap().lane_sections[0];
+        let b_right = &b_section.right.as_ref().unwrap().lane[0];
+        assert_eq!(
+            b_right.link.as_ref().unwrap().predecessor,
+            Some(LaneLinkElement { id: -1 })
+        );
+    }
+}
+
+#[cfg(test)]
+mod generators {
+    use crate::generators::*;
+    use crate::opendrive::*;
+    use crate::types::ElementId;
+    use lyon_geom::Segment;
+    use std::f64::consts::PI;
+
+    fn pose_at(road: &Road, contact_point: &ContactPoint) -> (f64, f64, f64) {
+        let geometries = &road.plan_view.geometries;
+        match contact_point {
+            ContactPoint::Start => (
+                geometries[0].x.get(),
+                geometries[0].y.get(),
+                geometries[0].hdg.radians,
+            ),
+            ContactPoint::End => {
+                let last = geometries.last().unwrap();
+                let end = last.as_segment().to();
+                (end.x, end.y, last.end_hdg().radians)
+            }
+        }
+    }
+
+    fn assert_same_pose(a: (f64, f64, f64), b: (f64, f64, f64), flipped: bool) {
+        assert_approx_eq!(a.0, b.0, 1e-6);
+        assert_approx_eq!(a.1, b.1, 1e-6);
+        let turn = if flipped { PI } else { 0.0 };
+        let dh = (a.2 - b.2 + turn).sin();
+        assert_approx_eq!(dh, 0.0, 1e-6);
+        assert!((a.2 - b.2 + turn).cos() > 0.0);
+    }
+
+    /// All roads linked to each other meet with matching position and heading
+    fn assert_continuous(root: &Root) {
+        let road = |id: &ElementId| root.roads.iter().find(|r| r.id == *id).unwrap();
+        for r in root.roads.iter() {
+            assert!(r.validate().is_ok());
+            let link = match r.link {
+                Some(ref link) => link,
+                None => continue,
+            };
+            if let Some(ref succ) = link.successor {
+                if succ.element_type == ElementType::Road {
+                    let other = pose_at(road(&succ.element_id), &succ.contact_point);
+                    let flipped = succ.contact_point == ContactPoint::End;
+                    assert_same_pose(pose_at(r, &ContactPoint::End), other, flipped);
+                }
+            }
+            if let Some(ref pred) = link.predecessor {
+                if pred.element_type == ElementType::Road {
+                    let other = pose_at(road(&pred.element_id), &pred.contact_point);
+                    let flipped = pred.contact_point == ContactPoint::Start;
+                    assert_same_pose(pose_at(r, &ContactPoint::Start), other, flipped);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_highway() {
+        let root = straight_highway(&HighwayParams {
+            lanes: 3,
+            ..Default::default()
+        });
+        assert_eq!(root.roads.len(), 1);
+        let section = &root.roads[0].lanes.as_ref().unwrap().lane_sections[0];
+        assert_eq!(section.left.as_ref().unwrap().lane.len(), 3);
+        assert_eq!(section.right.as_ref().unwrap().lane.len(), 3);
+        assert_approx_eq!(root.header.east, 1000.0);
+        assert_approx_eq!(root.header.west, 0.0);
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let a = roundabout(&Default::default());
+        let b = roundabout(&Default::default());
+        assert_eq!(a.header, b.header);
+        assert_eq!(a.header.date.timestamp(), 0);
+    }
+
+    #[test]
+    fn test_curves() {
+        let params = CurveParams::default();
+        let root = curve(&params);
+        let last = root.roads[0].plan_view.geometries.last().unwrap();
+        assert_approx_eq!(last.hdg.radians, params.angle);
+        assert_approx_eq!(root.header.north, 200.0 + 100.0);
+
+        let root = s_curve(&params);
+        let last = root.roads[0].plan_view.geometries.last().unwrap();
+        assert_approx_eq!(last.hdg.radians, 0.0);
+    }
+
+    #[test]
+    fn test_junctions() {
+        let root = x_junction(&JunctionParams {
+            lanes: 2,
+            ..Default::default()
+        });
+        assert_eq!(root.roads.len(), 4 + 12);
+        assert_eq!(root.junctions.len(), 1);
+        assert_eq!(root.junctions[0].connections.len(), 12);
+        assert_eq!(root.junctions[0].connections[0].lane_links.len(), 2);
+        assert_continuous(&root);
+
+        let root = t_junction(&Default::default());
+        assert_eq!(root.roads.len(), 3 + 6);
+        assert_continuous(&root);
+    }
+
+    #[test]
+    fn test_roundabout() {
+        let root = roundabout(&RoundaboutParams {
+            arms: 3,
+            ..Default::default()
+        });
+        assert_eq!(root.roads.len(), 3 + 3 + 9);
+        assert_eq!(root.junctions.len(), 3);
+        assert_continuous(&root);
+    }
+
+    #[test]
+    fn test_ramps() {
+        for &kind in [RampKind::Off, RampKind::On].iter() {
+            let root = ramp(&RampParams {
+                kind,
+                ..Default::default()
+            });
+            assert_eq!(root.roads.len(), 5);
+            assert_eq!(root.junctions[0].connections.len(), 2);
+            assert_continuous(&root);
+        }
+    }
+
+    #[test]
+    fn test_cul_de_sac() {
+        let root = cul_de_sac(&Default::default());
+        assert_eq!(root.roads.len(), 2);
+        assert_continuous(&root);
+    }
+}
+
+#[cfg(test)]
+mod geo {
+    use crate::errors::GeoReferenceError;
+    use crate::generators::*;
+    use crate::geo::*;
+    use crate::opendrive::*;
+
+    fn assert_near(a: (f64, f64), b: (f64, f64), tolerance: f64) {
+        assert!(
+            (a.0 - b.0).abs() < tolerance && (a.1 - b.1).abs() < tolerance,
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn test_utm() {
+        let utm = Projection::from_proj_string(
+            "+proj=utm +zone=32 +ellps=WGS84 +datum=WGS84 +units=m +no_defs",
+        )
+        .unwrap();
+        let xy = utm.forward(48.137154, 11.576124);
+        assert_near(xy, (691650.367, 5334754.247), 0.01);
+        assert_near(utm.inverse(xy.0, xy.1), (48.137154, 11.576124), 1e-9);
+
+        // the central meridian on the equator maps to the false easting
+        assert_near(utm.forward(0.0, 9.0), (500000.0, 0.0), 1e-6);
+
+        let south = Projection::from_proj_string("+proj=utm +zone=56 +south").unwrap();
+        let xy = south.forward(-33.8688, 151.2093);
+        assert_near(xy, (334368.634, 6250948.345), 0.01);
+        assert_near(south.inverse(xy.0, xy.1), (-33.8688, 151.2093), 1e-9);
+    }
+
+    #[test]
+    fn test_tmerc_origin() {
+        let tmerc = Projection::from_proj_string(
+            "+proj=tmerc +lat_0=49 +lon_0=8 +k=1 +x_0=1000 +y_0=2000 +ellps=GRS80",
+        )
+        .unwrap();
+        assert_near(tmerc.forward(49.0, 8.0), (1000.0, 2000.0), 1e-6);
+        let xy = tmerc.forward(49.01, 8.02);
+        assert_near(tmerc.inverse(xy.0, xy.1), (49.01, 8.02), 1e-9);
+    }
+
+    #[test]
+    fn test_mercator() {
+        let merc = Projection::from_proj_string("+proj=merc +lon_0=0 +datum=WGS84").unwrap();
+        assert_near(merc.forward(0.0, 10.0), (1113194.908, 0.0), 1e-3);
+        let xy = merc.forward(52.5, 13.4);
+        assert_near(merc.inverse(xy.0, xy.1), (52.5, 13.4), 1e-9);
+
+        let scaled = Projection::from_proj_string("+proj=merc +lat_ts=52.5").unwrap();
+        let x = scaled.forward(0.0, 10.0).0;
+        assert!(x > 1113194.908 * 0.6 && x < 1113194.908 * 0.62);
+    }
+
+    #[test]
+    fn test_invalid() {
+        match Projection::from_proj_string("+proj=lcc +lat_1=33 +lat_2=45") {
+            Err(GeoReferenceError::UnsupportedProjection(ref p)) if p == "lcc" => {}
+            other => panic!("{:?}", other),
+        }
+        assert!(Projection::from_proj_string("+proj=utm").is_err());
+        assert!(Projection::from_proj_string("+proj=tmerc +k=abc").is_err());
+        assert!(Projection::from_proj_string("+proj=tmerc +ellps=foo").is_err());
+    }
+
+    #[test]
+    fn test_root_offset() {
+        let s = r##"<OpenDRIVE>
+            <header revMajor="1" revMinor="6" name="" version="1.00" north="0.0" south="0.0" east="0.0" west="0.0" maxRoad="0" maxJunc="0" maxPrg="0">
+                <geoReference><![CDATA[+proj=utm +zone=32 +datum=WGS84]]></geoReference>
+                <offset x="691000.0" y="5334000.0" z="500.0" hdg="0.5"/>
+            </header>
+        </OpenDRIVE>"##;
+        let root: Root = serde_xml_rs::from_str(s).unwrap();
+        let offset = root.header.offset.unwrap();
+        assert_eq!(offset.z, 500.0);
+
+        let utm = root.projection().unwrap();
+        assert_near(
+            root.to_geodetic(0.0, 0.0).unwrap(),
+            utm.inverse(691000.0, 5334000.0),
+            1e-12,
+        );
+        // a point ahead along the rotated x axis
+        let (sin, cos) = 0.5f64.sin_cos();
+        assert_near(
+            root.to_geodetic(100.0, 0.0).unwrap(),
+            utm.inverse(691000.0 + 100.0 * cos, 5334000.0 + 100.0 * sin),
+            1e-12,
+        );
+
+        let (lat, lon) = root.to_geodetic(123.0, -45.0).unwrap();
+        assert_near(root.from_geodetic(lat, lon).unwrap(), (123.0, -45.0), 1e-6);
+    }
+
+    #[test]
+    fn test_missing() {
+        let root = straight_highway(&Default::default());
+        match root.to_geodetic(0.0, 0.0) {
+            Err(GeoReferenceError::Missing) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod evaluation {
+    use crate::builder::*;
+    use crate::opendrive::*;
+    use lyon_geom::Segment;
+
+    fn geometry(hdg: f64, length: f64, element: GeometryElement) -> Geometry {
+        Geometry {
+            hdg: crate::types::Angle::radians(hdg),
+            length: crate::types::Length::new(length),
+            ..super::geometry(element)
+        }
+    }
+
+    #[test]
+    fn test_geometry_pose() {
+        let elements = vec![
+            GeometryElement::Line,
+            GeometryElement::Arc { curvature: 0.05 },
+            GeometryElement::Arc { curvature: -0.02 },
+            GeometryElement::Spiral {
+                curv_start: 0.0,
+                curv_end: 0.04,
+            },
+        ];
+        for element in elements {
+            let g = geometry(0.7, 30.0, element);
+            let end = g.as_segment().to();
+            let (x, y, hdg) = g.pose_at(30.0);
+            assert_approx_eq!(x, end.x, 1e-9);
+            assert_approx_eq!(y, end.y, 1e-9);
+            assert_approx_eq!(hdg, g.end_hdg().radians, 1e-12);
+            assert_eq!(g.pose_at(0.0), (1.0, 2.0, 0.7));
+        }
+    }
+
+    #[test]
+    fn test_poly3_pose() {
+        // v = 0.01 u², arc length of the parabola from 0 to 10 is
+        // (u/2) sqrt(1 + 4k²u²) + asinh(2ku) / 4k with k = 0.01
+        let length = 5.0 * (1.04f64).sqrt() + (0.2f64).asinh() / 0.04;
+        let g = geometry(
+            0.0,
+            length,
+            GeometryElement::Poly3 {
+                a: 0.0,
+                b: 0.0,
+                c: 0.01,
+                d: 0.0,
+            },
+        );
+        let (x, y, hdg) = g.pose_at(length);
+        assert_approx_eq!(x, 1.0 + 10.0, 1e-6);
+        assert_approx_eq!(y, 2.0 + 1.0, 1e-6);
+        assert_approx_eq!(hdg, (0.2f64).atan(), 1e-6);
+    }
+
+    #[test]
+    fn test_poly3_segment() {
+        let g = geometry(
+            0.3,
+            20.0,
+            GeometryElement::Poly3 {
+                a: 0.5,
+                b: 0.1,
+                c: 0.01,
+                d: -0.0005,
+            },
+        );
+        let segment = g.as_segment();
+        let (x, y, _) = g.pose_at(0.0);
+        assert_approx_eq!(segment.from().x, x, 1e-9);
+        assert_approx_eq!(segment.from().y, y, 1e-9);
+        let (x, y, _) = g.pose_at(20.0);
+        assert_approx_eq!(segment.to().x, x, 1e-6);
+        assert_approx_eq!(segment.to().y, y, 1e-6);
+        assert_approx_eq!(segment.approximate_length(1e-6), 20.0, 1e-4);
+    }
+
+    #[test]
+    fn test_lane_borders() {
+        let road = RoadBuilder::new()
+            .line(100.0)
+            .lane_offset(0.0, 0.5, 0.0, 0.0, 0.0)
+            .lanes(
+                LaneSectionBuilder::new()
+                    .left(LaneBuilder::new(LaneType::Driving, 3.0))
+                    .left(LaneBuilder::new(LaneType::Sidewalk, 2.0))
+                    .right(
+                        LaneBuilder::new(LaneType::Driving, 3.5).width(0.0, 3.5, 0.01, 0.0, 0.0),
+                    ),
+            )
+            .build();
+        assert_eq!(road.lane_section_index_at(50.0), Some(0));
+        assert_eq!(road.lane_section_range(0), (0.0, 100.0));
+
+        let borders = road.lane_borders(0, 50.0);
+        let ids: Vec<i32> = borders.iter().map(|b| b.id).collect();
+        assert_eq!(ids, vec![2, 1, 0, -1]);
+        assert_approx_eq!(borders[0].inner, 3.5);
+        assert_approx_eq!(borders[0].outer, 5.5);
+        assert_approx_eq!(borders[2].outer, 0.5);
+        assert_approx_eq!(borders[3].outer, 0.5 - 4.0);
+
+        let (x, y) = road.position_at(10.0, -1.0);
+        assert_approx_eq!(x, 10.0);
+        assert_approx_eq!(y, -1.0);
+    }
+
+    #[test]
+    fn test_sample_s() {
+        let road = RoadBuilder::new().line(10.0).arc(50.0, 0.1).build();
+        let stations = road.sample_s(0.0, road.length.get(), 0.01).unwrap();
+        assert_eq!(stations[0], 0.0);
+        assert_eq!(stations[1], 10.0);
+        assert_eq!(*stations.last().unwrap(), 60.0);
+        for (a, b) in stations.iter().zip(stations.iter().skip(1)).skip(1) {
+            let sagitta = 0.1 * (b - a) * (b - a) / 8.0;
+            assert!(sagitta <= 0.01 + 1e-9);
+        }
+
+        for &tolerance in [0.0, -0.01, f64::NAN, f64::INFINITY].iter() {
+            assert!(road.sample_s(0.0, road.length.get(), tolerance).is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod geojson {
+    use crate::errors::SampleError;
+    use crate::generators::*;
+    use crate::geojson::*;
+    use crate::opendrive::*;
+    use crate::types::SignalId;
+
+    fn features<'a>(collection: &'a serde_json::Value, kind: &str) -> Vec<&'a serde_json::Value> {
+        collection["features"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|f| f["properties"]["kind"] == kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_highway() {
+        let root = straight_highway(&HighwayParams {
+            lanes: 2,
+            ..Default::default()
+        });
+        let collection = to_geojson(&root, &Default::default()).unwrap();
+        assert_eq!(collection["type"], "FeatureCollection");
+        assert_eq!(features(&collection, "referenceLine").len(), 1);
+
+        let lanes = features(&collection, "lane");
+        assert_eq!(lanes.len(), 4);
+        let lane = lanes
+            .iter()
+            .find(|f| f["properties"]["lane"] == -1)
+            .unwrap();
+        assert_eq!(lane["properties"]["type"], "driving");
+        assert!(lane["properties"]["speed"].as_f64().unwrap() > 0.0);
+        let ring = lane["geometry"]["coordinates"][0].as_array().unwrap();
+        assert_eq!(ring.first(), ring.last());
+        let borders = root.roads[0].lane_borders(0, 0.0);
+        let outer = borders.iter().find(|b| b.id == -1).unwrap().outer;
+        assert!(outer < 0.0);
+        for point in ring {
+            let y = point[1].as_f64().unwrap();
+            assert!(y <= 1e-9 && y >= outer - 1e-9);
+        }
+
+        // center line plus the outer border of every lane
+        assert_eq!(features(&collection, "roadMark").len(), 5);
+
+        // exterior rings are counterclockwise on both sides of the road
+        for lane in lanes {
+            let ring = lane["geometry"]["coordinates"][0].as_array().unwrap();
+            let area: f64 = ring
+                .windows(2)
+                .map(|pair| {
+                    let (a, b) = (&pair[0], &pair[1]);
+                    a[0].as_f64().unwrap() * b[1].as_f64().unwrap()
+                        - b[0].as_f64().unwrap() * a[1].as_f64().unwrap()
+                })
+                .sum();
+            assert!(area > 0.0, "lane {}", lane["properties"]["lane"]);
+        }
+    }
+
+    #[test]
+    fn test_invalid_tolerance() {
+        let root = straight_highway(&Default::default());
+        let options = GeoJsonOptions {
+            tolerance: 0.0,
+            ..Default::default()
+        };
+        let err = to_geojson(&root, &options).unwrap_err();
+        assert!(err.downcast_ref::<SampleError>().is_some());
+    }
+
+    #[test]
+    fn test_geodetic() {
+        let mut root = straight_highway(&Default::default());
+        assert!(to_geojson(
+            &root,
+            &GeoJsonOptions {
+                coordinates: Coordinates::Geodetic,
+                ..Default::default()
+            }
+        )
+        .is_err());
+
+        root.header.geo_reference = Some(GeoReference {
+            projection: "+proj=utm +zone=32 +datum=WGS84".to_string(),
+        });
+        root.header.offset = Some(Offset {
+            x: 691000.0,
+            y: 5334000.0,
+            ..Default::default()
+        });
+        root.roads[0].signals = Some(Signals {
+            signals: vec![Signal {
+                s: 0.0,
+                t: 0.0,
+                id: SignalId::from(1),
+                name: None,
+                dynamic: true,
+                orientation: Orientation::Positive,
+                z_offset: 0.0,
+                country: None,
+                signal_type: "1000001".to_string(),
+                subtype: "-1".to_string(),
+                value: None,
+                unit: None,
+                height: None,
+                width: None,
+                text: None,
+                h_offset: 0.0,
+                pitch: 0.0,
+                roll: 0.0,
+            }],
+        });
+        let collection = to_geojson(
+            &root,
+            &GeoJsonOptions {
+                coordinates: Coordinates::Geodetic,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let signals = features(&collection, "signal");
+        assert_eq!(signals.len(), 1);
+        let (lat, lon) = root.to_geodetic(0.0, 0.0).unwrap();
+        assert_approx_eq!(
+            signals[0]["geometry"]["coordinates"][0].as_f64().unwrap(),
+            lon,
+            1e-12
+        );
+        assert_approx_eq!(
+            signals[0]["geometry"]["coordinates"][1].as_f64().unwrap(),
+            lat,
+            1e-12
+        );
+    }
+}
+
+#[cfg(test)]
+mod svg {
+    use crate::builder::*;
+    use crate::generators::*;
+    use crate::opendrive::*;
+    use crate::svg::*;
+
+    #[test]
+    fn test_highway() {
+        let root = straight_highway(&HighwayParams {
+            lanes: 2,
+            length: 100.0,
+            ..Default::default()
+        });
+        let options = SvgOptions::default();
+        let svg = to_svg(&root, &options).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+
+        // 100 m of road plus the margin on both sides
+        let width = (100.0 + 2.0 * options.margin) * options.scale;
+        assert!(svg.contains(&format!("width=\"{:.1}\"", width)));
+        assert_eq!(svg.matches("fill=\"#505050\"").count(), 4);
+        assert!(svg.contains("stroke-dasharray"));
+        assert!(svg.contains(">road 1</text>"));
+        assert!(svg.contains(">-2</text>"));
+    }
+
+    #[test]
+    fn test_road_mark_styles() {
+        let road = RoadBuilder::new()
+            .id(1)
+            .line(50.0)
+            .lanes(
+                LaneSectionBuilder::new()
+                    .center_mark(RoadMarkType::SolidSolid)
+                    .right(LaneBuilder::new(LaneType::Driving, 3.5).mark(RoadMarkType::BottsDots)),
+            )
+            .build();
+        let root = Root {
+            header: straight_highway(&Default::default()).header,
+            roads: vec![road],
+            junctions: vec![],
+        };
+        let svg = to_svg(
+            &root,
+            &SvgOptions {
+                labels: false,
+                tick_spacing: 0.0,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        // two lines for the double center mark and one dotted line
+        assert_eq!(svg.matches("fill=\"none\" stroke=\"#ffffff\"").count(), 3);
+        assert!(svg.contains("stroke-linecap=\"round\""));
+        assert!(!svg.contains("<text"));
+    }
+
+    #[test]
+    fn test_arc_flattening() {
+        let road = RoadBuilder::new().arc(50.0, -0.05).build();
+        let g = &road.plan_view.geometries[0];
+        let mut last = None;
+        g.as_segment()
+            .for_each_flattened(0.01, &mut |p| last = Some(p));
+        let (x, y, _) = g.pose_at(50.0);
+        let last = last.unwrap();
+        assert_approx_eq!(last.x, x, 1e-9);
+        assert_approx_eq!(last.y, y, 1e-9);
+    }
+
+    #[test]
+    fn test_arc_road() {
+        for &curvature in &[0.05, -0.05] {
+            let road = RoadBuilder::new().id(1).arc(50.0, curvature).build();
+            let root = Root {
+                header: straight_highway(&Default::default()).header,
+                roads: vec![road],
+                junctions: vec![],
+            };
+            let svg = to_svg(
+                &root,
+                &SvgOptions {
+                    labels: false,
+                    tick_spacing: 0.0,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            let line = svg
+                .lines()
+                .find(|line| line.contains("stroke=\"#1f5fbf\""))
+                .unwrap();
+            let d = line.split('"').nth(1).unwrap();
+            let coordinates: Vec<f64> = d
+                .split(|c| c == 'M' || c == 'L' || c == ',')
+                .filter(|c| !c.is_empty())
+                .map(|c| c.parse().unwrap())
+                .collect();
+            // more than the two ends of a chord
+            assert!(coordinates.len() > 4);
+            assert!(coordinates.iter().all(|c| c.is_finite()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod mesh {
+    use crate::builder::*;
+    use crate::generators::*;
+    use crate::mesh::*;
+    use crate::opendrive::*;
+
+    fn single_road(road: Road) -> Root {
+        Root {
+            header: straight_highway(&Default::default()).header,
+            roads: vec![road],
+            junctions: vec![],
+        }
+    }
+
+    #[test]
+    fn test_flat_highway() {
+        let root = straight_highway(&HighwayParams {
+            lanes: 2,
+            length: 100.0,
+            ..Default::default()
+        });
+        let mesh = road_mesh(&root, &Default::default()).unwrap();
+        // one group per lane
+        let names: Vec<&str> = mesh.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "road_1_s0_lane_1",
+                "road_1_s0_lane_2",
+                "road_1_s0_lane_-1",
+                "road_1_s0_lane_-2",
+            ]
+        );
+        assert!(mesh.groups.iter().all(|g| g.material == "Driving"));
+        // 4 lanes with two vertices at the start and end of the road
+        assert_eq!(mesh.positions.len(), 4 * 2 * 2);
+        assert_eq!(mesh.triangle_count(), 4 * 2);
+        for n in mesh.normals.iter() {
+            assert_approx_eq!(n[2], 1.0);
+        }
+    }
+
+    #[test]
+    fn test_elevation_and_superelevation() {
+        let road = RoadBuilder::new()
+            .line(100.0)
+            .elevation(0.0, 10.0, 0.1, 0.0, 0.0)
+            .superelevation(0.0, 0.05, 0.0, 0.0, 0.0)
+            .lanes(
+                LaneSectionBuilder::new()
+                    .left(LaneBuilder::new(LaneType::Driving, 4.0))
+                    .right(LaneBuilder::new(LaneType::Driving, 4.0)),
+            )
+            .build();
+        assert_approx_eq!(road.elevation_at(100.0), 20.0);
+        assert_approx_eq!(road.elevation_slope_at(50.0), 0.1);
+
+        let mesh = road_mesh(&single_road(road), &Default::default()).unwrap();
+        let z_max = mesh.positions.iter().map(|p| p[2]).fold(f64::MIN, f64::max);
+        let z_min = mesh.positions.iter().map(|p| p[2]).fold(f64::MAX, f64::min);
+        assert_approx_eq!(z_max, 20.0 + 4.0 * 0.05f64.sin());
+        assert_approx_eq!(z_min, 10.0 - 4.0 * 0.05f64.sin());
+        for n in mesh.normals.iter() {
+            assert!(n[2] > 0.9);
+        }
+    }
+
+    #[test]
+    fn test_sidewalk() {
+        let road = RoadBuilder::new()
+            .line(20.0)
+            .lanes(
+                LaneSectionBuilder::new()
+                    .right(LaneBuilder::new(LaneType::Driving, 3.5))
+                    .right(LaneBuilder::new(LaneType::Sidewalk, 2.0)),
+            )
+            .build();
+        let root = single_road(road);
+
+        let mesh = road_mesh(&root, &Default::default()).unwrap();
+        assert!(mesh.groups.iter().all(|g| g.material != CURB_MATERIAL));
+
+        let mesh = road_mesh(
+            &root,
+            &MeshOptions {
+                sidewalk_height: Some(0.15),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let curb = mesh
+            .groups
+            .iter()
+            .find(|g| g.material == CURB_MATERIAL)
+            .unwrap();
+        assert_eq!(curb.triangles.len(), 2);
+        // the curb faces the driving lane, i.e. +y
+        for &v in curb.triangles[0].iter() {
+            assert!(mesh.normals[v as usize][1] > 0.99);
+        }
+        let sidewalk = mesh
+            .groups
+            .iter()
+            .find(|g| g.material == "Sidewalk")
+            .unwrap();
+        for &v in sidewalk.triangles.iter().flat_map(|t| t.iter()) {
+            assert_approx_eq!(mesh.positions[v as usize][2], 0.15);
+        }
+    }
+
+    #[test]
+    fn test_writers() {
+        let root = straight_highway(&HighwayParams {
+            lanes: 1,
+            length: 10.0,
+            ..Default::default()
+        });
+        let mesh = road_mesh(&root, &Default::default()).unwrap();
+
+        let mut obj = Vec::new();
+        mesh.write_obj(&mut obj, Some("road.mtl")).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert!(obj.contains("mtllib road.mtl"));
+        assert!(obj.contains("g road_1_s0_lane_1\nusemtl Driving"));
+        assert!(obj.contains("g road_1_s0_lane_-1\nusemtl Driving"));
+        assert_eq!(obj.lines().filter(|l| l.starts_with("v ")).count(), 8);
+        assert_eq!(obj.lines().filter(|l| l.starts_with("f ")).count(), 4);
+
+        // the lanes share their material
+        let mut mtl = Vec::new();
+        mesh.write_mtl(&mut mtl).unwrap();
+        let mtl = String::from_utf8(mtl).unwrap();
+        assert_eq!(mtl.matches("newmtl").count(), 1);
+
+        let mut glb = Vec::new();
+        mesh.write_glb(&mut glb).unwrap();
+        assert_eq!(&glb[0..4], b"glTF");
+        let u32_at = |i: usize| {
+            u32::from(glb[i])
+                | u32::from(glb[i + 1]) << 8
+                | u32::from(glb[i + 2]) << 16
+                | u32::from(glb[i + 3]) << 24
+        };
+        assert_eq!(u32_at(8) as usize, glb.len());
+        let json_length = u32_at(12) as usize;
+        assert_eq!(&glb[16..20], b"JSON");
+        let document: serde_json::Value =
+            serde_json::from_slice(&glb[20..20 + json_length]).unwrap();
+        assert_eq!(document["accessors"].as_array().unwrap().len(), 5);
+        assert_eq!(document["accessors"][3]["count"], 6);
+        assert_eq!(document["nodes"][1]["name"], "road_1_s0_lane_-1");
+        assert_eq!(document["materials"].as_array().unwrap().len(), 1);
+        let bin_length = u32_at(20 + json_length) as usize;
+        assert_eq!(document["buffers"][0]["byteLength"], bin_length);
+        assert_eq!(&glb[24 + json_length..28 + json_length], b"BIN\0");
+    }
+}
+
+#[cfg(test)]
+mod lane_graph {
+    use crate::generators::*;
+    use crate::lane_graph::*;
+    use crate::opendrive::*;
+    use crate::types::RoadId;
+
+    #[test]
+    fn test_highway() {
+        let root = straight_highway(&HighwayParams {
+            lanes: 2,
+            ..Default::default()
+        });
+        let graph = LaneGraph::new(&root);
+        assert_eq!(graph.lanes().count(), 4);
+        for lane in graph.lanes() {
+            assert!(graph.successors(lane).is_empty());
+            assert!(graph.predecessors(lane).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_junction() {
+        let root = t_junction(&Default::default());
+        let graph = LaneGraph::new(&root);
+        // traffic enters the junction on the left lanes of the arms
+        let incoming = LaneKey {
+            road: 0,
+            section: 0,
+            lane: 1,
+        };
+        let successors = graph.successors(&incoming);
+        assert_eq!(successors.len(), 2);
+        for next in successors {
+            assert!(root.roads[next.road].junction.is_some());
+            assert_eq!(next.lane, -1);
+            assert_eq!(graph.predecessors(next), &[incoming]);
+            // and leaves it on the right lane of another arm
+            let outgoing = graph.successors(next);
+            assert_eq!(outgoing.len(), 1);
+            assert_ne!(outgoing[0].road, 0);
+            assert_eq!(outgoing[0].lane, -1);
+        }
+        let outgoing = LaneKey {
+            lane: -1,
+            ..incoming
+        };
+        assert!(graph.successors(&outgoing).is_empty());
+        assert_eq!(graph.predecessors(&outgoing).len(), 2);
+    }
+
+    #[test]
+    fn test_driving_direction() {
+        let mut root = straight_highway(&HighwayParams {
+            lanes: 1,
+            ..Default::default()
+        });
+        let id = root.roads[0].id.clone();
+        root.split_road(&id, 50.0, RoadId::from(2)).unwrap();
+        let key = |road, lane| LaneKey {
+            road,
+            section: 0,
+            lane,
+        };
+        let graph = LaneGraph::new(&root);
+        assert_eq!(graph.successors(&key(0, -1)), &[key(1, -1)]);
+        assert_eq!(graph.successors(&key(1, 1)), &[key(0, 1)]);
+
+        // left hand traffic drives the right lanes against s
+        for road in root.roads.iter_mut() {
+            road.rule = Some(TrafficRule::LeftHand);
+        }
+        let graph = LaneGraph::new(&root);
+        assert_eq!(graph.successors(&key(1, -1)), &[key(0, -1)]);
+        assert_eq!(graph.successors(&key(0, 1)), &[key(1, 1)]);
+
+        // and reversed lanes against the convention of their side
+        for road in root.roads.iter_mut() {
+            let section = &mut road.lanes.as_mut().unwrap().lane_sections[0];
+            for lane in section.right.as_mut().unwrap().lane.iter_mut() {
+                lane.direction = Some(LaneDirection::Reversed);
+            }
+        }
+        let graph = LaneGraph::new(&root);
+        assert_eq!(graph.successors(&key(0, -1)), &[key(1, -1)]);
+        assert_eq!(graph.successors(&key(0, 1)), &[key(1, 1)]);
+        let road = &root.roads[0];
+        let section = &road.lanes.as_ref().unwrap().lane_sections[0];
+        assert!(is_forward(road, section.lane(-1).unwrap()));
+        assert!(is_forward(road, section.lane(1).unwrap()));
+    }
+}
+
+#[cfg(test)]
+mod lanelet2 {
+    use crate::generators::*;
+    use crate::lanelet2::*;
+    use crate::opendrive::*;
+    use crate::types::{JunctionId, SignalId};
+    use std::collections::HashMap;
+
+    type Ways = HashMap<String, Vec<String>>;
+
+    /// Bounds of the lanelets and node lists of the ways of an OSM document
+    fn parse(osm: &str) -> (Vec<(String, String)>, Ways) {
+        let attribute = |line: &str, name: &str| {
+            let start = line.find(&format!("{}=\"", name)).unwrap() + name.len() + 2;
+            line[start..].split('"').next().unwrap().to_string()
+        };
+        let mut ways = Ways::new();
+        let mut lanelets = Vec::new();
+        let mut way = None;
+        let mut bounds = (String::new(), String::new());
+        for line in osm.lines().map(str::trim) {
+            if line.starts_with("<way") {
+                way = Some(attribute(line, "id"));
+            } else if line.starts_with("<nd") {
+                ways.entry(way.clone().unwrap())
+                    .or_default()
+                    .push(attribute(line, "ref"));
+            } else if line.starts_with("<member") && line.contains("role=\"left\"") {
+                bounds.0 = attribute(line, "ref");
+            } else if line.starts_with("<member") && line.contains("role=\"right\"") {
+                bounds.1 = attribute(line, "ref");
+            } else if line == r#"<tag k="type" v="lanelet"/>"# {
+                lanelets.push(bounds.clone());
+            }
+        }
+        (lanelets, ways)
+    }
+
+    #[test]
+    fn test_highway() {
+        let mut root = straight_highway(&HighwayParams {
+            lanes: 2,
+            length: 100.0,
+            ..Default::default()
+        });
+        root.roads[0].signals = Some(Signals {
+            signals: vec![Signal {
+                s: 90.0,
+                t: -8.0,
+                id: SignalId::from(7),
+                name: None,
+                dynamic: true,
+                orientation: Orientation::Positive,
+                z_offset: 3.0,
+                country: None,
+                signal_type: "1000001".to_string(),
+                subtype: "-1".to_string(),
+                value: None,
+                unit: None,
+                height: None,
+                width: None,
+                text: None,
+                h_offset: 0.0,
+                pitch: 0.0,
+                roll: 0.0,
+            }],
+        });
+        let osm = to_lanelet2(&root, &Default::default()).unwrap();
+        assert!(osm.starts_with("<?xml"));
+        assert!(osm.trim_end().ends_with("</osm>"));
+
+        let (lanelets, ways) = parse(&osm);
+        assert_eq!(lanelets.len(), 4);
+        assert_eq!(osm.matches(r#"<tag k="subtype" v="highway"/>"#).count(), 4);
+        // the lanelets, the virtual sign and the regulatory element
+        assert_eq!(osm.matches(r#"<tag k="speed_limit" v="130"/>"#).count(), 6);
+        assert_eq!(
+            osm.matches(r#"<tag k="subtype" v="traffic_light"/>"#)
+                .count(),
+            1
+        );
+        // only the forward lanes refer to the traffic light
+        assert_eq!(osm.matches(r#"role="regulatory_element""#).count(), 4 + 2);
+        // the center line, once per direction, and the outer borders are
+        // solid, the borders between the lanes are broken
+        assert_eq!(osm.matches(r#"<tag k="subtype" v="solid"/>"#).count(), 4);
+        assert_eq!(osm.matches(r#"<tag k="subtype" v="dashed"/>"#).count(), 2);
+
+        // bounds of a lanelet start at the same side of the road and neighbours
+        // share their bounds
+        for (left, right) in lanelets.iter() {
+            assert_eq!(ways[left].len(), 2);
+            assert_eq!(ways[right].len(), 2);
+        }
+        let shared = lanelets
+            .iter()
+            .filter(|(left, _)| lanelets.iter().any(|(_, right)| right == left))
+            .count();
+        assert_eq!(shared, 2);
+
+        // without a geoReference the inertial origin is placed at lat/lon 0
+        assert!(osm.contains(r#"lat="0.0000000000" lon="0.0000000000""#));
+    }
+
+    #[test]
+    fn test_junction() {
+        let root = x_junction(&Default::default());
+        let osm = to_lanelet2(&root, &Default::default()).unwrap();
+        let (lanelets, ways) = parse(&osm);
+        // two lanes per arm and one per connecting road
+        assert_eq!(lanelets.len(), 4 * 2 + 4 * 3);
+
+        // every lanelet is connected to the lanelets linked in the lane graph
+        let starts: Vec<(&String, &String)> = lanelets
+            .iter()
+            .map(|(l, r)| (&ways[l][0], &ways[r][0]))
+            .collect();
+        let connected = lanelets
+            .iter()
+            .filter(|(l, r)| {
+                let end = (ways[l].last().unwrap(), ways[r].last().unwrap());
+                starts.contains(&end)
+            })
+            .count();
+        // every incoming lane and every connecting road has a successor
+        assert_eq!(connected, 4 + 12);
+    }
+
+    #[test]
+    fn test_invalid_geo_reference() {
+        let mut root = straight_highway(&Default::default());
+        root.header.geo_reference = Some(GeoReference {
+            projection: "+proj=unknown".to_string(),
+        });
+        assert!(to_lanelet2(&root, &Default::default()).is_err());
+    }
+
+    #[test]
+    fn test_import_curve() {
+        let original = curve(&CurveParams {
+            lanes: 2,
+            ..Default::default()
+        });
+        let osm = to_lanelet2(
+            &original,
+            &Lanelet2Options {
+                origin: (48.0, 11.0),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        // importing with the same origin restores the inertial frame
+        let options = Lanelet2ImportOptions {
+            origin: Some((48.0, 11.0)),
+            ..Default::default()
+        };
+        let root = from_lanelet2(osm.as_bytes(), &options).unwrap();
+        assert_eq!(root.roads.len(), 1);
+        assert!(root.junctions.is_empty());
+        assert!(root.header.geo_reference.is_some());
+
+        let road = &root.roads[0];
+        let length = original.roads[0].length.get();
+        assert!((road.length.get() - length).abs() < 0.01 * length);
+        assert_eq!(road.road_types[0].road_type, RoadTypeKind::Rural);
+        assert_approx_eq!(road.speed_at(0.0).unwrap(), 80.0 / 3.6, 1e-9);
+
+        let section = &road.lanes.as_ref().unwrap().lane_sections[0];
+        assert_eq!(section.left_lanes().count(), 2);
+        assert_eq!(section.right_lanes().count(), 2);
+        assert_eq!(
+            section.center.lane[0].road_marks[0].road_mark_type,
+            RoadMarkType::Solid
+        );
+        assert_eq!(
+            section.lane(-1).unwrap().road_marks[0].road_mark_type,
+            RoadMarkType::Broken
+        );
+        assert_eq!(
+            section.lane(2).unwrap().road_marks[0].road_mark_type,
+            RoadMarkType::Solid
+        );
+        let mut s = 0.0;
+        while s < road.length.get() {
+            for lane in section.left_lanes().chain(section.right_lanes()) {
+                assert_approx_eq!(lane.width_at(s), 3.5, 0.1);
+            }
+            s += 5.0;
+        }
+
+        // the imported reference line follows the original one, possibly in
+        // the opposite direction
+        let ends = |road: &Road| {
+            let (x0, y0) = road.position_at(0.0, 0.0);
+            let (x1, y1) = road.position_at(road.length.get(), 0.0);
+            ((x0, y0), (x1, y1))
+        };
+        let ((a, b), (c, d)) = (ends(road), ends(&original.roads[0]));
+        let near = |p: (f64, f64), q: (f64, f64)| (p.0 - q.0).hypot(p.1 - q.1) < 0.05;
+        assert!((near(a, c) && near(b, d)) || (near(a, d) && near(b, c)));
+    }
+
+    #[test]
+    fn test_import_junction() {
+        let original = x_junction(&Default::default());
+        let osm = to_lanelet2(&original, &Default::default()).unwrap();
+        let root = from_lanelet2(osm.as_bytes(), &Default::default()).unwrap();
+        assert_eq!(root.roads.len(), 4 + 12);
+        assert_eq!(root.junctions.len(), 1);
+        assert_eq!(root.junctions[0].connections.len(), 12);
+        assert_eq!(
+            root.roads
+                .iter()
+                .filter(|r| r.junction == Some(JunctionId::from(1)))
+                .count(),
+            12
+        );
+
+        let graph = crate::lane_graph::LaneGraph::new(&root);
+        let arm = root
+            .roads
+            .iter()
+            .position(|r| r.junction.is_none())
+            .unwrap();
+        let section = &root.roads[arm].lanes.as_ref().unwrap().lane_sections[0];
+        let entering = section
+            .left_lanes()
+            .chain(section.right_lanes())
+            .map(|lane| crate::lane_graph::LaneKey {
+                road: arm,
+                section: 0,
+                lane: lane.id,
+            })
+            .find(|lane| !graph.successors(lane).is_empty())
+            .unwrap();
+        assert_eq!(graph.successors(&entering).len(), 3);
+        for next in graph.successors(&entering) {
+            assert_eq!(root.roads[next.road].junction, Some(JunctionId::from(1)));
+            assert_eq!(graph.successors(next).len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_import_reversed_bounds() {
+        let osm = r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" lat="0" lon="0"/>
+  <node id="2" lat="0" lon="0.0005"/>
+  <node id="3" lat="0.00003" lon="0"/>
+  <node id="4" lat="0.00003" lon="0.0005"/>
+  <way id="10">
+    <nd ref="3"/>
+    <nd ref="4"/>
+    <tag k="type" v="line_thin"/>
+    <tag k="subtype" v="solid_dashed"/>
+  </way>
+  <way id="11">
+    <nd ref="2"/>
+    <nd ref="1"/>
+    <tag k="type" v="curbstone"/>
+  </way>
+  <relation id="20">
+    <member type="way" ref="11" role="right"/>
+    <member type="way" ref="10" role="left"/>
+    <tag k="type" v="lanelet"/>
+    <tag k="subtype" v="road"/>
+    <tag k="speed_limit" v="50 km/h"/>
+  </relation>
+</osm>"#;
+        let root = from_lanelet2(osm.as_bytes(), &Default::default()).unwrap();
+        assert_eq!(root.roads.len(), 1);
+        let road = &root.roads[0];
+        assert_approx_eq!(road.length.get(), 55.66, 0.01);
+        let (_, _, hdg) = road.plan_view.pose_at(0.0);
+        assert_approx_eq!(hdg, 0.0, 1e-6);
+        assert_approx_eq!(road.speed_at(0.0).unwrap(), 50.0 / 3.6, 1e-9);
+
+        let section = &road.lanes.as_ref().unwrap().lane_sections[0];
+        let lane = section.lane(-1).unwrap();
+        assert_approx_eq!(lane.width_at(0.0), 3.317, 0.01);
+        assert_eq!(lane.road_marks[0].road_mark_type, RoadMarkType::Curb);
+        assert_eq!(
+            section.center.lane[0].road_marks[0].road_mark_type,
+            RoadMarkType::SolidBroken
+        );
+
+        let missing = osm.replace(r#"<nd ref="1"/>"#, r#"<nd ref="5"/>"#);
+        assert!(from_lanelet2(missing.as_bytes(), &Default::default()).is_err());
+    }
+}
+
+#[cfg(test)]
+mod fitting {
+    use crate::builder::*;
+    use crate::fitting::*;
+    use crate::opendrive::*;
+
+    /// Points every `step` along the reference line of `road`
+    fn sample(road: &Road, step: f64) -> Vec<(f64, f64)> {
+        let length = road.length.get();
+        let n = (length / step).ceil() as usize;
+        (0..=n)
+            .map(|i| {
+                let (x, y, _) = road.plan_view.pose_at(length * i as f64 / n as f64);
+                (x, y)
+            })
+            .collect()
+    }
+
+    /// Maximum distance of the points to the densely sampled plan view
+    fn max_distance(plan_view: &PlanView, points: &[(f64, f64)]) -> f64 {
+        let samples: Vec<(f64, f64)> = plan_view
+            .sample_s(1e-4)
+            .unwrap()
+            .into_iter()
+            .map(|s| {
+                let (x, y, _) = plan_view.pose_at(s);
+                (x, y)
+            })
+            .collect();
+        points
+            .iter()
+            .map(|p| {
+                samples
+                    .windows(2)
+                    .map(|w| {
+                        let (a, b) = (w[0], w[1]);
+                        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+                        let t = ((p.0 - a.0) * dx + (p.1 - a.1) * dy) / (dx * dx + dy * dy);
+                        let t = t.clamp(0.0, 1.0);
+                        (p.0 - a.0 - t * dx).hypot(p.1 - a.1 - t * dy)
+                    })
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .fold(0.0, f64::max)
+    }
+
+    fn assert_continuous(plan_view: &PlanView, continuity: Continuity) {
+        for (a, b) in plan_view
+            .geometries
+            .iter()
+            .zip(plan_view.geometries.iter().skip(1))
+        {
+            let (x, y, hdg) = a.pose_at(a.length.get());
+            assert_approx_eq!(a.s.get() + a.length.get(), b.s.get(), 1e-9);
+            assert_approx_eq!(x, b.x.get(), 1e-9);
+            assert_approx_eq!(y, b.y.get(), 1e-9);
+            assert_approx_eq!((hdg - b.hdg.radians).sin(), 0.0, 1e-9);
+            if continuity == Continuity::G2 {
+                assert_approx_eq!(a.curvature_at(a.length.get()), b.curvature_at(0.0), 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fit_line_and_arc() {
+        let line = RoadBuilder::new().start(3.0, -1.0, 0.4).line(120.0).build();
+        let plan_view = fit_plan_view(&sample(&line, 1.0), &Default::default());
+        assert_eq!(plan_view.geometries.len(), 1);
+        assert_eq!(plan_view.geometries[0].element, GeometryElement::Line);
+        assert_approx_eq!(plan_view.sum_length().get(), 120.0, 1e-6);
+
+        let arc = RoadBuilder::new().arc(80.0, -0.02).build();
+        let plan_view = fit_plan_view(&sample(&arc, 1.0), &Default::default());
+        assert_eq!(plan_view.geometries.len(), 1);
+        match plan_view.geometries[0].element {
+            GeometryElement::Arc { curvature } => assert_approx_eq!(curvature, -0.02, 1e-6),
+            ref other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_fit_clothoid_road() {
+        let road = RoadBuilder::new()
+            .start(10.0, 20.0, 1.0)
+            .line(50.0)
+            .spiral(30.0, 0.0, 0.02)
+            .arc(40.0, 0.02)
+            .spiral(30.0, 0.02, -0.01)
+            .arc(30.0, -0.01)
+            .build();
+        let points = sample(&road, 1.0);
+        for &continuity in &[Continuity::G1, Continuity::G2] {
+            let options = FitOptions {
+                tolerance: 0.02,
+                continuity,
+                ..Default::default()
+            };
+            let plan_view = fit_plan_view(&points, &options);
+            assert!(plan_view.geometries.len() <= 10, "{:#?}", plan_view);
+            assert!(max_distance(&plan_view, &points) <= 0.02);
+            assert_continuous(&plan_view, continuity);
+            let (x, y, _) = plan_view.geometries[0].pose_at(0.0);
+            assert_eq!((x, y), points[0]);
+        }
+    }
+
+    #[test]
+    fn test_fit_without_param_poly3() {
+        // a sine wave that is not composed of the supported elements
+        let points: Vec<(f64, f64)> = (0..=200)
+            .map(|i| {
+                let x = i as f64 * 0.5;
+                (x, 5.0 * (x / 15.0).sin())
+            })
+            .collect();
+        for &param_poly3 in &[true, false] {
+            let options = FitOptions {
+                tolerance: 0.05,
+                param_poly3,
+                ..Default::default()
+            };
+            let plan_view = fit_plan_view(&points, &options);
+            assert!(max_distance(&plan_view, &points) <= 0.05);
+            assert_continuous(&plan_view, Continuity::G1);
+            let has_param_poly3 = plan_view
+                .geometries
+                .iter()
+                .any(|g| matches!(g.element, GeometryElement::ParamPoly3 { .. }));
+            assert!(param_poly3 || !has_param_poly3);
+        }
+    }
+
+    #[test]
+    fn test_param_poly3_evaluation() {
+        let g = Geometry {
+            s: crate::types::Length::new(0.0),
+            x: crate::types::Length::new(1.0),
+            y: crate::types::Length::new(2.0),
+            hdg: crate::types::Angle::radians(0.5),
+            length: crate::types::Length::new(10.0),
+            element: GeometryElement::ParamPoly3 {
+                a_u: 0.0,
+                b_u: 1.0,
+                c_u: 0.0,
+                d_u: 0.0,
+                a_v: 0.0,
+                b_v: 0.0,
+                c_v: 0.05,
+                d_v: 0.0,
+                p_range: PRange::ArcLength,
+            },
+        };
+        let (x, y, hdg) = g.pose_at(10.0);
+        let (sin, cos) = 0.5f64.sin_cos();
+        assert_approx_eq!(x, 1.0 + 10.0 * cos - 5.0 * sin, 1e-9);
+        assert_approx_eq!(y, 2.0 + 10.0 * sin + 5.0 * cos, 1e-9);
+        assert_approx_eq!(hdg, 0.5 + 1.0f64.atan(), 1e-9);
+        assert_approx_eq!(g.end_hdg().radians, hdg, 1e-9);
+        assert_approx_eq!(g.curvature_at(0.0), 0.1, 1e-9);
+
+        use lyon_geom::Segment;
+        let end = g.as_segment().to();
+        assert_approx_eq!(end.x, x, 1e-9);
+        assert_approx_eq!(end.y, y, 1e-9);
+    }
+}
+
+#[cfg(test)]
+mod network {
+    use crate::builder::*;
+    use crate::errors::ValidationError;
+    use crate::generators::*;
+    use crate::types::RoadId;
+    use crate::Localization;
+
+    #[test]
+    fn test_bounding_box() {
+        let root = straight_highway(&HighwayParams {
+            length: 100.0,
+            lanes: 2,
+            lane_width: 3.5,
+            ..Default::default()
+        });
+        let b = root.bounding_box(0.1).unwrap().unwrap();
+        assert_approx_eq!(b.west, 0.0);
+        assert_approx_eq!(b.east, 100.0);
+        assert_approx_eq!(b.south, -7.0);
+        assert_approx_eq!(b.north, 7.0);
+    }
+
+    #[test]
+    fn test_lane_position() {
+        let root = straight_highway(&HighwayParams {
+            length: 100.0,
+            lanes: 2,
+            lane_width: 3.5,
+            ..Default::default()
+        });
+        let position = root.lane_position_at(30.0, -5.0).unwrap();
+        assert_eq!((position.road, position.section, position.lane), (0, 0, -2));
+        assert_approx_eq!(position.s, 30.0);
+        assert_approx_eq!(position.t, -5.0);
+        assert_eq!(root.lane_position_at(30.0, 1.0).unwrap().lane, 1);
+        assert!(root.lane_position_at(30.0, 8.0).is_none());
+        assert!(root.lane_position_at(-1.0, 1.0).is_none());
+
+        let road = RoadBuilder::new().arc(50.0, 0.02).build();
+        let (x, y) = road.position_at(20.0, 1.5);
+        let (s, t) = road.project(x, y);
+        assert_approx_eq!(s, 20.0, 1e-9);
+        assert_approx_eq!(t, 1.5, 1e-9);
+    }
+
+    #[test]
+    fn test_localize() {
+        let root = straight_highway(&HighwayParams {
+            length: 100.0,
+            lanes: 2,
+            lane_width: 3.5,
+            ..Default::default()
+        });
+        let right = root.localize(30.0, -5.0, 0.1).unwrap();
+        assert_eq!(right.road_id, RoadId::from(1));
+        assert_eq!(right.position.lane, -2);
+        assert_approx_eq!(right.heading, 0.1);
+        assert_approx_eq!(right.offset, 0.25);
+        assert_approx_eq!(right.left, 1.5);
+        assert_approx_eq!(right.right, 2.0);
+
+        // left lanes are driven against the direction of the reference line
+        let left = root.localize(30.0, 1.0, std::f64::consts::PI).unwrap();
+        assert_eq!(left.position.lane, 1);
+        assert_approx_eq!(left.heading, 0.0);
+        assert_approx_eq!(left.offset, 0.75);
+        assert_approx_eq!(left.left, 1.0);
+        assert_approx_eq!(left.right, 2.5);
+        assert!(root.localize(30.0, 8.0, 0.0).is_none());
+
+        // in the junction the heading selects the straight or turning lane
+        let root = t_junction(&Default::default());
+        let junction = |l: &Localization| root.roads[l.position.road].junction.is_some();
+        let straight = root.localize(3.0, -1.75, 0.0).unwrap();
+        let turning = root.localize(3.0, -1.75, std::f64::consts::PI).unwrap();
+        assert!(junction(&straight) && junction(&turning));
+        assert_ne!(straight.road_id, turning.road_id);
+        assert_approx_eq!(straight.heading, 0.0);
+        assert!(turning.heading.abs() < std::f64::consts::FRAC_PI_2);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut root = t_junction(&Default::default());
+        assert!(root.validate().is_empty());
+
+        let id = root.roads[0].id.clone();
+        root.roads
+            .push(RoadBuilder::new().id(id).line(10.0).build());
+        root.junctions[0].connections[0].connecting_road = Some(RoadId::from(99));
+        let findings = root.validate();
+        assert_eq!(findings.len(), 2);
+        assert!(matches!(findings[0], ValidationError::DuplicateRoadId(_)));
+        match findings[1] {
+            ValidationError::Junction(_, ref err) => {
+                assert!(
+                    matches!(**err, ValidationError::MissingRoad(ref id) if *id == RoadId::from(99))
+                )
+            }
+            ref err => panic!("unexpected finding {}", err),
+        }
+    }
+
+    #[test]
+    fn test_pose() {
+        let road = RoadBuilder::new()
+            .line(20.0)
+            .elevation(0.0, 1.0, 0.1, 0.0, 0.0)
+            .superelevation(0.0, 0.05, 0.0, 0.0, 0.0)
+            .build();
+        let pose = road.pose_at(10.0, 0.0, 0.0);
+        assert_approx_eq!(pose.x, 10.0);
+        assert_approx_eq!(pose.z, 2.0);
+        assert_approx_eq!(pose.heading, 0.0);
+        assert_approx_eq!(pose.pitch, -0.1f64.atan());
+        assert_approx_eq!(pose.roll, 0.05);
+
+        // h is measured along the surface normal, which leans backwards
+        // uphill and to the right on a road falling to the right
+        let above = road.pose_at(10.0, 0.0, 1.0);
+        let normal = pose.to_world(0.0, 0.0, 1.0);
+        assert_approx_eq!(above.x, normal.0);
+        assert_approx_eq!(above.y, normal.1);
+        assert_approx_eq!(above.z, normal.2);
+        assert!(above.x < 10.0 && above.y < 0.0);
+    }
+
+    #[test]
+    fn test_track_position() {
+        let road = RoadBuilder::new()
+            .line(10.0)
+            .arc(30.0, 0.02)
+            .elevation(0.0, 0.0, 0.05, 0.001, 0.0)
+            .superelevation(0.0, -0.04, 0.0, 0.0, 0.0)
+            .build();
+        for &(s, t, h) in [(5.0, 2.0, 0.5), (25.0, -3.5, 1.5), (38.0, 1.0, 0.0)].iter() {
+            let pose = road.pose_at(s, t, h);
+            let (s1, t1, h1) = road.track_position(pose.x, pose.y, pose.z);
+            assert_approx_eq!(s1, s, 1e-6);
+            assert_approx_eq!(t1, t, 1e-6);
+            assert_approx_eq!(h1, h, 1e-6);
+        }
+    }
+}
+
+#[cfg(test)]
+mod diff {
+    use crate::builder::*;
+    use crate::diff::*;
+    use crate::generators::*;
+    use crate::opendrive::*;
+    use crate::types::{JunctionId, RoadId};
+
+    fn highway(lane_width: f64) -> Root {
+        straight_highway(&HighwayParams {
+            length: 100.0,
+            lanes: 2,
+            lane_width,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_equal() {
+        let root = t_junction(&Default::default());
+        assert!(diff(&root, &root, &Default::default()).unwrap().is_empty());
+        // within the tolerance
+        let diff = diff(&highway(3.5), &highway(3.5 + 1e-9), &Default::default()).unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "");
+    }
+
+    #[test]
+    fn test_lane_width() {
+        let diff = diff(&highway(3.5), &highway(3.75), &Default::default()).unwrap();
+        assert!(diff.header.is_empty());
+        assert_eq!(diff.roads.len(), 1);
+        let changes = match diff.roads[0] {
+            Change::Changed(ref id, ref changes) if *id == RoadId::from(1) => changes,
+            ref change => panic!("unexpected change {:?}", change),
+        };
+        // two lanes on either side
+        assert_eq!(changes.fields.len(), 4);
+        let field = &changes.fields[0];
+        assert_eq!(field.path, "lanes.laneSection[0].left.lane[0].width[0].a");
+        assert_eq!(field.old.as_ref().unwrap().as_f64(), Some(3.5));
+        assert_eq!(field.new.as_ref().unwrap().as_f64(), Some(3.75));
+        assert_approx_eq!(changes.deviation.reference_line, 0.0, 1e-9);
+        assert_approx_eq!(changes.deviation.lane_boundaries, 0.5, 1e-9);
+    }
+
+    #[test]
+    fn test_reference_line() {
+        let road = |y: f64| RoadBuilder::new().start(0.0, y, 0.0).line(50.0).build();
+        let mut a = highway(3.5);
+        let mut b = highway(3.5);
+        a.roads = vec![road(0.0)];
+        b.roads = vec![road(0.2)];
+        let diff = diff(&a, &b, &Default::default()).unwrap();
+        match diff.roads[0] {
+            Change::Changed(_, ref changes) => {
+                assert_eq!(changes.fields.len(), 1);
+                assert_eq!(changes.fields[0].path, "planView.geometry[0].y");
+                assert_approx_eq!(changes.deviation.reference_line, 0.2, 1e-9);
+            }
+            ref change => panic!("unexpected change {:?}", change),
+        }
+    }
+
+    #[test]
+    fn test_ids_and_angles() {
+        let mut a = t_junction(&Default::default());
+        let mut b = t_junction(&Default::default());
+        a.junctions[0].connections[0].incoming_road = RoadId::from("7");
+        b.junctions[0].connections[0].incoming_road = RoadId::from("007");
+        // angles are serialized as strings but still compared as numbers
+        b.roads[0].plan_view.geometries[0].hdg.radians += 1e-9;
+
+        let diff = diff(&a, &b, &Default::default()).unwrap();
+        assert!(diff.roads.is_empty());
+        match diff.junctions[..] {
+            [Change::Changed(_, ref fields)] => {
+                assert_eq!(fields.len(), 1);
+                assert_eq!(fields[0].path, "connection[0].incomingRoad");
+            }
+            ref changes => panic!("unexpected changes {:?}", changes),
+        }
+    }
+
+    #[test]
+    fn test_added_and_removed() {
+        let a = t_junction(&Default::default());
+        let mut b = t_junction(&Default::default());
+        let removed = b.roads.pop().unwrap().id;
+        b.roads.push(RoadBuilder::new().id(200).line(10.0).build());
+        b.junctions[0].connections[0].contact_point = ContactPoint::End;
+        b.junctions.push(Junction {
+            name: "new".to_string(),
+            id: JunctionId::from(99),
+            junction_type: JunctionType::Default,
+            connections: vec![],
+        });
+
+        let diff = diff(&a, &b, &Default::default()).unwrap();
+        assert_eq!(
+            diff.roads,
+            vec![Change::Removed(removed), Change::Added(RoadId::from(200))]
+        );
+        assert_eq!(diff.junctions.len(), 2);
+        match diff.junctions[0] {
+            Change::Changed(_, ref fields) => {
+                assert_eq!(fields.len(), 1);
+                assert_eq!(fields[0].path, "connection[0].contactPoint");
+            }
+            ref change => panic!("unexpected change {:?}", change),
+        }
+        assert_eq!(diff.junctions[1], Change::Added(JunctionId::from(99)));
+        assert!(diff.to_string().contains("road 200 added\n"));
+    }
+
+    #[test]
+    fn test_missing_date() {
+        let parse = |date: &str| {
+            let xml = format!(
+                r#"<OpenDRIVE><header revMajor="1" revMinor="4" name="a" {}/></OpenDRIVE>"#,
+                date
+            );
+            crate::from_reader(xml.as_bytes()).unwrap()
+        };
+        let (a, b) = (parse(""), parse(""));
+        assert!(!a.header.has_date());
+        assert!(diff(&a, &b, &Default::default()).unwrap().is_empty());
+        let dated = parse(r#"date="2019-03-01T10:00:00""#);
+        assert!(dated.header.has_date());
+        assert!(diff(&a, &dated, &Default::default()).unwrap().is_empty());
+        assert!(diff(&dated, &a, &Default::default()).unwrap().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod revision {
+    use crate::lane_graph::*;
+    use crate::opendrive::*;
+    use crate::revision::*;
+    use crate::types::RoadId;
+
+    const ODR_1_7: &str = r##"
+    <OpenDRIVE>
+        <header revMajor="1" revMinor="7" name="rev" date="2021-08-01T12:30:00" vendor="test">
+        </header>
+        <road name="" length="10.0" id="a1" junction="-1" rule="LHT">
+            <planView>
+                <geometry s="0.0" x="0.0" y="0.0" hdg="0.0" length="10.0"><line/></geometry>
+            </planView>
+            <lanes>
+                <laneSection s="0.0">
+                    <center>
+                        <lane id="0" type="none" level="false">
+                            <roadMark sOffset="0.0" type="solid solid" weight="standard" color="standard" width="0.13" laneChange="none" height="0.0"/>
+                        </lane>
+                    </center>
+                    <right>
+                        <lane id="-1" type="driving" level="false" direction="standard" advisory="none">
+                            <width sOffset="0.0" a="3.5" b="0.0" c="0.0" d="0.0"/>
+                        </lane>
+                    </right>
+                </laneSection>
+            </lanes>
+        </road>
+        <road name="" length="10.0" id="a2" junction="-1">
+            <link>
+                <successor elementType="junction" elementId="j1" contactPoint="end"/>
+            </link>
+            <planView>
+                <geometry s="0.0" x="10.0" y="0.0" hdg="0.0" length="10.0"><line/></geometry>
+            </planView>
+            <lanes>
+                <laneSection s="0.0">
+                    <center>
+                        <lane id="0" type="none" level="false"/>
+                    </center>
+                    <right>
+                        <lane id="-1" type="driving" level="false">
+                            <width sOffset="0.0" a="3.5" b="0.0" c="0.0" d="0.0"/>
+                        </lane>
+                    </right>
+                </laneSection>
+            </lanes>
+        </road>
+        <road name="" length="10.0" id="a3" junction="-1">
+            <link>
+                <predecessor elementType="junction" elementId="j1" contactPoint="start"/>
+            </link>
+            <planView>
+                <geometry s="0.0" x="20.0" y="0.0" hdg="0.0" length="10.0"><line/></geometry>
+            </planView>
+            <lanes>
+                <laneSection s="0.0">
+                    <center>
+                        <lane id="0" type="none" level="false"/>
+                    </center>
+                    <right>
+                        <lane id="-1" type="driving" level="false">
+                            <width sOffset="0.0" a="3.5" b="0.0" c="0.0" d="0.0"/>
+                        </lane>
+                    </right>
+                </laneSection>
+            </lanes>
+        </road>
+        <junction name="j" id="j1" type="direct">
+            <connection id="0" incomingRoad="a2" linkedRoad="a3" contactPoint="start">
+                <laneLink from="-1" to="-1"/>
+            </connection>
+        </junction>
+    </OpenDRIVE>
+    "##;
+
+    #[test]
+    fn test_parse_1_7() {
+        let root = crate::from_reader(ODR_1_7.as_bytes()).unwrap();
+        assert_eq!(root.header.revision(), Revision::V1_7);
+        assert_eq!(
+            root.header.vendor.as_ref().map(String::as_str),
+            Some("test")
+        );
+        assert_eq!(root.header.max_road, 0);
+        let road = &root.roads[0];
+        assert_eq!(road.traffic_rule(), TrafficRule::LeftHand);
+        let section = &road.lanes.as_ref().unwrap().lane_sections[0];
+        let lane = section.right_lanes().next().unwrap();
+        assert_eq!(lane.direction, Some(LaneDirection::Standard));
+        assert_eq!(lane.advisory, Some(LaneAdvisory::None));
+        let center = &section.center.lane[0];
+        assert_eq!(
+            center.road_marks[0].road_mark_type,
+            RoadMarkType::SolidSolid
+        );
+        assert_eq!(root.junctions[0].junction_type, JunctionType::Direct);
+        let connection = &root.junctions[0].connections[0];
+        assert_eq!(connection.connecting_road, None);
+        assert_eq!(connection.linked_road, Some(RoadId::from("a3")));
+        assert_eq!(connection.connected_road(), Some(&RoadId::from("a3")));
+        assert!(root.validate().is_empty());
+
+        // the linked road directly follows the incoming road
+        let graph = LaneGraph::new(&root);
+        let key = |road| LaneKey {
+            road,
+            section: 0,
+            lane: -1,
+        };
+        assert_eq!(graph.successors(&key(1)), &[key(2)]);
+        assert_eq!(graph.predecessors(&key(2)), &[key(1)]);
+    }
+
+    #[test]
+    fn test_feature_report() {
+        let root = crate::from_reader(ODR_1_7.as_bytes()).unwrap();
+        let report = feature_report(&root);
+        assert_eq!(report.required_revision(), Some(Revision::V1_7));
+        assert_eq!(report.undefined().count(), 0);
+        let features: Vec<Feature> = report.usages.iter().map(|usage| usage.feature).collect();
+        assert!(features.contains(&Feature::DirectJunctions));
+        assert!(features.contains(&Feature::StringIds));
+        assert!(features.contains(&Feature::TrafficRule));
+    }
+
+    #[test]
+    fn test_strict() {
+        let declared_1_4 = ODR_1_7.replace("revMinor=\"7\"", "revMinor=\"4\"");
+        let root = crate::from_reader(declared_1_4.as_bytes()).unwrap();
+        let report = feature_report(&root);
+        assert!(report
+            .undefined()
+            .any(|usage| usage.feature == Feature::LaneDirection));
+
+        let strict = ParseOptions { strict: true };
+        assert!(crate::from_reader_with(declared_1_4.as_bytes(), &strict).is_err());
+        assert!(crate::from_reader_with(ODR_1_7.as_bytes(), &strict).is_ok());
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let s = r##"
+        <OpenDRIVE>
+            <header revMajor="1" revMinor="4" name="" date="Tue Mar 11 08:53:30 2014" maxRoad="1">
+            </header>
+            <road name="" length="20.0" id="1" junction="-1">
+                <planView>
+                    <geometry s="0.0" x="1.0" y="2.0" hdg="0.5" length="20.0">
+                        <poly3 a="0.5" b="0.1" c="0.01" d="-0.0005"/>
+                    </geometry>
+                </planView>
+            </road>
+        </OpenDRIVE>
+        "##;
+        let mut root = crate::from_reader(s.as_bytes()).unwrap();
+        let poly3 = root.roads[0].plan_view.geometries[0].pose_at(20.0);
+        let warnings = migrate(&mut root, Revision::V1_6).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(root.header.revision(), Revision::V1_6);
+        assert_eq!(root.header.max_road, 0);
+        let geometry = &root.roads[0].plan_view.geometries[0];
+        match geometry.element {
+            GeometryElement::ParamPoly3 { .. } => {}
+            ref element => panic!("unexpected {:?}", element),
+        }
+        let (x, y, hdg) = geometry.pose_at(20.0);
+        assert_approx_eq!(x, poly3.0, 1e-6);
+        assert_approx_eq!(y, poly3.1, 1e-6);
+        assert_approx_eq!(hdg, poly3.2, 1e-6);
+    }
+
+    #[test]
+    fn test_downgrade() {
+        let mut root = crate::from_reader(ODR_1_7.as_bytes()).unwrap();
+        let warnings = migrate(&mut root, Revision::V1_4).unwrap();
+        let resolution = |feature: Feature| {
+            warnings
+                .iter()
+                .find(|warning| warning.feature == feature)
+                .map(|warning| warning.resolution)
+        };
+        assert_eq!(resolution(Feature::TrafficRule), Some(Resolution::Removed));
+        assert_eq!(
+            resolution(Feature::LaneDirection),
+            Some(Resolution::Removed)
+        );
+        assert_eq!(
+            resolution(Feature::DirectJunctions),
+            Some(Resolution::Replaced("default junctions"))
+        );
+        assert_eq!(resolution(Feature::StringIds), Some(Resolution::Kept));
+
+        assert_eq!(root.header.revision(), Revision::V1_4);
+        assert_eq!(root.roads[0].rule, None);
+        assert_eq!(root.junctions[0].junction_type, JunctionType::Default);
+        // older revisions name the linked road the connecting road
+        let connection = &root.junctions[0].connections[0];
+        assert_eq!(connection.connecting_road, Some(RoadId::from("a3")));
+        assert_eq!(connection.linked_road, None);
+        let report = feature_report(&root);
+        let undefined: Vec<Feature> = report.undefined().map(|usage| usage.feature).collect();
+        assert_eq!(undefined, vec![Feature::StringIds]);
+    }
+
+    #[test]
+    fn test_downgrade_offset() {
+        let s = ODR_1_7.replace(
+            "</header>",
+            r#"<offset x="100.0" y="50.0" z="2.0" hdg="1.5707963267948966"/></header>"#,
+        );
+        let mut root = crate::from_reader(s.as_bytes()).unwrap();
+        let before = root.roads[0].position_at(10.0, 0.0);
+        migrate(&mut root, Revision::V1_5).unwrap();
+        assert_eq!(root.header.offset, None);
+        let after = root.roads[0].position_at(10.0, 0.0);
+        assert_approx_eq!(after.0, 100.0 - before.1, 1e-9);
+        assert_approx_eq!(after.1, 50.0 + before.0, 1e-9);
+        let elevation = &root.roads[0].elevation_profile.as_ref().unwrap().elevations;
+        assert_approx_eq!(elevation[0].a, 2.0);
+    }
+}
+
+#[cfg(test)]
+mod trajectory {
+    use crate::errors::TrajectoryError;
+    use crate::generators::*;
+    use crate::lane_graph::*;
+    use crate::trajectory::*;
+
+    #[test]
+    fn test_lane_change() {
+        let root = straight_highway(&HighwayParams {
+            length: 100.0,
+            lanes: 2,
+            lane_width: 3.5,
+            ..Default::default()
+        });
+        let key = |lane| LaneKey {
+            road: 0,
+            section: 0,
+            lane,
+        };
+        let options = TrajectoryOptions::default();
+        let points = trajectory(&root, &[key(-1), key(-2)], &options).unwrap();
+        let (first, last) = (points[0], *points.last().unwrap());
+        assert_eq!((first.lane, last.lane), (key(-1), key(-2)));
+        assert_approx_eq!(first.y, -1.75);
+        assert_approx_eq!(last.y, -5.25);
+        assert!(last.s > 100.0);
+        for pair in points.windows(2) {
+            assert!(pair[1].s > pair[0].s);
+            assert!(pair[1].y <= pair[0].y + 1e-9);
+        }
+        // the lane change turns right and back, limiting the speed
+        let max_curvature = points.iter().map(|p| p.curvature.abs()).fold(0.0, f64::max);
+        assert!(max_curvature > 0.0 && max_curvature < 0.03);
+        for p in points.iter() {
+            assert!(p.speed.powi(2) * p.curvature.abs() <= options.max_lateral_acceleration + 1e-9);
+        }
+
+        assert!(matches!(
+            trajectory(&root, &[key(-1), key(1)], &options),
+            Err(TrajectoryError::NotConnected(_, _))
+        ));
+        assert!(matches!(
+            trajectory(&root, &[], &options),
+            Err(TrajectoryError::EmptyRoute)
+        ));
+        for &step in [0.0, -0.5, f64::NAN].iter() {
+            let options = TrajectoryOptions {
+                step,
+                ..Default::default()
+            };
+            assert!(matches!(
+                trajectory(&root, &[key(-1)], &options),
+                Err(TrajectoryError::InvalidStep(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_turn() {
+        let root = t_junction(&Default::default());
+        let graph = LaneGraph::new(&root);
+        let start = LaneKey {
+            road: 0,
+            section: 0,
+            lane: 1,
+        };
+        // the right turn is the shorter connecting road
+        let turn = *graph
+            .successors(&start)
+            .iter()
+            .min_by(|a, b| {
+                let length = |key: &LaneKey| root.roads[key.road].length.get();
+                length(a).partial_cmp(&length(b)).unwrap()
+            })
+            .unwrap();
+        let route = [start, turn, graph.successors(&turn)[0]];
+        let options = TrajectoryOptions::default();
+        let points = trajectory(&root, &route, &options).unwrap();
+
+        let last = points.last().unwrap();
+        assert_approx_eq!(last.heading, -std::f64::consts::FRAC_PI_2, 1e-6);
+        // the lane center of the turn has a radius of 15 m + 1.75 m
+        let radius = 15.0 + 1.75;
+        let max_curvature = points.iter().map(|p| p.curvature.abs()).fold(0.0, f64::max);
+        assert_approx_eq!(max_curvature, 1.0 / radius, 1e-3);
+        let min_speed = points.iter().map(|p| p.speed).fold(f64::INFINITY, f64::min);
+        assert_approx_eq!(
+            min_speed,
+            (options.max_lateral_acceleration * radius).sqrt(),
+            0.05
+        );
+        assert_approx_eq!(points[0].speed, 50.0 / 3.6);
+    }
+}
+
+#[cfg(test)]
+mod curvature {
+    use crate::builder::*;
+    use crate::curvature::*;
+
+    #[test]
+    fn test_profile() {
+        let road = RoadBuilder::new()
+            .line(10.0)
+            .spiral(20.0, 0.0, 0.02)
+            .arc(30.0, 0.02)
+            .line(10.0)
+            .build();
+        let profile = CurvatureProfile::new(&road);
+        let functions: Vec<_> = profile.pieces.iter().map(|p| p.function).collect();
+        assert_eq!(
+            functions,
+            vec![
+                CurvatureFunction::Constant(0.0),
+                CurvatureFunction::Linear {
+                    start: 0.0,
+                    end: 0.02
+                },
+                CurvatureFunction::Constant(0.02),
+                CurvatureFunction::Constant(0.0),
+            ]
+        );
+        assert_approx_eq!(profile.curvature_at(20.0), 0.01);
+
+        let options = CurvatureOptions::default();
+        let analysis = profile.analyze(&options);
+        assert_approx_eq!(analysis.max_curvature, 0.02);
+        assert_approx_eq!(analysis.min_radius.unwrap(), 50.0);
+        // the arc ends in a line without transition
+        assert_eq!(analysis.discontinuities.len(), 1);
+        let jump = analysis.discontinuities[0];
+        assert_eq!(jump.quantity, Quantity::Curvature);
+        assert_approx_eq!(jump.s, 60.0);
+        assert_approx_eq!(jump.before, 0.02);
+        assert_approx_eq!(jump.after, 0.0);
+        let speed = (9.80665 * options.side_friction * 50.0f64).sqrt();
+        assert_approx_eq!(analysis.design_speed.unwrap(), speed);
+
+        // superelevation towards the inside of the curve raises the speed
+        let banked = RoadBuilder::new()
+            .arc(30.0, 0.02)
+            .superelevation(0.0, -0.05, 0.0, 0.0, 0.0)
+            .build();
+        let banked = CurvatureProfile::new(&banked).analyze(&options);
+        assert!(banked.design_speed.unwrap() > speed);
+
+        let straight = RoadBuilder::new().line(10.0).build();
+        let straight = CurvatureProfile::new(&straight).analyze(&options);
+        assert_eq!(straight.min_radius, None);
+        assert_eq!(straight.design_speed, None);
+    }
+
+    #[test]
+    fn test_csv() {
+        let road = RoadBuilder::new()
+            .line(10.0)
+            .arc(10.0, 0.1)
+            .elevation(0.0, 2.0, 0.0, 0.0, 0.0)
+            .build();
+        let options = CurvatureOptions {
+            step: 5.0,
+            ..Default::default()
+        };
+        let mut csv = Vec::new();
+        write_csv(Some(&road), &options, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "road,s,x,y,hdg,kappa,z");
+        // 2 samples per element and the end of the road
+        assert_eq!(lines.len(), 1 + 5);
+        let last: Vec<f64> = lines[5]
+            .split(',')
+            .skip(1)
+            .map(|value| value.parse().unwrap())
+            .collect();
+        assert_approx_eq!(last[0], 20.0);
+        assert_approx_eq!(last[3], 1.0);
+        assert_approx_eq!(last[4], 0.1);
+        assert_approx_eq!(last[5], 2.0);
+    }
+}
+
+#[cfg(test)]
+mod stream {
+    use crate::revision::ParseOptions;
+    use crate::stream::*;
+    use crate::types::{JunctionId, RoadId};
+    use crate::BoundingBox;
+
+    const ODR: &str = r##"
+    <OpenDRIVE>
+        <header revMajor="1" revMinor="6" name="stream" date="2021-08-01T12:30:00">
+            <geoReference><![CDATA[+proj=utm +zone=32 +ellps=WGS84 +datum=WGS84 +units=m +no_defs]]></geoReference>
+        </header>
+        <road name="a" length="10.0" id="1" junction="-1">
+            <planView>
+                <geometry s="0.0" x="0.0" y="0.0" hdg="0.0" length="10.0"><line/></geometry>
+            </planView>
+        </road>
+        <road name="b" length="10.0" id="2" junction="7">
+            <planView>
+                <geometry s="0.0" x="100.0" y="0.0" hdg="0.0" length="10.0"><arc curvature="0.01"/></geometry>
+            </planView>
+            <lanes>
+                <laneSection s="0.0">
+                    <center><lane id="0" type="none" level="false"/></center>
+                    <right>
+                        <lane id="-1" type="driving" level="false">
+                            <width sOffset="0.0" a="3.5" b="0.0" c="0.0" d="0.0"/>
+                        </lane>
+                    </right>
+                </laneSection>
+            </lanes>
+        </road>
+        <junction name="j" id="7">
+            <connection id="0" incomingRoad="1" connectingRoad="2" contactPoint="start"/>
+        </junction>
+    </OpenDRIVE>
+    "##;
+
+    fn records(filter: RecordFilter) -> Vec<Record> {
+        RoadIterator::with_filter(ODR.as_bytes(), filter)
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_records() {
+        let root = crate::from_reader(ODR.as_bytes()).unwrap();
+        let records = records(Default::default());
+        assert_eq!(records.len(), 4);
+        let mut roads = Vec::new();
+        let mut junctions = Vec::new();
+        for record in records {
+            match record {
+                Record::Header(header) => assert_eq!(header, root.header),
+                Record::Road(road) => roads.push(road),
+                Record::Junction(junction) => junctions.push(junction),
+            }
+        }
+        assert_eq!(roads, root.roads);
+        assert_eq!(junctions, root.junctions);
+    }
+
+    #[test]
+    fn test_filter() {
+        let road_ids = |records: Vec<Record>| -> Vec<RoadId> {
+            records
+                .into_iter()
+                .filter_map(|record| match record {
+                    Record::Road(road) => Some(road.id),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let by_id = records(RecordFilter {
+            road_ids: Some(vec![RoadId::from(2)].into_iter().collect()),
+            junction_ids: Some(Default::default()),
+            ..Default::default()
+        });
+        assert!(!by_id.iter().any(|r| matches!(r, Record::Junction(_))));
+        assert_eq!(road_ids(by_id), vec![RoadId::from(2)]);
+
+        let near_origin = records(RecordFilter {
+            bounding_box: Some(BoundingBox {
+                west: -5.0,
+                south: -5.0,
+                east: 5.0,
+                north: 5.0,
+            }),
+            ..Default::default()
+        });
+        assert!(near_origin
+            .iter()
+            .any(|r| matches!(r, Record::Junction(j) if j.id == JunctionId::from(7))));
+        assert_eq!(road_ids(near_origin), vec![RoadId::from(1)]);
+
+        // the lanes count towards the extent of a road
+        let below_road_2 = records(RecordFilter {
+            bounding_box: Some(BoundingBox {
+                west: 104.0,
+                south: -3.0,
+                east: 106.0,
+                north: -2.0,
+            }),
+            ..Default::default()
+        });
+        assert_eq!(road_ids(below_road_2), vec![RoadId::from(2)]);
+    }
+
+    #[test]
+    fn test_error() {
+        let truncated = &ODR[..ODR.find("<junction").unwrap()];
+        let results: Vec<_> = RoadIterator::new(truncated.as_bytes()).collect();
+        assert_eq!(results.len(), 4);
+        assert!(results[3].is_err());
+
+        // records without id are not dropped silently
+        let without_id = ODR.replace(r#"id="2" "#, "");
+        let results: Vec<_> = RoadIterator::new(without_id.as_bytes()).collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[2].is_err());
+    }
+
+    #[test]
+    fn test_revision() {
+        let major = ODR.replace(r#"revMajor="1""#, r#"revMajor="2""#);
+        let results: Vec<_> = RoadIterator::new(major.as_bytes()).collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+
+        // string ids are defined since OpenDRIVE 1.5
+        let string_id = ODR
+            .replace(r#"revMinor="6""#, r#"revMinor="4""#)
+            .replace(r#"id="7""#, r#"id="j7""#);
+        assert!(RoadIterator::new(string_id.as_bytes()).all(|record| record.is_ok()));
+        let strict = RecordFilter {
+            parse_options: ParseOptions { strict: true },
+            ..Default::default()
+        };
+        let results: Vec<_> = RoadIterator::with_filter(string_id.as_bytes(), strict).collect();
+        assert_eq!(results.len(), 4);
+        assert!(results[..3].iter().all(|record| record.is_ok()));
+        assert!(results[3].is_err());
+    }
+}
+
+#[cfg(test)]
+mod cache {
+    use crate::cache::*;
+    use crate::errors::CacheError;
+    use crate::BoundingBox;
+
+    const ODR: &str = r##"
+    <OpenDRIVE>
+        <header revMajor="1" revMinor="7" name="cache" date="2021-08-01T12:30:00.25+02:00">
+            <offset x="10.0" y="20.0" z="0.0" hdg="0.1"/>
+        </header>
+        <road name="a" length="100.0" id="ramp_a" junction="-1" rule="LHT">
+            <planView>
+                <geometry s="0.0" x="0.0" y="0.0" hdg="0.0" length="100.0"><line/></geometry>
+            </planView>
+            <lanes>
+                <laneSection s="0.0">
+                    <center>
+                        <lane id="0" type="none" level="false">
+                            <roadMark sOffset="0.0" type="solid solid" weight="standard" color="standard" width="0.13" laneChange="none" height="0.0"/>
+                        </lane>
+                    </center>
+                    <right>
+                        <lane id="-1" type="driving" level="no" direction="standard">
+                            <width sOffset="0.0" a="3.5" b="0.0" c="0.0" d="0.0"/>
+                        </lane>
+                    </right>
+                </laneSection>
+            </lanes>
+            <objects>
+                <object type="pole" name="lamp" id="7" s="10.0" t="-5.0" dynamic="no" orientation="+" radius="0.1" height="6.0"/>
+            </objects>
+        </road>
+        <road name="b" length="50.0" id="007" junction="j1">
+            <planView>
+                <geometry s="0.0" x="200.0" y="0.0" hdg="1.5" length="50.0"><arc curvature="0.01"/></geometry>
+            </planView>
+            <lanes>
+                <laneSection s="0.0">
+                    <center><lane id="0" type="none" level="false"/></center>
+                    <right>
+                        <lane id="-1" type="driving" level="1">
+                            <width sOffset="0.0" a="3.5" b="0.0" c="0.0" d="0.0"/>
+                        </lane>
+                    </right>
+                </laneSection>
+            </lanes>
+        </road>
+        <junction name="j" id="j1" type="direct">
+        </junction>
+    </OpenDRIVE>
+    "##;
+
+    fn network() -> Network {
+        Network::new(
+            crate::from_reader(ODR.as_bytes()).unwrap(),
+            &Default::default(),
+        )
+        .unwrap()
+    }
+
+    fn write(network: &Network, key: u64) -> Vec<u8> {
+        let mut data = Vec::new();
+        network.write(key, &mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let network = network();
+        let read = Network::read(&write(&network, 42)[..], 42).unwrap();
+        assert_eq!(read.root.header, network.root.header);
+        assert_eq!(read.root.roads, network.root.roads);
+        assert_eq!(read.root.junctions, network.root.junctions);
+        assert_eq!(read.lane_graph, network.lane_graph);
+        assert_eq!(read.spatial_index, network.spatial_index);
+        assert_eq!(read.samples, network.samples);
+        assert_eq!(read.root.roads[1].id.to_string(), "007");
+        assert_eq!(read.root.roads[1].junction, Some("j1".into()));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let network = network();
+        let data = write(&network, 42);
+        assert!(matches!(
+            Network::read(&data[..], 43),
+            Err(CacheError::Outdated)
+        ));
+
+        let mut corrupt = data.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            Network::read(&corrupt[..], 42),
+            Err(CacheError::Corrupt)
+        ));
+
+        let mut version = data.clone();
+        version[4] += 1;
+        assert!(matches!(
+            Network::read(&version[..], 42),
+            Err(CacheError::UnsupportedVersion(v)) if v == FORMAT_VERSION + 1
+        ));
+
+        assert!(matches!(
+            Network::read(ODR.as_bytes(), 42),
+            Err(CacheError::NotACache)
+        ));
+    }
+
+    #[test]
+    fn test_spatial_index() {
+        let network = network();
+        let index = &network.spatial_index;
+        assert_eq!(index.roads_at(50.0, -1.0), &[0]);
+        assert!(index.roads_at(60.0, 60.0).is_empty());
+        assert!(index.roads_at(-50.0, 0.0).is_empty());
+        let all = BoundingBox {
+            west: -1000.0,
+            south: -1000.0,
+            east: 1000.0,
+            north: 1000.0,
+        };
+        assert_eq!(index.roads_in(&all), vec![0, 1]);
+        for &(x, y) in [(50.0, -1.0), (50.0, 1.0), (199.0, 10.0), (150.0, 0.0)].iter() {
+            assert_eq!(
+                network.lane_position_at(x, y),
+                network.root.lane_position_at(x, y)
+            );
+        }
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = std::env::temp_dir().join(format!("opendrive-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("network.xodr");
+        let cache = dir.join("network.odrc");
+        let options = CacheOptions::default();
+
+        std::fs::write(&source, ODR).unwrap();
+        let network = load(&source, &cache, &options).unwrap();
+        assert_eq!(network.root.roads.len(), 2);
+        let data = std::fs::read(&cache).unwrap();
+        assert!(Network::read(&data[..], key(ODR.as_bytes(), &options)).is_ok());
+        // a second load reads the cache
+        let cached = load(&source, &cache, &options).unwrap();
+        assert_eq!(cached.root.roads, network.root.roads);
+        assert_eq!(std::fs::read(&cache).unwrap(), data);
+
+        // a changed source invalidates the cache
+        let changed = ODR.replace("name=\"b\"", "name=\"c\"");
+        std::fs::write(&source, &changed).unwrap();
+        let network = load(&source, &cache, &options).unwrap();
+        assert_eq!(network.root.roads[1].name, "c");
+        let data = std::fs::read(&cache).unwrap();
+        assert!(Network::read(&data[..], key(changed.as_bytes(), &options)).is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod parallel {
+    use crate::mesh::road_mesh;
+    use crate::svg::to_svg;
+
+    #[test]
+    fn test_map_order() {
+        let items: Vec<usize> = (0..1000).collect();
+        let squares = crate::parallel::map(&items, |i| i * i);
+        assert_eq!(squares, items.iter().map(|i| i * i).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let file = std::fs::File::open("CulDeSac.xodr").unwrap();
+        let root = crate::from_reader(file).unwrap();
+        let mesh = road_mesh(&root, &Default::default()).unwrap();
+        let svg = to_svg(&root, &Default::default()).unwrap();
+        for _ in 0..3 {
+            let again = road_mesh(&root, &Default::default()).unwrap();
+            assert_eq!(again.positions, mesh.positions);
+            assert_eq!(
+                again
+                    .groups
+                    .iter()
+                    .map(|g| &g.triangles)
+                    .collect::<Vec<_>>(),
+                mesh.groups.iter().map(|g| &g.triangles).collect::<Vec<_>>()
+            );
+            assert_eq!(to_svg(&root, &Default::default()).unwrap(), svg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod arc_length {
+    use crate::arc_length::*;
+    use crate::builder::*;
+    use crate::types::Length;
+    use crate::*;
+
+    fn geometry(element: GeometryElement) -> Geometry {
+        Geometry {
+            s: Length::new(5.0),
+            ..super::geometry(element)
+        }
+    }
+
+    fn geometries() -> Vec<Geometry> {
+        vec![
+            geometry(GeometryElement::Line),
+            geometry(GeometryElement::Arc { curvature: -0.05 }),
+            geometry(GeometryElement::Spiral {
+                curv_start: 0.01,
+                curv_end: 0.06,
+            }),
+            geometry(GeometryElement::Poly3 {
+                a: 0.5,
+                b: 0.1,
+                c: 0.02,
+                d: -0.001,
+            }),
+            geometry(GeometryElement::ParamPoly3 {
+                a_u: 0.0,
+                b_u: 20.0,
+                c_u: -1.0,
+                d_u: 0.5,
+                a_v: 0.0,
+                b_v: 0.0,
+                c_v: 3.0,
+                d_v: -1.0,
+                p_range: PRange::Normalized,
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_evaluation() {
+        for g in geometries() {
+            assert_approx_eq!(g.length(), 20.0);
+            for i in 1..20 {
+                let s = i as f64;
+                // the heading changes with the curvature, except for
+                // paramPoly3 where s is only proportional to p
+                let e = 1e-4;
+                let rate = (g.heading_at_s(s + e) - g.heading_at_s(s - e)) / (2.0 * e);
+                if !matches!(g.element, GeometryElement::ParamPoly3 { .. }) {
+                    assert_approx_eq!(rate, g.curvature_at_s(s), 1e-5);
+                }
+                // and the position with the heading
+                let (x0, y0) = g.position_at_s(s - e);
+                let (x1, y1) = g.position_at_s(s + e);
+                assert_approx_eq!((y1 - y0).atan2(x1 - x0), g.heading_at_s(s), 1e-6);
+            }
+        }
+        // the length of poly3 elements is measured along the curve
+        let g = &geometries()[3];
+        let n = 2000;
+        let mut polyline = 0.0;
+        for i in 0..n {
+            let (x0, y0) = g.position_at_s(20.0 * i as f64 / n as f64);
+            let (x1, y1) = g.position_at_s(20.0 * (i + 1) as f64 / n as f64);
+            polyline += (x1 - x0).hypot(y1 - y0);
+        }
+        assert_approx_eq!(polyline, 20.0, 1e-4);
+        assert_approx_eq!(g.end_hdg().radians, g.heading_at_s(20.0), 1e-9);
+    }
+
+    #[test]
+    fn test_split() {
+        for g in geometries() {
+            let (a, b) = g.split_at_s(8.0);
+            assert_eq!(
+                std::mem::discriminant(&a.element),
+                std::mem::discriminant(&g.element)
+            );
+            assert_eq!(
+                std::mem::discriminant(&b.element),
+                std::mem::discriminant(&g.element)
+            );
+            assert_approx_eq!(a.length(), 8.0);
+            assert_approx_eq!(b.length(), 12.0);
+            assert_approx_eq!(a.s.get(), 5.0);
+            assert_approx_eq!(b.s.get(), 13.0);
+            for i in 0..=8 {
+                let s = i as f64;
+                let (x, y) = a.position_at_s(s);
+                let (gx, gy) = g.position_at_s(s);
+                assert_approx_eq!(x, gx, 1e-6);
+                assert_approx_eq!(y, gy, 1e-6);
+                assert_approx_eq!(a.heading_at_s(s), g.heading_at_s(s), 1e-7);
+                assert_approx_eq!(a.curvature_at_s(s), g.curvature_at_s(s), 1e-7);
+            }
+            for i in 0..=12 {
+                let s = i as f64;
+                let (x, y) = b.position_at_s(s);
+                let (gx, gy) = g.position_at_s(8.0 + s);
+                assert_approx_eq!(x, gx, 1e-6);
+                assert_approx_eq!(y, gy, 1e-6);
+                assert_approx_eq!(b.heading_at_s(s), g.heading_at_s(8.0 + s), 1e-7);
+                assert_approx_eq!(b.curvature_at_s(s), g.curvature_at_s(8.0 + s), 1e-7);
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_plan_view() {
+        let road = RoadBuilder::new()
+            .line(10.0)
+            .spiral(20.0, 0.0, 0.05)
+            .arc(15.0, 0.05)
+            .build();
+        let plan_view = &road.plan_view;
+        assert_approx_eq!(plan_view.length(), 45.0);
+        let (a, b) = plan_view.split_at_s(20.0);
+        assert_eq!(a.geometries.len(), 2);
+        assert_eq!(b.geometries.len(), 2);
+        assert_approx_eq!(a.length(), 20.0);
+        assert_approx_eq!(b.length(), 25.0);
+        assert_approx_eq!(b.geometries[0].s.get(), 0.0);
+        assert_approx_eq!(b.geometries[1].s.get(), 10.0);
+        for i in 0..=25 {
+            let s = i as f64;
+            let (x, y) = b.position_at_s(s);
+            let (px, py) = plan_view.position_at_s(20.0 + s);
+            assert_approx_eq!(x, px, 1e-6);
+            assert_approx_eq!(y, py, 1e-6);
+            assert_approx_eq!(
+                b.curvature_at_s(s),
+                plan_view.curvature_at_s(20.0 + s),
+                1e-9
+            );
+        }
+        // splitting at the end of an element doesn't split it
+        let (a, b) = plan_view.split_at_s(10.0);
+        assert_eq!(a.geometries.len(), 1);
+        assert_eq!(b.geometries.len(), 2);
+    }
+}
+
+#[cfg(test)]
+mod offset {
+    use super::geometry;
+    use crate::builder::*;
+    use crate::errors::OffsetError;
+    use crate::offset::*;
+    use crate::types;
+    use crate::*;
+    use lyon_geom::Segment;
+
+    /// Distance from x, y to the flattened segments
+    fn distance(segments: &[types::Segment<f64>], x: f64, y: f64) -> f64 {
+        let mut points = vec![segments[0].from()];
+        for segment in segments {
+            segment.for_each_flattened(1e-5, &mut |p| points.push(p));
+        }
+        let p = euclid::point2(x, y);
+        points
+            .windows(2)
+            .map(|pair| {
+                let v = pair[1] - pair[0];
+                let f = if v.square_length() > 0.0 {
+                    ((p - pair[0]).dot(v) / v.square_length()).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                (pair[0] + v * f - p).length()
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Largest distance of the offset of `g` at `t` from `segments`
+    fn deviation(g: &Geometry, t: f64, segments: &[types::Segment<f64>]) -> f64 {
+        (0..=100)
+            .map(|i| {
+                let (x, y, hdg) = g.pose_at(g.length.get() * i as f64 / 100.0);
+                distance(segments, x - t * hdg.sin(), y + t * hdg.cos())
+            })
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_exact() {
+        let line = geometry(GeometryElement::Line);
+        let segments = offset_segment(&line.as_segment(), 2.0, 0.01).unwrap();
+        assert_eq!(segments.len(), 1);
+        assert!(matches!(segments[0], types::Segment::Line(_)));
+        assert!(deviation(&line, 2.0, &segments) < 1e-9);
+
+        for &(curvature, t, radius) in
+            [(0.05, 2.0, 18.0), (-0.05, 2.0, 22.0), (0.05, -3.0, 23.0)].iter()
+        {
+            let arc = geometry(GeometryElement::Arc { curvature });
+            let segments = offset_segment(&arc.as_segment(), t, 0.01).unwrap();
+            assert_eq!(segments.len(), 1);
+            match segments[0] {
+                types::Segment::Arc(ref offset) => assert_approx_eq!(offset.radii.x, radius, 1e-9),
+                _ => panic!("offset of an arc is not an arc"),
+            }
+            assert!(deviation(&arc, t, &segments) < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_cusp() {
+        let arc = geometry(GeometryElement::Arc { curvature: 0.1 });
+        assert!(matches!(
+            offset_segment(&arc.as_segment(), 12.0, 0.01),
+            Err(OffsetError::Cusp(..))
+        ));
+        // away from the center the offset is fine
+        assert!(offset_segment(&arc.as_segment(), -12.0, 0.01).is_ok());
+
+        let spiral = geometry(GeometryElement::Spiral {
+            curv_start: 0.0,
+            curv_end: 0.5,
+        });
+        assert!(matches!(
+            offset_segment(&spiral.as_segment(), 3.0, 0.01),
+            Err(OffsetError::Cusp(..))
+        ));
+    }
+
+    #[test]
+    fn test_approximation() {
+        let geometries = vec![
+            geometry(GeometryElement::Spiral {
+                curv_start: 0.01,
+                curv_end: 0.06,
+            }),
+            geometry(GeometryElement::Poly3 {
+                a: 0.5,
+                b: 0.1,
+                c: 0.02,
+                d: -0.001,
+            }),
+            geometry(GeometryElement::ParamPoly3 {
+                a_u: 0.0,
+                b_u: 20.0,
+                c_u: -1.0,
+                d_u: 0.5,
+                a_v: 0.0,
+                b_v: 0.0,
+                c_v: 3.0,
+                d_v: -1.0,
+                p_range: PRange::Normalized,
+            }),
+        ];
+        for g in geometries {
+            for &t in [1.5, -3.0].iter() {
+                let segments = offset_segment(&g.as_segment(), t, 0.01).unwrap();
+                // far fewer curves than a polyline within the tolerance
+                assert!(segments.len() <= 4);
+                assert!(deviation(&g, t, &segments) < 0.01);
+                for pair in segments.windows(2) {
+                    assert!((pair[0].to() - pair[1].from()).length() < 1e-12);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_lane_boundary() {
+        let road = RoadBuilder::new()
+            .line(50.0)
+            .arc(40.0, 0.02)
+            .spiral(30.0, 0.02, 0.0)
+            .lanes(
+                LaneSectionBuilder::new()
+                    .left(LaneBuilder::new(LaneType::Driving, 3.5))
+                    .right(
+                        LaneBuilder::new(LaneType::Driving, 3.0).width(60.0, 3.0, 0.02, 0.0, 0.0),
+                    ),
+            )
+            .build();
+
+        let left = lane_boundary(&road, 0, 1, 0.01).unwrap();
+        assert!(matches!(left[0], types::Segment::Line(_)));
+        assert!(matches!(left[1], types::Segment::Arc(_)));
+        let right = lane_boundary(&road, 0, -1, 0.01).unwrap();
+        for (segments, lane) in [(left, 1), (right, -1)].iter() {
+            for i in 0..=240 {
+                let s = road.length.get() * i as f64 / 240.0;
+                let borders = road.lane_borders(0, s);
+                let outer = borders.iter().find(|b| b.id == *lane).unwrap().outer;
+                let (x, y) = road.position_at(s, outer);
+                assert!(distance(segments, x, y) < 0.01);
+            }
+        }
+
+        assert!(matches!(
+            lane_boundary(&road, 0, 2, 0.01),
+            Err(OffsetError::UnknownLane(2))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod edit {
+    use crate::builder::*;
+    use crate::errors::EditError;
+    use crate::generators::straight_highway;
+    use crate::types::{Length, RoadId};
+    use crate::*;
+    use lyon_geom::Segment;
+    use serde_xml_rs::from_str;
+
+    fn road() -> Road {
+        let mut road = RoadBuilder::new()
+            .id(1)
+            .line(40.0)
+            .arc(30.0, 0.02)
+            .spiral(20.0, 0.02, -0.01)
+            .elevation(0.0, 1.0, 0.01, 0.0001, 0.0)
+            .elevation(50.0, 1.4, -0.02, 0.0, 0.00001)
+            .superelevation(0.0, 0.01, 0.001, 0.0, 0.0)
+            .lane_offset(0.0, 0.2, 0.01, 0.0, 0.0)
+            .lane_section(
+                0.0,
+                LaneSectionBuilder::new()
+                    .left(
+                        LaneBuilder::new(LaneType::Driving, 3.5)
+                            .width(10.0, 3.5, 0.05, -0.001, 0.0),
+                    )
+                    .right(LaneBuilder::new(LaneType::Driving, 3.0).mark(RoadMarkType::Solid)),
+            )
+            .lane_section(
+                60.0,
+                LaneSectionBuilder::new()
+                    .left(LaneBuilder::new(LaneType::Driving, 3.5))
+                    .right(LaneBuilder::new(LaneType::Driving, 3.0).width(0.0, 3.0, 0.02, 0.0, 0.0))
+                    .right(LaneBuilder::new(LaneType::Shoulder, 1.0)),
+            )
+            .predecessor(ElementType::Road, 0, ContactPoint::End)
+            .successor(ElementType::Road, 2, ContactPoint::Start)
+            .build();
+        road.objects = Some(
+            from_str(
+                r#"<objects>
+                    <object type="barrier" id="1" s="30.0" t="-5.0" validLength="30.0" orientation="+"/>
+                    <object type="pole" id="2" s="50.0" t="-4.0" hdg="0.5" orientation="+"/>
+                </objects>"#,
+            )
+            .unwrap(),
+        );
+        road.signals = Some(
+            from_str(
+                r#"<signals>
+                    <signal s="20.0" t="-4.0" id="3" dynamic="no" orientation="+" type="274"/>
+                    <signal s="80.0" t="4.0" id="4" dynamic="no" orientation="-" type="274"/>
+                </signals>"#,
+            )
+            .unwrap(),
+        );
+        road
+    }
+
+    /// The lanes of `a` at `sa` match those of `b` at `sb`, with ids and
+    /// lateral offsets negated if `reversed`
+    fn assert_same(a: &Road, sa: f64, b: &Road, sb: f64, reversed: bool) {
+        let sign = if reversed { -1.0 } else { 1.0 };
+        let borders_a = a.lane_borders(a.lane_section_index_at(sa).unwrap(), sa);
+        let borders_b = b.lane_borders(b.lane_section_index_at(sb).unwrap(), sb);
+        assert_eq!(borders_a.len(), borders_b.len());
+        for border in borders_a {
+            let id = if reversed { -border.id } else { border.id };
+            let other = borders_b.iter().find(|b| b.id == id).unwrap();
+            assert_approx_eq!(border.outer, sign * other.outer, 1e-9);
+            let (xa, ya) = a.position_at(sa, border.outer);
+            let (xb, yb) = b.position_at(sb, other.outer);
+            assert_approx_eq!(xa, xb, 1e-6);
+            assert_approx_eq!(ya, yb, 1e-6);
+        }
+        assert_approx_eq!(a.elevation_at(sa), b.elevation_at(sb), 1e-9);
+        assert_approx_eq!(
+            a.superelevation_at(sa),
+            sign * b.superelevation_at(sb),
+            1e-9
+        );
+    }
+
+    fn stations(start: f64, end: f64) -> impl Iterator<Item = f64> {
+        (0..=50).map(move |i| start + (end - start) * i as f64 / 50.0)
+    }
+
+    #[test]
+    fn test_split() {
+        let road = road();
+        let (first, second) = road.split_at(45.0, RoadId::from(5)).unwrap();
+        assert_approx_eq!(first.length.get(), 45.0);
+        assert_approx_eq!(second.length.get(), 45.0);
+        assert!(first.validate().is_ok());
+        assert!(second.validate().is_ok());
+        for s in stations(0.0, 45.0) {
+            assert_same(&road, s, &first, s.min(45.0 - 1e-9), false);
+        }
+        for s in stations(45.0, 90.0) {
+            assert_same(&road, s, &second, s - 45.0, false);
+        }
+
+        let successor = first.link.as_ref().unwrap().successor.as_ref().unwrap();
+        assert_eq!(successor.element_id, RoadId::from(5));
+        assert_eq!(successor.contact_point, ContactPoint::Start);
+        let link = second.link.as_ref().unwrap();
+        assert_eq!(
+            link.predecessor.as_ref().unwrap().element_id,
+            RoadId::from(1)
+        );
+        assert_eq!(
+            link.predecessor.as_ref().unwrap().contact_point,
+            ContactPoint::End
+        );
+        assert_eq!(link.successor, road.link.as_ref().unwrap().successor);
+
+        // the lanes of the cut section continue in themselves
+        let last = first.lanes.as_ref().unwrap().lane_sections.last().unwrap();
+        let lane = last.left_lanes().next().unwrap();
+        assert_eq!(
+            lane.link.as_ref().unwrap().successor.as_ref().unwrap().id,
+            1
+        );
+        let sections = &second.lanes.as_ref().unwrap().lane_sections;
+        assert_eq!(sections.len(), 2);
+        assert_approx_eq!(sections[1].s, 15.0);
+
+        let objects = &first.objects.as_ref().unwrap().objects;
+        assert_eq!(objects.len(), 1);
+        assert_approx_eq!(objects[0].valid_length, 15.0);
+        let objects = &second.objects.as_ref().unwrap().objects;
+        assert_eq!(objects.len(), 2);
+        assert_approx_eq!(objects[0].s, 0.0);
+        assert_approx_eq!(objects[0].valid_length, 15.0);
+        assert_approx_eq!(objects[1].s, 5.0);
+        // object ids stay unique
+        assert_eq!(first.objects.as_ref().unwrap().objects[0].id, "1");
+        assert_eq!(objects[0].id, "1_1");
+        assert_eq!(objects[1].id, "2");
+        assert_eq!(first.signals.as_ref().unwrap().signals.len(), 1);
+        assert_approx_eq!(second.signals.as_ref().unwrap().signals[0].s, 35.0);
+
+        // within the database
+        let mut other = RoadBuilder::new().id(6).line(10.0).build();
+        other.objects = Some(Objects {
+            objects: vec![Object {
+                id: "1_1".to_string(),
+                ..road.objects.as_ref().unwrap().objects[1].clone()
+            }],
+        });
+        let mut root = Root {
+            header: straight_highway(&Default::default()).header,
+            roads: vec![road.clone(), other],
+            junctions: vec![],
+        };
+        root.split_road(&RoadId::from(1), 45.0, RoadId::from(5))
+            .unwrap();
+        let objects = &root.roads[1].objects.as_ref().unwrap().objects;
+        assert_eq!(objects[0].id, "1_2");
+
+        assert!(matches!(
+            road.split_at(0.0, RoadId::from(5)),
+            Err(EditError::OutOfRange(_))
+        ));
+        assert!(matches!(
+            road.split_at(90.0, RoadId::from(5)),
+            Err(EditError::OutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn test_trim() {
+        let road = road();
+        let trimmed = road.trimmed(20.0, 70.0).unwrap();
+        assert_approx_eq!(trimmed.length.get(), 50.0);
+        for s in stations(20.0, 70.0 - 1e-9) {
+            assert_same(&road, s, &trimmed, s - 20.0, false);
+        }
+        let link = trimmed.link.as_ref().unwrap();
+        assert!(link.predecessor.is_none() && link.successor.is_none());
+        let sections = &trimmed.lanes.as_ref().unwrap().lane_sections;
+        assert_eq!(sections.len(), 2);
+        assert_approx_eq!(sections[1].s, 40.0);
+        for lane in sections[1].right_lanes() {
+            assert!(lane.link.iter().all(|link| link.successor.is_none()));
+        }
+
+        let whole = road.trimmed(0.0, 90.0).unwrap();
+        assert_eq!(whole.link, road.link);
+        assert!(matches!(
+            road.trimmed(-5.0, 10.0),
+            Err(EditError::OutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn test_merge() {
+        let road = road();
+        let (first, second) = road.split_at(45.0, RoadId::from(5)).unwrap();
+        let merged = first.merge(&second).unwrap();
+        assert_approx_eq!(merged.length.get(), 90.0);
+        assert!(merged.validate().is_ok());
+        for s in stations(0.0, 90.0) {
+            assert_same(&road, s, &merged, s, false);
+        }
+        assert_eq!(merged.link, road.link);
+        assert_eq!(merged.objects.as_ref().unwrap().objects.len(), 3);
+        assert_eq!(merged.signals, road.signals);
+
+        assert!(matches!(
+            second.merge(&first),
+            Err(EditError::NotLinked(..))
+        ));
+    }
+
+    #[test]
+    fn test_reverse() {
+        let road = road();
+        let reversed = road.reversed().unwrap();
+        assert!(reversed.validate().is_ok());
+        for s in stations(0.0, 90.0) {
+            assert_same(&road, s, &reversed, 90.0 - s, true);
+        }
+        let link = reversed.link.as_ref().unwrap();
+        assert_eq!(link.predecessor, road.link.as_ref().unwrap().successor);
+        assert_eq!(link.successor, road.link.as_ref().unwrap().predecessor);
+
+        let objects = &reversed.objects.as_ref().unwrap().objects;
+        assert_eq!(objects[0].id, "2");
+        assert_approx_eq!(objects[0].s, 40.0);
+        assert_approx_eq!(objects[0].t, 4.0);
+        assert_eq!(objects[0].orientation, Orientation::Negative);
+        assert_approx_eq!(objects[1].s, 30.0);
+        let signals = &reversed.signals.as_ref().unwrap().signals;
+        assert_approx_eq!(signals[0].s, 10.0);
+        assert_eq!(signals[0].orientation, Orientation::Positive);
+
+        let twice = reversed.reversed().unwrap();
+        for s in stations(0.0, 90.0) {
+            assert_same(&road, s, &twice, s, false);
+        }
+
+        // the solid line stays on the same side of the road
+        let mut road = RoadBuilder::new()
+            .line(10.0)
+            .lanes(
+                LaneSectionBuilder::new()
+                    .center_mark(RoadMarkType::SolidBroken)
+                    .left(LaneBuilder::new(LaneType::Driving, 3.5).mark(RoadMarkType::SolidBroken))
+                    .right(LaneBuilder::new(LaneType::Driving, 3.5)),
+            )
+            .build();
+        let section = &mut road.lanes.as_mut().unwrap().lane_sections[0];
+        section.center.lane[0].road_marks[0].lane_change = LaneChangeType::Increase;
+        let reversed = road.reversed().unwrap();
+        let section = &reversed.lanes.as_ref().unwrap().lane_sections[0];
+        let mark = &section.center.lane[0].road_marks[0];
+        assert_eq!(mark.road_mark_type, RoadMarkType::BrokenSolid);
+        assert_eq!(mark.lane_change, LaneChangeType::Decrease);
+        let right = section.right_lanes().next().unwrap();
+        assert_eq!(
+            right.road_marks[0].road_mark_type,
+            RoadMarkType::SolidBroken
+        );
+    }
+
+    #[test]
+    fn test_reverse_polynomials() {
+        let geometry = |s: f64, element: GeometryElement| Geometry {
+            s: Length::new(s),
+            ..super::geometry(element)
+        };
+        let mut road = RoadBuilder::new().line(40.0).build();
+        road.plan_view.geometries = vec![
+            geometry(
+                0.0,
+                GeometryElement::Poly3 {
+                    a: 0.5,
+                    b: 0.1,
+                    c: 0.02,
+                    d: -0.001,
+                },
+            ),
+            geometry(
+                20.0,
+                GeometryElement::ParamPoly3 {
+                    a_u: 0.0,
+                    b_u: 20.0,
+                    c_u: -1.0,
+                    d_u: 0.5,
+                    a_v: 0.0,
+                    b_v: 0.0,
+                    c_v: 3.0,
+                    d_v: -1.0,
+                    p_range: PRange::Normalized,
+                },
+            ),
+        ];
+        let reversed = road.reversed().unwrap();
+        let geometries = &reversed.plan_view.geometries;
+        for (g, r) in road
+            .plan_view
+            .geometries
+            .iter()
+            .zip(geometries.iter().rev())
+        {
+            let (g, r) = (g.as_segment(), r.as_segment());
+            for &t in [0.0, 0.25, 0.5, 1.0].iter() {
+                let (p, q) = (g.sample(t), r.sample(1.0 - t));
+                assert_approx_eq!(p.x, q.x, 1e-9);
+                assert_approx_eq!(p.y, q.y, 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_root() {
+        let lanes = || {
+            LaneSectionBuilder::new()
+                .left(LaneBuilder::new(LaneType::Driving, 3.5))
+                .right(LaneBuilder::new(LaneType::Driving, 3.5))
+        };
+        let mut a = RoadBuilder::new().id(1).line(20.0).lanes(lanes()).build();
+        let mut b = RoadBuilder::new()
+            .id(2)
+            .start_at_end_of(&a)
+            .arc(30.0, 0.02)
+            .lanes(lanes())
+            .build();
+        let mut c = RoadBuilder::new()
+            .id(3)
+            .start_at_end_of(&b)
+            .line(20.0)
+            .lanes(lanes())
+            .build();
+        link_roads(&mut a, &mut b);
+        link_roads(&mut b, &mut c);
+        let mut root = Root {
+            header: straight_highway(&Default::default()).header,
+            roads: vec![a, b, c],
+            junctions: vec![],
+        };
+        let predecessor = |root: &Root| {
+            root.roads[root.roads.len() - 1]
+                .link
+                .as_ref()
+                .unwrap()
+                .predecessor
+                .clone()
+                .unwrap()
+        };
+
+        root.split_road(&RoadId::from(2), 10.0, RoadId::from(4))
+            .unwrap();
+        assert_eq!(root.roads.len(), 4);
+        assert_eq!(root.roads[2].id, RoadId::from(4));
+        assert_eq!(predecessor(&root).element_id, RoadId::from(4));
+
+        root.merge_roads(&RoadId::from(2), &RoadId::from(4))
+            .unwrap();
+        assert_eq!(root.roads.len(), 3);
+        assert_approx_eq!(root.roads[1].length.get(), 30.0);
+        assert_eq!(predecessor(&root).element_id, RoadId::from(2));
+
+        root.reverse_road(&RoadId::from(2)).unwrap();
+        assert_eq!(predecessor(&root).contact_point, ContactPoint::Start);
+        let section = &root.roads[2].lanes.as_ref().unwrap().lane_sections[0];
+        let lane = section.left_lanes().next().unwrap();
+        assert_eq!(
+            lane.link.as_ref().unwrap().predecessor.as_ref().unwrap().id,
+            -1
+        );
+        // lane -1 of road 3 and lane 1 of the reversed road 2 link each
+        // other, the lanes of roads 1 and 3 keep their ids
+        let lane_link = |road: &Road, section: usize, id: i32| {
+            let section = &road.lanes.as_ref().unwrap().lane_sections[section];
+            let lane = section
+                .left_lanes()
+                .chain(section.right_lanes())
+                .find(|lane| lane.id == id)
+                .unwrap();
+            let link = lane.link.as_ref().unwrap();
+            (
+                link.predecessor.as_ref().map(|l| l.id),
+                link.successor.as_ref().map(|l| l.id),
+            )
+        };
+        let reversed = &root.roads[1];
+        let sections = reversed.lanes.as_ref().unwrap().lane_sections.len();
+        assert_eq!(sections, 2);
+        assert_eq!(lane_link(&root.roads[2], 0, -1).0, Some(1));
+        assert_eq!(lane_link(reversed, 0, 1), (Some(-1), Some(1)));
+        assert_eq!(lane_link(reversed, 1, 1), (Some(1), Some(-1)));
+        assert_eq!(lane_link(reversed, 0, -1), (Some(1), Some(-1)));
+        assert_eq!(lane_link(reversed, 1, -1), (Some(-1), Some(1)));
+        assert_eq!(lane_link(&root.roads[0], 0, -1).1, Some(1));
+        // the reversed road starts where road 3 starts
+        let (x, y) = root.roads[1].position_at(0.0, 0.0);
+        let (x3, y3) = root.roads[2].position_at(0.0, 0.0);
+        assert_approx_eq!(x, x3, 1e-9);
+        assert_approx_eq!(y, y3, 1e-9);
+
+        assert!(matches!(
+            root.reverse_road(&RoadId::from(9)),
+            Err(EditError::UnknownRoad(_))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod crop {
+    use crate::builder::*;
+    use crate::crop::*;
+    use crate::errors::ValidationError;
+    use crate::generators::straight_highway;
+    use crate::types::RoadId;
+    use crate::*;
+
+    fn network() -> Root {
+        let lanes = || {
+            LaneSectionBuilder::new()
+                .left(LaneBuilder::new(LaneType::Driving, 3.5))
+                .right(LaneBuilder::new(LaneType::Driving, 3.5))
+        };
+        let mut a = RoadBuilder::new().id(1).line(100.0).lanes(lanes()).build();
+        let mut b = RoadBuilder::new()
+            .id(2)
+            .start_at_end_of(&a)
+            .line(100.0)
+            .lanes(lanes())
+            .build();
+        let mut c = RoadBuilder::new()
+            .id(3)
+            .start_at_end_of(&b)
+            .line(100.0)
+            .lanes(lanes())
+            .build();
+        link_roads(&mut a, &mut b);
+        link_roads(&mut b, &mut c);
+        Root {
+            header: straight_highway(&Default::default()).header,
+            roads: vec![a, b, c],
+            junctions: vec![],
+        }
+    }
+
+    #[test]
+    fn test_region() {
+        let triangle = Region::Polygon(vec![(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)]);
+        assert!(triangle.contains(2.0, 2.0));
+        assert!(!triangle.contains(6.0, 6.0));
+        assert!(!triangle.contains(-1.0, 2.0));
+        let b = triangle.bounding_box();
+        assert_eq!((b.west, b.south, b.east, b.north), (0.0, 0.0, 10.0, 10.0));
+        let region = Region::BoundingBox(b);
+        assert!(region.contains(6.0, 6.0));
+        assert!(region.contains(10.0, 0.0));
+        assert!(!region.contains(10.5, 0.0));
+    }
+
+    #[test]
+    fn test_crop() {
+        let mut root = network();
+        root.header.north = 50.0;
+        let cropped = root.crop(&Region::BoundingBox(BoundingBox {
+            west: 50.0,
+            south: -10.0,
+            east: 130.0,
+            north: 10.0,
+        }));
+        assert_eq!(cropped.header, root.header);
+        assert_eq!(cropped.roads.len(), 2);
+        assert!(cropped.validate().is_empty());
+
+        // cut at the boundary
+        let (a, b) = (&cropped.roads[0], &cropped.roads[1]);
+        assert_approx_eq!(a.length.get(), 50.0, 1e-8);
+        assert_approx_eq!(a.position_at(0.0, 0.0).0, 50.0, 1e-8);
+        assert_approx_eq!(b.length.get(), 30.0, 1e-8);
+        assert_approx_eq!(b.position_at(30.0, 0.0).0, 130.0, 1e-8);
+
+        // the links within the region remain
+        let link = a.link.as_ref().unwrap();
+        assert!(link.predecessor.is_none());
+        assert_eq!(link.successor.as_ref().unwrap().element_id, RoadId::from(2));
+        let link = b.link.as_ref().unwrap();
+        assert_eq!(
+            link.predecessor.as_ref().unwrap().element_id,
+            RoadId::from(1)
+        );
+        assert!(link.successor.is_none());
+        let section = b.lanes.as_ref().unwrap().lane_sections.last().unwrap();
+        for lane in section.left_lanes().chain(section.right_lanes()) {
+            assert!(lane.link.iter().all(|link| link.successor.is_none()));
+        }
+    }
+
+    #[test]
+    fn test_parts() {
+        let root = network();
+        // a U shape the first road passes twice
+        let u_shape = Region::Polygon(vec![
+            (10.0, -5.0),
+            (30.0, -5.0),
+            (30.0, 5.0),
+            (60.0, 5.0),
+            (60.0, -5.0),
+            (80.0, -5.0),
+            (80.0, 10.0),
+            (10.0, 10.0),
+        ]);
+        let cropped = root.crop(&u_shape);
+        assert_eq!(cropped.roads.len(), 2);
+        assert_eq!(cropped.roads[0].id, RoadId::from(1));
+        assert_eq!(cropped.roads[1].id, RoadId::from("1_1"));
+        for road in cropped.roads.iter() {
+            assert_approx_eq!(road.length.get(), 20.0, 1e-8);
+            let link = road.link.as_ref().unwrap();
+            assert!(link.predecessor.is_none() && link.successor.is_none());
+        }
+        assert_approx_eq!(cropped.roads[1].position_at(0.0, 0.0).0, 60.0, 1e-8);
+
+        // ids of other roads and objects are not reused
+        let mut root = network();
+        root.roads[1].id = RoadId::from("1_1");
+        root.roads[0].objects = Some(
+            serde_xml_rs::from_str(
+                r#"<objects>
+                    <object type="barrier" id="7" s="0.0" t="-5.0" validLength="100.0" orientation="+"/>
+                </objects>"#,
+            )
+            .unwrap(),
+        );
+        let cropped = root.crop(&u_shape);
+        assert_eq!(cropped.roads[1].id, RoadId::from("1_2"));
+        let object_id = |road: &Road| road.objects.as_ref().unwrap().objects[0].id.clone();
+        assert_eq!(object_id(&cropped.roads[0]), "7");
+        assert_eq!(object_id(&cropped.roads[1]), "7_1");
+    }
+
+    #[test]
+    fn test_junctions() {
+        let file = std::fs::File::open("CulDeSac.xodr").unwrap();
+        let root = crate::from_reader(file).unwrap();
+        let b = root.bounding_box(0.1).unwrap().unwrap();
+        let cropped = root.crop(&Region::BoundingBox(BoundingBox {
+            east: (b.west + b.east) / 2.0,
+            ..b
+        }));
+        assert!(!cropped.roads.is_empty());
+        assert!(cropped.roads.len() <= root.roads.len() + 1);
+        assert_eq!(cropped.header, root.header);
+        for finding in cropped.validate() {
+            let finding = match finding {
+                ValidationError::Road(_, finding) | ValidationError::Junction(_, finding) => {
+                    *finding
+                }
+                finding => finding,
+            };
+            assert!(!matches!(
+                finding,
+                ValidationError::MissingRoad(_) | ValidationError::MissingJunction(_)
+            ));
+        }
+
+        // the whole extent keeps everything
+        let whole = root.crop(&Region::BoundingBox(BoundingBox {
+            west: b.west - 1.0,
+            south: b.south - 1.0,
+            east: b.east + 1.0,
+            north: b.north + 1.0,
+        }));
+        assert_eq!(whole.roads, root.roads);
+        assert_eq!(whole.junctions, root.junctions);
+    }
+}
@@ -1,27 +1,28 @@
-use proj5::prelude::*;
+use crate::geo::*;
 
 #[test]
-#[ignore]
 fn test_proj() {
-    let ellipsoid = WGS_1984_ELLIPSOID;
+    // WGS84 semi-major axis and squared eccentricity
+    const A: f64 = 6_378_137.0;
+    const E2: f64 = 0.006_694_379_990_14;
 
-    let src = CoordinateSource::CoordinateBuf(Box::new(CoordinateBuf {
-        data: vec![
-            (8.044918800000e+1, 2.488364150000e+2),
-            (8.064432870000e+1, 2.488880630000e+2),
-            (8.098982890000e+1, 2.489818280000e+2),
-            (8.144194620000e+1, 2.491042410000e+2),
-        ],
-        crs: Box::new(MercatorSystem {}),
-        ellipsoid: ellipsoid,
-    }));
+    let merc = Projection::from_proj_string("+proj=merc +ellps=WGS84").unwrap();
+    let data = vec![
+        (8.044918800000e+1, 2.488364150000e+2),
+        (8.064432870000e+1, 2.488880630000e+2),
+        (8.098982890000e+1, 2.489818280000e+2),
+        (8.144194620000e+1, 2.491042410000e+2),
+    ];
 
-    let mut tgt = CoordinateSource::LonLatBuf(Box::new(LonLatBuf {
-        data: Vec::new(),
-        ellipsoid: ellipsoid,
-    }));
+    for (x, y) in data {
+        let (lat, lon) = merc.inverse(x, y);
+        // Close to the origin the Mercator projection is a plain scaling by
+        // the radii of curvature on the equator
+        assert_approx_eq!(lon, (x / A).to_degrees(), 1e-12);
+        assert_approx_eq!(lat, (y / (A * (1.0 - E2))).to_degrees(), 1e-12);
 
-    let mut strategy = MultithreadingStrategy::SingleCore;
-    src.project(&mut tgt, &mut strategy);
-    println!("first batch of coordinates: {:#?}", tgt.get_data_ref());
+        let (px, py) = merc.forward(lat, lon);
+        assert_approx_eq!(px, x, 1e-6);
+        assert_approx_eq!(py, y, 1e-6);
+    }
 }
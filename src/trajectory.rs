@@ -0,0 +1,296 @@
+//! Reference trajectories along a route through the lane graph
+//!
+//! A route is a sequence of lanes, each either a successor of the previous
+//! one in the `LaneGraph` or an adjacent lane of the same lane section with
+//! the same driving direction. The latter is a lane change: the trajectory
+//! moves from the center of one lane to the center of the other along a
+//! quintic transition, so the curvature stays continuous. Several lane
+//! changes in one lane section are spread evenly over it.
+//!
+//! The trajectory follows the lane centers and is sampled at a constant step
+//! along the reference line. Heading, curvature and curvature rate are
+//! computed from the samples. The velocity profile respects the speed limits
+//! of the road type records, a maximum lateral acceleration in curves and
+//! maximum longitudinal acceleration and deceleration.
+use crate::errors::TrajectoryError;
+use crate::lane_graph::{is_forward, LaneGraph, LaneKey};
+use crate::opendrive::*;
+
+#[derive(Debug, Clone)]
+pub struct TrajectoryOptions {
+    /// m step of the samples along the reference line
+    pub step: f64,
+    /// m length of a lane change
+    pub lane_change_length: f64,
+    /// m/s speed on roads without a speed limit
+    pub default_speed: f64,
+    /// m/s² maximum lateral acceleration
+    pub max_lateral_acceleration: f64,
+    /// m/s² maximum longitudinal acceleration
+    pub max_acceleration: f64,
+    /// m/s² maximum longitudinal deceleration
+    pub max_deceleration: f64,
+}
+
+impl Default for TrajectoryOptions {
+    fn default() -> TrajectoryOptions {
+        TrajectoryOptions {
+            step: 0.5,
+            lane_change_length: 30.0,
+            default_speed: 50.0 / 3.6,
+            max_lateral_acceleration: 2.0,
+            max_acceleration: 2.0,
+            max_deceleration: 3.0,
+        }
+    }
+}
+
+/// A sample of a trajectory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrajectoryPoint {
+    /// lane the sample lies in, the target lane from the middle of a lane
+    /// change on
+    pub lane: LaneKey,
+    /// m distance along the trajectory
+    pub s: f64,
+    pub x: f64,
+    pub y: f64,
+    /// rad inertial heading
+    pub heading: f64,
+    /// 1/m positive to the left
+    pub curvature: f64,
+    /// 1/m² derivative of the curvature along the trajectory
+    pub curvature_rate: f64,
+    /// m/s
+    pub speed: f64,
+}
+
+/// Consecutive lanes of a route within one lane section
+struct Visit {
+    road: usize,
+    section: usize,
+    lanes: Vec<i32>,
+}
+
+/// Generate the trajectory along `route`
+pub fn trajectory(
+    root: &Root,
+    route: &[LaneKey],
+    options: &TrajectoryOptions,
+) -> Result<Vec<TrajectoryPoint>, TrajectoryError> {
+    let graph = LaneGraph::new(root);
+    let visits = visits(root, &graph, route)?;
+
+    let mut points: Vec<TrajectoryPoint> = Vec::new();
+    // s along the reference line of the road of each sample
+    let mut stations = Vec::new();
+    for visit in visits.iter() {
+        for (lane, station, x, y) in sample_visit(root, visit, options)? {
+            if let Some(last) = points.last() {
+                if (x - last.x).hypot(y - last.y) < 1e-6 {
+                    continue;
+                }
+            }
+            stations.push(station);
+            points.push(TrajectoryPoint {
+                lane,
+                s: 0.0,
+                x,
+                y,
+                heading: 0.0,
+                curvature: 0.0,
+                curvature_rate: 0.0,
+                speed: 0.0,
+            });
+        }
+    }
+    differentiate(&mut points);
+    velocity_profile(root, &mut points, &stations, options);
+    Ok(points)
+}
+
+/// Group the route by lane section and check that it is connected
+fn visits(
+    root: &Root,
+    graph: &LaneGraph,
+    route: &[LaneKey],
+) -> Result<Vec<Visit>, TrajectoryError> {
+    let first = route.first().ok_or(TrajectoryError::EmptyRoute)?;
+    if !graph.lanes().any(|lane| lane == first) {
+        return Err(TrajectoryError::UnknownLane(*first));
+    }
+    let mut visits = vec![Visit {
+        road: first.road,
+        section: first.section,
+        lanes: vec![first.lane],
+    }];
+    for pair in route.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        if graph.successors(&from).contains(&to) {
+            visits.push(Visit {
+                road: to.road,
+                section: to.section,
+                lanes: vec![to.lane],
+            });
+        } else if to.road == from.road
+            && to.section == from.section
+            && (to.lane - from.lane).abs() == 1
+            && to.lane != 0
+            && graph.lanes().any(|lane| *lane == to)
+            && lane_forward(root, to) == lane_forward(root, from)
+        {
+            visits.last_mut().unwrap().lanes.push(to.lane);
+        } else {
+            return Err(TrajectoryError::NotConnected(from, to));
+        }
+    }
+    Ok(visits)
+}
+
+/// Whether a lane is driven in the direction of increasing s, if it exists
+fn lane_forward(root: &Root, key: LaneKey) -> Option<bool> {
+    let road = root.roads.get(key.road)?;
+    let lane = road
+        .lanes
+        .as_ref()?
+        .lane_sections
+        .get(key.section)?
+        .lane(key.lane)?;
+    Some(is_forward(road, lane))
+}
+
+/// Lane, s and inertial position of the samples of a visit in driving
+/// direction
+fn sample_visit(
+    root: &Root,
+    visit: &Visit,
+    options: &TrajectoryOptions,
+) -> Result<Vec<(LaneKey, f64, f64, f64)>, TrajectoryError> {
+    if !(options.step > 0.0 && options.step.is_finite()) {
+        return Err(TrajectoryError::InvalidStep(options.step));
+    }
+    let road = &root.roads[visit.road];
+    let (start, end) = road.lane_section_range(visit.section);
+    let length = end - start;
+    let key = |lane: i32| LaneKey {
+        road: visit.road,
+        section: visit.section,
+        lane,
+    };
+    let forward = lane_forward(root, key(visit.lanes[0]))
+        .ok_or_else(|| TrajectoryError::UnknownLane(key(visit.lanes[0])))?;
+    let center = |lane: i32, s: f64| {
+        road.lane_borders(visit.section, s)
+            .into_iter()
+            .find(|borders| borders.id == lane)
+            .map(|borders| (borders.inner + borders.outer) / 2.0)
+            .ok_or_else(|| TrajectoryError::UnknownLane(key(lane)))
+    };
+
+    // the lane changes are centered in equal parts of the lane section
+    let changes = visit.lanes.len() - 1;
+    let part = length / changes.max(1) as f64;
+    let transition = options.lane_change_length.min(part);
+    let change_start = |j: usize| (j as f64 + 0.5) * part - transition / 2.0;
+
+    let n = ((length / options.step).ceil() as usize).max(1);
+    let mut samples = Vec::with_capacity(n + 1);
+    for i in 0..=n {
+        let distance = length * i as f64 / n as f64;
+        let s = if forward {
+            start + distance
+        } else {
+            end - distance
+        };
+        let mut t = center(visit.lanes[0], s)?;
+        let mut lane = visit.lanes[0];
+        for j in 0..changes {
+            let u = ((distance - change_start(j)) / transition).clamp(0.0, 1.0);
+            if u <= 0.0 {
+                break;
+            }
+            let (from, to) = (visit.lanes[j], visit.lanes[j + 1]);
+            t += smoothstep(u) * (center(to, s)? - center(from, s)?);
+            if u >= 0.5 {
+                lane = to;
+            }
+        }
+        let (x, y) = road.position_at(s, t);
+        samples.push((key(lane), s, x, y));
+    }
+    Ok(samples)
+}
+
+/// Quintic transition from 0 to 1 with vanishing first and second
+/// derivatives at both ends
+fn smoothstep(u: f64) -> f64 {
+    u * u * u * (10.0 + u * (-15.0 + 6.0 * u))
+}
+
+/// Distance, heading, curvature and curvature rate from the positions
+fn differentiate(points: &mut [TrajectoryPoint]) {
+    for i in 1..points.len() {
+        let (a, b) = (points[i - 1], points[i]);
+        points[i].s = a.s + (b.x - a.x).hypot(b.y - a.y);
+    }
+    let n = points.len();
+    if n < 2 {
+        return;
+    }
+    for i in 0..n {
+        let (a, b) = (points[i.saturating_sub(1)], points[(i + 1).min(n - 1)]);
+        points[i].heading = (b.y - a.y).atan2(b.x - a.x);
+    }
+    for i in 1..n - 1 {
+        let (a, b, c) = (points[i - 1], points[i], points[i + 1]);
+        // signed curvature of the circle through three consecutive samples
+        let cross = (b.x - a.x) * (c.y - b.y) - (b.y - a.y) * (c.x - b.x);
+        let lengths = (b.s - a.s) * (c.s - b.s) * (c.x - a.x).hypot(c.y - a.y);
+        points[i].curvature = if lengths > 0.0 {
+            2.0 * cross / lengths
+        } else {
+            0.0
+        };
+    }
+    points[0].curvature = points[1].curvature;
+    points[n - 1].curvature = points[n - 2].curvature;
+    for i in 0..n {
+        let (a, b) = (points[i.saturating_sub(1)], points[(i + 1).min(n - 1)]);
+        points[i].curvature_rate = if b.s > a.s {
+            (b.curvature - a.curvature) / (b.s - a.s)
+        } else {
+            0.0
+        };
+    }
+}
+
+/// Speeds within the speed limits and the lateral acceleration limit,
+/// reachable with the longitudinal acceleration limits
+fn velocity_profile(
+    root: &Root,
+    points: &mut [TrajectoryPoint],
+    stations: &[f64],
+    options: &TrajectoryOptions,
+) {
+    for (point, &s) in points.iter_mut().zip(stations) {
+        let limit = root.roads[point.lane.road]
+            .speed_at(s)
+            .unwrap_or(options.default_speed);
+        let curve = if point.curvature != 0.0 {
+            (options.max_lateral_acceleration / point.curvature.abs()).sqrt()
+        } else {
+            f64::INFINITY
+        };
+        point.speed = limit.min(curve);
+    }
+    for i in 1..points.len() {
+        let ds = points[i].s - points[i - 1].s;
+        let reachable = (points[i - 1].speed.powi(2) + 2.0 * options.max_acceleration * ds).sqrt();
+        points[i].speed = points[i].speed.min(reachable);
+    }
+    for i in (1..points.len()).rev() {
+        let ds = points[i].s - points[i - 1].s;
+        let reachable = (points[i].speed.powi(2) + 2.0 * options.max_deceleration * ds).sqrt();
+        points[i - 1].speed = points[i - 1].speed.min(reachable);
+    }
+}
@@ -11,6 +11,25 @@ pub enum Segment<S> {
     Quadratic(lyon_geom::QuadraticBezierSegment<S>),
     Cubic(lyon_geom::CubicBezierSegment<S>),
     Arc(lyon_geom::Arc<S>),
+    Clothoid(lyon_geom::Clothoid<S>),
+}
+
+impl Segment<f64> {
+    /// Approximates the curve with a sequence of line segments, calling
+    /// `call_back` with every point after the start point.
+    pub fn for_each_flattened<F: FnMut(euclid::Point2D<f64>)>(
+        &self,
+        tolerance: f64,
+        call_back: &mut F,
+    ) {
+        match self {
+            Segment::Line(ref line) => call_back(line.to),
+            Segment::Quadratic(ref quad) => quad.for_each_flattened(tolerance, call_back),
+            Segment::Cubic(ref cub) => cub.for_each_flattened(tolerance, call_back),
+            Segment::Arc(ref arc) => arc.for_each_flattened(tolerance, call_back),
+            Segment::Clothoid(ref clothoid) => clothoid.for_each_flattened(tolerance, call_back),
+        }
+    }
 }
 
 /// Forward all trait functions through to the underlying types
@@ -24,6 +43,7 @@ impl lyon_geom::Segment for Segment<f64> {
             Segment::Quadratic(ref quad) => quad.from(),
             Segment::Cubic(ref cub) => cub.from(),
             Segment::Arc(ref arc) => arc.from(),
+            Segment::Clothoid(ref clothoid) => clothoid.from(),
         }
     }
     fn to(&self) -> euclid::Point2D<Self::Scalar> {
@@ -32,6 +52,7 @@ impl lyon_geom::Segment for Segment<f64> {
             Segment::Quadratic(ref quad) => quad.to(),
             Segment::Cubic(ref cub) => cub.to(),
             Segment::Arc(ref arc) => arc.to(),
+            Segment::Clothoid(ref clothoid) => clothoid.to(),
         }
     }
     fn sample(&self, t: Self::Scalar) -> euclid::Point2D<Self::Scalar> {
@@ -40,6 +61,7 @@ impl lyon_geom::Segment for Segment<f64> {
             Segment::Quadratic(ref quad) => quad.sample(t),
             Segment::Cubic(ref cub) => cub.sample(t),
             Segment::Arc(ref arc) => arc.sample(t),
+            Segment::Clothoid(ref clothoid) => clothoid.sample(t),
         }
     }
     fn x(&self, t: Self::Scalar) -> Self::Scalar {
@@ -48,6 +70,7 @@ impl lyon_geom::Segment for Segment<f64> {
             Segment::Quadratic(ref quad) => quad.x(t),
             Segment::Cubic(ref cub) => cub.x(t),
             Segment::Arc(ref arc) => arc.x(t),
+            Segment::Clothoid(ref clothoid) => clothoid.x(t),
         }
     }
     fn y(&self, t: Self::Scalar) -> Self::Scalar {
@@ -56,6 +79,7 @@ impl lyon_geom::Segment for Segment<f64> {
             Segment::Quadratic(ref quad) => quad.y(t),
             Segment::Cubic(ref cub) => cub.y(t),
             Segment::Arc(ref arc) => arc.y(t),
+            Segment::Clothoid(ref clothoid) => clothoid.y(t),
         }
     }
     fn derivative(&self, _t: Self::Scalar) -> euclid::Vector2D<Self::Scalar> {
@@ -64,6 +88,7 @@ impl lyon_geom::Segment for Segment<f64> {
             Segment::Quadratic(ref quad) => quad.derivative(_t),
             Segment::Cubic(ref cub) => cub.derivative(_t),
             Segment::Arc(ref arc) => arc.derivative(_t),
+            Segment::Clothoid(ref clothoid) => clothoid.derivative(_t),
         }
     }
     fn dx(&self, _t: Self::Scalar) -> Self::Scalar {
@@ -72,6 +97,7 @@ impl lyon_geom::Segment for Segment<f64> {
             Segment::Quadratic(ref quad) => quad.dx(_t),
             Segment::Cubic(ref cub) => cub.dx(_t),
             Segment::Arc(ref arc) => arc.dx(_t),
+            Segment::Clothoid(ref clothoid) => clothoid.dx(_t),
         }
     }
     fn dy(&self, _t: Self::Scalar) -> Self::Scalar {
@@ -80,6 +106,7 @@ impl lyon_geom::Segment for Segment<f64> {
             Segment::Quadratic(ref quad) => quad.dy(_t),
             Segment::Cubic(ref cub) => cub.dy(_t),
             Segment::Arc(ref arc) => arc.dy(_t),
+            Segment::Clothoid(ref clothoid) => clothoid.dy(_t),
         }
     }
     fn split_range(&self, t_range: Range<Self::Scalar>) -> Self {
@@ -88,6 +115,7 @@ impl lyon_geom::Segment for Segment<f64> {
             Segment::Quadratic(ref quad) => Segment::Quadratic(quad.split_range(t_range)),
             Segment::Cubic(ref cub) => Segment::Cubic(cub.split_range(t_range)),
             Segment::Arc(ref arc) => Segment::Arc(arc.split_range(t_range)),
+            Segment::Clothoid(ref clothoid) => Segment::Clothoid(clothoid.split_range(t_range)),
         }
     }
     fn split(&self, t: Self::Scalar) -> (Self, Self) {
@@ -108,6 +136,10 @@ impl lyon_geom::Segment for Segment<f64> {
                 let (a, b) = arc.split(t);
                 (Segment::Arc(a), Segment::Arc(b))
             }
+            Segment::Clothoid(ref clothoid) => {
+                let (a, b) = clothoid.split(t);
+                (Segment::Clothoid(a), Segment::Clothoid(b))
+            }
         }
     }
     fn before_split(&self, t: Self::Scalar) -> Self {
@@ -116,6 +148,7 @@ impl lyon_geom::Segment for Segment<f64> {
             Segment::Quadratic(ref quad) => Segment::Quadratic(quad.before_split(t)),
             Segment::Cubic(ref cub) => Segment::Cubic(cub.before_split(t)),
             Segment::Arc(ref arc) => Segment::Arc(arc.before_split(t)),
+            Segment::Clothoid(ref clothoid) => Segment::Clothoid(clothoid.before_split(t)),
         }
     }
     fn after_split(&self, t: Self::Scalar) -> Self {
@@ -124,6 +157,7 @@ impl lyon_geom::Segment for Segment<f64> {
             Segment::Quadratic(ref quad) => Segment::Quadratic(quad.after_split(t)),
             Segment::Cubic(ref cub) => Segment::Cubic(cub.after_split(t)),
             Segment::Arc(ref arc) => Segment::Arc(arc.after_split(t)),
+            Segment::Clothoid(ref clothoid) => Segment::Clothoid(clothoid.after_split(t)),
         }
     }
     fn flip(&self) -> Self {
@@ -132,6 +166,7 @@ impl lyon_geom::Segment for Segment<f64> {
             Segment::Quadratic(ref quad) => Segment::Quadratic(quad.flip()),
             Segment::Cubic(ref cub) => Segment::Cubic(cub.flip()),
             Segment::Arc(ref arc) => Segment::Arc(arc.flip()),
+            Segment::Clothoid(ref clothoid) => Segment::Clothoid(clothoid.flip()),
         }
     }
     fn approximate_length(&self, _tolerance: Self::Scalar) -> Self::Scalar {
@@ -140,6 +175,188 @@ impl lyon_geom::Segment for Segment<f64> {
             Segment::Quadratic(ref quad) => quad.approximate_length(_tolerance),
             Segment::Cubic(ref cub) => cub.approximate_length(_tolerance),
             Segment::Arc(ref arc) => arc.approximate_length(_tolerance),
+            Segment::Clothoid(ref clothoid) => clothoid.approximate_length(_tolerance),
         }
     }
 }
+
+/// Storage of an identifier. Ids that are plain decimal numbers, by far the
+/// most common case, are kept as numbers so they neither allocate nor compare
+/// as strings. Any other id, e.g. `road_12a` or `007`, is kept verbatim.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum IdRepr {
+    Number(u64),
+    Name(String),
+}
+
+impl IdRepr {
+    fn parse(id: &str) -> IdRepr {
+        let canonical = !id.is_empty() && (id == "0" || !id.starts_with('0'));
+        match id.parse::<u64>() {
+            Ok(number) if canonical && id.bytes().all(|b| b.is_ascii_digit()) => {
+                IdRepr::Number(number)
+            }
+            _ => IdRepr::Name(id.to_string()),
+        }
+    }
+}
+
+/// Defines a newtype for an OpenDRIVE identifier that converts from numbers
+/// and strings and (de)serializes numeric ids as numbers
+macro_rules! id_type {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(IdRepr);
+
+        impl $name {
+            /// The id as number if it is a plain decimal number
+            pub fn as_u64(&self) -> Option<u64> {
+                match self.0 {
+                    IdRepr::Number(number) => Some(number),
+                    IdRepr::Name(_) => None,
+                }
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> $name {
+                $name(IdRepr::Number(0))
+            }
+        }
+
+        impl From<u32> for $name {
+            fn from(id: u32) -> $name {
+                $name(IdRepr::Number(u64::from(id)))
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> $name {
+                $name(IdRepr::parse(id))
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> $name {
+                $name(IdRepr::parse(&id))
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = std::convert::Infallible;
+
+            fn from_str(id: &str) -> Result<$name, Self::Err> {
+                Ok($name::from(id))
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                match self.0 {
+                    IdRepr::Number(number) => write!(f, "{}", number),
+                    IdRepr::Name(ref name) => f.write_str(name),
+                }
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                match self.0 {
+                    IdRepr::Number(number) if serializer.is_human_readable() => {
+                        serializer.serialize_u64(number)
+                    }
+                    IdRepr::Number(number) => serializer.serialize_str(&number.to_string()),
+                    IdRepr::Name(ref name) => serializer.serialize_str(name),
+                }
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<$name, D::Error> {
+                // formats that are not self describing store every id as string
+                if !deserializer.is_human_readable() {
+                    return deserializer.deserialize_str(IdVisitor).map($name);
+                }
+                deserializer.deserialize_any(IdVisitor).map($name)
+            }
+        }
+    };
+}
+
+id_type!(
+    /// Identifier of a road, unique within the database
+    RoadId
+);
+id_type!(
+    /// Identifier of a junction, unique within the database
+    JunctionId
+);
+id_type!(
+    /// Identifier of a signal, unique within the database
+    SignalId
+);
+id_type!(
+    /// Identifier of a connection, unique within its junction
+    ConnectionId
+);
+id_type!(
+    /// Identifier of the road or junction a link points to, depending on
+    /// the element type of the link
+    ElementId
+);
+
+/// Compare and convert link targets with the ids of the elements they name
+macro_rules! element_id_conversions {
+    ($($name:ident),*) => {
+        $(
+            impl From<$name> for ElementId {
+                fn from(id: $name) -> ElementId {
+                    ElementId(id.0)
+                }
+            }
+
+            impl From<ElementId> for $name {
+                fn from(id: ElementId) -> $name {
+                    $name(id.0)
+                }
+            }
+
+            impl PartialEq<$name> for ElementId {
+                fn eq(&self, other: &$name) -> bool {
+                    self.0 == other.0
+                }
+            }
+
+            impl PartialEq<ElementId> for $name {
+                fn eq(&self, other: &ElementId) -> bool {
+                    self.0 == other.0
+                }
+            }
+        )*
+    };
+}
+
+element_id_conversions!(RoadId, JunctionId);
+
+struct IdVisitor;
+
+impl<'de> serde::de::Visitor<'de> for IdVisitor {
+    type Value = IdRepr;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an identifier")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, id: &str) -> Result<IdRepr, E> {
+        Ok(IdRepr::parse(id.trim()))
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, id: u64) -> Result<IdRepr, E> {
+        Ok(IdRepr::Number(id))
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, id: i64) -> Result<IdRepr, E> {
+        Ok(IdRepr::parse(&id.to_string()))
+    }
+}